@@ -0,0 +1,70 @@
+//! Times parsing of a large synthetic jacquard draft. Run with `cargo bench --bench parse`.
+//!
+//! As a baseline, also times configparser, which the crate used to read WIF files with, on
+//! the same text. That covers only the INI stage of the old parser, so it's a lower bound on
+//! what a full parse used to cost.
+
+use std::{
+    fmt::Write,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const SHAFTS: u32 = 1200;
+const PICKS: u32 = 4000;
+
+fn jacquard(shafts: u32, picks: u32) -> String {
+    let mut s = String::new();
+    writeln!(
+        s,
+        "[WIF]\nVersion=1.1\nDate=April 20, 1997\nDevelopers=wif@mhsoft.com"
+    )
+    .unwrap();
+    writeln!(
+        s,
+        "Source Program=bench\n\n[CONTENTS]\nWEAVING=true\nWARP=true\nWEFT=true"
+    )
+    .unwrap();
+    writeln!(s, "THREADING=true\nLIFTPLAN=true\n").unwrap();
+    writeln!(s, "[WEAVING]\nShafts={shafts}\nTreadles=0\n").unwrap();
+    writeln!(s, "[WARP]\nThreads={shafts}\n\n[WEFT]\nThreads={picks}\n").unwrap();
+    writeln!(s, "[THREADING]").unwrap();
+    for end in 1..=shafts {
+        writeln!(s, "{end}={end}").unwrap();
+    }
+    writeln!(s, "\n[LIFTPLAN]").unwrap();
+    for pick in 1..=picks {
+        let lifted: Vec<String> = (1..=shafts)
+            .filter(|shaft| (shaft * 7 + pick * 3) % 5 < 2)
+            .map(|shaft| shaft.to_string())
+            .collect();
+        writeln!(s, "{pick}={}", lifted.join(",")).unwrap();
+    }
+    s
+}
+
+// The mean time of `runs` calls to `f`.
+fn time(runs: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..runs {
+        f();
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    let text = jacquard(SHAFTS, PICKS);
+    let runs = 5;
+    let baseline = time(runs, || {
+        let mut ini = configparser::ini::Ini::new();
+        black_box(ini.read(black_box(text.clone())).unwrap());
+    });
+    let per_run = time(runs, || {
+        black_box(wif::parse(black_box(&text)).unwrap());
+    });
+    println!(
+        "{} KiB ({SHAFTS} shafts x {PICKS} picks): wif::parse {per_run:?} per run, \
+         configparser alone {baseline:?} per run",
+        text.len() / 1024
+    );
+}
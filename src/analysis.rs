@@ -0,0 +1,76 @@
+use std::collections::BTreeSet;
+
+use crate::{Shaft, Weft, Wif};
+
+#[cfg(test)]
+mod tests;
+
+mod balance;
+mod color_and_weave;
+mod firmness;
+mod floats;
+mod loom_lint;
+mod profile;
+mod reed_fit;
+mod selvedge;
+mod shaft_load;
+mod symmetry;
+mod treadling_errors;
+mod twill_angle;
+mod unweavable;
+mod wallpaper;
+pub use balance::*;
+pub use color_and_weave::*;
+pub use firmness::*;
+pub use floats::*;
+pub use loom_lint::*;
+pub use profile::*;
+pub use reed_fit::*;
+pub use selvedge::*;
+pub use shaft_load::*;
+pub use symmetry::*;
+pub use treadling_errors::*;
+pub use twill_angle::*;
+pub use unweavable::*;
+pub use wallpaper::*;
+
+/// The shafts lifted on each pick, in order, with gaps in the liftplan treated as empty lifts.
+pub(crate) fn lift_sequence(wif: &Wif) -> Option<Vec<BTreeSet<Shaft>>> {
+    let liftplan = wif.liftplan.as_ref()?;
+    let last = liftplan.keys().next_back().map_or(0, |w| w.0);
+    let picks = last.max(wif.height().unwrap_or(0));
+    Some(
+        (1..=picks)
+            .map(|pick| liftplan.get(&Weft(pick)).cloned().unwrap_or_default())
+            .collect(),
+    )
+}
+
+/// The shafts raised on each pick, as [`lift_sequence`] gives them on a rising shed. On a
+/// sinking shed the liftplan lists the shafts that go down, so each pick raises the rest.
+pub(crate) fn raised_sequence(wif: &Wif) -> Option<Vec<BTreeSet<Shaft>>> {
+    lift_sequence(wif).map(|lifts| raised(wif, lifts))
+}
+
+/// The shafts each of `lifts`, as listed in a liftplan or tieup, raises: the listed ones on
+/// a rising shed, and all the others on a sinking one.
+pub(crate) fn raised(wif: &Wif, lifts: Vec<BTreeSet<Shaft>>) -> Vec<BTreeSet<Shaft>> {
+    let rising_shed = wif
+        .weaving
+        .as_ref()
+        .and_then(|w| w.rising_shed)
+        .unwrap_or(true);
+    if rising_shed {
+        return lifts;
+    }
+    let shafts = wif.shafts().unwrap_or(0);
+    lifts
+        .iter()
+        .map(|lift| {
+            (1..=shafts)
+                .map(Shaft)
+                .filter(|shaft| !lift.contains(shaft))
+                .collect()
+        })
+        .collect()
+}
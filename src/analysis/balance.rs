@@ -0,0 +1,58 @@
+use crate::{Drawdown, Result, Wif};
+
+/// How much of one face of the cloth the warp covers, as fractions from 0 (all weft) to 1
+/// (all warp).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaceBalance {
+    pub overall: f64,
+    /// One entry per end, end 1 first: the share of picks it shows on.
+    pub ends: Vec<f64>,
+    /// One entry per pick, pick 1 first: the share of ends showing on it.
+    pub picks: Vec<f64>,
+}
+
+impl FaceBalance {
+    fn new(drawdown: &Drawdown, warp_on_top: bool) -> FaceBalance {
+        let shows = |cell: bool| cell == warp_on_top;
+        let fraction = |count: usize, of: u32| {
+            if of == 0 {
+                0.
+            } else {
+                count as f64 / of as f64
+            }
+        };
+        let (ends, picks) = (drawdown.ends(), drawdown.picks());
+        let mut per_end = vec![0; ends as usize];
+        let mut per_pick = Vec::with_capacity(picks as usize);
+        for row in drawdown.rows() {
+            for (count, &cell) in per_end.iter_mut().zip(row) {
+                *count += shows(cell) as usize;
+            }
+            per_pick.push(row.iter().filter(|&&cell| shows(cell)).count());
+        }
+        FaceBalance {
+            overall: fraction(per_pick.iter().sum(), ends * picks),
+            ends: per_end.into_iter().map(|n| fraction(n, picks)).collect(),
+            picks: per_pick.into_iter().map(|n| fraction(n, ends)).collect(),
+        }
+    }
+}
+
+/// The warp's share of the face of the cloth, as drawn in the drawdown, and of the back.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Balance {
+    pub face: FaceBalance,
+    pub back: FaceBalance,
+}
+
+impl Wif {
+    /// How warp- or weft-faced the cloth is on each face, overall and for every end and pick,
+    /// from the [`drawdown`](Self::drawdown). A balanced weave is near 0.5 everywhere.
+    pub fn balance(&self) -> Result<Balance> {
+        let drawdown = self.drawdown()?;
+        Ok(Balance {
+            face: FaceBalance::new(&drawdown, true),
+            back: FaceBalance::new(&drawdown, false),
+        })
+    }
+}
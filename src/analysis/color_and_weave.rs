@@ -0,0 +1,88 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::{ColorIndex, Result, Structure, Warp, Weft, Wif};
+
+/// A classic pattern that comes from the color orders rather than the structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorEffect {
+    /// Plain weave with two colors alternating thread by thread, except where a block ends
+    /// and the same color comes twice, turning the lines 90 degrees.
+    LogCabin,
+    /// A structure other than plain weave, with two colors alternating thread by thread in
+    /// both warp and weft.
+    ShadowWeave,
+}
+
+impl ColorEffect {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorEffect::LogCabin => "log cabin",
+            ColorEffect::ShadowWeave => "shadow weave",
+        }
+    }
+}
+
+impl fmt::Display for ColorEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The colors the cloth shows, from [`Wif::color_and_weave`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorAndWeave {
+    /// One row per pick, pick 1 first, holding the color of whichever thread is on top at
+    /// each end. `None` where that thread has no color.
+    pub grid: Vec<Vec<Option<ColorIndex>>>,
+    pub effect: Option<ColorEffect>,
+}
+
+// Two colors taking turns, with at least one place where a color comes twice and none where
+// it comes three times.
+fn log_cabin_order(order: &[Option<ColorIndex>]) -> bool {
+    let repeats = order.windows(2).filter(|w| w[0] == w[1]).count();
+    repeats > 0 && order.windows(3).all(|w| w[0] != w[1] || w[1] != w[2])
+}
+
+fn alternates(order: &[Option<ColorIndex>]) -> bool {
+    order.len() >= 2 && order.windows(2).all(|w| w[0] != w[1])
+}
+
+impl Wif {
+    /// The color of each cell of the drawdown, and the color-and-weave effect it makes, if it
+    /// is one of the classics. Both need exactly two colors between the warp and weft, with
+    /// no uncolored threads.
+    pub fn color_and_weave(&self) -> Result<ColorAndWeave> {
+        let drawdown = self.drawdown()?;
+        let warp: Vec<_> = (1..=drawdown.ends())
+            .map(|end| self.warp_color_idx(Warp(end)))
+            .collect();
+        let weft: Vec<_> = (1..=drawdown.picks())
+            .map(|pick| self.weft_color_idx(Weft(pick)))
+            .collect();
+        let grid = drawdown
+            .rows()
+            .zip(&weft)
+            .map(|(row, &weft)| {
+                row.iter()
+                    .zip(&warp)
+                    .map(|(&warp_on_top, &warp)| if warp_on_top { warp } else { weft })
+                    .collect()
+            })
+            .collect();
+
+        let colors: BTreeSet<_> = warp.iter().chain(&weft).collect();
+        let two_colors = colors.len() == 2 && !colors.contains(&None);
+        let effect = match self.summary().structure {
+            _ if !two_colors => None,
+            Structure::PlainWeave if log_cabin_order(&warp) || log_cabin_order(&weft) => {
+                Some(ColorEffect::LogCabin)
+            }
+            Structure::PlainWeave => None,
+            _ if alternates(&warp) && alternates(&weft) => Some(ColorEffect::ShadowWeave),
+            _ => None,
+        };
+        Ok(ColorAndWeave { grid, effect })
+    }
+}
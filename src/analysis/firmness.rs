@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use crate::{Drawdown, Result, Wif};
+
+use super::FaceFloats;
+
+/// How firmly a draft binds its threads, from [`Wif::firmness`]. Compare drafts by
+/// [`factor`](Self::factor), or by interlacements per unit area when both have a sett.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Firmness {
+    /// The average length of the warp floats on both faces.
+    pub warp_float: f64,
+    /// The average length of the weft floats on both faces.
+    pub weft_float: f64,
+    /// The average length of every float: 1 for plain weave, longer for looser weaves.
+    pub float_index: f64,
+    /// Places where a thread passes from one face to the other, along every end and pick.
+    pub interlacements: u64,
+    /// Interlacements per square inch of cloth, when the [WARP] and [WEFT] spacing give its
+    /// size.
+    pub per_square_inch: Option<f64>,
+}
+
+impl Firmness {
+    /// The reciprocal of the float index, from 1 for plain weave towards 0 as floats get
+    /// longer. Higher is firmer.
+    pub fn factor(&self) -> f64 {
+        if self.float_index > 0. {
+            1. / self.float_index
+        } else {
+            0.
+        }
+    }
+
+    pub fn per_square_centimeter(&self) -> Option<f64> {
+        self.per_square_inch.map(|n| n / 2.54 / 2.54)
+    }
+}
+
+// The total length and number of the floats of both faces, warp then weft.
+fn totals(faces: [&FaceFloats; 2]) -> [(u64, usize); 2] {
+    let sum = |lengths: &BTreeMap<u32, usize>| {
+        lengths.iter().fold((0, 0), |(length, count), (&l, &n)| {
+            (length + u64::from(l) * n as u64, count + n)
+        })
+    };
+    let add = |(a, b): (u64, usize), (c, d): (u64, usize)| (a + c, b + d);
+    [
+        add(sum(&faces[0].warp.lengths), sum(&faces[1].warp.lengths)),
+        add(sum(&faces[0].weft.lengths), sum(&faces[1].weft.lengths)),
+    ]
+}
+
+fn mean((length, count): (u64, usize)) -> f64 {
+    if count == 0 {
+        0.
+    } else {
+        length as f64 / count as f64
+    }
+}
+
+// Changes from warp to weft or back between neighbouring cells, down every end and along
+// every pick.
+fn interlacements(drawdown: &Drawdown) -> u64 {
+    let rows: Vec<&[bool]> = drawdown.rows().collect();
+    let along_picks: usize = rows
+        .iter()
+        .map(|row| row.windows(2).filter(|w| w[0] != w[1]).count())
+        .sum();
+    let down_ends: usize = rows
+        .windows(2)
+        .map(|w| w[0].iter().zip(w[1]).filter(|(a, b)| a != b).count())
+        .sum();
+    (along_picks + down_ends) as u64
+}
+
+impl Wif {
+    /// Float lengths and interlacements, from the [`drawdown`](Self::drawdown) and
+    /// [`float_report`](Self::float_report). Floats stop at the edges of the draft, as in the
+    /// float report. The cloth's area comes from
+    /// [`fabric_dimensions`](Self::fabric_dimensions), so a closer sett packs more
+    /// interlacements into each square inch.
+    pub fn firmness(&self) -> Result<Firmness> {
+        let floats = self.float_report()?;
+        let [warp, weft] = totals([&floats.face, &floats.back]);
+        let interlacements = interlacements(&self.drawdown()?);
+        let dimensions = self.fabric_dimensions();
+        let per_square_inch = match (dimensions.warp, dimensions.weft) {
+            (Some(warp), Some(weft)) if warp.inches > 0. && weft.inches > 0. => {
+                Some(interlacements as f64 / (warp.inches * weft.inches))
+            }
+            _ => None,
+        };
+        Ok(Firmness {
+            warp_float: mean(warp),
+            weft_float: mean(weft),
+            float_index: mean((warp.0 + weft.0, warp.1 + weft.1)),
+            interlacements,
+            per_square_inch,
+        })
+    }
+}
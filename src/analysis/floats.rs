@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use crate::{Drawdown, Result, Warp, Weft, Wif};
+
+/// One float: a run of a thread over consecutive crossing threads, starting at `end` and
+/// `pick`. Warp floats run down an end from `pick`, weft floats along a pick from `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Float {
+    pub end: Warp,
+    pub pick: Weft,
+    pub length: u32,
+}
+
+/// The floats of one thread direction on one face.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FloatStats {
+    pub longest: u32,
+    /// How many floats there are of each length.
+    pub lengths: BTreeMap<u32, usize>,
+    /// Every float of the longest length, in thread order.
+    pub worst: Vec<Float>,
+}
+
+impl FloatStats {
+    fn add(&mut self, float: Float) {
+        *self.lengths.entry(float.length).or_default() += 1;
+        if float.length > self.longest {
+            self.longest = float.length;
+            self.worst.clear();
+        }
+        if float.length == self.longest {
+            self.worst.push(float);
+        }
+    }
+}
+
+/// Warp and weft floats on one face of the cloth.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaceFloats {
+    pub warp: FloatStats,
+    pub weft: FloatStats,
+}
+
+/// Floats on the face of the cloth, as drawn in the drawdown, and on the back, where every
+/// interlacement is reversed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FloatReport {
+    pub face: FaceFloats,
+    pub back: FaceFloats,
+}
+
+impl FloatReport {
+    /// The longest float of either thread on either face.
+    pub fn longest(&self) -> u32 {
+        [&self.face, &self.back]
+            .iter()
+            .flat_map(|side| [side.warp.longest, side.weft.longest])
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// Calls `found` with the start and length of each run of `value` in `line`.
+fn runs(line: impl Iterator<Item = bool>, value: bool, mut found: impl FnMut(u32, u32)) {
+    let mut run = None;
+    for (i, cell) in (1..).zip(line.chain([!value])) {
+        match (cell == value, run) {
+            (true, None) => run = Some(i),
+            (false, Some(start)) => {
+                found(start, i - start);
+                run = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn floats(drawdown: &Drawdown, warp_on_top: bool) -> FaceFloats {
+    let mut floats = FaceFloats::default();
+    for end in 1..=drawdown.ends() {
+        let column = drawdown.rows().map(|row| row[end as usize - 1]);
+        runs(column, warp_on_top, |pick, length| {
+            floats.warp.add(Float {
+                end: Warp(end),
+                pick: Weft(pick),
+                length,
+            })
+        });
+    }
+    for (pick, row) in (1..).zip(drawdown.rows()) {
+        runs(row.iter().copied(), !warp_on_top, |end, length| {
+            floats.weft.add(Float {
+                end: Warp(end),
+                pick: Weft(pick),
+                length,
+            })
+        });
+    }
+    floats
+}
+
+impl Wif {
+    /// Every warp and weft float on both faces of the cloth, from the
+    /// [`drawdown`](Self::drawdown). Lengths count the threads floated over, so a thread
+    /// that goes over one and under the next has floats of length 1. Floats stop at the
+    /// edges of the draft rather than wrapping into the next repeat.
+    pub fn float_report(&self) -> Result<FloatReport> {
+        let drawdown = self.drawdown()?;
+        Ok(FloatReport {
+            face: floats(&drawdown, true),
+            back: floats(&drawdown, false),
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::BTreeSet;
+
+use crate::{loom::LoomProfile, Treadle, Weft, Wif};
+
+/// A way to rewrite the draft that would resolve a [`LoomLint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    /// [`Wif::expand_skeleton_tieup`]
+    ExpandSkeletonTieup,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoomLint {
+    /// A pick presses several treadles, which the loom can't do.
+    MultiTreadlePick {
+        pick: Weft,
+        treadles: BTreeSet<Treadle>,
+    },
+    /// Running `conversion` would fix the picks above, using this many treadles.
+    SuggestConversion {
+        conversion: Conversion,
+        treadles_needed: u32,
+    },
+}
+
+/// Checks the treadling against what the loom can physically do.
+pub fn lint_for_loom(wif: &Wif, loom: &LoomProfile) -> Vec<LoomLint> {
+    if loom.loom_type.allows_multiple_treadles() {
+        return vec![];
+    }
+    let Some(treadling) = wif.treadling.as_ref() else {
+        return vec![];
+    };
+    let mut lints: Vec<LoomLint> = treadling
+        .iter()
+        .filter(|(_, treadles)| treadles.len() > 1)
+        .map(|(&pick, treadles)| LoomLint::MultiTreadlePick {
+            pick,
+            treadles: treadles.clone(),
+        })
+        .collect();
+    if !lints.is_empty() {
+        let combinations: BTreeSet<_> = treadling.values().filter(|t| !t.is_empty()).collect();
+        lints.push(LoomLint::SuggestConversion {
+            conversion: Conversion::ExpandSkeletonTieup,
+            treadles_needed: combinations.len() as u32,
+        });
+    }
+    lints
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use crate::{Shaft, Warp, Weaving, Wif};
+
+/// The block weave a threading was recognised as, from [`Wif::profile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockStructure {
+    /// Blocks on pairs of neighbouring shafts, 1-2 as block A, 2-3 as B and so on round to
+    /// the last shaft and 1, sharing an end where one block turns into the next. A unit is
+    /// two ends.
+    Overshot,
+    /// Four-end units threaded tie-down, pattern, tie-down, pattern, with shafts 1 and 2 as
+    /// the tie-downs and each shaft from 3 as a block.
+    SummerAndWinter,
+}
+
+impl BlockStructure {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockStructure::Overshot => "overshot",
+            BlockStructure::SummerAndWinter => "summer and winter",
+        }
+    }
+}
+
+impl fmt::Display for BlockStructure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One run of a single block across the threading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileBlock {
+    /// Numbered from 1, written as A, B, C and so on.
+    pub block: u32,
+    pub first: Warp,
+    pub last: Warp,
+    pub units: u32,
+}
+
+/// A threading reduced to its blocks, the way weavers design block weaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub structure: BlockStructure,
+    /// How many blocks the threading has room for, used or not.
+    pub block_count: u32,
+    /// In threading order.
+    pub blocks: Vec<ProfileBlock>,
+}
+
+impl Profile {
+    /// The profile threading: one block number per unit.
+    pub fn threading(&self) -> Vec<u32> {
+        self.blocks
+            .iter()
+            .flat_map(|b| std::iter::repeat_n(b.block, b.units as usize))
+            .collect()
+    }
+
+    /// The profile as a draft of its own, one end per unit threaded on the shaft numbered
+    /// for its block, with a shaft for each block.
+    pub fn to_wif(&self) -> Wif {
+        let threading = self.threading();
+        let mut wif = Wif::blank(threading.len() as u32, 0);
+        wif.weaving = Some(Weaving {
+            shafts: self.block_count,
+            treadles: 0,
+            rising_shed: Some(true),
+        });
+        wif.threading = Some(
+            (1..)
+                .map(Warp)
+                .zip(threading)
+                .map(|(end, block)| (end, [Shaft(block)].into()))
+                .collect(),
+        );
+        wif
+    }
+}
+
+fn letter(block: u32) -> String {
+    match char::from_u32('A' as u32 + block - 1) {
+        Some(c) if block <= 26 => c.to_string(),
+        _ => block.to_string(),
+    }
+}
+
+/// Written as blocks and units, e.g. `overshot: A×2 B×3 A×2`.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.structure)?;
+        for block in &self.blocks {
+            write!(f, " {}×{}", letter(block.block), block.units)?;
+        }
+        Ok(())
+    }
+}
+
+// Adds `units` of `block` over `first..=last`, merging it into the run before if that's the
+// same block.
+fn push(blocks: &mut Vec<ProfileBlock>, block: u32, first: u32, last: u32, units: u32) {
+    match blocks.last_mut() {
+        Some(prev) if prev.block == block => {
+            prev.last = Warp(last);
+            prev.units += units;
+        }
+        _ => blocks.push(ProfileBlock {
+            block,
+            first: Warp(first),
+            last: Warp(last),
+            units,
+        }),
+    }
+}
+
+fn summer_and_winter(shafts: &[u32]) -> Option<Vec<ProfileBlock>> {
+    if shafts.is_empty() {
+        return None;
+    }
+    let mut blocks = vec![];
+    for (unit, ends) in (0..).zip(shafts.chunks(4)) {
+        let &[tie, pattern, other_tie, again] = ends else {
+            return None;
+        };
+        let ties_ok = tie != other_tie && tie <= 2 && other_tie <= 2;
+        if !ties_ok || pattern < 3 || pattern != again {
+            return None;
+        }
+        push(&mut blocks, pattern - 2, unit * 4 + 1, unit * 4 + 4, 1);
+    }
+    Some(blocks)
+}
+
+fn overshot(shafts: &[u32], shaft_count: u32) -> Option<Vec<ProfileBlock>> {
+    if shaft_count < 3 || shafts.len() < 2 {
+        return None;
+    }
+    // The block each neighbouring pair of ends weaves, by the first end of the pair.
+    let pairs: Vec<u32> = shafts
+        .windows(2)
+        .map(|w| {
+            let (low, high) = (w[0].min(w[1]), w[0].max(w[1]));
+            match high - low {
+                1 => Some(low),
+                d if d == shaft_count - 1 => Some(high),
+                _ => None,
+            }
+        })
+        .collect::<Option<_>>()?;
+    let mut runs: Vec<(u32, u32, u32)> = vec![];
+    for (end, block) in (1..).zip(pairs) {
+        match runs.last_mut() {
+            Some((prev, _, count)) if *prev == block => *count += 1,
+            _ => runs.push((block, end, 1)),
+        }
+    }
+    // A twill draw moves to a new block on every end; overshot stays on some block.
+    if runs.iter().all(|&(_, _, pairs)| pairs < 2) {
+        return None;
+    }
+    let mut blocks = vec![];
+    for (block, first, pairs) in runs {
+        push(&mut blocks, block, first, first + pairs, pairs.div_ceil(2));
+    }
+    Some(blocks)
+}
+
+impl Wif {
+    /// The block profile of the threading, if it reads as summer and winter or overshot.
+    /// Every end must be threaded on exactly one shaft. Summer and winter is tried first, and
+    /// a threading that only ever moves to the next block, such as a straight or point draw,
+    /// isn't taken for overshot.
+    pub fn profile(&self) -> Option<Profile> {
+        let threading = self.threading.as_ref()?;
+        let ends = threading
+            .keys()
+            .next_back()
+            .map_or(0, |w| w.0)
+            .max(self.width().unwrap_or(0));
+        let shafts: Vec<u32> = (1..=ends)
+            .map(|end| match threading.get(&Warp(end)) {
+                Some(shafts) if shafts.len() == 1 => shafts.first().map(|s| s.0),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        let highest = shafts.iter().copied().max().unwrap_or(0);
+        let shaft_count = self.shafts().unwrap_or(0).max(highest);
+        if let Some(blocks) = summer_and_winter(&shafts) {
+            return Some(Profile {
+                structure: BlockStructure::SummerAndWinter,
+                block_count: shaft_count.saturating_sub(2),
+                blocks,
+            });
+        }
+        overshot(&shafts, shaft_count).map(|blocks| Profile {
+            structure: BlockStructure::Overshot,
+            block_count: shaft_count,
+            blocks,
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{loom::LoomProfile, Result, Wif, WifError};
+
+// How far the sett may stray from a whole number of ends per dent and still sley evenly.
+const SLEYING_TOLERANCE: f64 = 0.02;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReedFit {
+    pub ends: u32,
+    /// Ends per inch.
+    pub sett: f64,
+    /// Width in the reed, in inches.
+    pub weaving_width: f64,
+    /// Whether the weaving width fits within the reed.
+    pub fits: bool,
+    /// The loom's reeds that achieve the sett with the same number of ends in every dent.
+    pub sleyings: Vec<Sleying>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sleying {
+    pub dents_per_inch: u32,
+    pub ends_per_dent: u32,
+}
+
+/// Works out the draft's width and sett from its warp spacing, zoom included as in
+/// [`Wif::fabric_dimensions`], and checks them against the loom's reeds.
+pub fn check_reed_fit(wif: &Wif, loom: &LoomProfile) -> Result<ReedFit> {
+    let warp = wif.warp.as_ref().ok_or_else(|| WifError::MissingSection {
+        section: crate::sections::WARP.into(),
+    })?;
+    let units = warp
+        .units
+        .as_ref()
+        .ok_or_else(|| WifError::MissingRequiredField {
+            section: crate::sections::WARP.into(),
+            field: "Units".into(),
+        })?;
+    let mut weaving_width = 0.;
+    for end in 1..=warp.threads {
+        let spacing = wif
+            .warp_spacing_at(end)
+            .ok_or_else(|| WifError::MissingRequiredField {
+                section: crate::sections::WARP.into(),
+                field: "Spacing".into(),
+            })?;
+        weaving_width += spacing.inches().ok_or_else(|| WifError::UnknownUnits {
+            units: units.as_str().into(),
+        })?;
+    }
+    let sett = if weaving_width > 0. {
+        warp.threads as f64 / weaving_width
+    } else {
+        0.
+    };
+    let sleyings =
+        loom.reeds
+            .iter()
+            .filter_map(|&dents_per_inch| {
+                let per_dent = sett / dents_per_inch as f64;
+                let rounded = per_dent.round();
+                (rounded >= 1. && (per_dent - rounded).abs() <= SLEYING_TOLERANCE * rounded)
+                    .then_some(Sleying {
+                        dents_per_inch,
+                        ends_per_dent: rounded as u32,
+                    })
+            })
+            .collect();
+    Ok(ReedFit {
+        ends: warp.threads,
+        sett,
+        weaving_width,
+        fits: weaving_width <= loom.max_width,
+        sleyings,
+    })
+}
@@ -0,0 +1,92 @@
+use crate::{Result, Weft, Wif};
+
+/// One side of the cloth, named by the end at its edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// End 1.
+    FirstEnd,
+    /// The last end.
+    LastEnd,
+}
+
+impl Edge {
+    fn other(self) -> Edge {
+        match self {
+            Edge::FirstEnd => Edge::LastEnd,
+            Edge::LastEnd => Edge::FirstEnd,
+        }
+    }
+}
+
+/// A turn of the weft at an edge that the edge end doesn't catch: it stays up, or stays
+/// down, for both `pick` and the pick after it, so the weft loops around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelvedgeMiss {
+    pub pick: Weft,
+    pub edge: Edge,
+}
+
+/// Where the weft fails to catch the edge ends, from [`Wif::selvedge_catch`], depending on
+/// which side the first pick is thrown from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelvedgeCatch {
+    /// With pick 1 thrown from the end 1 side, so the weft turns at the last end after it.
+    pub from_first_end: Vec<SelvedgeMiss>,
+    /// With pick 1 thrown from the last end's side.
+    pub from_last_end: Vec<SelvedgeMiss>,
+}
+
+impl SelvedgeCatch {
+    /// The side to throw pick 1 from so every turn is caught, preferring end 1's side.
+    pub fn clean_start(&self) -> Option<Edge> {
+        if self.from_first_end.is_empty() {
+            Some(Edge::FirstEnd)
+        } else if self.from_last_end.is_empty() {
+            Some(Edge::LastEnd)
+        } else {
+            None
+        }
+    }
+
+    /// Whether floating selvedges are needed, because some turn is missed whichever side
+    /// the weaving starts from.
+    pub fn needs_floating_selvedges(&self) -> bool {
+        self.clean_start().is_none()
+    }
+}
+
+impl Wif {
+    /// Checks, from the [`drawdown`](Self::drawdown), whether the weft catches the end at
+    /// each edge every time it turns there, for either starting side.
+    pub fn selvedge_catch(&self) -> Result<SelvedgeCatch> {
+        let drawdown = self.drawdown()?;
+        let ends = drawdown.ends();
+        if ends == 0 {
+            return Ok(SelvedgeCatch::default());
+        }
+        let rows: Vec<&[bool]> = drawdown.rows().collect();
+        let misses = |start: Edge| {
+            // The edge the weft turns at after each pick alternates, starting opposite `start`.
+            let mut edge = start.other();
+            let mut misses = vec![];
+            for (pick, pair) in (1..).zip(rows.windows(2)) {
+                let end = match edge {
+                    Edge::FirstEnd => 0,
+                    Edge::LastEnd => ends as usize - 1,
+                };
+                if pair[0][end] == pair[1][end] {
+                    misses.push(SelvedgeMiss {
+                        pick: Weft(pick),
+                        edge,
+                    });
+                }
+                edge = edge.other();
+            }
+            misses
+        };
+        Ok(SelvedgeCatch {
+            from_first_end: misses(Edge::FirstEnd),
+            from_last_end: misses(Edge::LastEnd),
+        })
+    }
+}
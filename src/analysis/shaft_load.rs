@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Shaft, Weft, Wif};
+
+use super::raised_sequence;
+
+/// What one pick lifts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickLoad {
+    pub pick: Weft,
+    pub shafts: BTreeSet<Shaft>,
+    /// Ends threaded on at least one of the lifted shafts.
+    pub ends_lifted: u32,
+    /// `ends_lifted` as a share of all the ends.
+    pub fraction: f64,
+}
+
+/// How heavy each pick is to lift, from [`Wif::shaft_load`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaftLoad {
+    pub ends: u32,
+    pub picks: Vec<PickLoad>,
+    /// The average share of the ends lifted per pick.
+    pub mean_fraction: f64,
+    /// The heaviest pick, the first if several tie.
+    pub heaviest: Option<Weft>,
+}
+
+impl ShaftLoad {
+    /// Picks lifting more than `fraction` of the ends, such as 0.9 for the ones that will be
+    /// hard to treadle on a jack loom.
+    pub fn heavier_than(&self, fraction: f64) -> impl Iterator<Item = &PickLoad> + '_ {
+        self.picks.iter().filter(move |p| p.fraction > fraction)
+    }
+}
+
+impl Wif {
+    /// The shafts and number of ends each pick lifts, from the liftplan. Heddles count once
+    /// per end, so an end on two lifted shafts counts once. Picks the liftplan leaves out
+    /// lift nothing on a rising shed, and every shaft on a sinking one.
+    pub fn shaft_load(&self) -> ShaftLoad {
+        let ends = self.width().unwrap_or(0);
+        let mut heddles: BTreeMap<Shaft, Vec<u32>> = BTreeMap::new();
+        for (end, shafts) in self.threading.iter().flatten() {
+            for &shaft in shafts {
+                heddles.entry(shaft).or_default().push(end.0);
+            }
+        }
+        let picks: Vec<PickLoad> = (1..)
+            .map(Weft)
+            .zip(raised_sequence(self).unwrap_or_default())
+            .map(|(pick, shafts)| {
+                let lifted: BTreeSet<u32> = shafts
+                    .iter()
+                    .filter_map(|s| heddles.get(s))
+                    .flatten()
+                    .copied()
+                    .collect();
+                let ends_lifted = lifted.len() as u32;
+                PickLoad {
+                    pick,
+                    shafts,
+                    ends_lifted,
+                    fraction: if ends == 0 {
+                        0.
+                    } else {
+                        ends_lifted as f64 / ends as f64
+                    },
+                }
+            })
+            .collect();
+        let mean_fraction = if picks.is_empty() {
+            0.
+        } else {
+            picks.iter().map(|p| p.fraction).sum::<f64>() / picks.len() as f64
+        };
+        let heaviest = picks
+            .iter()
+            .rev()
+            .max_by_key(|p| p.ends_lifted)
+            .map(|p| p.pick);
+        ShaftLoad {
+            ends,
+            picks,
+            mean_fraction,
+            heaviest,
+        }
+    }
+}
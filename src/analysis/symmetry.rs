@@ -0,0 +1,198 @@
+use std::collections::BTreeSet;
+
+use crate::{Table, Warp, Weft, Wif};
+
+/// Threads `first..=last` that read the same forwards and backwards, as in a point draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mirror {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl Mirror {
+    /// The thread the mirror turns on, or the midpoint between the two middle threads.
+    pub fn axis(&self) -> f64 {
+        (self.first + self.last) as f64 / 2.
+    }
+}
+
+/// Threads `first..=last` each on the next shaft or treadle up, or each on the next one down,
+/// wrapping from the last back to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StraightRun {
+    pub first: u32,
+    pub last: u32,
+    pub ascending: bool,
+}
+
+/// Back-to-back ascending straight runs of `run_length` threads over `first..=last`, each
+/// starting `step` shafts or treadles above the one before, as in 1234 2345 3456.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Advancing {
+    pub first: u32,
+    pub last: u32,
+    pub run_length: u32,
+    pub step: u32,
+}
+
+/// The shapes found in a threading or treadling. Only threads on a single shaft or treadle
+/// take part; others break any pattern they fall in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patterns {
+    /// Every mirror at least three threads long, centered on each turning point.
+    pub mirrors: Vec<Mirror>,
+    /// Every straight run at least three threads long that isn't part of a longer one.
+    pub straight_runs: Vec<StraightRun>,
+    /// Every advancing sequence of at least two runs.
+    pub advancing: Vec<Advancing>,
+}
+
+// The single value each thread uses, `None` for threads on none or several.
+fn singles<K: Ord, V: Ord + Copy>(
+    table: Option<&Table<K, BTreeSet<V>>>,
+    threads: u32,
+    key: fn(u32) -> K,
+    value: fn(V) -> u32,
+) -> Vec<Option<u32>> {
+    (1..=threads)
+        .map(|n| {
+            let set = table?.get(&key(n))?;
+            match set.len() {
+                1 => set.first().copied().map(value),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn mirrors(seq: &[Option<u32>]) -> Vec<Mirror> {
+    let same = |a: usize, b: usize| seq[a].is_some() && seq[a] == seq[b];
+    let mut mirrors = vec![];
+    // Centers on a thread (`odd`) or between two threads.
+    for center in 0..seq.len() {
+        if seq[center].is_none() {
+            continue;
+        }
+        for odd in [true, false] {
+            let (mut left, mut right) = if odd {
+                (center, center)
+            } else if center + 1 < seq.len() && same(center, center + 1) {
+                (center, center + 1)
+            } else {
+                continue;
+            };
+            while left > 0 && right + 1 < seq.len() && same(left - 1, right + 1) {
+                left -= 1;
+                right += 1;
+            }
+            // Threads all on the same shaft or treadle never turn, so they aren't a mirror.
+            let turns = seq[left..=right].iter().any(|v| *v != seq[center]);
+            if right - left >= 2 && turns {
+                mirrors.push(Mirror {
+                    first: left as u32 + 1,
+                    last: right as u32 + 1,
+                });
+            }
+        }
+    }
+    mirrors
+}
+
+fn straight_runs(seq: &[Option<u32>], count: u32) -> Vec<StraightRun> {
+    if count == 0 {
+        return vec![];
+    }
+    let next = |v: u32, ascending: bool| {
+        if ascending {
+            v % count + 1
+        } else {
+            (v + count - 2) % count + 1
+        }
+    };
+    let mut runs = vec![];
+    for ascending in [true, false] {
+        let mut start = 0;
+        for i in 1..=seq.len() {
+            let continues = i < seq.len()
+                && matches!((seq[i - 1], seq[i]), (Some(a), Some(b)) if a != b && next(a, ascending) == b);
+            if !continues {
+                if i - start >= 3 {
+                    runs.push(StraightRun {
+                        first: start as u32 + 1,
+                        last: i as u32,
+                        ascending,
+                    });
+                }
+                start = i;
+            }
+        }
+    }
+    runs.sort_by_key(|r| r.first);
+    runs
+}
+
+fn advancing(seq: &[Option<u32>], runs: &[StraightRun], count: u32) -> Vec<Advancing> {
+    if count == 0 {
+        return vec![];
+    }
+    let ascending: Vec<_> = runs.iter().filter(|r| r.ascending).collect();
+    let start = |r: &StraightRun| seq[r.first as usize - 1].unwrap_or(0);
+    let mut found = vec![];
+    let mut i = 0;
+    while i < ascending.len() {
+        let first = ascending[i];
+        let run_length = first.last - first.first + 1;
+        let mut j = i;
+        let mut step = None;
+        while let Some(&next) = ascending.get(j + 1) {
+            let prev = ascending[j];
+            let this_step = (start(next) + count - start(prev)) % count;
+            let fits = next.first == prev.last + 1
+                && next.last - next.first + 1 == run_length
+                && this_step != 0
+                && step.is_none_or(|s| s == this_step);
+            if !fits {
+                break;
+            }
+            step = Some(this_step);
+            j += 1;
+        }
+        if let Some(step) = step {
+            found.push(Advancing {
+                first: first.first,
+                last: ascending[j].last,
+                run_length,
+                step,
+            });
+        }
+        i = j + 1;
+    }
+    found
+}
+
+// `count` is the number of shafts or treadles, raised to cover any the threads use beyond it.
+fn patterns(seq: Vec<Option<u32>>, count: u32) -> Patterns {
+    let count = seq.iter().flatten().fold(count, |count, &v| count.max(v));
+    let straight_runs = straight_runs(&seq, count);
+    Patterns {
+        mirrors: mirrors(&seq),
+        advancing: advancing(&seq, &straight_runs, count),
+        straight_runs,
+    }
+}
+
+impl Wif {
+    /// Mirrors, straight runs and advancing sequences in the threading, by end number.
+    pub fn threading_patterns(&self) -> Patterns {
+        let ends = self.width().unwrap_or(0);
+        let seq = singles(self.threading.as_ref(), ends, Warp, |s| s.0);
+        patterns(seq, self.shafts().unwrap_or(0))
+    }
+
+    /// Mirrors, straight runs and advancing sequences in the treadling, by pick number.
+    pub fn treadling_patterns(&self) -> Patterns {
+        let picks = self.height().unwrap_or(0);
+        let seq = singles(self.treadling.as_ref(), picks, Weft, |t| t.0);
+        patterns(seq, self.treadles().unwrap_or(0))
+    }
+}
@@ -0,0 +1,577 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    loom::{LoomProfile, LoomType},
+    Shaft, Treadle, Warp, Weft,
+};
+
+use super::{
+    check_reed_fit, find_treadling_errors, find_unweavable, lift_sequence, lint_for_loom,
+    Conversion, LoomLint, Sleying, TreadlingError, Unweavable,
+};
+
+fn sample() -> crate::Wif {
+    crate::parse(include_str!("../sample.wif")).unwrap()
+}
+
+// A draft threading one shaft per end and lifting the given shafts on each pick, with
+// as many shafts as it uses.
+fn woven<L>(
+    threading: impl IntoIterator<Item = u32>,
+    liftplan: impl IntoIterator<Item = L>,
+) -> crate::Wif
+where
+    L: IntoIterator<Item = u32>,
+{
+    crate::WifBuilder::new()
+        .threading_from(threading)
+        .liftplan(liftplan)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn treadling_errors_are_located() {
+    let wif = sample();
+    let draft = lift_sequence(&wif).unwrap();
+    assert!(find_treadling_errors(&wif, &draft[..40])
+        .unwrap()
+        .is_empty());
+
+    let mut woven: Vec<BTreeSet<Shaft>> = draft[..40].to_vec();
+    let wrong: BTreeSet<Shaft> = [Shaft(1)].into();
+    woven[29] = wrong.clone();
+    woven.insert(12, woven[11].clone());
+    woven.remove(4);
+    let errors = find_treadling_errors(&wif, &woven).unwrap();
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert_eq!(errors[0], TreadlingError::Skipped { pick: Weft(5) });
+    assert!(matches!(errors[1], TreadlingError::Duplicated { .. }));
+    assert_eq!(
+        errors[2],
+        TreadlingError::Mistreadled {
+            pick: Weft(30),
+            record_index: 29,
+            expected: draft[29].clone(),
+            actual: wrong,
+        }
+    );
+}
+
+#[test]
+fn reed_fit_reports_width_and_sleyings() {
+    let mut wif = sample();
+    wif.warp_spacing = None;
+    let warp = wif.warp.as_mut().unwrap();
+    warp.threads = 400;
+    warp.units = Some(crate::Units::Inches);
+    warp.spacing = Some(0.1);
+    let loom = crate::loom::LoomProfile {
+        max_width: 36.,
+        reeds: vec![8, 10, 12, 5],
+        ..Default::default()
+    };
+    let fit = check_reed_fit(&wif, &loom).unwrap();
+    assert!((fit.weaving_width - 40.).abs() < 1e-9);
+    let zoomed = {
+        let mut wif = wif.clone();
+        wif.warp.as_mut().unwrap().spacing_zoom = Some(2);
+        check_reed_fit(&wif, &loom).unwrap()
+    };
+    assert!((zoomed.weaving_width - 80.).abs() < 1e-9);
+    assert!((fit.sett - 10.).abs() < 1e-9);
+    assert!(!fit.fits);
+    assert_eq!(
+        fit.sleyings,
+        vec![
+            Sleying {
+                dents_per_inch: 10,
+                ends_per_dent: 1
+            },
+            Sleying {
+                dents_per_inch: 5,
+                ends_per_dent: 2
+            },
+        ]
+    );
+}
+
+#[test]
+fn countermarch_lint_flags_multi_treadle_picks() {
+    let mut wif = sample();
+    let treadles: std::collections::BTreeSet<_> = [Treadle(1), Treadle(2)].into();
+    wif.treadling
+        .as_mut()
+        .unwrap()
+        .insert(Weft(3), treadles.clone());
+    wif.liftplan = None;
+    wif.build_or_validate_liftplan().unwrap();
+    let jack = LoomProfile::default();
+    assert!(lint_for_loom(&wif, &jack).is_empty());
+
+    let countermarch = LoomProfile {
+        loom_type: LoomType::Countermarch,
+        ..Default::default()
+    };
+    let lints = lint_for_loom(&wif, &countermarch);
+    assert_eq!(
+        lints[0],
+        LoomLint::MultiTreadlePick {
+            pick: Weft(3),
+            treadles
+        }
+    );
+    let Some(LoomLint::SuggestConversion {
+        conversion: Conversion::ExpandSkeletonTieup,
+        treadles_needed,
+    }) = lints.last().cloned()
+    else {
+        panic!("{lints:?}");
+    };
+
+    let liftplan = wif.liftplan.clone();
+    assert_eq!(wif.expand_skeleton_tieup(), treadles_needed);
+    assert!(lint_for_loom(&wif, &countermarch).is_empty());
+    wif.build_or_validate_liftplan().unwrap();
+    assert_eq!(wif.liftplan, liftplan);
+}
+
+#[test]
+fn unweavable_threads_are_located() {
+    let mut wif = sample();
+    assert_eq!(find_unweavable(&wif), vec![]);
+
+    let threaded: BTreeSet<Shaft> = wif
+        .threading
+        .iter()
+        .flat_map(|t| t.values().flatten())
+        .copied()
+        .collect();
+    let liftplan = wif.liftplan.as_mut().unwrap();
+    liftplan.insert(Weft(2), BTreeSet::new());
+    liftplan.insert(Weft(5), threaded);
+    wif.threading.as_mut().unwrap().remove(&Warp(7));
+    assert_eq!(
+        find_unweavable(&wif),
+        vec![
+            Unweavable::EmptyLift { pick: Weft(2) },
+            Unweavable::FullLift { pick: Weft(5) },
+            Unweavable::Unthreaded { end: Warp(7) },
+        ]
+    );
+
+    wif.liftplan = None;
+    assert_eq!(
+        find_unweavable(&wif),
+        vec![Unweavable::Unthreaded { end: Warp(7) }]
+    );
+}
+
+#[test]
+fn dead_ends_are_located() {
+    use crate::WifBuilder;
+    // Shaft 3 never rises and shaft 4 always does.
+    let wif = WifBuilder::new()
+        .threading_from([1, 2, 3, 1, 2, 4, 1, 2])
+        .liftplan([vec![1, 4], vec![2, 4], vec![1, 4], vec![2, 4]])
+        .build()
+        .unwrap();
+    assert_eq!(
+        find_unweavable(&wif),
+        vec![
+            Unweavable::NeverLifted { end: Warp(3) },
+            Unweavable::AlwaysLifted { end: Warp(6) },
+        ]
+    );
+
+    // An end on a dead shaft is still woven if it's also on a live one.
+    let mut wif = wif;
+    wif.threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(3), [Shaft(1), Shaft(3)].into());
+    assert_eq!(
+        find_unweavable(&wif),
+        vec![Unweavable::AlwaysLifted { end: Warp(6) }]
+    );
+
+    // On a sinking shed the liftplan lists the shafts that go down, so shaft 3 always rises
+    // and shaft 4 never does.
+    let sinking = WifBuilder::new()
+        .threading_from([1, 2, 3, 1, 2, 4, 1, 2])
+        .liftplan([vec![1, 4], vec![2, 4], vec![1, 4], vec![2, 4]])
+        .rising_shed(false)
+        .build()
+        .unwrap();
+    assert_eq!(
+        find_unweavable(&sinking),
+        vec![
+            Unweavable::AlwaysLifted { end: Warp(3) },
+            Unweavable::NeverLifted { end: Warp(6) },
+        ]
+    );
+}
+
+#[test]
+fn float_report_finds_the_longest_floats() {
+    use super::Float;
+    use crate::bundle::longest_floats;
+    let wif = sample();
+    let report = wif.float_report().unwrap();
+    let (warp, weft) = longest_floats(&wif.to_drawdown_matrix());
+    assert_eq!(report.face.warp.longest, warp);
+    assert_eq!(report.face.weft.longest, weft);
+    assert!(report.longest() >= warp.max(weft));
+
+    let drawdown = wif.drawdown().unwrap();
+    let cells = (drawdown.ends() * drawdown.picks()) as usize;
+    for (side, warp_on_top) in [(&report.face, true), (&report.back, false)] {
+        // On each face, every cell is part of one warp float or one weft float.
+        let covered: usize = [&side.warp, &side.weft]
+            .iter()
+            .map(|stats| {
+                stats
+                    .lengths
+                    .iter()
+                    .map(|(&l, &n)| l as usize * n)
+                    .sum::<usize>()
+            })
+            .sum();
+        assert_eq!(covered, cells);
+        for &Float { end, pick, length } in &side.warp.worst {
+            assert_eq!(length, side.warp.longest);
+            for p in pick.0..pick.0 + length {
+                assert_eq!(drawdown.row(p).unwrap()[end.0 as usize - 1], warp_on_top);
+            }
+        }
+    }
+}
+
+#[test]
+fn balance_of_plain_weave_and_warp_faced_twill() {
+    let plain = woven([1, 2].repeat(4), [[1], [2]].repeat(4))
+        .balance()
+        .unwrap();
+    assert_eq!(plain.face.overall, 0.5);
+    assert!(plain
+        .face
+        .ends
+        .iter()
+        .chain(&plain.face.picks)
+        .all(|&f| f == 0.5));
+
+    // A 3/1 twill shows the warp on three picks in four.
+    let lifts = [[1, 2, 3], [2, 3, 4], [3, 4, 1], [4, 1, 2]];
+    let twill = woven([1, 2, 3, 4].repeat(2), lifts.repeat(2))
+        .balance()
+        .unwrap();
+    assert_eq!(twill.face.overall, 0.75);
+    assert_eq!(twill.back.overall, 0.25);
+    assert_eq!(twill.face.ends, vec![0.75; 8]);
+    assert_eq!(twill.back.picks, vec![0.25; 8]);
+
+    let sample = sample().balance().unwrap();
+    let ends = sample.face.ends.len() as f64;
+    let mean = sample.face.ends.iter().sum::<f64>() / ends;
+    assert!((mean - sample.face.overall).abs() < 1e-9);
+    assert!((sample.face.overall + sample.back.overall - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn threading_patterns_find_points_runs_and_advances() {
+    use super::{Advancing, Mirror, StraightRun};
+    let threaded = |shafts: &[u32]| woven(shafts.iter().copied(), [[]; 0]);
+    let point = threaded(&[1, 2, 3, 4, 3, 2, 1, 2, 3, 4]).threading_patterns();
+    assert_eq!(
+        point.mirrors,
+        [Mirror { first: 1, last: 7 }, Mirror { first: 4, last: 10 }]
+    );
+    assert_eq!(point.mirrors[0].axis(), 4.);
+    let run = |first, last, ascending| StraightRun {
+        first,
+        last,
+        ascending,
+    };
+    assert_eq!(
+        point.straight_runs,
+        [run(1, 4, true), run(4, 7, false), run(7, 10, true)]
+    );
+    assert!(point.advancing.is_empty());
+
+    let advancing = threaded(&[1, 2, 3, 4, 2, 3, 4, 5, 3, 4, 5, 6]).threading_patterns();
+    assert_eq!(
+        advancing.advancing,
+        [Advancing {
+            first: 1,
+            last: 12,
+            run_length: 4,
+            step: 1,
+        }]
+    );
+    assert!(advancing.mirrors.is_empty());
+
+    // A straight draw wraps from the last shaft back to the first.
+    let straight = threaded(&[1, 2, 3, 4, 1, 2, 3, 4]).threading_patterns();
+    assert_eq!(straight.straight_runs, [run(1, 8, true)]);
+
+    // Without [WEAVING], the shafts the threading uses are counted instead.
+    let mut unwoven = threaded(&[1, 2, 3, 4, 4, 4, 4]);
+    unwoven.weaving = None;
+    let patterns = unwoven.threading_patterns();
+    assert_eq!(patterns.straight_runs, [run(1, 4, true)]);
+    assert!(patterns.mirrors.is_empty());
+    let flat = threaded(&[1; 6]).threading_patterns();
+    assert_eq!(flat, Default::default());
+
+    // An end on two shafts can't be the turning point.
+    let mut broken = threaded(&[1, 2, 3, 2, 1]);
+    broken
+        .threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(3), [Shaft(3), Shaft(4)].into());
+    assert!(broken.threading_patterns().mirrors.is_empty());
+}
+
+#[test]
+fn shaft_load_counts_lifted_ends() {
+    use crate::WifBuilder;
+    let wif = WifBuilder::new()
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4, 1, 2])
+        .liftplan([vec![1], vec![1, 2, 3], vec![], vec![2, 4]])
+        .build()
+        .unwrap();
+    let load = wif.shaft_load();
+    assert_eq!(load.ends, 10);
+    let lifted: Vec<_> = load.picks.iter().map(|p| p.ends_lifted).collect();
+    assert_eq!(lifted, [3, 8, 0, 5]);
+    assert_eq!(load.picks[1].fraction, 0.8);
+    assert_eq!(load.heaviest, Some(Weft(2)));
+    assert_eq!(load.mean_fraction, 0.4);
+    let heavy: Vec<_> = load.heavier_than(0.5).map(|p| p.pick).collect();
+    assert_eq!(heavy, [Weft(2)]);
+
+    // The same cloth on a sinking shed lists the shafts that stay down
+    let sinking = WifBuilder::new()
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4, 1, 2])
+        .liftplan([vec![2, 3, 4], vec![4], vec![1, 2, 3, 4], vec![1, 3]])
+        .rising_shed(false)
+        .build()
+        .unwrap();
+    assert_eq!(sinking.shaft_load(), load);
+}
+
+#[test]
+fn selvedge_catch_on_plain_weave_and_twill() {
+    use super::{Edge, SelvedgeMiss};
+    // Plain weave alternates every end, so every turn is caught.
+    let plain = woven([1, 2, 1, 2], [[1], [2], [1], [2]])
+        .selvedge_catch()
+        .unwrap();
+    assert_eq!(plain.clean_start(), Some(Edge::FirstEnd));
+    assert!(!plain.needs_floating_selvedges());
+
+    // In a 2/2 twill each end stays up for two picks, so only one starting side works.
+    let twill = woven([1, 2, 3, 4], [[1, 2], [2, 3], [3, 4], [4, 1], [1, 2]])
+        .selvedge_catch()
+        .unwrap();
+    let picks: Vec<_> = twill.from_first_end.iter().map(|m| m.pick).collect();
+    assert_eq!(picks, [Weft(1), Weft(2), Weft(3), Weft(4)]);
+    assert_eq!(
+        twill.from_first_end[0],
+        SelvedgeMiss {
+            pick: Weft(1),
+            edge: Edge::LastEnd
+        }
+    );
+    assert_eq!(twill.clean_start(), Some(Edge::LastEnd));
+
+    // Edge ends that never rise can't catch the weft from either side.
+    let stuck = woven([1, 2, 3, 4], [[2], [3], [2], [3]])
+        .selvedge_catch()
+        .unwrap();
+    assert_eq!(stuck.from_last_end.len(), 3);
+    assert!(stuck.needs_floating_selvedges());
+}
+
+#[test]
+fn firmness_of_plain_weave_and_twill() {
+    let plain = woven([1, 2, 1, 2], [[1], [2], [1], [2]])
+        .firmness()
+        .unwrap();
+    assert_eq!(plain.float_index, 1.);
+    assert_eq!(plain.factor(), 1.);
+    // Three changes down each of 4 ends and along each of 4 picks.
+    assert_eq!(plain.interlacements, 24);
+    assert_eq!(plain.per_square_inch, None);
+
+    let twill = woven([1, 2, 3, 4], [[1, 2], [2, 3], [3, 4], [4, 1]])
+        .firmness()
+        .unwrap();
+    assert!(twill.float_index > plain.float_index);
+    assert!(twill.factor() < plain.factor());
+    assert!(twill.interlacements < plain.interlacements);
+    assert_eq!(twill.warp_float, twill.weft_float);
+
+    let wif = sample();
+    let firmness = wif.firmness().unwrap();
+    let dimensions = wif.fabric_dimensions();
+    let area = dimensions.warp.unwrap().inches * dimensions.weft.unwrap().inches;
+    let per_square_inch = firmness.per_square_inch.unwrap();
+    assert!((per_square_inch - firmness.interlacements as f64 / area).abs() < 1e-9);
+    assert!((firmness.per_square_centimeter().unwrap() * 6.4516 - per_square_inch).abs() < 1e-9);
+}
+
+#[test]
+fn profiles_of_summer_and_winter_and_overshot() {
+    use super::{BlockStructure, ProfileBlock};
+    use crate::Shaft;
+    let threaded = |threading: &[u32]| woven(threading.iter().copied(), [[]; 0]);
+
+    let snw = threaded(&[1, 3, 2, 3, 1, 3, 2, 3, 1, 4, 2, 4])
+        .profile()
+        .unwrap();
+    assert_eq!(snw.structure, BlockStructure::SummerAndWinter);
+    assert_eq!(snw.block_count, 2);
+    assert_eq!(
+        snw.blocks[0],
+        ProfileBlock {
+            block: 1,
+            first: Warp(1),
+            last: Warp(8),
+            units: 2
+        }
+    );
+    assert_eq!(snw.to_string(), "summer and winter: A×2 B×1");
+    let draft = snw.to_wif();
+    assert_eq!(draft.width(), Some(3));
+    assert_eq!(draft.shafts(), Some(2));
+    assert_eq!(
+        draft.threading.unwrap()[&Warp(3)],
+        BTreeSet::from([Shaft(2)])
+    );
+
+    // Neighbouring blocks share the end where one turns into the next.
+    let overshot = threaded(&[1, 2, 1, 2, 3, 2, 3, 4, 3, 4]).profile().unwrap();
+    assert_eq!(overshot.structure, BlockStructure::Overshot);
+    assert_eq!(overshot.to_string(), "overshot: A×2 B×2 C×2");
+    let ends: Vec<_> = overshot.blocks.iter().map(|b| (b.first, b.last)).collect();
+    assert_eq!(
+        ends,
+        [(Warp(1), Warp(4)), (Warp(4), Warp(7)), (Warp(7), Warp(10))]
+    );
+    assert_eq!(overshot.threading(), [1, 1, 2, 2, 3, 3]);
+
+    assert_eq!(threaded(&[1, 2, 3, 4, 1, 2, 3, 4]).profile(), None);
+    // Shafts 1 and 3 of 4 skip the block between them.
+    let mut skipping = threaded(&[1, 3, 1, 3]);
+    skipping.weaving.as_mut().unwrap().shafts = 4;
+    assert_eq!(skipping.profile(), None);
+}
+
+#[test]
+fn color_and_weave_effects_are_classified() {
+    use super::ColorEffect;
+    use crate::generators::log_cabin;
+    use crate::{Color, ColorIndex, WifBuilder};
+    let (dark, light) = (ColorIndex(1), ColorIndex(2));
+    let cabin = log_cabin(&[4, 4], (dark, light)).plain_weave();
+    let result = cabin.color_and_weave().unwrap();
+    assert_eq!(result.effect, Some(ColorEffect::LogCabin));
+    assert_eq!(result.grid.len(), 8);
+    // The first block shows solid lines along pick 1 and the second, flipped, across it.
+    assert_eq!(result.grid[0][..4], [Some(dark); 4]);
+    assert_eq!(result.grid[0][4..], [Some(light), Some(dark)].repeat(2));
+
+    // Alternating every thread in plain weave only makes pinstripes.
+    let stripes = log_cabin(&[8], (dark, light)).plain_weave();
+    assert_eq!(stripes.color_and_weave().unwrap().effect, None);
+
+    let black = Color {
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    let white = Color {
+        red: 255,
+        green: 255,
+        blue: 255,
+    };
+    let shadow = WifBuilder::new()
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4])
+        .liftplan([vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 1]])
+        .warp_colors([black, white].repeat(4))
+        .weft_colors([black, white].repeat(2))
+        .build()
+        .unwrap();
+    assert_eq!(
+        shadow.color_and_weave().unwrap().effect,
+        Some(ColorEffect::ShadowWeave)
+    );
+    assert_eq!(
+        sample().color_and_weave().unwrap().grid.len() as u32,
+        sample().height().unwrap()
+    );
+}
+
+#[test]
+fn twill_angle_follows_structure_and_sett() {
+    use crate::Units;
+    let draw = || (0..16).map(|end| end % 8 + 1);
+    let regular: Vec<[u32; 2]> = (0..8).map(|i| [i + 1, (i + 1) % 8 + 1]).collect();
+    let mut wif = woven(draw(), regular.repeat(4));
+    let angle = wif.twill_angle().unwrap();
+    assert_eq!((angle.ends.abs(), angle.picks), (1, 1));
+    assert!((angle.square - 45.).abs() < 1e-9);
+    assert_eq!(angle.at_sett, None);
+
+    // Twice as many ends as picks per inch steepens the line.
+    let warp = wif.warp.as_mut().unwrap();
+    warp.units = Some(Units::Inches);
+    warp.spacing = Some(0.05);
+    let weft = wif.weft.as_mut().unwrap();
+    weft.units = Some(Units::Inches);
+    weft.spacing = Some(0.1);
+    let at_sett = wif.twill_angle().unwrap().at_sett.unwrap();
+    assert!((at_sett - 2f64.atan().to_degrees()).abs() < 1e-9);
+
+    // Each lift woven twice moves the line one end every two picks.
+    let steep: Vec<[u32; 2]> = regular.iter().flat_map(|&l| [l, l]).collect();
+    let angle = woven(draw(), steep.repeat(2)).twill_angle().unwrap();
+    assert_eq!((angle.ends.abs(), angle.picks), (1, 2));
+    assert!((angle.square - 2f64.atan().to_degrees()).abs() < 1e-9);
+
+    let lifts = [[1, 3, 5, 7], [2, 4, 6, 8]];
+    assert_eq!(woven(draw(), lifts.repeat(16)).twill_angle(), None);
+}
+
+#[test]
+fn wallpaper_groups_of_common_weaves() {
+    use super::WallpaperGroup;
+    // One repeat of a straight draw on `shafts`, twice over, lifting `lifted` in turn.
+    let wallpaper = |shafts: u32, lifted: &dyn Fn(u32) -> Vec<u32>| {
+        let draw = (0..shafts * 2).map(|end| end % shafts + 1);
+        let lifts = (0..shafts * 2).map(|pick| lifted(pick % shafts));
+        woven(draw, lifts).wallpaper().unwrap()
+    };
+
+    let plain = wallpaper(2, &|pick| vec![pick + 1]);
+    assert_eq!(plain.group, WallpaperGroup::P4m);
+    assert_eq!(plain.translations, [(2, 0), (1, 1)]);
+    assert_eq!(plain.cell, 2);
+    assert_eq!(plain.fundamental_domain, 0.25);
+
+    // Two-wide diagonals only have glides along them.
+    let twill = wallpaper(4, &|pick| vec![pick + 1, (pick + 1) % 4 + 1]);
+    assert_eq!(twill.group, WallpaperGroup::Pmg);
+    assert_eq!((twill.ends, twill.picks, twill.cell), (4, 4, 4));
+
+    let thin_twill = wallpaper(4, &|pick| vec![pick + 1]);
+    assert_eq!(thin_twill.group, WallpaperGroup::Pmm);
+
+    // The satin's points sit on a square lattice turned off the grid.
+    let satin = wallpaper(5, &|pick| vec![pick * 2 % 5 + 1]);
+    assert_eq!(satin.group, WallpaperGroup::P4);
+    assert_eq!(satin.translations, [(5, 0), (2, 1)]);
+    assert_eq!(satin.fundamental_domain, 1.25);
+}
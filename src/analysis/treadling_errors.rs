@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+
+use crate::{progress, Progress, Result, Shaft, Weft, Wif, WifError};
+
+use super::lift_sequence;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreadlingError {
+    /// A pick in the draft that has no counterpart in the woven record.
+    Skipped { pick: Weft },
+    /// A woven pick that repeats the lift of the draft pick next to it.
+    Duplicated { pick: Weft, record_index: usize },
+    /// A woven pick that matches nothing nearby in the draft.
+    Extra { record_index: usize },
+    /// A woven pick that lifted different shafts than the draft asked for.
+    Mistreadled {
+        pick: Weft,
+        record_index: usize,
+        expected: BTreeSet<Shaft>,
+        actual: BTreeSet<Shaft>,
+    },
+}
+
+// Skips and duplicates only shift the alignment locally, so a band around the diagonal
+// keeps memory linear in the length of the record.
+const MAX_DRIFT: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Step {
+    Diagonal,
+    SkipDraft,
+    ExtraWoven,
+}
+
+/// Aligns a recorded sequence of lifts against the draft's liftplan and reports the picks
+/// where the two disagree. The record may stop partway through the draft, or run past it if
+/// several repeats were woven.
+pub fn find_treadling_errors(
+    draft: &Wif,
+    woven: &[BTreeSet<Shaft>],
+) -> Result<Vec<TreadlingError>> {
+    find_treadling_errors_with_progress(draft, woven, &mut ())
+}
+
+/// Like [`find_treadling_errors`], reporting progress as the record is aligned.
+pub fn find_treadling_errors_with_progress(
+    draft: &Wif,
+    woven: &[BTreeSet<Shaft>],
+    progress: &mut dyn Progress,
+) -> Result<Vec<TreadlingError>> {
+    let sequence = lift_sequence(draft).ok_or(WifError::MissingLiftplan)?;
+    if sequence.is_empty() {
+        return Ok(woven
+            .iter()
+            .enumerate()
+            .map(|(record_index, _)| TreadlingError::Extra { record_index })
+            .collect());
+    }
+    // Tile the draft so that records covering several repeats still align.
+    let wanted = woven.len() + MAX_DRIFT + 1;
+    let expected: Vec<(Weft, &BTreeSet<Shaft>)> = sequence
+        .iter()
+        .enumerate()
+        .map(|(i, lift)| (Weft(i as u32 + 1), lift))
+        .cycle()
+        .take(wanted.max(sequence.len()))
+        .collect();
+
+    let band = 2 * MAX_DRIFT + 1;
+    let n = woven.len();
+    let m = expected.len();
+    // Cell (i, d) holds the best cost of aligning woven[..i] with expected[..i + d - MAX_DRIFT].
+    let mut cost = vec![u32::MAX; (n + 1) * band];
+    let mut steps = vec![Step::Diagonal; (n + 1) * band];
+    let col = |i: usize, d: usize| (i + d).checked_sub(MAX_DRIFT).filter(|&j| j <= m);
+    for d in MAX_DRIFT..band {
+        if let Some(j) = col(0, d) {
+            cost[d] = j as u32;
+            steps[d] = Step::SkipDraft;
+        }
+    }
+    for i in 1..=n {
+        if i % 256 == 0 {
+            progress::report(progress, i, n)?;
+        }
+        for d in 0..band {
+            let Some(j) = col(i, d) else {
+                continue;
+            };
+            let mut best = (u32::MAX, Step::Diagonal);
+            if j > 0 {
+                let prev = cost[(i - 1) * band + d];
+                if prev != u32::MAX {
+                    let penalty = u32::from(woven[i - 1] != *expected[j - 1].1);
+                    best = best.min((prev + penalty, Step::Diagonal));
+                }
+            }
+            if d + 1 < band {
+                let prev = cost[(i - 1) * band + d + 1];
+                if prev != u32::MAX {
+                    best = best.min((prev + 1, Step::ExtraWoven));
+                }
+            }
+            if d > 0 && j > 0 {
+                let prev = cost[i * band + d - 1];
+                if prev != u32::MAX {
+                    best = best.min((prev + 1, Step::SkipDraft));
+                }
+            }
+            cost[i * band + d] = best.0;
+            steps[i * band + d] = best.1;
+        }
+    }
+
+    // The weaver may have stopped anywhere, so unwoven picks at the end are free.
+    let Some((mut d, _)) = (0..band)
+        .filter(|&d| col(n, d).is_some())
+        .map(|d| (d, cost[n * band + d]))
+        .min_by_key(|&(_, c)| c)
+    else {
+        return Err(WifError::MissingLiftplan);
+    };
+    let mut i = n;
+    let mut errors = Vec::new();
+    while let Some(j) = col(i, d).filter(|&j| i > 0 || j > 0) {
+        match steps[i * band + d] {
+            Step::Diagonal => {
+                let (pick, lift) = expected[j - 1];
+                if woven[i - 1] != *lift {
+                    errors.push(TreadlingError::Mistreadled {
+                        pick,
+                        record_index: i - 1,
+                        expected: lift.clone(),
+                        actual: woven[i - 1].clone(),
+                    });
+                }
+                i -= 1;
+            }
+            Step::ExtraWoven => {
+                let neighbours = [j.checked_sub(1), Some(j)];
+                let duplicate_of = neighbours
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|k| expected.get(k))
+                    .find(|(_, lift)| **lift == woven[i - 1]);
+                errors.push(match duplicate_of {
+                    Some(&(pick, _)) => TreadlingError::Duplicated {
+                        pick,
+                        record_index: i - 1,
+                    },
+                    None => TreadlingError::Extra {
+                        record_index: i - 1,
+                    },
+                });
+                i -= 1;
+                d += 1;
+            }
+            Step::SkipDraft => {
+                errors.push(TreadlingError::Skipped {
+                    pick: expected[j - 1].0,
+                });
+                d -= 1;
+            }
+        }
+    }
+    errors.reverse();
+    progress::report(progress, n, n)?;
+    Ok(errors)
+}
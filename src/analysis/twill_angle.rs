@@ -0,0 +1,87 @@
+use crate::summary::period;
+use crate::Wif;
+
+/// The diagonal of a twill-like structure, from [`Wif::twill_angle`]. The line moves `ends`
+/// ends every `picks` picks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwillAngle {
+    /// Positive when the line moves towards higher ends as the picks go on, negative when it
+    /// moves back towards end 1.
+    pub ends: i32,
+    pub picks: u32,
+    /// Degrees from the weft with as many picks as ends per inch: 45 for a regular twill.
+    pub square: f64,
+    /// Degrees from the weft at the draft's own sett, when the [WARP] and [WEFT] spacing
+    /// give one.
+    pub at_sett: Option<f64>,
+}
+
+// Degrees above the weft of a line climbing `picks` picks over `ends` ends.
+fn degrees(ends: i32, picks: u32, ends_per_inch: f64, picks_per_inch: f64) -> f64 {
+    let run = ends.unsigned_abs() as f64 / ends_per_inch;
+    let rise = picks as f64 / picks_per_inch;
+    rise.atan2(run).to_degrees()
+}
+
+// The shift of the smallest size taking each line of `unit` to the next, turned into the
+// signed distance it moves along the other axis, if there is one.
+fn shift(unit: &[Vec<bool>]) -> Option<i32> {
+    let (lines, len) = (unit.len(), unit.first()?.len());
+    let moves_by = |step: usize| {
+        (0..lines).all(|y| (0..len).all(|x| unit[(y + 1) % lines][(x + step) % len] == unit[y][x]))
+    };
+    (1..len)
+        .filter(|&step| moves_by(step))
+        .map(|step| {
+            if step * 2 <= len {
+                step as i32
+            } else {
+                -((len - step) as i32)
+            }
+        })
+        .min_by_key(|step| step.unsigned_abs())
+}
+
+impl Wif {
+    /// The angle of the diagonal in the drawdown, found from the smallest repeat of the
+    /// structure: either each pick is the one before moved over by some ends, or each end is
+    /// the one before moved up by some picks, for steep twills. Structures repeating on fewer
+    /// than 3 ends or picks, like plain weave, have no diagonal.
+    pub fn twill_angle(&self) -> Option<TwillAngle> {
+        let rows = self.to_drawdown_matrix();
+        let columns: Vec<Vec<bool>> = (0..rows.first().map_or(0, Vec::len))
+            .map(|end| rows.iter().map(|row| row[end]).collect())
+            .collect();
+        let (picks, ends) = (period(&rows), period(&columns));
+        if picks < 3 || ends < 3 {
+            return None;
+        }
+        let unit: Vec<Vec<bool>> = rows[..picks]
+            .iter()
+            .map(|row| row[..ends].to_vec())
+            .collect();
+        let transposed: Vec<Vec<bool>> = columns[..ends]
+            .iter()
+            .map(|column| column[..picks].to_vec())
+            .collect();
+        let (ends, picks) = match (shift(&unit), shift(&transposed)) {
+            (Some(across), Some(up)) if up.abs() < across.abs() => (up.signum(), up.unsigned_abs()),
+            (Some(across), _) => (across, 1),
+            (None, Some(up)) => (up.signum(), up.unsigned_abs()),
+            (None, None) => return None,
+        };
+        let dimensions = self.fabric_dimensions();
+        let at_sett = match (dimensions.warp, dimensions.weft) {
+            (Some(warp), Some(weft)) => {
+                Some(degrees(ends, picks, warp.per_inch(), weft.per_inch()))
+            }
+            _ => None,
+        };
+        Some(TwillAngle {
+            ends,
+            picks,
+            square: degrees(ends, picks, 1., 1.),
+            at_sett,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::BTreeSet;
+
+use crate::{liftplan_from_threading_and_treadle, Warp, Weft, Wif};
+
+use super::{lift_sequence, raised};
+
+/// A thread that would leave a fault in the cloth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unweavable {
+    /// A pick that lifts none of the threaded shafts, so no shed opens.
+    EmptyLift { pick: Weft },
+    /// A pick that lifts every threaded shaft, so no shed opens.
+    FullLift { pick: Weft },
+    /// An end not threaded on any shaft, which won't be woven in.
+    Unthreaded { end: Warp },
+    /// An end no pick lifts, which floats under the whole length of the cloth.
+    NeverLifted { end: Warp },
+    /// An end every pick lifts, which floats over the whole length of the cloth.
+    AlwaysLifted { end: Warp },
+}
+
+/// Finds the picks that open no shed, then the ends threaded on no shaft or never
+/// interlacing, in thread order. Sheds are judged against the shafts the threading uses, so
+/// lifting all of those counts as a full lift even if the loom has more. Without a liftplan,
+/// the lifts come from the treadling and tieup; with no lifts at all, only unthreaded ends
+/// are reported. On a sinking shed, each pick lifts the shafts its lift doesn't list.
+pub fn find_unweavable(wif: &Wif) -> Vec<Unweavable> {
+    let threaded: BTreeSet<_> = wif
+        .threading
+        .iter()
+        .flat_map(|t| t.values().flatten())
+        .copied()
+        .collect();
+    let lifts = lift_sequence(wif).or_else(|| {
+        let built =
+            liftplan_from_threading_and_treadle(wif.treadling.as_ref(), wif.tieup.as_ref())?;
+        let picks = built.keys().next_back().map_or(0, |w| w.0);
+        Some(
+            (1..=picks.max(wif.height().unwrap_or(0)))
+                .map(|pick| built.get(&Weft(pick)).cloned().unwrap_or_default())
+                .collect(),
+        )
+    });
+    let lifts = lifts.map_or_else(Vec::new, |lifts| raised(wif, lifts));
+    let mut found: Vec<_> = (1..)
+        .map(Weft)
+        .zip(&lifts)
+        .filter_map(|(pick, lift)| {
+            let raised = threaded.intersection(lift).count();
+            if raised == 0 {
+                Some(Unweavable::EmptyLift { pick })
+            } else if raised == threaded.len() {
+                Some(Unweavable::FullLift { pick })
+            } else {
+                None
+            }
+        })
+        .collect();
+    found.extend(wif.warp_ends().filter_map(|end| {
+        let index = end.index;
+        if end.shafts.is_empty() {
+            return Some(Unweavable::Unthreaded { end: index });
+        }
+        let lifted = lifts
+            .iter()
+            .filter(|lift| !lift.is_disjoint(&end.shafts))
+            .count();
+        match lifted {
+            _ if lifts.is_empty() => None,
+            0 => Some(Unweavable::NeverLifted { end: index }),
+            n if n == lifts.len() => Some(Unweavable::AlwaysLifted { end: index }),
+            _ => None,
+        }
+    }));
+    found
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+
+use crate::summary::period;
+use crate::Wif;
+
+/// The plane symmetry groups a pattern on a square grid can have. Hexagonal and trigonal
+/// groups need a lattice the grid can't hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WallpaperGroup {
+    P1,
+    P2,
+    Pm,
+    Pg,
+    Cm,
+    Pmm,
+    Pmg,
+    Pgg,
+    Cmm,
+    P4,
+    P4m,
+    P4g,
+}
+
+impl WallpaperGroup {
+    /// The short international notation, e.g. `pmm`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WallpaperGroup::P1 => "p1",
+            WallpaperGroup::P2 => "p2",
+            WallpaperGroup::Pm => "pm",
+            WallpaperGroup::Pg => "pg",
+            WallpaperGroup::Cm => "cm",
+            WallpaperGroup::Pmm => "pmm",
+            WallpaperGroup::Pmg => "pmg",
+            WallpaperGroup::Pgg => "pgg",
+            WallpaperGroup::Cmm => "cmm",
+            WallpaperGroup::P4 => "p4",
+            WallpaperGroup::P4m => "p4m",
+            WallpaperGroup::P4g => "p4g",
+        }
+    }
+}
+
+impl fmt::Display for WallpaperGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The symmetry of the drawdown as an endless pattern, from [`Wif::wallpaper`]. Cells are
+/// taken as square, as the drawdown is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wallpaper {
+    pub group: WallpaperGroup,
+    /// The smallest rectangle that repeats, in ends and picks.
+    pub ends: u32,
+    pub picks: u32,
+    /// Two shifts, in ends and picks, that together make every shift taking the pattern onto
+    /// itself: the first along the picks, the second the shortest one to a later pick.
+    pub translations: [(u32, u32); 2],
+    /// Cells in the smallest repeating unit, which may be a parallelogram.
+    pub cell: u32,
+    /// Cells in the smallest piece that the symmetries tile the plane with: the cell divided
+    /// among its rotations and reflections.
+    pub fundamental_domain: f64,
+}
+
+// The eight symmetries of the square grid, as (x, y) -> (a x + b y, c x + d y).
+const IDENTITY: [i64; 4] = [1, 0, 0, 1];
+const QUARTER_TURN: [i64; 4] = [0, -1, 1, 0];
+const HALF_TURN: [i64; 4] = [-1, 0, 0, -1];
+const THREE_QUARTER_TURN: [i64; 4] = [0, 1, -1, 0];
+// Reflections, each with its axis.
+const ACROSS_ENDS: ([i64; 4], (i64, i64)) = ([-1, 0, 0, 1], (0, 1));
+const ACROSS_PICKS: ([i64; 4], (i64, i64)) = ([1, 0, 0, -1], (1, 0));
+const DIAGONAL: ([i64; 4], (i64, i64)) = ([0, 1, 1, 0], (1, 1));
+const ANTIDIAGONAL: ([i64; 4], (i64, i64)) = ([0, -1, -1, 0], (1, -1));
+
+fn apply(g: [i64; 4], (x, y): (i64, i64)) -> (i64, i64) {
+    (g[0] * x + g[1] * y, g[2] * x + g[3] * y)
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// One repeat of the drawdown and the shifts that map it onto itself.
+struct Pattern {
+    unit: Vec<Vec<bool>>,
+    // The lattice basis (a, 0), (b, c), with 0 <= b < a.
+    a: i64,
+    b: i64,
+    c: i64,
+}
+
+impl Pattern {
+    fn new(unit: Vec<Vec<bool>>) -> Pattern {
+        let (width, height) = (unit[0].len() as i64, unit.len() as i64);
+        let mut pattern = Pattern {
+            unit,
+            a: width,
+            b: 0,
+            c: height,
+        };
+        let mut shifts = (1..height).flat_map(|c| (0..width).map(move |b| (b, c)));
+        if let Some((b, c)) = shifts.find(|&t| pattern.moves_onto_itself(IDENTITY, t)) {
+            (pattern.b, pattern.c) = (b, c);
+        }
+        pattern
+    }
+
+    fn at(&self, (x, y): (i64, i64)) -> bool {
+        let row = &self.unit[y.rem_euclid(self.unit.len() as i64) as usize];
+        row[x.rem_euclid(row.len() as i64) as usize]
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let width = self.unit[0].len() as i64;
+        (0..self.unit.len() as i64).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    fn contains(&self, (x, y): (i64, i64)) -> bool {
+        y.rem_euclid(self.c) == 0 && (x - y / self.c * self.b).rem_euclid(self.a) == 0
+    }
+
+    fn moves_onto_itself(&self, g: [i64; 4], (tx, ty): (i64, i64)) -> bool {
+        self.cells().all(|p| {
+            let (x, y) = apply(g, p);
+            self.at((x + tx, y + ty)) == self.at(p)
+        })
+    }
+
+    // A shift that makes `g` a symmetry, if one does. `g` must map the lattice onto itself
+    // for that, and then a shift within one repeat is enough.
+    fn symmetry(&self, g: [i64; 4]) -> Option<(i64, i64)> {
+        let basis = [(self.a, 0), (self.b, self.c)];
+        if !basis.iter().all(|&l| self.contains(apply(g, l))) {
+            return None;
+        }
+        self.cells().find(|&t| self.moves_onto_itself(g, t))
+    }
+
+    // Whether a reflection shifted by `t` can be made a true mirror by adding a lattice
+    // shift, rather than only a glide: its shift along the axis must be one the lattice has.
+    fn is_mirror(&self, (axis_x, axis_y): (i64, i64), (tx, ty): (i64, i64)) -> bool {
+        let along = |(x, y): (i64, i64)| x * axis_x + y * axis_y;
+        let step = gcd(along((self.a, 0)), along((self.b, self.c)));
+        step != 0 && along((tx, ty)).rem_euclid(step) == 0
+    }
+
+    // Whether the lattice has shifts half way between those along and across `axis`.
+    fn is_centered(&self, (axis_x, axis_y): (i64, i64)) -> bool {
+        let limit = self.a * self.c * 2;
+        let shortest = |(x, y): (i64, i64)| {
+            (1..=limit)
+                .find(|&k| self.contains((k * x, k * y)))
+                .unwrap_or(limit)
+        };
+        let along = shortest((axis_x, axis_y));
+        let across = shortest((-axis_y, axis_x));
+        let area = (axis_x * axis_x + axis_y * axis_y) * along * across;
+        area != self.a * self.c
+    }
+}
+
+impl Wif {
+    /// The wallpaper group of the drawdown, found from its smallest repeat, along with the
+    /// lattice of shifts and the size of the fundamental domain. `None` for an empty
+    /// drawdown.
+    pub fn wallpaper(&self) -> Option<Wallpaper> {
+        let rows = self.to_drawdown_matrix();
+        let columns: Vec<Vec<bool>> = (0..rows.first().map_or(0, Vec::len))
+            .map(|end| rows.iter().map(|row| row[end]).collect())
+            .collect();
+        if columns.is_empty() {
+            return None;
+        }
+        let (picks, ends) = (period(&rows), period(&columns));
+        let unit = rows[..picks]
+            .iter()
+            .map(|row| row[..ends].to_vec())
+            .collect();
+        let pattern = Pattern::new(unit);
+
+        let turns = [QUARTER_TURN, HALF_TURN, THREE_QUARTER_TURN];
+        let rotations = turns.map(|g| pattern.symmetry(g).is_some());
+        let reflections: Vec<((i64, i64), bool)> =
+            [ACROSS_ENDS, ACROSS_PICKS, DIAGONAL, ANTIDIAGONAL]
+                .into_iter()
+                .filter_map(|(g, axis)| {
+                    let t = pattern.symmetry(g)?;
+                    Some((axis, pattern.is_mirror(axis, t)))
+                })
+                .collect();
+        let mirrors = reflections.iter().filter(|(_, mirror)| *mirror).count();
+        let group = match (rotations, reflections.len()) {
+            ([true, ..], 0) => WallpaperGroup::P4,
+            ([true, ..], _) => {
+                let axial = reflections
+                    .iter()
+                    .any(|&(axis, m)| m && axis.0 * axis.1 == 0);
+                let diagonal = reflections
+                    .iter()
+                    .any(|&(axis, m)| m && axis.0 * axis.1 != 0);
+                if axial && diagonal {
+                    WallpaperGroup::P4m
+                } else {
+                    WallpaperGroup::P4g
+                }
+            }
+            ([_, true, _], 0) => WallpaperGroup::P2,
+            ([_, true, _], _) => match mirrors {
+                2 if pattern.is_centered(reflections[0].0) => WallpaperGroup::Cmm,
+                2 => WallpaperGroup::Pmm,
+                1 => WallpaperGroup::Pmg,
+                _ => WallpaperGroup::Pgg,
+            },
+            (_, 0) => WallpaperGroup::P1,
+            _ => match reflections[0] {
+                (axis, true) if pattern.is_centered(axis) => WallpaperGroup::Cm,
+                (_, true) => WallpaperGroup::Pm,
+                (_, false) => WallpaperGroup::Pg,
+            },
+        };
+
+        let order = 1 + rotations.iter().filter(|&&r| r).count() + reflections.len();
+        let cell = (pattern.a * pattern.c) as u32;
+        Some(Wallpaper {
+            group,
+            ends: ends as u32,
+            picks: picks as u32,
+            translations: [(pattern.a as u32, 0), (pattern.b as u32, pattern.c as u32)],
+            cell,
+            fundamental_domain: cell as f64 / order as f64,
+        })
+    }
+}
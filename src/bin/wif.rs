@@ -0,0 +1,239 @@
+use std::{io, process::ExitCode};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+use wif::{Shaft, Treadle, Warp, WarpOrWeft, Weft, Wif};
+
+const USAGE: &str = "usage: wif view <file.wif>";
+
+struct Viewer {
+    wif: Wif,
+    path: String,
+    // Zero-based index of the first visible warp end and weft pick
+    x: u32,
+    y: u32,
+    show_threading: bool,
+    show_treadling: bool,
+}
+
+fn rgb(color: Option<[u8; 3]>) -> Color {
+    match color {
+        Some([r, g, b]) => Color::Rgb(r, g, b),
+        None => Color::Reset,
+    }
+}
+
+impl Viewer {
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let width = self.wif.width().unwrap_or(0);
+            let height = self.wif.height().unwrap_or(0);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left | KeyCode::Char('h') => self.x = self.x.saturating_sub(1),
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.x = (self.x + 1).min(width.saturating_sub(1))
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.y = self.y.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.y = (self.y + 1).min(height.saturating_sub(1))
+                }
+                KeyCode::PageUp => self.y = self.y.saturating_sub(20),
+                KeyCode::PageDown => self.y = (self.y + 20).min(height.saturating_sub(1)),
+                KeyCode::Home => (self.x, self.y) = (0, 0),
+                KeyCode::Char('t') => self.show_threading = !self.show_threading,
+                KeyCode::Char('r') => self.show_treadling = !self.show_treadling,
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let shafts = self.wif.shafts().unwrap_or(0) as u16;
+        let treadles = self.wif.treadles().unwrap_or(0) as u16;
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let threading_height = if self.show_threading { shafts + 2 } else { 0 };
+        let [threading_area, body] =
+            Layout::vertical([Constraint::Length(threading_height), Constraint::Min(0)])
+                .areas(main);
+        let treadling_width = if self.show_treadling {
+            treadles * 2 + 2
+        } else {
+            0
+        };
+        let [drawdown_area, treadling_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(treadling_width)])
+                .areas(body);
+
+        if self.show_threading {
+            self.draw_threading(frame, threading_area, shafts as u32);
+        }
+        if self.show_treadling {
+            self.draw_treadling(frame, treadling_area, treadles as u32);
+        }
+        self.draw_drawdown(frame, drawdown_area);
+        self.draw_status(frame, status);
+    }
+
+    fn visible_ends(&self, inner: Rect) -> impl Iterator<Item = u32> {
+        let width = self.wif.width().unwrap_or(0);
+        (self.x + 1..=width).take(inner.width as usize / 2)
+    }
+
+    fn visible_picks(&self, inner: Rect) -> impl Iterator<Item = u32> {
+        let height = self.wif.height().unwrap_or(0);
+        (self.y + 1..=height).take(inner.height as usize)
+    }
+
+    fn draw_drawdown(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Drawdown ");
+        let inner = block.inner(area);
+        let lines: Vec<Line> = self
+            .visible_picks(inner)
+            .map(|weft| {
+                let spans: Vec<Span> = self
+                    .visible_ends(inner)
+                    .map(|warp| {
+                        let color = match self.wif.warp_or_weft(warp, weft) {
+                            Some(WarpOrWeft::Warp) => self.wif.warp_color_u8(warp),
+                            Some(WarpOrWeft::Weft) => self.wif.weft_color_u8(weft),
+                            None => None,
+                        };
+                        Span::styled("  ", Style::default().bg(rgb(color)))
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_threading(&self, frame: &mut Frame, area: Rect, shafts: u32) {
+        let block = Block::bordered().title(" Threading ");
+        let inner = block.inner(area);
+        let threading = self.wif.threading.as_ref();
+        let lines: Vec<Line> = (1..=shafts)
+            .rev()
+            .map(|shaft| {
+                let spans: Vec<Span> = self
+                    .visible_ends(inner)
+                    .map(|warp| {
+                        let threaded = threading
+                            .and_then(|t| t.get(&Warp::from(warp)))
+                            .is_some_and(|s| s.contains(&Shaft::from(shaft)));
+                        if threaded {
+                            Span::styled(
+                                "■ ",
+                                Style::default().fg(rgb(self.wif.warp_color_u8(warp))),
+                            )
+                        } else {
+                            Span::raw("· ")
+                        }
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_treadling(&self, frame: &mut Frame, area: Rect, treadles: u32) {
+        let block = Block::bordered().title(" Treadling ");
+        let inner = block.inner(area);
+        let treadling = self.wif.treadling.as_ref();
+        let lines: Vec<Line> = self
+            .visible_picks(inner)
+            .map(|weft| {
+                let spans: Vec<Span> = (1..=treadles)
+                    .map(|treadle| {
+                        let pressed = treadling
+                            .and_then(|t| t.get(&Weft::from(weft)))
+                            .is_some_and(|t| t.contains(&Treadle(treadle)));
+                        if pressed {
+                            Span::styled(
+                                "■ ",
+                                Style::default().fg(rgb(self.wif.weft_color_u8(weft))),
+                            )
+                        } else {
+                            Span::raw("· ")
+                        }
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_status(&self, frame: &mut Frame, area: Rect) {
+        let title = self
+            .wif
+            .text
+            .as_ref()
+            .and_then(|t| t.title.clone())
+            .unwrap_or_else(|| self.path.clone());
+        let colors = self.wif.color_table.as_ref().map_or(0, |ct| ct.len());
+        let status = format!(
+            " {title} | {} ends × {} picks | {} shafts, {} treadles | {colors} colors | end {}, pick {} | ←↑↓→ scroll  t threading  r treadling  q quit",
+            self.wif.width().unwrap_or(0),
+            self.wif.height().unwrap_or(0),
+            self.wif.shafts().unwrap_or(0),
+            self.wif.treadles().unwrap_or(0),
+            self.x + 1,
+            self.y + 1,
+        );
+        let style = Style::default().add_modifier(Modifier::REVERSED);
+        frame.render_widget(Paragraph::new(status).style(style), area);
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [command, path] = &args[..] else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    if command != "view" {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+    let wif = match Wif::from_path(path) {
+        Ok(wif) => wif,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let viewer = Viewer {
+        wif,
+        path: path.clone(),
+        x: 0,
+        y: 0,
+        show_threading: true,
+        show_treadling: true,
+    };
+    let mut terminal = ratatui::init();
+    let result = viewer.run(&mut terminal);
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
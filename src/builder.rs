@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    sections, Color, Result, Shaft, Table, Text, Tieup, Treadle, Warp, Weaving, Weft, Wif, WifError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Builds a [`Wif`] from threading, tieup and treadling or liftplan, filling in the header,
+/// thread counts and CONTENTS. Shaft and treadle counts default to the highest ones used.
+#[derive(Debug, Clone, Default)]
+pub struct WifBuilder {
+    shafts: Option<u32>,
+    treadles: Option<u32>,
+    rising_shed: Option<bool>,
+    title: Option<String>,
+    source: Option<(String, String)>,
+    threading: Table<Warp, BTreeSet<Shaft>>,
+    tieup: Option<Tieup>,
+    treadling: Option<Table<Weft, BTreeSet<Treadle>>>,
+    liftplan: Option<Table<Weft, BTreeSet<Shaft>>>,
+    warp_colors: Vec<Color>,
+    weft_colors: Vec<Color>,
+}
+
+fn numbered<K: Ord, V>(values: impl IntoIterator<Item = V>, key: impl Fn(u32) -> K) -> Table<K, V> {
+    (1..).map(key).zip(values).collect()
+}
+
+fn check_range<K, T>(
+    section: &str,
+    what: &'static str,
+    table: Option<&Table<K, BTreeSet<T>>>,
+    max: u32,
+    index: impl Fn(&T) -> u32,
+) -> Result<()> {
+    let highest = table
+        .into_iter()
+        .flat_map(|t| t.values().flatten())
+        .map(index)
+        .max();
+    match highest {
+        Some(value) if value > max => Err(WifError::OutOfRange {
+            section: section.into(),
+            what,
+            value,
+            max,
+        }),
+        _ => Ok(()),
+    }
+}
+
+impl WifBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shafts(mut self, shafts: u32) -> Self {
+        self.shafts = Some(shafts);
+        self
+    }
+
+    pub fn treadles(mut self, treadles: u32) -> Self {
+        self.treadles = Some(treadles);
+        self
+    }
+
+    pub fn rising_shed(mut self, rising_shed: bool) -> Self {
+        self.rising_shed = Some(rising_shed);
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// The program recorded in the header. Defaults to this crate.
+    pub fn source_program(
+        mut self,
+        program: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        self.source = Some((program.into(), version.into()));
+        self
+    }
+
+    /// One shaft per end, starting from end 1.
+    pub fn threading_from(mut self, shafts: impl IntoIterator<Item = u32>) -> Self {
+        self.threading = numbered(shafts.into_iter().map(|s| [Shaft(s)].into()), Warp);
+        self
+    }
+
+    pub fn tieup(mut self, tieup: Tieup) -> Self {
+        self.tieup = Some(tieup);
+        self
+    }
+
+    /// One treadle per pick, starting from pick 1.
+    pub fn treadling(mut self, treadles: impl IntoIterator<Item = u32>) -> Self {
+        self.treadling = Some(numbered(
+            treadles.into_iter().map(|t| [Treadle(t)].into()),
+            Weft,
+        ));
+        self
+    }
+
+    /// The shafts lifted on each pick, starting from pick 1.
+    pub fn liftplan<I>(mut self, lifts: impl IntoIterator<Item = I>) -> Self
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        self.liftplan = Some(numbered(
+            lifts
+                .into_iter()
+                .map(|lift| lift.into_iter().map(Shaft).collect()),
+            Weft,
+        ));
+        self
+    }
+
+    /// One color per end, starting from end 1, on the 0-999 scale. Colors are added to the
+    /// color table as needed.
+    pub fn warp_colors(mut self, colors: impl IntoIterator<Item = Color>) -> Self {
+        self.warp_colors = colors.into_iter().collect();
+        self
+    }
+
+    /// One color per pick, starting from pick 1.
+    pub fn weft_colors(mut self, colors: impl IntoIterator<Item = Color>) -> Self {
+        self.weft_colors = colors.into_iter().collect();
+        self
+    }
+
+    /// Checks that every shaft and treadle used is in range and that treadling comes with a
+    /// tieup, then builds the draft and its liftplan.
+    pub fn build(self) -> Result<Wif> {
+        if self.treadling.is_some() && self.tieup.is_none() {
+            return Err(WifError::MissingSection {
+                section: sections::TIEUP.into(),
+            });
+        }
+        let highest_shaft = self
+            .threading
+            .values()
+            .chain(self.tieup.iter().flat_map(|t| t.values()))
+            .chain(self.liftplan.iter().flat_map(|l| l.values()))
+            .flatten()
+            .map(|s| s.0)
+            .max()
+            .unwrap_or(0);
+        let highest_treadle = self
+            .treadling
+            .iter()
+            .flat_map(|t| t.values().flatten())
+            .chain(self.tieup.iter().flat_map(|t| t.keys()))
+            .map(|t| t.0)
+            .max()
+            .unwrap_or(0);
+        let shafts = self.shafts.unwrap_or(highest_shaft);
+        let treadles = self.treadles.unwrap_or(highest_treadle);
+        check_range(
+            sections::THREADING,
+            "shaft",
+            Some(&self.threading),
+            shafts,
+            |s| s.0,
+        )?;
+        check_range(
+            sections::LIFTPLAN,
+            "shaft",
+            self.liftplan.as_ref(),
+            shafts,
+            |s| s.0,
+        )?;
+        check_range(sections::TIEUP, "shaft", self.tieup.as_ref(), shafts, |s| {
+            s.0
+        })?;
+        check_range(
+            sections::TREADLING,
+            "treadle",
+            self.treadling.as_ref(),
+            treadles,
+            |t| t.0,
+        )?;
+
+        let ends = (self.threading.len() as u32).max(self.warp_colors.len() as u32);
+        let picks = [
+            self.treadling.as_ref().map_or(0, |t| t.len()),
+            self.liftplan.as_ref().map_or(0, |l| l.len()),
+            self.weft_colors.len(),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0) as u32;
+        let mut wif = Wif::blank(ends, picks);
+        if let Some((program, version)) = self.source {
+            wif.wif_header.source_program = program;
+            wif.wif_header.source_version = Some(version);
+        }
+        if shafts > 0 || treadles > 0 {
+            wif.weaving = Some(Weaving {
+                shafts,
+                treadles,
+                rising_shed: self.rising_shed,
+            });
+        }
+        wif.text = self.title.map(|title| Text {
+            title: Some(title),
+            author: None,
+            address: None,
+            email: None,
+            telephone: None,
+            fax: None,
+        });
+        if !self.threading.is_empty() {
+            wif.threading = Some(self.threading);
+        }
+        wif.tieup = self.tieup;
+        wif.treadling = self.treadling;
+        wif.liftplan = self.liftplan;
+        if !self.warp_colors.is_empty() {
+            let colors = self
+                .warp_colors
+                .into_iter()
+                .map(|color| wif.find_or_add_color(color))
+                .collect::<Vec<_>>();
+            wif.warp_colors = Some(numbered(colors, Warp));
+        }
+        if !self.weft_colors.is_empty() {
+            let colors = self
+                .weft_colors
+                .into_iter()
+                .map(|color| wif.find_or_add_color(color))
+                .collect::<Vec<_>>();
+            wif.weft_colors = Some(numbered(colors, Weft));
+        }
+        wif.build_or_validate_liftplan()?;
+        Ok(wif)
+    }
+}
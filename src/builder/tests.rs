@@ -0,0 +1,65 @@
+use super::WifBuilder;
+use crate::{Color, Shaft, Tieup, TieupMatrix, Treadle, Warp, Weft, WifError};
+
+fn twill_tieup() -> Tieup {
+    Tieup::from_matrix(&[
+        vec![true, false, false, true],
+        vec![true, true, false, false],
+        vec![false, true, true, false],
+        vec![false, false, true, true],
+    ])
+}
+
+#[test]
+fn builds_twill_with_counts_and_liftplan() {
+    let black = Color {
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    let wif = WifBuilder::new()
+        .title("2/2 twill")
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4])
+        .tieup(twill_tieup())
+        .treadling([1, 2, 3, 4])
+        .warp_colors([black; 8])
+        .build()
+        .unwrap();
+    assert_eq!((wif.shafts(), wif.treadles()), (Some(4), Some(4)));
+    assert_eq!((wif.width(), wif.height()), (Some(8), Some(4)));
+    assert_eq!(
+        wif.liftplan.as_ref().unwrap()[&Weft(1)],
+        [Shaft(1), Shaft(2)].into()
+    );
+    assert_eq!(wif.warp_color(Warp(8)), Some(black));
+
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    let reread = crate::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    assert!(reread.contents.declares("TREADLING"));
+    assert_eq!(reread.treadling.unwrap()[&Weft(4)], [Treadle(4)].into());
+}
+
+#[test]
+fn rejects_out_of_range_and_missing_tieup() {
+    let err = WifBuilder::new()
+        .shafts(2)
+        .threading_from([1, 2, 3])
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        WifError::OutOfRange {
+            value: 3,
+            max: 2,
+            ..
+        }
+    ));
+
+    let err = WifBuilder::new()
+        .threading_from([1, 2])
+        .treadling([1, 2])
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, WifError::MissingSection { .. }));
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::{ColorIndex, Drawdown, Progress, Result, Warp, WarpOrWeft, Weft, Wif};
+
+#[cfg(test)]
+mod tests;
+mod zip;
+
+/// What to include in a [`DraftBundle`].
+#[derive(Debug, Clone)]
+pub struct BundleOptions {
+    /// Names for color table entries, used in the yarn report.
+    pub yarn_names: BTreeMap<ColorIndex, String>,
+    /// Largest thumbnail side, in threads. `None` leaves the thumbnail out.
+    pub thumbnail_size: Option<u32>,
+}
+
+impl Default for BundleOptions {
+    fn default() -> Self {
+        BundleOptions {
+            yarn_names: BTreeMap::new(),
+            thumbnail_size: Some(64),
+        }
+    }
+}
+
+/// Size and structure figures for a draft.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DraftStats {
+    pub shafts: Option<u32>,
+    pub treadles: Option<u32>,
+    pub ends: u32,
+    pub picks: u32,
+    /// Longest run of picks a single end floats over on the face, in threads.
+    pub longest_warp_float: u32,
+    /// Longest run of ends a single pick floats over on the face, in threads.
+    pub longest_weft_float: u32,
+    /// Share of interlacements showing warp on the face.
+    pub warp_fraction: f64,
+}
+
+/// How much of one color the draft uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct YarnUsage {
+    /// Color table index.
+    pub color: ColorIndex,
+    pub rgb: Option<[u8; 3]>,
+    pub name: Option<String>,
+    pub ends: u32,
+    pub picks: u32,
+}
+
+/// The top-left corner of the drawdown, one pixel per interlacement, rows in pick order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Thumbnail {
+    /// The thumbnail as a binary PPM image.
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        ppm.extend(self.pixels.iter().flatten());
+        ppm
+    }
+}
+
+/// A draft together with everything a gallery or archive needs to show it.
+#[derive(Debug, Clone)]
+pub struct DraftBundle {
+    /// The draft with its liftplan filled in.
+    pub draft: Wif,
+    pub stats: DraftStats,
+    pub yarns: Vec<YarnUsage>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+fn longest_run(line: impl Iterator<Item = bool>) -> u32 {
+    let (mut longest, mut run) = (0, 0);
+    for on_top in line {
+        run = if on_top { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+/// Longest warp and weft floats on the face of a drawdown laid out as in
+/// [`Wif::to_drawdown_matrix`].
+pub(crate) fn longest_floats(warp_on_top: &[Vec<bool>]) -> (u32, u32) {
+    let ends = warp_on_top.first().map_or(0, Vec::len);
+    let warp = (0..ends)
+        .map(|end| longest_run(warp_on_top.iter().map(|row| row[end])))
+        .max()
+        .unwrap_or(0);
+    let weft = warp_on_top
+        .iter()
+        .map(|row| longest_run(row.iter().map(|&warp| !warp)))
+        .max()
+        .unwrap_or(0);
+    (warp, weft)
+}
+
+fn stats(wif: &Wif, drawdown: &Drawdown) -> DraftStats {
+    let (ends, picks) = (drawdown.ends(), drawdown.picks());
+    // warp_on_top[pick][end]
+    let warp_on_top = drawdown.to_matrix();
+    let warp_cells = warp_on_top.iter().flatten().filter(|&&w| w).count();
+    let cells = ends as usize * picks as usize;
+    let (longest_warp_float, longest_weft_float) = longest_floats(&warp_on_top);
+    DraftStats {
+        shafts: wif.shafts(),
+        treadles: wif.treadles(),
+        ends,
+        picks,
+        longest_warp_float,
+        longest_weft_float,
+        warp_fraction: if cells == 0 {
+            0.
+        } else {
+            warp_cells as f64 / cells as f64
+        },
+    }
+}
+
+fn yarns(wif: &Wif, names: &BTreeMap<ColorIndex, String>) -> Vec<YarnUsage> {
+    let mut usage: BTreeMap<ColorIndex, YarnUsage> = BTreeMap::new();
+    let new = |color, rgb| YarnUsage {
+        color,
+        rgb,
+        name: names.get(&color).cloned(),
+        ends: 0,
+        picks: 0,
+    };
+    for end in (1..=wif.width().unwrap_or(0)).map(Warp) {
+        if let Some(color) = wif.warp_color_idx(end) {
+            let rgb = wif.warp_color_u8(end);
+            usage.entry(color).or_insert_with(|| new(color, rgb)).ends += 1;
+        }
+    }
+    for pick in (1..=wif.height().unwrap_or(0)).map(Weft) {
+        if let Some(color) = wif.weft_color_idx(pick) {
+            let rgb = wif.weft_color_u8(pick);
+            usage.entry(color).or_insert_with(|| new(color, rgb)).picks += 1;
+        }
+    }
+    usage.into_values().collect()
+}
+
+fn thumbnail(wif: &Wif, drawdown: &Drawdown, size: u32) -> Thumbnail {
+    let width = drawdown.ends().min(size);
+    let height = drawdown.picks().min(size);
+    let blank = [255, 255, 255];
+    let pixels = (1..=height)
+        .flat_map(|pick| {
+            (1..=width).map(move |end| match drawdown.get(end, pick) {
+                Some(WarpOrWeft::Warp) => wif.warp_color_u8(end),
+                _ => wif.weft_color_u8(pick),
+            })
+        })
+        .map(|pixel| pixel.unwrap_or(blank))
+        .collect();
+    Thumbnail {
+        width,
+        height,
+        pixels,
+    }
+}
+
+impl Wif {
+    /// Collects the draft, its stats, a yarn report and a thumbnail in one value.
+    pub fn bundle(&self, options: &BundleOptions) -> Result<DraftBundle> {
+        self.bundle_with_progress(options, &mut ())
+    }
+
+    /// Like [`bundle`](Self::bundle), reporting progress as the drawdown behind the stats
+    /// and thumbnail is worked out.
+    pub fn bundle_with_progress(
+        &self,
+        options: &BundleOptions,
+        progress: &mut dyn Progress,
+    ) -> Result<DraftBundle> {
+        let mut draft = self.clone();
+        draft.build_or_validate_liftplan()?;
+        let drawdown = draft.drawdown_with_progress(progress)?;
+        Ok(DraftBundle {
+            stats: stats(&draft, &drawdown),
+            yarns: yarns(&draft, &options.yarn_names),
+            thumbnail: options
+                .thumbnail_size
+                .map(|size| thumbnail(&draft, &drawdown, size)),
+            draft,
+        })
+    }
+}
+
+impl DraftBundle {
+    /// The bundle as a zip archive, stored without compression: the draft as `draft.wif`,
+    /// the thumbnail, if any, as `thumbnail.ppm`, and with the `json` feature the whole
+    /// bundle as `bundle.json`. Fails if `output` does, or if an entry is 4 GiB or more.
+    pub fn write_zip<W: Write>(&self, output: W) -> io::Result<W> {
+        let mut draft = vec![];
+        self.draft.write(&mut draft)?;
+        let mut files = vec![("draft.wif", draft)];
+        if let Some(thumbnail) = &self.thumbnail {
+            files.push(("thumbnail.ppm", thumbnail.to_ppm()));
+        }
+        #[cfg(feature = "json")]
+        files.push(("bundle.json", self.to_json().into_bytes()));
+        zip::write_stored(output, &files)
+    }
+}
+
+#[cfg(feature = "json")]
+impl DraftBundle {
+    /// The bundle as JSON, with the draft in the form written by [`Wif::to_json`].
+    pub fn to_json(&self) -> String {
+        #[derive(serde::Serialize)]
+        struct BundleJson<'a> {
+            draft: crate::wif::Document,
+            stats: &'a DraftStats,
+            yarns: &'a [YarnUsage],
+            #[serde(skip_serializing_if = "Option::is_none")]
+            thumbnail: Option<&'a Thumbnail>,
+        }
+
+        serde_json::to_string_pretty(&BundleJson {
+            draft: crate::wif::Document::new(&self.draft),
+            stats: &self.stats,
+            yarns: &self.yarns,
+            thumbnail: self.thumbnail.as_ref(),
+        })
+        .expect("JSON documents only contain strings, numbers and string-keyed maps")
+    }
+}
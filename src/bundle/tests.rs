@@ -0,0 +1,90 @@
+use super::BundleOptions;
+use crate::generators::log_cabin;
+use crate::ColorIndex;
+
+#[test]
+fn bundle_collects_stats_yarns_and_thumbnail() {
+    let mut wif = log_cabin(&[4, 4], (ColorIndex(1), ColorIndex(2))).plain_weave();
+    wif.find_or_add_color(crate::Color {
+        red: 0,
+        green: 0,
+        blue: 0,
+    });
+    wif.find_or_add_color(crate::Color {
+        red: 999,
+        green: 999,
+        blue: 999,
+    });
+    let options = BundleOptions {
+        yarn_names: [(ColorIndex(1), "Black".to_string())].into(),
+        thumbnail_size: Some(6),
+    };
+    let bundle = wif.bundle(&options).unwrap();
+
+    let stats = &bundle.stats;
+    assert_eq!((stats.ends, stats.picks), (8, 8));
+    assert_eq!((stats.longest_warp_float, stats.longest_weft_float), (1, 1));
+    assert_eq!(stats.warp_fraction, 0.5);
+
+    assert_eq!(bundle.yarns.len(), 2);
+    assert_eq!(bundle.yarns[0].name.as_deref(), Some("Black"));
+    assert_eq!((bundle.yarns[0].ends, bundle.yarns[0].picks), (4, 4));
+    assert_eq!(bundle.yarns[1].rgb, Some([255, 255, 255]));
+
+    let thumbnail = bundle.thumbnail.unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (6, 6));
+    // End 1 is black and raised on pick 1
+    assert_eq!(thumbnail.pixels[0], [0, 0, 0]);
+    assert_eq!(thumbnail.pixels.len(), 36);
+}
+
+#[test]
+fn bundle_reports_progress_and_can_be_cancelled() {
+    let wif = log_cabin(&[4, 4], (ColorIndex(1), ColorIndex(2))).plain_weave();
+    let mut fractions = vec![];
+    let mut record = |fraction: f64| {
+        fractions.push(fraction);
+        true
+    };
+    wif.bundle_with_progress(&BundleOptions::default(), &mut record)
+        .unwrap();
+    assert_eq!(fractions.len(), 8);
+    assert_eq!(fractions.last(), Some(&1.0));
+
+    let mut cancel = |fraction: f64| fraction < 0.5;
+    let err = wif
+        .bundle_with_progress(&BundleOptions::default(), &mut cancel)
+        .unwrap_err();
+    assert!(matches!(err, crate::WifError::Cancelled));
+}
+
+#[test]
+fn zip_stores_the_draft_and_thumbnail() {
+    use super::zip::crc32;
+    let u16_at = |bytes: &[u8], at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at =
+        |bytes: &[u8], at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    let wif = log_cabin(&[4, 4], (ColorIndex(1), ColorIndex(2))).plain_weave();
+    let bundle = wif.bundle(&BundleOptions::default()).unwrap();
+    let zip = bundle.write_zip(vec![]).unwrap();
+
+    // The first entry is the draft, stored as written.
+    assert_eq!(u32_at(&zip, 0), 0x0403_4b50);
+    let (size, name_len) = (u32_at(&zip, 22) as usize, u16_at(&zip, 26) as usize);
+    assert_eq!(&zip[30..30 + name_len], b"draft.wif");
+    let data = &zip[30 + name_len..30 + name_len + size];
+    assert_eq!(u32_at(&zip, 14), crc32(data));
+    let reread = crate::parse(std::str::from_utf8(data).unwrap()).unwrap();
+    assert_eq!(reread.to_drawdown_matrix(), wif.to_drawdown_matrix());
+
+    // The end record counts every entry and points back at the central directory.
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+    let entries = if cfg!(feature = "json") { 3 } else { 2 };
+    assert_eq!(u16_at(&zip, end + 10), entries);
+    let central = u32_at(&zip, end + 16) as usize;
+    assert_eq!(u32_at(&zip, central), 0x0201_4b50);
+    assert_eq!(central + u32_at(&zip, end + 12) as usize, end);
+}
@@ -0,0 +1,88 @@
+use std::io::{self, Write};
+
+// The CRC-32 zip uses (reflected, polynomial 0xEDB88320), a bit at a time.
+pub(super) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Every entry is dated 1980-01-01 00:00, the earliest date zip can record, so the same
+// bundle always gives the same bytes.
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn too_large() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "bundle is too large for a zip without ZIP64",
+    )
+}
+
+fn u16_len(len: usize) -> io::Result<u16> {
+    u16::try_from(len).map_err(|_| too_large())
+}
+
+fn u32_len(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| too_large())
+}
+
+// Writes `files` as a zip archive, stored without compression, returning `output`.
+pub(super) fn write_stored<W: Write>(mut output: W, files: &[(&str, Vec<u8>)]) -> io::Result<W> {
+    let mut central = Vec::new();
+    let mut offset = 0usize;
+    for (name, data) in files {
+        let crc = crc32(data);
+        let (name_len, size) = (u16_len(name.len())?, u32_len(data.len())?);
+        let mut local = Vec::with_capacity(30 + name.len());
+        local.extend(0x0403_4b50u32.to_le_bytes());
+        for field in [20, 0, 0, DOS_TIME, DOS_DATE] {
+            local.extend(field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            local.extend(field.to_le_bytes());
+        }
+        local.extend(name_len.to_le_bytes());
+        local.extend(0u16.to_le_bytes());
+        local.extend(name.as_bytes());
+
+        central.extend(0x0201_4b50u32.to_le_bytes());
+        for field in [20, 20, 0, 0, DOS_TIME, DOS_DATE] {
+            central.extend(field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            central.extend(field.to_le_bytes());
+        }
+        for field in [name_len, 0, 0, 0, 0] {
+            central.extend(field.to_le_bytes());
+        }
+        central.extend(0u32.to_le_bytes());
+        central.extend(u32_len(offset)?.to_le_bytes());
+        central.extend(name.as_bytes());
+
+        output.write_all(&local)?;
+        output.write_all(data)?;
+        offset += local.len() + data.len();
+    }
+    let entries = u16_len(files.len())?;
+    let mut end = Vec::with_capacity(22);
+    end.extend(0x0605_4b50u32.to_le_bytes());
+    for field in [0, 0, entries, entries] {
+        end.extend(field.to_le_bytes());
+    }
+    end.extend(u32_len(central.len())?.to_le_bytes());
+    end.extend(u32_len(offset)?.to_le_bytes());
+    end.extend(0u16.to_le_bytes());
+    output.write_all(&central)?;
+    output.write_all(&end)?;
+    Ok(output)
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::wif::sections;
+use crate::wifparse::WifParse;
+use crate::{Color, ColorIndex, Shaft, Table, Treadle, Warp, Weft, Wif};
+
+#[cfg(test)]
+mod tests;
+
+/// A changed field of one of the single-valued sections, written as it would be in the file.
+/// `None` means the field (or its whole section) is absent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub section: &'static str,
+    pub field: &'static str,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A changed entry of a table. `old` is `None` for an added entry and `new` for a removed one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change<K, V> {
+    pub key: K,
+    pub old: Option<V>,
+    pub new: Option<V>,
+}
+
+/// The differences between two drafts, section by section, from [`diff`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DraftDiff {
+    /// Changes to [WIF], [TEXT], [WEAVING], [WARP] and [WEFT].
+    pub fields: Vec<FieldChange>,
+    pub color_table: Vec<Change<ColorIndex, Color>>,
+    pub notes: Vec<Change<u32, String>>,
+    pub tieup: Vec<Change<Treadle, BTreeSet<Shaft>>>,
+    /// Ends whose shafts changed, were added or were removed.
+    pub threading: Vec<Change<Warp, BTreeSet<Shaft>>>,
+    pub warp_colors: Vec<Change<Warp, ColorIndex>>,
+    pub treadling: Vec<Change<Weft, BTreeSet<Treadle>>>,
+    pub liftplan: Vec<Change<Weft, BTreeSet<Shaft>>>,
+    pub weft_colors: Vec<Change<Weft, ColorIndex>>,
+}
+
+impl DraftDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == DraftDiff::default()
+    }
+}
+
+fn show(value: Option<&impl WifParse>) -> String {
+    value
+        .and_then(WifParse::unparse)
+        .unwrap_or_else(|| "(none)".into())
+}
+
+fn mark<T>(old: Option<T>, new: Option<T>) -> char {
+    match (old, new) {
+        (None, _) => '+',
+        (_, None) => '-',
+        _ => '~',
+    }
+}
+
+fn write_changes<K: fmt::Display, V: WifParse>(
+    f: &mut fmt::Formatter<'_>,
+    section: &str,
+    changes: &[Change<K, V>],
+) -> fmt::Result {
+    for change in changes {
+        let (old, new) = (change.old.as_ref(), change.new.as_ref());
+        writeln!(
+            f,
+            "{} [{section}] {}: {} -> {}",
+            mark(old, new),
+            change.key,
+            show(old),
+            show(new)
+        )?;
+    }
+    Ok(())
+}
+
+/// One line per change, `+` for added entries, `-` for removed ones and `~` for the rest.
+impl fmt::Display for DraftDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.fields {
+            writeln!(
+                f,
+                "{} [{}] {}: {} -> {}",
+                mark(change.old.as_ref(), change.new.as_ref()),
+                change.section,
+                change.field,
+                show(change.old.as_ref()),
+                show(change.new.as_ref())
+            )?;
+        }
+        write_changes(f, sections::COLOR_TABLE, &self.color_table)?;
+        write_changes(f, sections::NOTES, &self.notes)?;
+        write_changes(f, sections::TIEUP, &self.tieup)?;
+        write_changes(f, sections::THREADING, &self.threading)?;
+        write_changes(f, sections::WARP_COLORS, &self.warp_colors)?;
+        write_changes(f, sections::TREADLING, &self.treadling)?;
+        write_changes(f, sections::LIFTPLAN, &self.liftplan)?;
+        write_changes(f, sections::WEFT_COLORS, &self.weft_colors)
+    }
+}
+
+fn table<K: Ord + Copy, V: PartialEq + Clone>(
+    old: Option<&Table<K, V>>,
+    new: Option<&Table<K, V>>,
+) -> Vec<Change<K, V>> {
+    let keys: BTreeSet<K> = old
+        .iter()
+        .chain(new.iter())
+        .flat_map(|t| t.keys())
+        .copied()
+        .collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = old.and_then(|t| t.get(&key));
+            let new = new.and_then(|t| t.get(&key));
+            (old != new).then(|| Change {
+                key,
+                old: old.cloned(),
+                new: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+// Collects the fields that differ between the two drafts.
+struct Fields<'a> {
+    old: &'a Wif,
+    new: &'a Wif,
+    changes: Vec<FieldChange>,
+}
+
+impl Fields<'_> {
+    fn compare<T: WifParse>(
+        &mut self,
+        section: &'static str,
+        field: &'static str,
+        get: impl Fn(&Wif) -> Option<T>,
+    ) {
+        let old = get(self.old).as_ref().and_then(WifParse::unparse);
+        let new = get(self.new).as_ref().and_then(WifParse::unparse);
+        if old != new {
+            self.changes.push(FieldChange {
+                section,
+                field,
+                old,
+                new,
+            });
+        }
+    }
+}
+
+/// What changed between `old` and `new`, section by section. Fields are compared in their
+/// written form, and tables entry by entry.
+pub fn diff(old: &Wif, new: &Wif) -> DraftDiff {
+    let mut fields = Fields {
+        old,
+        new,
+        changes: vec![],
+    };
+    use sections::{TEXT, WARP, WEAVING, WEFT, WIF};
+    fields.compare(WIF, "Version", |w| Some(w.wif_header.version.clone()));
+    fields.compare(WIF, "Date", |w| Some(w.wif_header.date));
+    fields.compare(WIF, "Developers", |w| Some(w.wif_header.developers.clone()));
+    fields.compare(WIF, "Source Program", |w| {
+        Some(w.wif_header.source_program.clone())
+    });
+    fields.compare(WIF, "Source Version", |w| {
+        w.wif_header.source_version.clone()
+    });
+    fields.compare(TEXT, "Title", |w| w.text.as_ref()?.title.clone());
+    fields.compare(TEXT, "Author", |w| w.text.as_ref()?.author.clone());
+    fields.compare(TEXT, "Address", |w| w.text.as_ref()?.address.clone());
+    fields.compare(TEXT, "EMail", |w| w.text.as_ref()?.email.clone());
+    fields.compare(TEXT, "Telephone", |w| w.text.as_ref()?.telephone.clone());
+    fields.compare(TEXT, "FAX", |w| w.text.as_ref()?.fax.clone());
+    fields.compare(WEAVING, "Shafts", |w| Some(w.weaving.as_ref()?.shafts));
+    fields.compare(WEAVING, "Treadles", |w| Some(w.weaving.as_ref()?.treadles));
+    fields.compare(WEAVING, "Rising Shed", |w| w.weaving.as_ref()?.rising_shed);
+    fields.compare(WARP, "Threads", |w| Some(w.warp.as_ref()?.threads));
+    fields.compare(WARP, "Color", |w| w.warp.as_ref()?.color);
+    fields.compare(WARP, "Units", |w| w.warp.as_ref()?.units.clone());
+    fields.compare(WARP, "Spacing", |w| w.warp.as_ref()?.spacing);
+    fields.compare(WARP, "Thickness", |w| w.warp.as_ref()?.thickness);
+    fields.compare(WEFT, "Threads", |w| Some(w.weft.as_ref()?.threads));
+    fields.compare(WEFT, "Color", |w| w.weft.as_ref()?.color);
+    fields.compare(WEFT, "Units", |w| w.weft.as_ref()?.units.clone());
+    fields.compare(WEFT, "Spacing", |w| w.weft.as_ref()?.spacing);
+    fields.compare(WEFT, "Thickness", |w| w.weft.as_ref()?.thickness);
+
+    DraftDiff {
+        fields: fields.changes,
+        color_table: table(old.color_table.as_ref(), new.color_table.as_ref()),
+        notes: table(old.notes.as_ref(), new.notes.as_ref()),
+        tieup: table(old.tieup.as_ref(), new.tieup.as_ref()),
+        threading: table(old.threading.as_ref(), new.threading.as_ref()),
+        warp_colors: table(old.warp_colors.as_ref(), new.warp_colors.as_ref()),
+        treadling: table(old.treadling.as_ref(), new.treadling.as_ref()),
+        liftplan: table(old.liftplan.as_ref(), new.liftplan.as_ref()),
+        weft_colors: table(old.weft_colors.as_ref(), new.weft_colors.as_ref()),
+    }
+}
@@ -0,0 +1,52 @@
+use super::{diff, Change, FieldChange};
+use crate::{Shaft, Text, Warp, Weft};
+
+#[test]
+fn identical_drafts_have_no_diff() {
+    let wif = crate::parse(include_str!("../sample.wif")).unwrap();
+    let diff = diff(&wif, &wif.clone());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn reports_changes_by_section() {
+    let old = crate::parse(include_str!("../sample.wif")).unwrap();
+    let mut new = old.clone();
+    new.text = Some(Text {
+        title: Some("Renamed".into()),
+        author: None,
+        address: None,
+        email: None,
+        telephone: None,
+        fax: None,
+    });
+    new.threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(2), [Shaft(7)].into());
+    new.liftplan.as_mut().unwrap().remove(&Weft(1));
+
+    let diff = diff(&old, &new);
+    assert!(diff.fields.contains(&FieldChange {
+        section: "TEXT",
+        field: "Title",
+        old: old.text.as_ref().and_then(|t| t.title.clone()),
+        new: Some("Renamed".into()),
+    }));
+    assert_eq!(
+        diff.threading,
+        vec![Change {
+            key: Warp(2),
+            old: Some(old.threading.as_ref().unwrap()[&Warp(2)].clone()),
+            new: Some([Shaft(7)].into()),
+        }]
+    );
+    assert_eq!(diff.liftplan.len(), 1);
+    assert_eq!(diff.liftplan[0].new, None);
+    assert!(diff.treadling.is_empty());
+
+    let text = diff.to_string();
+    assert!(text.contains("~ [THREADING] 2: "), "{text}");
+    assert!(text.contains("- [LIFTPLAN] 1: "), "{text}");
+}
@@ -0,0 +1,444 @@
+use std::collections::BTreeSet;
+
+use crate::wif::rescale;
+use crate::{
+    sections, Color, ColorIndex, Length, Result, Shaft, Table, Text, Tieup, Treadle, Warp, Weaving,
+    Weft, Wif, WifError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// How the shafts are raised on each pick.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lifting {
+    /// Treadles tied to shafts, and the treadles pressed on each pick.
+    Treadled {
+        tieup: Tieup,
+        treadling: Vec<BTreeSet<Treadle>>,
+    },
+    /// The shafts raised on each pick, as on a dobby or table loom.
+    Liftplan(Vec<BTreeSet<Shaft>>),
+}
+
+impl Lifting {
+    pub fn picks(&self) -> usize {
+        match self {
+            Lifting::Treadled { treadling, .. } => treadling.len(),
+            Lifting::Liftplan(lifts) => lifts.len(),
+        }
+    }
+
+    /// The shafts raised on each pick, pick 1 first.
+    pub fn lifts(&self) -> Vec<BTreeSet<Shaft>> {
+        match self {
+            Lifting::Treadled { tieup, treadling } => treadling
+                .iter()
+                .map(|treadles| {
+                    treadles
+                        .iter()
+                        .filter_map(|treadle| tieup.get(treadle))
+                        .flatten()
+                        .copied()
+                        .collect()
+                })
+                .collect(),
+            Lifting::Liftplan(lifts) => lifts.clone(),
+        }
+    }
+}
+
+/// A yarn's color, on the 0-999 scale, and size.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YarnSpec {
+    pub color: Option<Color>,
+    pub thickness: Option<Length>,
+    pub spacing: Option<Length>,
+}
+
+/// The yarn on each end and pick, end 1 and pick 1 first, as indexes into
+/// [`Draft::yarns`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Colorway {
+    pub warp: Vec<usize>,
+    pub weft: Vec<usize>,
+}
+
+/// A draft apart from any file format. Every shaft and treadle it uses is within its
+/// counts, and every end and pick has a yarn; [`Draft::new`] and [`Draft::set_yarns`]
+/// check this, and converting from a [`Wif`] fills in whatever the file leaves out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Draft {
+    title: Option<String>,
+    shafts: u32,
+    treadles: u32,
+    rising_shed: bool,
+    threading: Vec<BTreeSet<Shaft>>,
+    lifting: Lifting,
+    yarns: Vec<YarnSpec>,
+    colorway: Colorway,
+}
+
+fn check_range(
+    section: &str,
+    what: &'static str,
+    used: impl Iterator<Item = u32>,
+    max: u32,
+) -> Result<()> {
+    match used.max() {
+        Some(value) if value > max => Err(WifError::OutOfRange {
+            section: section.into(),
+            what,
+            value,
+            max,
+        }),
+        _ => Ok(()),
+    }
+}
+
+// The index of `yarn` in `yarns`, adding it if it's new.
+fn intern(yarns: &mut Vec<YarnSpec>, yarn: YarnSpec) -> usize {
+    match yarns.iter().position(|known| *known == yarn) {
+        Some(index) => index,
+        None => {
+            yarns.push(yarn);
+            yarns.len() - 1
+        }
+    }
+}
+
+// A table keyed from 1, leaving out the `None`s, or `None` if that leaves nothing.
+fn numbered<K: Ord, V>(
+    values: impl IntoIterator<Item = Option<V>>,
+    key: impl Fn(u32) -> K,
+) -> Option<Table<K, V>> {
+    let table: Table<K, V> = (1..)
+        .zip(values)
+        .filter_map(|(i, value)| Some((key(i), value?)))
+        .collect();
+    (!table.is_empty()).then_some(table)
+}
+
+// The non-empty lines, for `numbered`.
+fn used<T: Clone>(lines: &[BTreeSet<T>]) -> impl Iterator<Item = Option<BTreeSet<T>>> + '_ {
+    lines
+        .iter()
+        .map(|line| (!line.is_empty()).then(|| line.clone()))
+}
+
+impl Draft {
+    /// A draft with every thread in one plain yarn and a rising shed. Fails if the
+    /// threading, tieup, treadling or liftplan goes beyond `shafts` or `treadles`.
+    pub fn new(
+        shafts: u32,
+        treadles: u32,
+        threading: Vec<BTreeSet<Shaft>>,
+        lifting: Lifting,
+    ) -> Result<Draft> {
+        check_range(
+            sections::THREADING,
+            "shaft",
+            threading.iter().flatten().map(|s| s.0),
+            shafts,
+        )?;
+        match &lifting {
+            Lifting::Treadled { tieup, treadling } => {
+                check_range(
+                    sections::TIEUP,
+                    "shaft",
+                    tieup.values().flatten().map(|s| s.0),
+                    shafts,
+                )?;
+                check_range(
+                    sections::TIEUP,
+                    "treadle",
+                    tieup.keys().map(|t| t.0),
+                    treadles,
+                )?;
+                check_range(
+                    sections::TREADLING,
+                    "treadle",
+                    treadling.iter().flatten().map(|t| t.0),
+                    treadles,
+                )?;
+            }
+            Lifting::Liftplan(lifts) => check_range(
+                sections::LIFTPLAN,
+                "shaft",
+                lifts.iter().flatten().map(|s| s.0),
+                shafts,
+            )?,
+        }
+        let colorway = Colorway {
+            warp: vec![0; threading.len()],
+            weft: vec![0; lifting.picks()],
+        };
+        Ok(Draft {
+            title: None,
+            shafts,
+            treadles,
+            rising_shed: true,
+            threading,
+            lifting,
+            yarns: vec![YarnSpec::default()],
+            colorway,
+        })
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+    }
+
+    pub fn shafts(&self) -> u32 {
+        self.shafts
+    }
+
+    pub fn treadles(&self) -> u32 {
+        self.treadles
+    }
+
+    pub fn rising_shed(&self) -> bool {
+        self.rising_shed
+    }
+
+    pub fn set_rising_shed(&mut self, rising_shed: bool) {
+        self.rising_shed = rising_shed;
+    }
+
+    pub fn ends(&self) -> usize {
+        self.threading.len()
+    }
+
+    pub fn picks(&self) -> usize {
+        self.lifting.picks()
+    }
+
+    /// The shafts each end is threaded on, end 1 first.
+    pub fn threading(&self) -> &[BTreeSet<Shaft>] {
+        &self.threading
+    }
+
+    pub fn lifting(&self) -> &Lifting {
+        &self.lifting
+    }
+
+    pub fn yarns(&self) -> &[YarnSpec] {
+        &self.yarns
+    }
+
+    pub fn colorway(&self) -> &Colorway {
+        &self.colorway
+    }
+
+    /// The yarn on an end, counting from 1.
+    pub fn warp_yarn(&self, warp: impl Into<Warp>) -> Option<&YarnSpec> {
+        let index = self
+            .colorway
+            .warp
+            .get(warp.into().0.checked_sub(1)? as usize)?;
+        self.yarns.get(*index)
+    }
+
+    /// The yarn on a pick, counting from 1.
+    pub fn weft_yarn(&self, weft: impl Into<Weft>) -> Option<&YarnSpec> {
+        let index = self
+            .colorway
+            .weft
+            .get(weft.into().0.checked_sub(1)? as usize)?;
+        self.yarns.get(*index)
+    }
+
+    /// Replaces the yarns and which thread uses which. Fails unless the colorway has one
+    /// entry per end and per pick, each naming one of `yarns`.
+    pub fn set_yarns(&mut self, yarns: Vec<YarnSpec>, colorway: Colorway) -> Result<()> {
+        for (what, threads, expected) in [
+            ("ends", &colorway.warp, self.ends()),
+            ("picks", &colorway.weft, self.picks()),
+        ] {
+            if threads.len() != expected {
+                return Err(WifError::ColorwayMismatch {
+                    what,
+                    expected,
+                    saw: threads.len(),
+                });
+            }
+        }
+        if let Some(&yarn) = colorway
+            .warp
+            .iter()
+            .chain(&colorway.weft)
+            .find(|&&yarn| yarn >= yarns.len())
+        {
+            return Err(WifError::UnknownYarn {
+                yarn,
+                yarns: yarns.len(),
+            });
+        }
+        self.yarns = yarns;
+        self.colorway = colorway;
+        Ok(())
+    }
+}
+
+/// Reads the draft's structure and resolves each thread's color, thickness and spacing.
+/// Treadling is kept when the file has both it and a tieup; otherwise the liftplan is used.
+/// Shaft and treadle counts grow to cover any the file uses beyond [WEAVING], and missing
+/// threading, treadling and liftplan entries become empty.
+impl From<&Wif> for Draft {
+    fn from(wif: &Wif) -> Self {
+        let threading: Vec<_> = wif.warp_ends().map(|end| end.shafts).collect();
+        let picks: Vec<_> = wif.weft_picks().collect();
+        let lifting = match (&wif.tieup, &wif.treadling) {
+            (Some(tieup), Some(_)) => Lifting::Treadled {
+                tieup: tieup.clone(),
+                treadling: picks.iter().map(|pick| pick.treadles.clone()).collect(),
+            },
+            _ => Lifting::Liftplan(picks.iter().map(|pick| pick.lift.clone()).collect()),
+        };
+        let highest_shaft = threading
+            .iter()
+            .chain(&lifting.lifts())
+            .chain(wif.tieup.iter().flat_map(|t| t.values()))
+            .flatten()
+            .map(|s| s.0)
+            .max();
+        let highest_treadle = match &lifting {
+            Lifting::Treadled { tieup, treadling } => tieup
+                .keys()
+                .chain(treadling.iter().flatten())
+                .map(|t| t.0)
+                .max(),
+            Lifting::Liftplan(_) => None,
+        };
+
+        let range = wif.color_palette.as_ref().map_or((0, 999), |p| p.range);
+        let scale = |color: Color| Color {
+            red: rescale(color.red, range, (0, 999)),
+            green: rescale(color.green, range, (0, 999)),
+            blue: rescale(color.blue, range, (0, 999)),
+        };
+        let mut yarns = vec![];
+        let warp = (1..=threading.len() as u32)
+            .map(Warp)
+            .map(|end| YarnSpec {
+                color: wif.warp_color(end).map(scale),
+                thickness: wif.warp_thickness_at(end),
+                spacing: wif.warp_spacing_at(end),
+            })
+            .map(|yarn| intern(&mut yarns, yarn))
+            .collect();
+        let weft = picks
+            .iter()
+            .map(|pick| YarnSpec {
+                color: wif.weft_color(pick.index).map(scale),
+                thickness: wif.weft_thickness_at(pick.index),
+                spacing: wif.weft_spacing_at(pick.index),
+            })
+            .map(|yarn| intern(&mut yarns, yarn))
+            .collect();
+
+        Draft {
+            title: wif.text.as_ref().and_then(|text| text.title.clone()),
+            shafts: wif.shafts().max(highest_shaft).unwrap_or(0),
+            treadles: wif.treadles().max(highest_treadle).unwrap_or(0),
+            rising_shed: wif
+                .weaving
+                .as_ref()
+                .and_then(|w| w.rising_shed)
+                .unwrap_or(true),
+            threading,
+            lifting,
+            yarns,
+            colorway: Colorway { warp, weft },
+        }
+    }
+}
+
+impl From<Wif> for Draft {
+    fn from(wif: Wif) -> Self {
+        Draft::from(&wif)
+    }
+}
+
+/// Writes the draft as a new file with a liftplan, and the tieup and treadling if it has
+/// them. Each side's lengths are written in the units of its first yarn that has one.
+impl From<&Draft> for Wif {
+    fn from(draft: &Draft) -> Self {
+        let mut wif = Wif::blank(draft.ends() as u32, draft.picks() as u32);
+        wif.weaving = Some(Weaving {
+            shafts: draft.shafts,
+            treadles: draft.treadles,
+            rising_shed: Some(draft.rising_shed),
+        });
+        wif.text = draft.title.clone().map(|title| Text {
+            title: Some(title),
+            author: None,
+            address: None,
+            email: None,
+            telephone: None,
+            fax: None,
+        });
+        wif.threading = numbered(used(&draft.threading), Warp);
+        if let Lifting::Treadled { tieup, treadling } = &draft.lifting {
+            wif.tieup = Some(tieup.clone());
+            wif.treadling = numbered(used(treadling), Weft);
+        }
+        wif.liftplan = numbered(used(&draft.lifting.lifts()), Weft);
+
+        let colors: Vec<Option<ColorIndex>> = draft
+            .yarns
+            .iter()
+            .map(|yarn| yarn.color.map(|color| wif.find_or_add_color(color)))
+            .collect();
+        let units = |threads: &[usize]| {
+            threads.iter().find_map(|&yarn| {
+                let yarn = &draft.yarns[yarn];
+                Some(
+                    yarn.thickness
+                        .as_ref()
+                        .or(yarn.spacing.as_ref())?
+                        .units
+                        .clone(),
+                )
+            })
+        };
+        let (warp, weft) = (&draft.colorway.warp, &draft.colorway.weft);
+        wif.warp_colors = numbered(warp.iter().map(|&yarn| colors[yarn]), Warp);
+        wif.weft_colors = numbered(weft.iter().map(|&yarn| colors[yarn]), Weft);
+        if let (Some(units), Some(section)) = (units(warp), wif.warp.as_mut()) {
+            section.units = Some(units.clone());
+            let value = |length: &Option<Length>| Some(length.as_ref()?.to(units.clone())?.value);
+            wif.warp_thickness = numbered(
+                warp.iter().map(|&yarn| value(&draft.yarns[yarn].thickness)),
+                Warp,
+            );
+            wif.warp_spacing = numbered(
+                warp.iter().map(|&yarn| value(&draft.yarns[yarn].spacing)),
+                Warp,
+            );
+        }
+        if let (Some(units), Some(section)) = (units(weft), wif.weft.as_mut()) {
+            section.units = Some(units.clone());
+            let value = |length: &Option<Length>| Some(length.as_ref()?.to(units.clone())?.value);
+            wif.weft_thickness = numbered(
+                weft.iter().map(|&yarn| value(&draft.yarns[yarn].thickness)),
+                Weft,
+            );
+            wif.weft_spacing = numbered(
+                weft.iter().map(|&yarn| value(&draft.yarns[yarn].spacing)),
+                Weft,
+            );
+        }
+        wif
+    }
+}
+
+impl From<Draft> for Wif {
+    fn from(draft: Draft) -> Self {
+        Wif::from(&draft)
+    }
+}
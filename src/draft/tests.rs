@@ -0,0 +1,95 @@
+use std::collections::BTreeSet;
+
+use super::{Colorway, Draft, Lifting, YarnSpec};
+use crate::{Color, Shaft, Treadle, Wif, WifError};
+
+fn sample() -> Wif {
+    crate::parse(include_str!("../sample.wif")).unwrap()
+}
+
+fn shafts(shafts: &[u32]) -> BTreeSet<Shaft> {
+    shafts.iter().map(|&s| Shaft(s)).collect()
+}
+
+#[test]
+fn round_trips_through_wif() {
+    let wif = sample();
+    let draft = Draft::from(&wif);
+    assert_eq!(draft.ends() as u32, wif.width().unwrap());
+    assert_eq!(draft.picks() as u32, wif.height().unwrap());
+    assert!(matches!(draft.lifting(), Lifting::Treadled { .. }));
+
+    let written = Wif::from(&draft);
+    assert!(written.structurally_equal(&wif));
+    assert_eq!(Draft::from(written), draft);
+}
+
+#[test]
+fn new_checks_ranges() {
+    let threading = vec![shafts(&[1]), shafts(&[5])];
+    let err = Draft::new(4, 0, threading, Lifting::Liftplan(vec![])).unwrap_err();
+    assert!(matches!(
+        err,
+        WifError::OutOfRange {
+            value: 5,
+            max: 4,
+            ..
+        }
+    ));
+
+    let lifting = Lifting::Treadled {
+        tieup: [(Treadle(3), shafts(&[1]))].into(),
+        treadling: vec![[Treadle(3)].into()],
+    };
+    let err = Draft::new(4, 2, vec![shafts(&[1])], lifting).unwrap_err();
+    assert!(matches!(
+        err,
+        WifError::OutOfRange {
+            what: "treadle",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn set_yarns_checks_colorway() {
+    let lifting = Lifting::Liftplan(vec![shafts(&[1]), shafts(&[2])]);
+    let mut draft = Draft::new(2, 0, vec![shafts(&[1]), shafts(&[2])], lifting).unwrap();
+    let red = YarnSpec {
+        color: Some(Color {
+            red: 999,
+            green: 0,
+            blue: 0,
+        }),
+        ..Default::default()
+    };
+    let yarns = vec![YarnSpec::default(), red.clone()];
+
+    let short = Colorway {
+        warp: vec![0],
+        weft: vec![0, 0],
+    };
+    assert!(matches!(
+        draft.set_yarns(yarns.clone(), short),
+        Err(WifError::ColorwayMismatch { what: "ends", .. })
+    ));
+    let unknown = Colorway {
+        warp: vec![0, 2],
+        weft: vec![0, 0],
+    };
+    assert!(matches!(
+        draft.set_yarns(yarns.clone(), unknown),
+        Err(WifError::UnknownYarn { yarn: 2, yarns: 2 })
+    ));
+
+    let colorway = Colorway {
+        warp: vec![0, 1],
+        weft: vec![1, 1],
+    };
+    draft.set_yarns(yarns, colorway).unwrap();
+    assert_eq!(draft.warp_yarn(2), Some(&red));
+    let wif = Wif::from(&draft);
+    assert_eq!(wif.warp_color(2u32), red.color);
+    assert_eq!(wif.warp_color(1u32), None);
+    assert_eq!(Draft::from(wif), draft);
+}
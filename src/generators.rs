@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use crate::{Color, ColorIndex, Result, Shaft, Treadle, Warp, Weaving, Weft, Wif, WifError};
+
+/// Bounds on the length of each randomly generated stripe, in threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StripeRuns {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Default for StripeRuns {
+    fn default() -> Self {
+        StripeRuns { min: 1, max: 8 }
+    }
+}
+
+/// SplitMix64; small, fast, and stable across releases so seeds stay reproducible.
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`. `bound` must be non-zero.
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+fn weighted_stripes(
+    rng: &mut SplitMix64,
+    color_idxs: &[ColorIndex],
+    weights: &[u32],
+    runs: StripeRuns,
+    threads: u32,
+) -> Vec<ColorIndex> {
+    let min = runs.min.max(1);
+    let max = runs.max.max(min);
+    let mut order = Vec::with_capacity(threads as usize);
+    let mut previous = None;
+    while order.len() < threads as usize {
+        // Don't repeat the previous color, or two stripes would merge and break the run limits
+        let total: u64 = weights
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| Some(i) != previous)
+            .map(|(_, &w)| w as u64)
+            .sum();
+        let choice = if total == 0 {
+            previous.unwrap_or(0)
+        } else {
+            let mut pick = rng.below(total);
+            let mut chosen = 0;
+            for (i, &w) in weights.iter().enumerate() {
+                if Some(i) == previous {
+                    continue;
+                }
+                if pick < w as u64 {
+                    chosen = i;
+                    break;
+                }
+                pick -= w as u64;
+            }
+            chosen
+        };
+        let len = min + rng.below((max - min + 1) as u64) as u32;
+        order.extend(std::iter::repeat_n(color_idxs[choice], len as usize));
+        previous = Some(choice);
+    }
+    order.truncate(threads as usize);
+    order
+}
+
+impl Wif {
+    /// Replaces the warp and weft color orders with weighted random stripes drawn from
+    /// `palette`. The same seed always produces the same colorway.
+    pub fn random_colorway(
+        &mut self,
+        palette: &[Color],
+        weights: &[u32],
+        seed: u64,
+        runs: StripeRuns,
+    ) -> Result<()> {
+        if palette.len() != weights.len() {
+            return Err(WifError::MismatchedWeights {
+                colors: palette.len(),
+                weights: weights.len(),
+            });
+        }
+        if weights.iter().all(|&w| w == 0) {
+            return Err(WifError::EmptyPalette);
+        }
+        let color_idxs: Vec<ColorIndex> = palette
+            .iter()
+            .map(|&color| self.find_or_add_color(color))
+            .collect();
+        let mut rng = SplitMix64::new(seed);
+
+        let warp_order = weighted_stripes(
+            &mut rng,
+            &color_idxs,
+            weights,
+            runs,
+            self.width().unwrap_or(0),
+        );
+        let weft_order = weighted_stripes(
+            &mut rng,
+            &color_idxs,
+            weights,
+            runs,
+            self.height().unwrap_or(0),
+        );
+        self.warp_colors = Some((1..).map(Warp).zip(warp_order).collect::<BTreeMap<_, _>>());
+        self.weft_colors = Some((1..).map(Weft).zip(weft_order).collect::<BTreeMap<_, _>>());
+        Ok(())
+    }
+}
+
+// The color of each of `threads` threads, running through `stripes` as often as it takes.
+fn repeat_stripes(stripes: &[(ColorIndex, u32)], threads: u32) -> Vec<ColorIndex> {
+    stripes
+        .iter()
+        .flat_map(|&(color, len)| std::iter::repeat_n(color, len as usize))
+        .cycle()
+        .take(threads as usize)
+        .collect()
+}
+
+impl Wif {
+    /// Colors the warp in stripes, given as each color with how many ends in a row it
+    /// covers, e.g. `[(blue, 8), (white, 2)]`, repeated across every end. Colors not yet in
+    /// the color table are added. Fails if the stripes cover no ends.
+    pub fn stripe_warp(&mut self, stripes: &[(Color, u32)]) -> Result<()> {
+        let order = self.stripe_order(stripes, self.width().unwrap_or(0))?;
+        self.warp_colors = Some((1..).map(Warp).zip(order).collect());
+        Ok(())
+    }
+
+    /// Colors the weft in stripes, as [`stripe_warp`](Self::stripe_warp) does the warp.
+    pub fn stripe_weft(&mut self, stripes: &[(Color, u32)]) -> Result<()> {
+        let order = self.stripe_order(stripes, self.height().unwrap_or(0))?;
+        self.weft_colors = Some((1..).map(Weft).zip(order).collect());
+        Ok(())
+    }
+
+    fn stripe_order(&mut self, stripes: &[(Color, u32)], threads: u32) -> Result<Vec<ColorIndex>> {
+        if stripes.iter().all(|&(_, len)| len == 0) {
+            return Err(WifError::EmptyStripes);
+        }
+        let stripes: Vec<(ColorIndex, u32)> = stripes
+            .iter()
+            .map(|&(color, len)| (self.find_or_add_color(color), len))
+            .collect();
+        Ok(repeat_stripes(&stripes, threads))
+    }
+}
+
+/// Warp and weft color orders, one color table index per thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorOrder {
+    pub warp: Vec<ColorIndex>,
+    pub weft: Vec<ColorIndex>,
+}
+
+impl ColorOrder {
+    /// Sets the warp and weft colors of `wif`, repeating each order across all of its threads.
+    pub fn apply(&self, wif: &mut Wif) {
+        let repeat = |order: &[ColorIndex], threads: u32| {
+            order
+                .iter()
+                .copied()
+                .cycle()
+                .take(if order.is_empty() {
+                    0
+                } else {
+                    threads as usize
+                })
+                .collect::<Vec<_>>()
+        };
+        let warp = repeat(&self.warp, wif.width().unwrap_or(0));
+        let weft = repeat(&self.weft, wif.height().unwrap_or(0));
+        wif.warp_colors = Some((1..).map(Warp).zip(warp).collect());
+        wif.weft_colors = Some((1..).map(Weft).zip(weft).collect());
+    }
+
+    /// A two-shaft plain weave draft, one repeat of the orders in size, woven in these colors.
+    pub fn plain_weave(&self) -> Wif {
+        let (ends, picks) = (self.warp.len() as u32, self.weft.len() as u32);
+        let mut wif = Wif::blank(ends, picks);
+        wif.weaving = Some(Weaving {
+            shafts: 2,
+            treadles: 2,
+            rising_shed: Some(true),
+        });
+        wif.threading = Some(
+            (1..=ends)
+                .map(|end| (Warp(end), [Shaft(2 - end % 2)].into()))
+                .collect(),
+        );
+        wif.tieup = Some((1..=2).map(|t| (Treadle(t), [Shaft(t)].into())).collect());
+        wif.treadling = Some(
+            (1..=picks)
+                .map(|pick| (Weft(pick), [Treadle(2 - pick % 2)].into()))
+                .collect(),
+        );
+        wif.build_or_validate_liftplan()
+            .expect("a draft without a liftplan can always have one built");
+        self.apply(&mut wif);
+        wif
+    }
+}
+
+/// Log cabin color-and-weave: each block alternates the two colors thread by thread, and each
+/// new block starts by repeating the last color of the one before, flipping the parity so the
+/// dark and light lines turn 90 degrees. The weft is woven as drawn in.
+pub fn log_cabin(block_lengths: &[u32], colors: (ColorIndex, ColorIndex)) -> ColorOrder {
+    let mut order = Vec::new();
+    for &len in block_lengths {
+        let first = match order.last() {
+            Some(&last) => last,
+            None => colors.0,
+        };
+        let second = if first == colors.0 {
+            colors.1
+        } else {
+            colors.0
+        };
+        order.extend([first, second].into_iter().cycle().take(len as usize));
+    }
+    ColorOrder {
+        warp: order.clone(),
+        weft: order,
+    }
+}
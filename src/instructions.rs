@@ -0,0 +1,338 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    analysis::lift_sequence, Color, ColorIndex, Shaft, Symbol, SymbolIndex, Treadle, Warp, Weft,
+    Wif,
+};
+
+/// One warp end to be drawn in, with a running heddle count for each shaft it goes on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawinStep {
+    pub warp: Warp,
+    pub shafts: BTreeSet<Shaft>,
+    /// For each shaft this end is threaded on, how many heddles on that shaft have been used
+    /// once this end is in place.
+    pub heddles: Vec<(Shaft, u32)>,
+    pub color: Option<ColorIndex>,
+}
+
+/// The threading of a draft as an end-by-end checklist, in the order the ends come off the
+/// lease cross.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawinPlan {
+    pub steps: Vec<DrawinStep>,
+}
+
+fn join<T: std::fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// One weft pick to be woven.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickStep {
+    pub pick: Weft,
+    pub treadles: BTreeSet<Treadle>,
+    pub shafts: BTreeSet<Shaft>,
+    pub color: Option<ColorIndex>,
+    pub symbol: Option<SymbolIndex>,
+    /// Whether this pick uses a different weft from the one before it.
+    pub shuttle_change: bool,
+}
+
+/// A weft that appears in the draft, for the legend of a chase list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegendEntry {
+    pub color: Option<ColorIndex>,
+    pub rgb: Option<Color>,
+    pub symbol: Option<SymbolIndex>,
+    pub symbol_char: Option<Symbol>,
+    pub yarn: Option<String>,
+    pub picks: usize,
+}
+
+/// The treadling or liftplan of a draft as a pick-by-pick chase list, with a legend of the
+/// wefts it uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeavingPlan {
+    pub steps: Vec<PickStep>,
+    pub legend: Vec<LegendEntry>,
+}
+
+/// Consecutive threads of one color, `None` for threads with no color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorRun {
+    pub color: Option<ColorIndex>,
+    pub count: u32,
+}
+
+/// A warp or weft color order as runs, the way it's written out for winding a warp, with the
+/// total threads of each color.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorRuns {
+    pub runs: Vec<ColorRun>,
+    pub totals: BTreeMap<Option<ColorIndex>, u32>,
+}
+
+impl ColorRuns {
+    fn new(colors: impl IntoIterator<Item = Option<ColorIndex>>) -> ColorRuns {
+        let mut order = ColorRuns::default();
+        for color in colors {
+            *order.totals.entry(color).or_default() += 1;
+            match order.runs.last_mut() {
+                Some(run) if run.color == color => run.count += 1,
+                _ => order.runs.push(ColorRun { color, count: 1 }),
+            }
+        }
+        order
+    }
+}
+
+/// E.g. `8×3, 2×1`, with `-` for threads with no color.
+impl std::fmt::Display for ColorRuns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, run) in self.runs.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            match run.color {
+                Some(color) => write!(f, "{}×{color}", run.count)?,
+                None => write!(f, "{}×-", run.count)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DrawinStep {
+    fn shafts_label(&self) -> String {
+        join(self.shafts.iter().map(|s| s.0))
+    }
+
+    fn heddles_label(&self) -> String {
+        join(
+            self.heddles
+                .iter()
+                .map(|(shaft, count)| format!("{}:{count}", shaft.0)),
+        )
+    }
+
+    fn color_label(&self) -> String {
+        self.color.map(|c| c.to_string()).unwrap_or_default()
+    }
+}
+
+impl DrawinPlan {
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("end,shafts,heddles,color\n");
+        for step in &self.steps {
+            let _ = writeln!(
+                out,
+                "{},{},{},{}",
+                step.warp,
+                step.shafts_label(),
+                step.heddles_label(),
+                step.color_label()
+            );
+        }
+        out
+    }
+
+    /// A printable checklist, `ends_per_page` ends to a page, with a tick box for each end.
+    pub fn to_text(&self, ends_per_page: usize) -> String {
+        let ends_per_page = ends_per_page.max(1);
+        let pages = self.steps.len().div_ceil(ends_per_page).max(1);
+        let mut out = String::new();
+        for (page, steps) in self.steps.chunks(ends_per_page).enumerate() {
+            if page > 0 {
+                out.push('\u{c}');
+            }
+            let _ = writeln!(out, "Page {} of {pages}", page + 1);
+            let _ = writeln!(
+                out,
+                "[ ] {:>6}  {:<10}  {:<14}  Color",
+                "End", "Shafts", "Heddles"
+            );
+            for step in steps {
+                let _ = writeln!(
+                    out,
+                    "[ ] {:>6}  {:<10}  {:<14}  {}",
+                    step.warp,
+                    step.shafts_label(),
+                    step.heddles_label(),
+                    step.color_label()
+                );
+            }
+        }
+        if self.steps.is_empty() {
+            let _ = writeln!(out, "Page 1 of {pages}");
+        }
+        out
+    }
+}
+
+impl LegendEntry {
+    fn label(&self) -> String {
+        let mut label = match self.color {
+            Some(color) => format!("color {color}"),
+            None => "default weft".to_string(),
+        };
+        if let Some(symbol) = self.symbol_char {
+            let _ = write!(label, " '{}'", symbol.as_char());
+        }
+        if let Some(yarn) = &self.yarn {
+            let _ = write!(label, " ({yarn})");
+        }
+        label
+    }
+}
+
+impl WeavingPlan {
+    fn legend_for(&self, step: &PickStep) -> Option<&LegendEntry> {
+        self.legend
+            .iter()
+            .find(|entry| entry.color == step.color && entry.symbol == step.symbol)
+    }
+
+    /// A printable chase list: the legend, then the picks in blocks of `picks_per_block`,
+    /// with a note wherever the shuttle changes.
+    pub fn to_text(&self, picks_per_block: usize) -> String {
+        let picks_per_block = picks_per_block.max(1);
+        let mut out = String::from("Legend\n");
+        for entry in &self.legend {
+            let rgb = entry
+                .rgb
+                .map(|c| format!("{},{},{}", c.red, c.green, c.blue))
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "  {:<40}  {:<12}  {} picks",
+                entry.label(),
+                rgb,
+                entry.picks
+            );
+        }
+        for (block, steps) in self.steps.chunks(picks_per_block).enumerate() {
+            let _ = writeln!(out, "\nBlock {}", block + 1);
+            for step in steps {
+                if step.shuttle_change {
+                    let weft = self
+                        .legend_for(step)
+                        .map(LegendEntry::label)
+                        .unwrap_or_default();
+                    let _ = writeln!(out, "  -- change shuttle: {weft}");
+                }
+                let lift = if step.treadles.is_empty() {
+                    format!("shafts {}", join(step.shafts.iter().map(|s| s.0)))
+                } else {
+                    format!("treadles {}", join(step.treadles.iter().map(|t| t.0)))
+                };
+                let _ = writeln!(out, "[ ] {:>6}  {lift}", step.pick);
+            }
+        }
+        out
+    }
+}
+
+impl Wif {
+    /// The weaving instructions for the draft. `yarn_names` gives a name for color table
+    /// indices, for the legend.
+    pub fn weaving_plan(&self, yarn_names: &BTreeMap<ColorIndex, String>) -> WeavingPlan {
+        let lifts = lift_sequence(self).unwrap_or_default();
+        let last_treadled = self
+            .treadling
+            .as_ref()
+            .and_then(|t| t.keys().next_back())
+            .map_or(0, |w| w.0);
+        let picks = (lifts.len() as u32).max(last_treadled);
+        let mut steps: Vec<PickStep> = Vec::with_capacity(picks as usize);
+        let mut legend: Vec<LegendEntry> = Vec::new();
+        for pick in (1..=picks).map(Weft) {
+            let color = self.weft_color_idx(pick);
+            let symbol = self
+                .weft_symbols
+                .as_ref()
+                .and_then(|ws| ws.get(&pick))
+                .copied()
+                .or_else(|| self.weft.as_ref()?.symbol_number);
+            let shuttle_change = steps
+                .last()
+                .is_some_and(|prev| prev.color != color || prev.symbol != symbol);
+            match legend
+                .iter_mut()
+                .find(|entry| entry.color == color && entry.symbol == symbol)
+            {
+                Some(entry) => entry.picks += 1,
+                None => legend.push(LegendEntry {
+                    color,
+                    rgb: color.and_then(|c| self.color_table.as_ref()?.get(&c).copied()),
+                    symbol,
+                    symbol_char: symbol
+                        .and_then(|s| self.weft_symbols_table.as_ref()?.get(&s).copied()),
+                    yarn: color.and_then(|c| yarn_names.get(&c).cloned()),
+                    picks: 1,
+                }),
+            }
+            steps.push(PickStep {
+                pick,
+                treadles: self
+                    .treadling
+                    .as_ref()
+                    .and_then(|t| t.get(&pick))
+                    .cloned()
+                    .unwrap_or_default(),
+                shafts: lifts.get(pick.0 as usize - 1).cloned().unwrap_or_default(),
+                color,
+                symbol,
+                shuttle_change,
+            });
+        }
+        WeavingPlan { steps, legend }
+    }
+
+    /// The color of every end, in order, as runs. Ends without their own color use the
+    /// [WARP] default.
+    pub fn warp_color_order(&self) -> ColorRuns {
+        let ends = self.width().unwrap_or(0);
+        ColorRuns::new((1..=ends).map(|end| self.warp_color_idx(Warp(end))))
+    }
+
+    /// The color of every pick, in order, as runs. Picks without their own color use the
+    /// [WEFT] default.
+    pub fn weft_color_order(&self) -> ColorRuns {
+        let picks = self.height().unwrap_or(0);
+        ColorRuns::new((1..=picks).map(|pick| self.weft_color_idx(Weft(pick))))
+    }
+
+    pub fn drawin_plan(&self) -> DrawinPlan {
+        let mut tally: BTreeMap<Shaft, u32> = BTreeMap::new();
+        let steps = self
+            .threading
+            .iter()
+            .flatten()
+            .map(|(&warp, shafts)| {
+                let heddles = shafts
+                    .iter()
+                    .map(|&shaft| {
+                        let count = tally.entry(shaft).or_default();
+                        *count += 1;
+                        (shaft, *count)
+                    })
+                    .collect();
+                DrawinStep {
+                    warp,
+                    shafts: shafts.clone(),
+                    heddles,
+                    color: self.warp_color_idx(warp),
+                }
+            })
+            .collect();
+        DrawinPlan { steps }
+    }
+}
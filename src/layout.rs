@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    analysis::lift_sequence, Result, Shaft, Table, Treadle, Warp, WarpOrWeft, Weft, Wif, WifError,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The direction in which copies of the draft are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatAxis {
+    /// Copies sit side by side across the warp, each dropped by the offset in picks
+    /// (a half-drop when the offset is half the pick repeat).
+    Warp,
+    /// Copies are stacked along the weft, each shifted by the offset in ends (a brick repeat
+    /// when the offset is half the end repeat).
+    Weft,
+}
+
+/// How [`Wif::repeat`] lines up the copies of the draft.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatStyle {
+    /// Copies line up in a grid.
+    Plain,
+    /// Each column of copies is dropped by half the pick repeat from the one before.
+    HalfDrop,
+    /// Each row of copies is shifted by half the end repeat from the one before.
+    Brick,
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// How far copy number `copy` is moved when each copy moves `offset` further than the last,
+// wrapping around `len` threads. Worked in u64 so large offsets don't overflow.
+fn offset_of(copy: u32, offset: u32, len: u32) -> u32 {
+    (u64::from(copy) * u64::from(offset) % u64::from(len)) as u32
+}
+
+// Copies a per-thread table across `tiles` repeats of `len` threads.
+fn tile<K, V>(table: &mut Option<Table<K, V>>, len: u32, tiles: u32, make: impl Fn(u32) -> K)
+where
+    K: Ord + Copy,
+    V: Clone,
+{
+    let Some(table) = table else {
+        return;
+    };
+    let original: Vec<(u32, V)> = (1..=len)
+        .filter_map(|i| table.get(&make(i)).map(|v| (i, v.clone())))
+        .collect();
+    for k in 1..tiles {
+        for (i, v) in &original {
+            table.insert(make(k * len + i), v.clone());
+        }
+    }
+}
+
+impl Wif {
+    /// Lays out copies of the draft with each one offset from the last, until the offsets come
+    /// back around to the start. Threading and liftplan are recomputed from the resulting
+    /// drawdown; treadling and tieup are kept only if the new rows fit on the loom's treadles.
+    /// Fails if the offset repeat needs more shafts than the loom has.
+    pub fn repeat_offset(&mut self, axis: RepeatAxis, offset: u32) -> Result<()> {
+        let lifts = lift_sequence(self).ok_or(WifError::MissingLiftplan)?;
+        let picks = lifts.len() as u32;
+        let ends = self.width().unwrap_or_else(|| {
+            self.threading
+                .as_ref()
+                .and_then(|t| t.keys().next_back())
+                .map_or(0, |w| w.0)
+        });
+        let len = match axis {
+            RepeatAxis::Warp => picks,
+            RepeatAxis::Weft => ends,
+        };
+        if len == 0 || offset.is_multiple_of(len) {
+            return Ok(());
+        }
+        let tiles = len / gcd(len, offset % len);
+        let drawdown = self.lifted_matrix(ends, picks);
+        let (new_ends, new_picks) = match axis {
+            RepeatAxis::Warp => (ends * tiles, picks),
+            RepeatAxis::Weft => (ends, picks * tiles),
+        };
+        // Zero-based from here on
+        self.weave_from(new_ends, new_picks, |end, pick| {
+            let (e, p) = match axis {
+                RepeatAxis::Warp => {
+                    let shift = offset_of(end / ends, offset, picks);
+                    (end % ends, (pick + picks - shift) % picks)
+                }
+                RepeatAxis::Weft => {
+                    let shift = offset_of(pick / picks, offset, ends);
+                    ((end + ends - shift) % ends, pick % picks)
+                }
+            };
+            drawdown[p as usize][e as usize]
+        })?;
+
+        match axis {
+            RepeatAxis::Warp => self.tile_warp(ends, tiles),
+            RepeatAxis::Weft => self.tile_weft(picks, tiles),
+        }
+        Ok(())
+    }
+
+    /// Tiles the draft `n_warp` times across and `n_weft` times along, lining the copies up
+    /// as `style` says, to give the full-size cloth. A plain repeat copies the threading,
+    /// treadling and liftplan as they are. A half-drop or brick repeat weaves the shifted
+    /// copies from a threading and liftplan recomputed from the drawdown, keeping treadling
+    /// and tieup only if the new rows fit on the loom's treadles, and fails if they need
+    /// more shafts than the loom has. Thread colors, thickness, spacing and symbols are
+    /// tiled unshifted either way. Counts of 0 are taken as 1.
+    pub fn repeat(&mut self, n_warp: u32, n_weft: u32, style: RepeatStyle) -> Result<()> {
+        let (n_warp, n_weft) = (n_warp.max(1), n_weft.max(1));
+        let lifts = lift_sequence(self).ok_or(WifError::MissingLiftplan)?;
+        let picks = lifts.len() as u32;
+        let ends = self.width().unwrap_or_else(|| {
+            self.threading
+                .as_ref()
+                .and_then(|t| t.keys().next_back())
+                .map_or(0, |w| w.0)
+        });
+        if ends == 0 || picks == 0 {
+            return Ok(());
+        }
+        match style {
+            RepeatStyle::Plain => {
+                tile(&mut self.threading, ends, n_warp, Warp);
+                tile(&mut self.treadling, picks, n_weft, Weft);
+                tile(&mut self.liftplan, picks, n_weft, Weft);
+            }
+            RepeatStyle::HalfDrop | RepeatStyle::Brick => {
+                let drawdown = self.lifted_matrix(ends, picks);
+                let (drop, shift) = match style {
+                    RepeatStyle::HalfDrop => (picks / 2, 0),
+                    _ => (0, ends / 2),
+                };
+                // Zero-based from here on
+                self.weave_from(ends * n_warp, picks * n_weft, |end, pick| {
+                    let drop = offset_of(end / ends, drop, picks);
+                    let shift = offset_of(pick / picks, shift, ends);
+                    let e = (end + ends - shift) % ends;
+                    let p = (pick + picks - drop) % picks;
+                    drawdown[p as usize][e as usize]
+                })?;
+            }
+        }
+        self.tile_warp(ends, n_warp);
+        self.tile_weft(picks, n_weft);
+        Ok(())
+    }
+
+    // Whether the warp is on top at each end of each pick, pick 1 first.
+    fn lifted_matrix(&self, ends: u32, picks: u32) -> Vec<Vec<bool>> {
+        (1..=picks)
+            .map(|pick| {
+                (1..=ends)
+                    .map(|end| self.warp_or_weft(end, pick) == Some(WarpOrWeft::Warp))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Replaces the threading and liftplan with ones weaving `new_ends` by `new_picks` with
+    // the warp up wherever `lifted` says, zero-based: one shaft per distinct end, in order
+    // of first use. Ends that are never lifted get a shaft of their own too, so they stay
+    // threaded. On a sinking shed the liftplan lists the shafts that go down. The treadling
+    // and tieup are rebuilt too if the draft had a treadling and the new rows fit, and
+    // dropped otherwise.
+    fn weave_from(
+        &mut self,
+        new_ends: u32,
+        new_picks: u32,
+        lifted: impl Fn(u32, u32) -> bool,
+    ) -> Result<()> {
+        // One shaft per distinct end, in order of first use
+        let mut columns: BTreeMap<Vec<bool>, Shaft> = BTreeMap::new();
+        let mut threading = Table::new();
+        for end in 0..new_ends {
+            let column: Vec<bool> = (0..new_picks).map(|pick| lifted(end, pick)).collect();
+            let next = Shaft(columns.len() as u32 + 1);
+            let shaft = *columns.entry(column).or_insert(next);
+            threading.insert(Warp(end + 1), BTreeSet::from([shaft]));
+        }
+        let needed = columns.len() as u32;
+        if let Some(available) = self.shafts() {
+            if needed > available {
+                return Err(WifError::RepeatNeedsMoreShafts { needed, available });
+            }
+        }
+        let rising_shed = self
+            .weaving
+            .as_ref()
+            .and_then(|w| w.rising_shed)
+            .unwrap_or(true);
+        let liftplan: Table<Weft, BTreeSet<Shaft>> = (0..new_picks)
+            .map(|pick| {
+                let shafts = columns
+                    .iter()
+                    .filter(|(column, _)| column[pick as usize] == rising_shed)
+                    .map(|(_, &shaft)| shaft)
+                    .collect();
+                (Weft(pick + 1), shafts)
+            })
+            .collect();
+
+        let treadles = self.treadles().unwrap_or(0);
+        let mut treadled = false;
+        if self.treadling.is_some() {
+            let mut rows: BTreeMap<&BTreeSet<Shaft>, Treadle> = BTreeMap::new();
+            let mut treadling = Table::new();
+            for (&pick, shafts) in &liftplan {
+                if shafts.is_empty() {
+                    continue;
+                }
+                let next = Treadle(rows.len() as u32 + 1);
+                let treadle = *rows.entry(shafts).or_insert(next);
+                treadling.insert(pick, BTreeSet::from([treadle]));
+            }
+            if rows.len() as u32 <= treadles {
+                self.tieup = Some(
+                    rows.into_iter()
+                        .map(|(shafts, treadle)| (treadle, shafts.clone()))
+                        .collect(),
+                );
+                self.treadling = Some(treadling);
+                treadled = true;
+            }
+        }
+        if !treadled {
+            self.drop_treadling();
+        }
+        self.threading = Some(threading);
+        self.liftplan = Some(liftplan);
+        Ok(())
+    }
+
+    fn tile_warp(&mut self, ends: u32, tiles: u32) {
+        tile(&mut self.warp_thickness, ends, tiles, Warp);
+        tile(&mut self.warp_thickness_zoom, ends, tiles, Warp);
+        tile(&mut self.warp_spacing, ends, tiles, Warp);
+        tile(&mut self.warp_spacing_zoom, ends, tiles, Warp);
+        tile(&mut self.warp_colors, ends, tiles, Warp);
+        tile(&mut self.warp_symbols, ends, tiles, Warp);
+        if let Some(warp) = self.warp.as_mut() {
+            warp.threads = ends * tiles;
+        }
+    }
+
+    fn tile_weft(&mut self, picks: u32, tiles: u32) {
+        tile(&mut self.weft_thickness, picks, tiles, Weft);
+        tile(&mut self.weft_thickness_zoom, picks, tiles, Weft);
+        tile(&mut self.weft_spacing, picks, tiles, Weft);
+        tile(&mut self.weft_spacing_zoom, picks, tiles, Weft);
+        tile(&mut self.weft_colors, picks, tiles, Weft);
+        tile(&mut self.weft_symbols, picks, tiles, Weft);
+        if let Some(weft) = self.weft.as_mut() {
+            weft.threads = picks * tiles;
+        }
+    }
+}
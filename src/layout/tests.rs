@@ -0,0 +1,167 @@
+use crate::{Shaft, Warp, Weft, WifError};
+
+use super::{RepeatAxis, RepeatStyle};
+
+fn draft(liftplan: &str) -> crate::Wif {
+    let s = format!(
+        "[WIF]\nVersion=1.1\nDate=April 20, 1997\nDevelopers=x\nSource Program=x\n\
+         [CONTENTS]\nWEAVING=true\nWARP=true\nWEFT=true\nTHREADING=true\nLIFTPLAN=true\n\
+         [WEAVING]\nShafts=4\nTreadles=4\n[WARP]\nThreads=4\n[WEFT]\nThreads=4\n\
+         [THREADING]\n1=1\n2=2\n3=3\n4=4\n[LIFTPLAN]\n{liftplan}"
+    );
+    crate::parse(&s).unwrap()
+}
+
+#[test]
+fn half_drop_twill_stays_on_four_shafts() {
+    let mut wif = draft("1=1,2\n2=2,3\n3=3,4\n4=4,1\n");
+    let before: Vec<_> = (1..=4)
+        .flat_map(|pick| (1..=4).map(move |end| (end, pick)))
+        .map(|(end, pick)| wif.warp_or_weft(end, pick))
+        .collect();
+    wif.repeat_offset(RepeatAxis::Warp, 2).unwrap();
+    assert_eq!(wif.width(), Some(8));
+    let threading = wif.threading.as_ref().unwrap();
+    assert!(threading.values().flatten().all(|s| *s <= Shaft(4)));
+    for pick in 1..=4u32 {
+        for end in 1..=4u32 {
+            let original = before[((pick - 1) * 4 + end - 1) as usize];
+            assert_eq!(wif.warp_or_weft(end, pick), original);
+            let dropped = (pick + 4 - 2 - 1) % 4 + 1;
+            let expected = before[((dropped - 1) * 4 + end - 1) as usize];
+            assert_eq!(wif.warp_or_weft(Warp(end + 4), Weft(pick)), expected);
+        }
+    }
+}
+
+#[test]
+fn offset_repeat_reports_shaft_shortage() {
+    let mut wif = draft("1=1\n2=1,2\n3=1,2,3\n4=4\n");
+    assert!(matches!(
+        wif.repeat_offset(RepeatAxis::Warp, 1),
+        Err(WifError::RepeatNeedsMoreShafts { available: 4, .. })
+    ));
+    // A brick repeat never needs more shafts than the draft has ends
+    wif.repeat_offset(RepeatAxis::Weft, 1).unwrap();
+    assert_eq!(wif.height(), Some(16));
+}
+
+#[test]
+fn offset_repeat_keeps_ends_that_never_rise() {
+    let mut wif = draft("1=1\n2=2\n3=1\n4=2\n");
+    wif.repeat_offset(RepeatAxis::Warp, 1).unwrap();
+    let threading = wif.threading.as_ref().unwrap();
+    assert_eq!(threading.len(), 16);
+    assert!(threading.values().all(|shafts| shafts.len() == 1));
+    assert!(threading.values().flatten().all(|s| *s <= Shaft(4)));
+}
+
+#[test]
+fn offset_repeat_takes_large_offsets() {
+    let mut wif = draft("1=1,2\n2=2,3\n3=3,4\n4=4,1\n");
+    let mut expected = wif.clone();
+    expected.repeat_offset(RepeatAxis::Warp, 1).unwrap();
+    wif.repeat_offset(RepeatAxis::Warp, 3_000_000_001).unwrap();
+    assert_eq!(cloth(&wif), cloth(&expected));
+}
+
+#[test]
+fn offset_repeat_keeps_a_sinking_shed_draft_the_right_way_up() {
+    let mut wif = draft("1=1,2\n2=2,3\n3=3,4\n4=4,1\n");
+    wif.weaving.as_mut().unwrap().rising_shed = Some(false);
+    let before = cloth(&wif);
+    wif.repeat_offset(RepeatAxis::Warp, 2).unwrap();
+    let after = cloth(&wif);
+    for pick in 0..4 {
+        for end in 0..4 {
+            assert_eq!(after[pick][end], before[pick][end]);
+            assert_eq!(after[pick][end + 4], before[(pick + 2) % 4][end]);
+        }
+    }
+}
+
+#[test]
+fn offset_repeat_drops_a_tieup_without_treadling() {
+    let mut wif = draft("1=1,2\n2=2,3\n3=3,4\n4=4,1\n");
+    wif.tieup = Some([(crate::Treadle(1), [Shaft(1)].into())].into());
+    wif.repeat_offset(RepeatAxis::Warp, 2).unwrap();
+    assert!(wif.tieup.is_none());
+    assert!(wif.treadling.is_none());
+}
+
+// The drawdown as rows of lifted ends, pick 1 first.
+fn cloth(wif: &crate::Wif) -> Vec<Vec<bool>> {
+    (1..=wif.height().unwrap())
+        .map(|pick| {
+            (1..=wif.width().unwrap())
+                .map(|end| wif.warp_or_weft(end, pick) == Some(crate::WarpOrWeft::Warp))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn plain_repeat_tiles_threading_and_liftplan() {
+    let mut wif = draft("1=1\n2=1,2\n3=1,2,3\n4=4\n");
+    let before = cloth(&wif);
+    wif.repeat(3, 2, RepeatStyle::Plain).unwrap();
+    assert_eq!((wif.width(), wif.height()), (Some(12), Some(8)));
+    assert_eq!(
+        wif.threading.as_ref().unwrap()[&Warp(10)],
+        [Shaft(2)].into()
+    );
+    let after = cloth(&wif);
+    for (pick, row) in after.iter().enumerate() {
+        for (end, &up) in row.iter().enumerate() {
+            assert_eq!(up, before[pick % 4][end % 4]);
+        }
+    }
+}
+
+#[test]
+fn half_drop_and_brick_repeats_shift_the_copies() {
+    let before = cloth(&draft("1=1\n2=1,2\n3=1,2,3\n4=4\n"));
+
+    let mut dropped = draft("1=1,2\n2=2,3\n3=3,4\n4=4,1\n");
+    let twill = cloth(&dropped);
+    dropped.repeat(2, 1, RepeatStyle::HalfDrop).unwrap();
+    let after = cloth(&dropped);
+    for pick in 0..4 {
+        for end in 0..4 {
+            assert_eq!(after[pick][end + 4], twill[(pick + 2) % 4][end]);
+        }
+    }
+
+    let mut brick = draft("1=1\n2=1,2\n3=1,2,3\n4=4\n");
+    brick.repeat(1, 3, RepeatStyle::Brick).unwrap();
+    assert_eq!((brick.width(), brick.height()), (Some(4), Some(12)));
+    let after = cloth(&brick);
+    for pick in 0..12 {
+        for end in 0..4 {
+            let shift = pick / 4 * 2;
+            assert_eq!(
+                after[pick][end],
+                before[pick % 4][(end + 4 - shift % 4) % 4]
+            );
+        }
+    }
+
+    let mut wif = draft("1=1\n2=1,2\n3=1,2,3\n4=4\n");
+    assert!(matches!(
+        wif.repeat(2, 1, RepeatStyle::HalfDrop),
+        Err(WifError::RepeatNeedsMoreShafts { available: 4, .. })
+    ));
+}
+
+#[test]
+fn half_drop_and_brick_repeats_keep_a_sinking_shed_draft_the_right_way_up() {
+    for style in [RepeatStyle::HalfDrop, RepeatStyle::Brick] {
+        let mut rising = draft("1=1,2\n2=2,3\n3=3,4\n4=4,1\n");
+        let mut sinking = draft("1=3,4\n2=1,4\n3=1,2\n4=2,3\n");
+        sinking.weaving.as_mut().unwrap().rising_shed = Some(false);
+        assert_eq!(cloth(&sinking), cloth(&rising));
+        rising.repeat(2, 2, style).unwrap();
+        sinking.repeat(2, 2, style).unwrap();
+        assert_eq!(cloth(&sinking), cloth(&rising));
+    }
+}
@@ -1,22 +1,76 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
     num::{ParseFloatError, ParseIntError},
+    path::PathBuf,
     str::FromStr,
+    sync::Arc,
 };
 
+pub mod analysis;
+pub mod bundle;
+pub mod generators;
+pub mod instructions;
+pub mod layout;
+pub mod loom;
+pub mod search;
 pub mod wifparse;
 
+mod builder;
+pub use builder::*;
+
+mod diff;
+pub use diff::*;
+
+mod draft;
+pub use draft::*;
+
+mod merge;
+pub use merge::*;
+
+mod progress;
+pub use progress::*;
+
+mod repeats;
+pub use repeats::*;
+
+mod summary;
+pub use summary::*;
+
+mod tieup;
+pub use tieup::*;
+
+mod units;
+pub use units::*;
+
 mod wif;
 pub use wif::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Treadle(pub u32);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Shaft(u32);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Warp(u32);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Weft(u32);
+/// An entry in the color table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ColorIndex(pub u32);
+/// An entry in a warp or weft symbol table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct SymbolIndex(pub u32);
 impl From<u32> for Treadle {
     fn from(value: u32) -> Self {
         Treadle(value)
@@ -37,6 +91,16 @@ impl From<u32> for Weft {
         Weft(value)
     }
 }
+impl From<u32> for ColorIndex {
+    fn from(value: u32) -> Self {
+        ColorIndex(value)
+    }
+}
+impl From<u32> for SymbolIndex {
+    fn from(value: u32) -> Self {
+        SymbolIndex(value)
+    }
+}
 impl FromStr for Treadle {
     type Err = ParseIntError;
 
@@ -65,6 +129,20 @@ impl FromStr for Weft {
         s.parse::<u32>().map(Weft)
     }
 }
+impl FromStr for ColorIndex {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+        s.parse::<u32>().map(ColorIndex)
+    }
+}
+impl FromStr for SymbolIndex {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+        s.parse::<u32>().map(SymbolIndex)
+    }
+}
 
 impl std::fmt::Display for Treadle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -81,6 +159,16 @@ impl std::fmt::Display for Weft {
         self.0.fmt(f)
     }
 }
+impl std::fmt::Display for ColorIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+impl std::fmt::Display for SymbolIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 type Table<S, T> = BTreeMap<S, T>;
 pub type Result<T, E = WifError> = std::result::Result<T, E>;
@@ -140,17 +228,76 @@ pub enum WifError {
     ExpectedBool { saw: String },
     #[error("Section {section} was indicated in CONTENTS, but could not be found")]
     MissingSection { section: String },
+    #[error("Key {key} appears more than once in section [{section}]")]
+    DuplicateKey { section: String, key: String },
     #[error("Could not parse table key for section [{section}]: saw {key}")]
     CouldNotParseTableKey { section: String, key: String },
+    #[error("Draft has no liftplan, and none could be built from its treadling and tieup")]
+    MissingLiftplan,
     #[error("Lift plan does not match treadling and tieup")]
     LiftPlanDoesNotMatchTreadling,
+    #[error("Expected a symbol (a character, a quoted character, or #code), but saw {saw}")]
+    InvalidSymbol { saw: String },
+    #[error("Expected one weight per palette color ({colors}), but saw {weights}")]
+    MismatchedWeights { colors: usize, weights: usize },
+    #[error("Palette is empty or all of its weights are zero")]
+    EmptyPalette,
+    #[error("Stripes are empty or all of their lengths are zero")]
+    EmptyStripes,
+    #[error("Offset repeat needs {needed} shafts, but the loom has {available}")]
+    RepeatNeedsMoreShafts { needed: u32, available: u32 },
+    #[error("Liftplan needs {needed} treadles, but only {available} are available")]
+    LiftplanNeedsMoreTreadles { needed: u32, available: u32 },
+    #[error("Draft does not weave two separate layers")]
+    NotDoubleweave,
+    #[error("Threading uses shaft {shaft}, but only {shafts} shafts are available")]
+    ThreadingExceedsShafts { shaft: u32, shafts: u32 },
+    #[error("[{section}] uses {what} {value}, but the draft only has {max}")]
+    OutOfRange {
+        section: String,
+        what: &'static str,
+        value: u32,
+        max: u32,
+    },
+    #[error("Colorway has {saw} {what}, but the draft has {expected}")]
+    ColorwayMismatch {
+        what: &'static str,
+        expected: usize,
+        saw: usize,
+    },
+    #[error("Color {idx} is not in the color table")]
+    UnknownColor { idx: u32 },
+    #[error("Colorway uses yarn {yarn}, but only {yarns} yarns are defined")]
+    UnknownYarn { yarn: usize, yarns: usize },
+    #[error("A drawdown of {ends} ends by {picks} picks is too large to hold")]
+    DrawdownTooLarge { ends: u32, picks: u32 },
+    #[error("Unknown units {units}")]
+    UnknownUnits { units: String },
     #[error("Colors must be three numbers")]
     ColorsMustBeThreeParts,
+    #[error("No [WIF] section was found in the text")]
+    NoWifSection,
+    #[error("Operation was cancelled")]
+    Cancelled,
     #[error("Could not parse WIF file: {0}")]
     CouldNotParseWifFile(String),
+    #[error("Could not read JSON draft: {0}")]
+    InvalidJson(String),
+    #[error("Unsupported JSON format {format} version {version}")]
+    UnsupportedJsonFormat { format: String, version: u32 },
+    #[error("Could not read WIF data: {0}")]
+    CouldNotRead(Arc<std::io::Error>),
+    #[error("Could not read {}: {error}", .path.display())]
+    CouldNotReadFile {
+        path: PathBuf,
+        error: Arc<std::io::Error>,
+    },
+    #[error("{}: {err}", .path.display())]
+    InFile { path: PathBuf, err: Box<WifError> },
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub red: u32,
     pub green: u32,
@@ -0,0 +1,182 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::wif::sections;
+use crate::Wif;
+
+#[cfg(test)]
+mod tests;
+
+/// How the loom's shafts are moved by its treadles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoomType {
+    /// Shafts rise independently, so several treadles can be pressed together.
+    #[default]
+    Jack,
+    /// Every shaft is tied to every treadle, either to rise or to sink, so only one treadle
+    /// can be pressed at a time.
+    Countermarch,
+    /// Shafts are balanced against each other in pairs.
+    Counterbalance,
+    /// Shafts are lifted by a dobby head straight from the liftplan, so there are no
+    /// treadles to run out of.
+    Dobby,
+}
+
+impl LoomType {
+    /// Whether a pick may use more than one treadle, or on a dobby, lift any shafts at all.
+    pub fn allows_multiple_treadles(&self) -> bool {
+        matches!(self, LoomType::Jack | LoomType::Dobby)
+    }
+
+    /// Whether the shafts are worked by treadles tied to them.
+    pub fn has_treadles(&self) -> bool {
+        !matches!(self, LoomType::Dobby)
+    }
+}
+
+/// The equipment a draft is to be woven on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoomProfile {
+    pub shafts: u32,
+    /// Ignored for a [`LoomType::Dobby`].
+    pub treadles: u32,
+    /// Usable length of the reed, in inches.
+    pub max_width: f64,
+    /// Whether treadling lifts the shafts tied to it, rather than lowering them.
+    pub rising_shed: bool,
+    pub loom_type: LoomType,
+    /// The reeds on hand, in dents per inch.
+    pub reeds: Vec<u32>,
+}
+
+/// A four-shaft, six-treadle jack loom with a 36 inch reed.
+impl Default for LoomProfile {
+    fn default() -> Self {
+        Self {
+            shafts: 4,
+            treadles: 6,
+            max_width: 36.,
+            rising_shed: true,
+            loom_type: LoomType::Jack,
+            reeds: vec![],
+        }
+    }
+}
+
+/// A reason a draft can't be woven on a loom as written, from [`Wif::check_against_loom`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoomIssue {
+    /// The draft threads more shafts than the loom has.
+    TooManyShafts { needed: u32, available: u32 },
+    /// The draft presses more treadles than the loom has. A draft with only a liftplan
+    /// needs one treadle for each different lift.
+    TooManyTreadles { needed: u32, available: u32 },
+    /// The warp is wider than the reed, both in inches.
+    TooWide { width: f64, max_width: f64 },
+    /// The draft is written for the other shed direction, so its tieup must be inverted.
+    ShedDirection { draft_rising_shed: bool },
+}
+
+impl LoomIssue {
+    /// A stable name for the issue, such as `too-many-shafts`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LoomIssue::TooManyShafts { .. } => "too-many-shafts",
+            LoomIssue::TooManyTreadles { .. } => "too-many-treadles",
+            LoomIssue::TooWide { .. } => "too-wide",
+            LoomIssue::ShedDirection { .. } => "shed-direction",
+        }
+    }
+
+    /// The section of the draft the issue is about.
+    pub fn section(&self) -> &'static str {
+        match self {
+            LoomIssue::TooWide { .. } => sections::WARP,
+            _ => sections::WEAVING,
+        }
+    }
+}
+
+impl fmt::Display for LoomIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoomIssue::TooManyShafts { needed, available } => {
+                write!(f, "needs {needed} shafts, but the loom has {available}")
+            }
+            LoomIssue::TooManyTreadles { needed, available } => {
+                write!(f, "needs {needed} treadles, but the loom has {available}")
+            }
+            LoomIssue::TooWide { width, max_width } => {
+                write!(f, "is {width:.1}\" wide, but the reed is {max_width:.1}\"")
+            }
+            LoomIssue::ShedDirection { draft_rising_shed } => {
+                let (draft, loom) = if *draft_rising_shed {
+                    ("rising", "sinking")
+                } else {
+                    ("sinking", "rising")
+                };
+                write!(
+                    f,
+                    "is written for a {draft} shed, but the loom has a {loom} shed"
+                )
+            }
+        }
+    }
+}
+
+impl Wif {
+    /// Whether the draft fits the loom: its shafts, treadles unless it's a dobby, width in
+    /// the reed and shed direction. Shafts and treadles are counted by how many are used, since unused ones
+    /// can be dropped. The width is only checked when every end has a spacing.
+    pub fn check_against_loom(&self, loom: &LoomProfile) -> Vec<LoomIssue> {
+        let mut issues = vec![];
+        let shafts: BTreeSet<_> = self
+            .threading
+            .iter()
+            .flat_map(|t| t.values().flatten())
+            .collect();
+        let needed = shafts.len() as u32;
+        if needed > loom.shafts {
+            issues.push(LoomIssue::TooManyShafts {
+                needed,
+                available: loom.shafts,
+            });
+        }
+
+        let needed = match (&self.treadling, &self.liftplan) {
+            (Some(treadling), _) => treadling.values().flatten().collect::<BTreeSet<_>>().len(),
+            (None, Some(liftplan)) => liftplan
+                .values()
+                .filter(|lift| !lift.is_empty())
+                .collect::<BTreeSet<_>>()
+                .len(),
+            (None, None) => 0,
+        } as u32;
+        if loom.loom_type.has_treadles() && needed > loom.treadles {
+            issues.push(LoomIssue::TooManyTreadles {
+                needed,
+                available: loom.treadles,
+            });
+        }
+
+        let spacings: Option<Vec<_>> = (1..=self.width().unwrap_or(0))
+            .map(|end| self.warp_spacing_at(end))
+            .collect();
+        if let Some(spacings) = spacings.filter(|s| !s.is_empty()) {
+            let width: Option<f64> = spacings.iter().map(|s| s.inches()).sum();
+            if let Some(width) = width.filter(|&width| width > loom.max_width) {
+                issues.push(LoomIssue::TooWide {
+                    width,
+                    max_width: loom.max_width,
+                });
+            }
+        }
+
+        let draft_rising_shed = self.weaving.as_ref().and_then(|w| w.rising_shed);
+        if let Some(draft_rising_shed) = draft_rising_shed.filter(|&r| r != loom.rising_shed) {
+            issues.push(LoomIssue::ShedDirection { draft_rising_shed });
+        }
+        issues
+    }
+}
@@ -0,0 +1,61 @@
+use super::{LoomIssue, LoomProfile, LoomType};
+
+fn sample() -> crate::Wif {
+    crate::parse(include_str!("../sample.wif")).unwrap()
+}
+
+#[test]
+fn big_enough_loom_has_no_issues() {
+    let loom = LoomProfile {
+        shafts: 8,
+        treadles: 10,
+        max_width: 60.,
+        ..Default::default()
+    };
+    assert_eq!(sample().check_against_loom(&loom), vec![]);
+}
+
+#[test]
+fn small_loom_reports_what_is_short() {
+    let mut wif = sample();
+    wif.weaving.as_mut().unwrap().rising_shed = Some(false);
+    let loom = LoomProfile {
+        max_width: 4.,
+        ..Default::default()
+    };
+    let issues = wif.check_against_loom(&loom);
+    assert_eq!(
+        issues[..2],
+        [
+            LoomIssue::TooManyShafts {
+                needed: 8,
+                available: 4
+            },
+            LoomIssue::TooManyTreadles {
+                needed: 8,
+                available: 6
+            },
+        ]
+    );
+    assert!(matches!(issues[2], LoomIssue::TooWide { width, .. } if width > 4.));
+    assert_eq!(
+        issues[3],
+        LoomIssue::ShedDirection {
+            draft_rising_shed: false
+        }
+    );
+
+    let dobby = LoomProfile {
+        shafts: 8,
+        treadles: 0,
+        max_width: 60.,
+        loom_type: LoomType::Dobby,
+        ..Default::default()
+    };
+    assert_eq!(
+        wif.check_against_loom(&dobby),
+        [LoomIssue::ShedDirection {
+            draft_rising_shed: false
+        }]
+    );
+}
@@ -0,0 +1,228 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::wif::sections;
+use crate::wifparse::WifParse;
+use crate::{diff, liftplan_from_threading_and_treadle, FieldChange, Table, Wif};
+
+#[cfg(test)]
+mod tests;
+
+/// A field or table entry that both sides changed differently. `key` is the field name or
+/// the thread, treadle or table index; values are written as they would be in the file, with
+/// `None` for an absent one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub section: &'static str,
+    pub key: String,
+    pub base: Option<String>,
+    pub mine: Option<String>,
+    pub theirs: Option<String>,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "(none)".into());
+        write!(
+            f,
+            "[{}] {}: base {}, mine {}, theirs {}",
+            self.section,
+            self.key,
+            show(&self.base),
+            show(&self.mine),
+            show(&self.theirs)
+        )
+    }
+}
+
+// Takes each entry from whichever side changed it, into `merged` (which starts as mine).
+fn merge_table<K, V>(
+    section: &'static str,
+    base: Option<&Table<K, V>>,
+    theirs: Option<&Table<K, V>>,
+    merged: &mut Option<Table<K, V>>,
+    conflicts: &mut Vec<MergeConflict>,
+) where
+    K: Ord + Copy + fmt::Display,
+    V: PartialEq + Clone + WifParse,
+{
+    let mine = merged.clone();
+    let keys: BTreeSet<K> = [base, mine.as_ref(), theirs]
+        .into_iter()
+        .flatten()
+        .flat_map(|t| t.keys())
+        .copied()
+        .collect();
+    for key in keys {
+        let b = base.and_then(|table| table.get(&key));
+        let m = mine.as_ref().and_then(|table| table.get(&key));
+        let t = theirs.and_then(|table| table.get(&key));
+        if m == t || b == t {
+            continue;
+        }
+        if b != m {
+            let show = |value: Option<&V>| value.and_then(WifParse::unparse);
+            conflicts.push(MergeConflict {
+                section,
+                key: key.to_string(),
+                base: show(b),
+                mine: show(m),
+                theirs: show(t),
+            });
+            continue;
+        }
+        let table = merged.get_or_insert_with(Default::default);
+        match t {
+            Some(value) => table.insert(key, value.clone()),
+            None => table.remove(&key),
+        };
+    }
+}
+
+// Copies one field, as named by `diff`, from theirs.
+fn take_field(merged: &mut Wif, theirs: &Wif, change: &FieldChange) {
+    use sections::{TEXT, WARP, WEAVING, WEFT, WIF};
+    match change.section {
+        WIF => {
+            let (m, t) = (&mut merged.wif_header, &theirs.wif_header);
+            match change.field {
+                "Version" => m.version = t.version.clone(),
+                "Date" => m.date = t.date,
+                "Developers" => m.developers = t.developers.clone(),
+                "Source Program" => m.source_program = t.source_program.clone(),
+                "Source Version" => m.source_version = t.source_version.clone(),
+                _ => {}
+            }
+        }
+        TEXT => match &theirs.text {
+            None => merged.text = None,
+            Some(t) => {
+                let m = merged.text.get_or_insert_with(|| t.clone());
+                match change.field {
+                    "Title" => m.title = t.title.clone(),
+                    "Author" => m.author = t.author.clone(),
+                    "Address" => m.address = t.address.clone(),
+                    "EMail" => m.email = t.email.clone(),
+                    "Telephone" => m.telephone = t.telephone.clone(),
+                    "FAX" => m.fax = t.fax.clone(),
+                    _ => {}
+                }
+            }
+        },
+        WEAVING => match &theirs.weaving {
+            None => merged.weaving = None,
+            Some(t) => {
+                let m = merged.weaving.get_or_insert_with(|| t.clone());
+                match change.field {
+                    "Shafts" => m.shafts = t.shafts,
+                    "Treadles" => m.treadles = t.treadles,
+                    "Rising Shed" => m.rising_shed = t.rising_shed,
+                    _ => {}
+                }
+            }
+        },
+        WARP => match &theirs.warp {
+            None => merged.warp = None,
+            Some(t) => {
+                let m = merged.warp.get_or_insert_with(|| t.clone());
+                match change.field {
+                    "Threads" => m.threads = t.threads,
+                    "Color" => m.color = t.color,
+                    "Units" => m.units = t.units.clone(),
+                    "Spacing" => m.spacing = t.spacing,
+                    "Thickness" => m.thickness = t.thickness,
+                    _ => {}
+                }
+            }
+        },
+        WEFT => match &theirs.weft {
+            None => merged.weft = None,
+            Some(t) => {
+                let m = merged.weft.get_or_insert_with(|| t.clone());
+                match change.field {
+                    "Threads" => m.threads = t.threads,
+                    "Color" => m.color = t.color,
+                    "Units" => m.units = t.units.clone(),
+                    "Spacing" => m.spacing = t.spacing,
+                    "Thickness" => m.thickness = t.thickness,
+                    _ => {}
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+/// Combines two drafts edited from a common `base`: each field and table entry comes from
+/// whichever side changed it. Fields [`diff`](crate::diff()) doesn't report, such as the
+/// palettes, come from `mine`. When the result has treadling and a tieup, its liftplan is
+/// rebuilt from them rather than merged. Entries both sides changed differently are returned
+/// as conflicts.
+pub fn merge(base: &Wif, mine: &Wif, theirs: &Wif) -> Result<Wif, Vec<MergeConflict>> {
+    let mut merged = mine.clone();
+    let mut conflicts = vec![];
+
+    let ours = diff(base, mine).fields;
+    for change in diff(base, theirs).fields {
+        let same_field = |c: &&FieldChange| c.section == change.section && c.field == change.field;
+        match ours.iter().find(same_field) {
+            Some(our) if our.new == change.new => {}
+            Some(our) => conflicts.push(MergeConflict {
+                section: change.section,
+                key: change.field.into(),
+                base: change.old.clone(),
+                mine: our.new.clone(),
+                theirs: change.new.clone(),
+            }),
+            None => take_field(&mut merged, theirs, &change),
+        }
+    }
+
+    macro_rules! merge_tables {
+        ($($field:ident: $section:ident),* $(,)?) => {
+            $(merge_table(
+                sections::$section,
+                base.$field.as_ref(),
+                theirs.$field.as_ref(),
+                &mut merged.$field,
+                &mut conflicts,
+            );)*
+        };
+    }
+    merge_tables!(
+        color_table: COLOR_TABLE,
+        notes: NOTES,
+        tieup: TIEUP,
+        warp_symbol_table: WARP_SYMBOL_TABLE,
+        weft_symbols_table: WEFT_SYMBOL_TABLE,
+        threading: THREADING,
+        warp_thickness: WARP_THICKNESS,
+        warp_thickness_zoom: WARP_THICKNESS_ZOOM,
+        warp_spacing: WARP_SPACING,
+        warp_spacing_zoom: WARP_SPACING_ZOOM,
+        warp_colors: WARP_COLORS,
+        warp_symbols: WARP_SYMBOLS,
+        treadling: TREADLING,
+        weft_thickness: WEFT_THICKNESS,
+        weft_thickness_zoom: WEFT_THICKNESS_ZOOM,
+        weft_spacing: WEFT_SPACING,
+        weft_spacing_zoom: WEFT_SPACING_ZOOM,
+        weft_colors: WEFT_COLORS,
+        weft_symbols: WEFT_SYMBOLS,
+    );
+    match liftplan_from_threading_and_treadle(merged.treadling.as_ref(), merged.tieup.as_ref()) {
+        Some(liftplan) => merged.liftplan = Some(liftplan),
+        None => {
+            merge_tables!(liftplan: LIFTPLAN);
+        }
+    }
+    if let (Some(table), Some(palette)) = (&merged.color_table, &mut merged.color_palette) {
+        palette.entries = crate::wif::palette_entries(table, |idx| idx.0);
+    }
+
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
@@ -0,0 +1,77 @@
+use super::{merge, MergeConflict};
+use crate::{ColorIndex, Shaft, Text, Treadle, Warp, Weft};
+
+fn sample() -> crate::Wif {
+    crate::parse(include_str!("../sample.wif")).unwrap()
+}
+
+#[test]
+fn combines_independent_edits() {
+    let base = sample();
+    let mut mine = base.clone();
+    mine.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(1), ColorIndex(1));
+    let mut theirs = base.clone();
+    theirs.text = Some(Text {
+        title: Some("Theirs".into()),
+        author: None,
+        address: None,
+        email: None,
+        telephone: None,
+        fax: None,
+    });
+    theirs.set_treadling({
+        let mut treadling = base.treadling.clone().unwrap();
+        treadling.insert(Weft(1), [Treadle(2)].into());
+        treadling
+    });
+    theirs.color_table.as_mut().unwrap().remove(&ColorIndex(2));
+
+    let merged = merge(&base, &mine, &theirs).unwrap();
+    assert_eq!(
+        merged.warp_colors.as_ref().unwrap()[&Warp(1)],
+        ColorIndex(1)
+    );
+    assert_eq!(merged.text.unwrap().title.as_deref(), Some("Theirs"));
+    assert_eq!(
+        merged.treadling.as_ref().unwrap()[&Weft(1)],
+        [Treadle(2)].into()
+    );
+    assert_eq!(merged.liftplan, theirs.liftplan);
+    assert!(!merged.color_table.unwrap().contains_key(&ColorIndex(2)));
+    assert_eq!(merged.color_palette.unwrap().entries, 82);
+}
+
+#[test]
+fn reports_conflicting_threads() {
+    let base = sample();
+    let mut mine = base.clone();
+    mine.threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(2), [Shaft(1)].into());
+    let mut theirs = base.clone();
+    theirs
+        .threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(2), [Shaft(3)].into());
+    theirs.threading.as_mut().unwrap().remove(&Warp(3));
+
+    let conflicts = merge(&base, &mine, &theirs).unwrap_err();
+    assert_eq!(
+        conflicts,
+        vec![MergeConflict {
+            section: "THREADING",
+            key: "2".into(),
+            base: base.threading.as_ref().unwrap()[&Warp(2)]
+                .iter()
+                .map(|s| s.0.to_string())
+                .reduce(|a, b| a + "," + &b),
+            mine: Some("1".into()),
+            theirs: Some("3".into()),
+        }]
+    );
+}
@@ -0,0 +1,37 @@
+/// Receives progress reports from long-running operations.
+///
+/// `update` is called with the fraction of work completed, from 0.0 to 1.0. Returning `false`
+/// cancels the operation, which then fails with [`WifError::Cancelled`](crate::WifError).
+pub trait Progress {
+    fn update(&mut self, fraction: f64) -> bool;
+}
+
+/// Ignores all progress reports.
+impl Progress for () {
+    fn update(&mut self, _fraction: f64) -> bool {
+        true
+    }
+}
+
+impl<F> Progress for F
+where
+    F: FnMut(f64) -> bool,
+{
+    fn update(&mut self, fraction: f64) -> bool {
+        self(fraction)
+    }
+}
+
+/// Reports `done` out of `total` steps, turning a cancellation into an error.
+pub(crate) fn report(progress: &mut dyn Progress, done: usize, total: usize) -> crate::Result<()> {
+    let fraction = if total == 0 {
+        1.0
+    } else {
+        done as f64 / total as f64
+    };
+    if progress.update(fraction) {
+        Ok(())
+    } else {
+        Err(crate::WifError::Cancelled)
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use crate::summary::period;
+use crate::{ColorIndex, Table, Warp, Weft, Wif};
+
+#[cfg(test)]
+mod tests;
+
+/// A sequence made of a unit repeated `count` times, followed by the first `remainder`
+/// items of one more unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeat {
+    pub length: usize,
+    pub count: usize,
+    pub remainder: usize,
+}
+
+impl Repeat {
+    fn of<T: PartialEq>(items: &[T]) -> Option<Repeat> {
+        if items.is_empty() {
+            return None;
+        }
+        let length = period(items);
+        Some(Repeat {
+            length,
+            count: items.len() / length,
+            remainder: items.len() % length,
+        })
+    }
+
+    // E.g. `24-end repeat × 18 + 3`.
+    fn describe(&self, f: &mut fmt::Formatter<'_>, thread: &str) -> fmt::Result {
+        write!(f, "{}-{thread} repeat × {}", self.length, self.count)?;
+        if self.remainder > 0 {
+            write!(f, " + {}", self.remainder)?;
+        }
+        Ok(())
+    }
+}
+
+/// The smallest repeating unit of each thread sequence of a draft, from
+/// [`Wif::detect_repeats`]. `None` where the draft has no such sequence.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Repeats {
+    pub threading: Option<Repeat>,
+    pub treadling: Option<Repeat>,
+    pub liftplan: Option<Repeat>,
+    pub warp_colors: Option<Repeat>,
+    pub weft_colors: Option<Repeat>,
+}
+
+/// One line per sequence, e.g. `threading: 24-end repeat × 18`.
+impl fmt::Display for Repeats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = [
+            ("threading", "end", self.threading),
+            ("treadling", "pick", self.treadling),
+            ("liftplan", "pick", self.liftplan),
+            ("warp colors", "end", self.warp_colors),
+            ("weft colors", "pick", self.weft_colors),
+        ];
+        for (name, thread, repeat) in lines {
+            if let Some(repeat) = repeat {
+                write!(f, "{name}: ")?;
+                repeat.describe(f, thread)?;
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The repeat of a per-thread table over threads `1..=threads`.
+fn sequence<K: Ord, V: PartialEq>(
+    table: Option<&Table<K, V>>,
+    threads: u32,
+    key: fn(u32) -> K,
+) -> Option<Repeat> {
+    let table = table?;
+    Repeat::of(
+        &(1..=threads)
+            .map(|n| table.get(&key(n)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+// The repeat of a color order, unless no thread has a color.
+fn colors(colors: Vec<Option<ColorIndex>>) -> Option<Repeat> {
+    if colors.iter().all(Option::is_none) {
+        return None;
+    }
+    Repeat::of(&colors)
+}
+
+impl Wif {
+    /// Finds the smallest repeat of the threading, treadling, liftplan and warp and weft
+    /// color orders. Each sequence runs over every end or pick; a missing entry counts as
+    /// its own value, and colors fall back to the [WARP] or [WEFT] default. The last repeat
+    /// may be partial.
+    pub fn detect_repeats(&self) -> Repeats {
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        Repeats {
+            threading: sequence(self.threading.as_ref(), ends, Warp),
+            treadling: sequence(self.treadling.as_ref(), picks, Weft),
+            liftplan: sequence(self.liftplan.as_ref(), picks, Weft),
+            warp_colors: colors(
+                (1..=ends)
+                    .map(|end| self.warp_color_idx(Warp(end)))
+                    .collect(),
+            ),
+            weft_colors: colors(
+                (1..=picks)
+                    .map(|pick| self.weft_color_idx(Weft(pick)))
+                    .collect(),
+            ),
+        }
+    }
+}
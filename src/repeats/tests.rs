@@ -0,0 +1,55 @@
+use super::Repeat;
+use crate::WifBuilder;
+
+#[test]
+fn finds_the_smallest_repeats() {
+    let wif = WifBuilder::new()
+        .threading_from((0..26).map(|end| [1, 2, 3, 4, 3, 2][end % 6]))
+        .liftplan(
+            [[1, 2], [2, 3], [3, 4], [4, 1]]
+                .iter()
+                .cycle()
+                .take(8)
+                .map(|l| l.to_vec()),
+        )
+        .build()
+        .unwrap();
+    let repeats = wif.detect_repeats();
+    assert_eq!(
+        repeats.threading,
+        Some(Repeat {
+            length: 6,
+            count: 4,
+            remainder: 2,
+        })
+    );
+    assert_eq!(
+        repeats.liftplan,
+        Some(Repeat {
+            length: 4,
+            count: 2,
+            remainder: 0,
+        })
+    );
+    assert_eq!(repeats.treadling, None);
+    assert!(repeats
+        .to_string()
+        .starts_with("threading: 6-end repeat × 4 + 2\nliftplan: 4-pick repeat × 2\n"));
+}
+
+#[test]
+fn sample_repeats() {
+    let wif = crate::parse(include_str!("../sample.wif")).unwrap();
+    let repeats = wif.detect_repeats();
+    for repeat in [repeats.threading, repeats.treadling, repeats.warp_colors] {
+        let repeat = repeat.unwrap();
+        assert!(repeat.count >= 1);
+        assert!(repeat.remainder < repeat.length);
+    }
+    let threading = repeats.threading.unwrap();
+    let ends = wif.width().unwrap() as usize;
+    assert_eq!(
+        threading.length * threading.count + threading.remainder,
+        ends
+    );
+}
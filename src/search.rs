@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+
+use crate::{Result, Shaft, Table, Weft, Wif, WifError};
+
+#[cfg(test)]
+mod tests;
+
+/// Which symmetry candidate liftplans must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Symmetry {
+    #[default]
+    None,
+    /// The liftplan reads the same forwards and backwards.
+    Mirror,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraints {
+    /// Only shafts `1..=shafts` are lifted.
+    pub shafts: u32,
+    /// Length of the liftplan repeat.
+    pub picks: u32,
+    /// Longest float allowed on either face, in threads.
+    pub max_float: u32,
+    /// Require the warp and weft to show equally on the face.
+    pub balanced: bool,
+    pub symmetry: Symmetry,
+    /// How many candidates to return.
+    pub limit: usize,
+    /// How many lifts and partial liftplans to try before giving up on finding more. Every
+    /// lift of the threaded shafts is tried once, so many shafts can use up the budget on
+    /// their own.
+    pub max_nodes: u64,
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Self {
+            shafts: 4,
+            picks: 4,
+            max_float: 3,
+            balanced: false,
+            symmetry: Symmetry::None,
+            limit: 20,
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The base draft with its liftplan replaced; treadling and tieup are dropped.
+    pub wif: Wif,
+    pub longest_float: u32,
+    /// Fraction of the face showing warp.
+    pub warp_fraction: f64,
+}
+
+// Longest run of equal values in a cyclic sequence.
+fn longest_run(len: usize, value: impl Fn(usize) -> bool) -> u32 {
+    if len == 0 {
+        return 0;
+    }
+    let Some(start) = (0..len).find(|&i| value(i) != value((i + len - 1) % len)) else {
+        return len as u32;
+    };
+    let (mut longest, mut run) = (0, 0);
+    for i in 0..len {
+        let i = (start + i) % len;
+        if i != start && value(i) == value((i + len - 1) % len) {
+            run += 1;
+        } else {
+            run = 1;
+        }
+        longest = longest.max(run);
+    }
+    longest
+}
+
+struct Search<'a> {
+    constraints: &'a Constraints,
+    /// Shaft mask for each threaded end
+    ends: Vec<u32>,
+    /// Lifts whose rows don't float too far across the warp
+    rows: Vec<u32>,
+    partial: Vec<u32>,
+    nodes: u64,
+    found: Vec<(Vec<u32>, u32, f64)>,
+}
+
+impl Search<'_> {
+    fn lifted(&self, end: usize, row: u32) -> bool {
+        self.ends[end] & row != 0
+    }
+
+    // Columns must not float too far within the rows chosen so far.
+    fn columns_ok(&self) -> bool {
+        let max = self.constraints.max_float as usize;
+        let Some(&last) = self.partial.last() else {
+            return true;
+        };
+        (0..self.ends.len()).all(|end| {
+            let state = self.lifted(end, last);
+            self.partial
+                .iter()
+                .rev()
+                .take_while(|&&row| self.lifted(end, row) == state)
+                .count()
+                <= max
+        })
+    }
+
+    fn run(&mut self) {
+        if self.nodes >= self.constraints.max_nodes {
+            return;
+        }
+        self.nodes += 1;
+        let picks = self.constraints.picks as usize;
+        let needed = match self.constraints.symmetry {
+            Symmetry::None => picks,
+            Symmetry::Mirror => picks.div_ceil(2),
+        };
+        if self.partial.len() == needed {
+            self.complete();
+            return;
+        }
+        for i in 0..self.rows.len() {
+            self.partial.push(self.rows[i]);
+            if self.columns_ok() {
+                self.run();
+            }
+            self.partial.pop();
+        }
+    }
+
+    fn complete(&mut self) {
+        let picks = self.constraints.picks as usize;
+        let mut rows = self.partial.clone();
+        if self.constraints.symmetry == Symmetry::Mirror {
+            rows.extend(self.partial[..picks - self.partial.len()].iter().rev());
+        }
+        // Rotations of a liftplan are the same structure; keep only the least one
+        if (1..picks).any(|shift| rows[shift..].iter().chain(&rows[..shift]).lt(rows.iter())) {
+            return;
+        }
+        let column_float = (0..self.ends.len())
+            .map(|end| longest_run(picks, |pick| self.lifted(end, rows[pick])))
+            .max()
+            .unwrap_or(0);
+        if column_float > self.constraints.max_float {
+            return;
+        }
+        let row_float = rows
+            .iter()
+            .map(|&row| longest_run(self.ends.len(), |end| self.lifted(end, row)))
+            .max()
+            .unwrap_or(0);
+        let warp_up = rows
+            .iter()
+            .map(|&row| {
+                (0..self.ends.len())
+                    .filter(|&e| self.lifted(e, row))
+                    .count()
+            })
+            .sum::<usize>();
+        let cells = picks * self.ends.len();
+        if self.constraints.balanced && warp_up.abs_diff(cells - warp_up) > 1 {
+            return;
+        }
+        let warp_fraction = warp_up as f64 / cells as f64;
+        self.found
+            .push((rows, column_float.max(row_float), warp_fraction));
+        if self.found.len() > self.constraints.limit * 4 {
+            self.rank();
+        }
+    }
+
+    // Shortest floats first, then closest to balanced.
+    fn rank(&mut self) {
+        self.found.sort_by(|a, b| {
+            let imbalance = |f: f64| (f - 0.5).abs();
+            a.1.cmp(&b.1)
+                .then(imbalance(a.2).total_cmp(&imbalance(b.2)))
+                .then(a.0.cmp(&b.0))
+        });
+        self.found.truncate(self.constraints.limit);
+    }
+}
+
+/// Enumerates liftplans for the threading of `base` that satisfy `constraints`, and returns
+/// the best of them as drafts, shortest floats first.
+pub fn find_structures(base: &Wif, constraints: &Constraints) -> Result<Vec<Candidate>> {
+    let shafts = constraints.shafts.min(31);
+    let mut ends = Vec::new();
+    for shaft_set in base.threading.iter().flat_map(|t| t.values()) {
+        let mut mask = 0;
+        for &Shaft(shaft) in shaft_set {
+            if shaft == 0 || shaft > shafts {
+                return Err(WifError::ThreadingExceedsShafts { shaft, shafts });
+            }
+            mask |= 1 << (shaft - 1);
+        }
+        if mask != 0 {
+            ends.push(mask);
+        }
+    }
+    if ends.is_empty() || constraints.picks == 0 {
+        return Ok(vec![]);
+    }
+    // Lifting a shaft no end is on changes nothing, so only the threaded shafts are tried,
+    // and never all of them at once.
+    let threaded = ends.iter().fold(0, |mask, end| mask | end);
+    let mut search = Search {
+        constraints,
+        rows: vec![],
+        ends,
+        partial: vec![],
+        nodes: 0,
+        found: vec![],
+    };
+    // Steps through the subsets of `threaded` in increasing order.
+    let mut row = 0u32;
+    loop {
+        row = (row | !threaded).wrapping_add(1) & threaded;
+        if row == 0 || row == threaded || search.nodes >= constraints.max_nodes {
+            break;
+        }
+        search.nodes += 1;
+        if longest_run(search.ends.len(), |end| search.lifted(end, row)) <= constraints.max_float {
+            search.rows.push(row);
+        }
+    }
+    search.run();
+    search.rank();
+
+    Ok(search
+        .found
+        .into_iter()
+        .map(|(rows, longest_float, warp_fraction)| {
+            let liftplan: Table<Weft, BTreeSet<Shaft>> = (1..)
+                .zip(rows)
+                .map(|(pick, row)| {
+                    let shafts = (1..=shafts)
+                        .filter(|shaft| row & (1 << (shaft - 1)) != 0)
+                        .map(Shaft)
+                        .collect();
+                    (Weft(pick), shafts)
+                })
+                .collect();
+            let mut wif = base.clone();
+            wif.liftplan = Some(liftplan);
+            wif.treadling = None;
+            wif.tieup = None;
+            if let Some(weft) = wif.weft.as_mut() {
+                weft.threads = constraints.picks;
+            }
+            Candidate {
+                wif,
+                longest_float,
+                warp_fraction,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,83 @@
+use super::{find_structures, Constraints, Symmetry};
+use crate::{Shaft, Weft};
+
+fn straight_draw() -> crate::Wif {
+    crate::parse(
+        "[WIF]\nVersion=1.1\nDate=April 20, 1997\nDevelopers=x\nSource Program=x\n\
+         [CONTENTS]\nWEAVING=true\nWARP=true\nWEFT=true\nTHREADING=true\n\
+         [WEAVING]\nShafts=4\nTreadles=4\n[WARP]\nThreads=4\n[WEFT]\nThreads=4\n\
+         [THREADING]\n1=1\n2=2\n3=3\n4=4\n",
+    )
+    .unwrap()
+}
+
+#[test]
+fn finds_plain_weave_and_twills() {
+    let base = straight_draw();
+    let constraints = Constraints {
+        max_float: 1,
+        picks: 2,
+        ..Default::default()
+    };
+    let plain = find_structures(&base, &constraints).unwrap();
+    assert_eq!(plain.len(), 1);
+    let liftplan = plain[0].wif.liftplan.as_ref().unwrap();
+    assert_eq!(liftplan[&Weft(1)], [Shaft(1), Shaft(3)].into());
+    assert_eq!(plain[0].warp_fraction, 0.5);
+
+    let constraints = Constraints {
+        max_float: 2,
+        balanced: true,
+        ..Default::default()
+    };
+    let balanced = find_structures(&base, &constraints).unwrap();
+    assert!(!balanced.is_empty());
+    assert!(balanced
+        .iter()
+        .all(|c| c.warp_fraction == 0.5 && c.longest_float <= 2));
+    // Plain weave comes first
+    assert_eq!(balanced[0].longest_float, 1);
+
+    let mirrored = find_structures(
+        &base,
+        &Constraints {
+            symmetry: Symmetry::Mirror,
+            ..constraints
+        },
+    )
+    .unwrap();
+    for candidate in mirrored {
+        let liftplan = candidate.wif.liftplan.unwrap();
+        let rows: Vec<_> = liftplan.values().collect();
+        assert!(rows.iter().eq(rows.iter().rev()));
+    }
+}
+
+#[test]
+fn lifts_count_against_the_node_budget() {
+    use crate::Warp;
+    // Four ends on 24 shafts give millions of lifts, more than the budget.
+    let mut base = straight_draw();
+    let threading = base.threading.as_mut().unwrap();
+    for end in 1..=4 {
+        threading.insert(Warp(end), (end * 6 - 5..=end * 6).map(Shaft).collect());
+    }
+    let constraints = Constraints {
+        shafts: 24,
+        max_nodes: 10_000,
+        ..Default::default()
+    };
+    assert!(find_structures(&base, &constraints).unwrap().is_empty());
+
+    // Shafts nothing is threaded on aren't lifted.
+    let constraints = Constraints {
+        shafts: 31,
+        ..Default::default()
+    };
+    let found = find_structures(&straight_draw(), &constraints).unwrap();
+    assert!(!found.is_empty());
+    assert!(found
+        .iter()
+        .flat_map(|c| c.wif.liftplan.as_ref().unwrap().values().flatten().copied())
+        .all(|shaft| shaft <= Shaft(4)));
+}
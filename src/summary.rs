@@ -0,0 +1,327 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::analysis::lift_sequence;
+use crate::bundle::longest_floats;
+use crate::{ColorIndex, Result, Warp, Weft, Wif};
+
+#[cfg(test)]
+mod tests;
+
+/// A best guess at the weave structure, from the smallest repeat of the drawdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Structure {
+    PlainWeave,
+    /// Each pick is the previous one moved over by one end.
+    Twill,
+    /// Each pick is the previous one moved over by a fixed step of more than one end, with a
+    /// single interlacement per pick.
+    Satin,
+    Other,
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Structure::PlainWeave => "plain weave",
+            Structure::Twill => "twill",
+            Structure::Satin => "satin",
+            Structure::Other => "other",
+        })
+    }
+}
+
+/// A one-line description of a draft, from [`Wif::summary`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DraftSummary {
+    /// Shafts with at least one end threaded on them.
+    pub shafts_used: u32,
+    /// Shafts declared in [WEAVING].
+    pub shafts: Option<u32>,
+    /// Treadles used by at least one pick.
+    pub treadles_used: u32,
+    pub treadles: Option<u32>,
+    pub ends: u32,
+    pub picks: u32,
+    /// Distinct colors used by the ends and picks.
+    pub colors: usize,
+    pub structure: Structure,
+    pub longest_warp_float: u32,
+    pub longest_weft_float: u32,
+}
+
+impl fmt::Display for DraftSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |used: u32, declared: Option<u32>| match declared {
+            Some(declared) if declared != used => format!("{used} of {declared}"),
+            _ => used.to_string(),
+        };
+        write!(
+            f,
+            "{}, {} ends × {} picks, {} shafts, {} treadles, {} colors, longest floats {} warp / {} weft",
+            self.structure,
+            self.ends,
+            self.picks,
+            count(self.shafts_used, self.shafts),
+            count(self.treadles_used, self.treadles),
+            self.colors,
+            self.longest_warp_float,
+            self.longest_weft_float,
+        )
+    }
+}
+
+/// How many ends and picks use one color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorUsage {
+    pub color: ColorIndex,
+    pub ends: u32,
+    pub picks: u32,
+}
+
+/// Everything a catalog or dashboard lists about a draft, from [`Wif::stats`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DraftStats {
+    pub ends: u32,
+    pub picks: u32,
+    /// Shafts declared in [WEAVING].
+    pub shafts: Option<u32>,
+    /// Shafts with at least one end threaded on them.
+    pub shafts_used: u32,
+    pub treadles: Option<u32>,
+    /// Treadles used by at least one pick.
+    pub treadles_used: u32,
+    /// Different sets of shafts lifted across the liftplan.
+    pub distinct_lifts: usize,
+    /// Colors used by at least one end or pick, in color table order.
+    pub colors: Vec<ColorUsage>,
+    /// The longest warp and weft floats on either face.
+    pub longest_warp_float: u32,
+    pub longest_weft_float: u32,
+    /// Lengths of the smallest repeats, from [`Wif::detect_repeats`].
+    pub threading_repeat: Option<usize>,
+    pub treadling_repeat: Option<usize>,
+    pub liftplan_repeat: Option<usize>,
+    pub warp_color_repeat: Option<usize>,
+    pub weft_color_repeat: Option<usize>,
+    /// Ends and picks per inch, when the [WARP] and [WEFT] spacing give them.
+    pub ends_per_inch: Option<f64>,
+    pub picks_per_inch: Option<f64>,
+}
+
+/// A rectangle of the drawdown woven in one structure, from [`Wif::regions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub first_end: Warp,
+    pub last_end: Warp,
+    pub first_pick: Weft,
+    pub last_pick: Weft,
+    pub structure: Structure,
+}
+
+impl Region {
+    pub fn ends(&self) -> u32 {
+        self.last_end.0 - self.first_end.0 + 1
+    }
+
+    pub fn picks(&self) -> u32 {
+        self.last_pick.0 - self.first_pick.0 + 1
+    }
+}
+
+// Smallest p such that every item equals the one p further on.
+pub(crate) fn period<T: PartialEq>(items: &[T]) -> usize {
+    (1..items.len())
+        .find(|&p| items.iter().zip(&items[p..]).all(|(a, b)| a == b))
+        .unwrap_or(items.len())
+}
+
+fn structure(warp_on_top: &[Vec<bool>]) -> Structure {
+    let rows = period(warp_on_top);
+    let columns: Vec<Vec<bool>> = (0..warp_on_top.first().map_or(0, Vec::len))
+        .map(|end| warp_on_top.iter().map(|row| row[end]).collect())
+        .collect();
+    let n = period(&columns);
+    if n != rows || n < 2 {
+        return Structure::Other;
+    }
+    // The repeat, and the step that takes each of its rows to the next.
+    let unit: Vec<&[bool]> = warp_on_top[..n].iter().map(|row| &row[..n]).collect();
+    let moves_by = |step: usize| {
+        (0..n).all(|y| (0..n).all(|x| unit[(y + 1) % n][(x + step) % n] == unit[y][x]))
+    };
+    let Some(step) = (1..n).find(|&step| moves_by(step)) else {
+        return Structure::Other;
+    };
+    let interlacements = unit[0].iter().filter(|&&warp| warp).count();
+    let single = interlacements == 1 || interlacements == n - 1;
+    match (n, step) {
+        (2, _) => Structure::PlainWeave,
+        (_, 1) => Structure::Twill,
+        _ if step == n - 1 => Structure::Twill,
+        _ if n >= 5 && single && gcd(step, n) == 1 => Structure::Satin,
+        _ => Structure::Other,
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Splits `items` into runs that each repeat a unit at least twice, choosing the longest run
+// at each step and the shortest unit among equals. Items that start no repeat are gathered
+// into runs of their own. Returned as start and end, exclusive.
+fn segments<T: PartialEq>(items: &[T]) -> Vec<(usize, usize)> {
+    let mut found = vec![];
+    let mut loose = None;
+    let mut start = 0;
+    while start < items.len() {
+        let rest = &items[start..];
+        let run = |unit: usize| {
+            unit + rest[unit..]
+                .iter()
+                .zip(rest)
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+        let longest = (1..=rest.len() / 2)
+            .map(|unit| (run(unit), unit))
+            .filter(|&(len, unit)| len >= unit * 2)
+            .fold(
+                None,
+                |best: Option<(usize, usize)>, (len, unit)| match best {
+                    Some((best_len, _)) if best_len >= len => best,
+                    _ => Some((len, unit)),
+                },
+            );
+        match longest {
+            Some((len, _)) => {
+                found.extend(loose.take().map(|loose| (loose, start)));
+                found.push((start, start + len));
+                start += len;
+            }
+            None => {
+                loose.get_or_insert(start);
+                start += 1;
+            }
+        }
+    }
+    found.extend(loose.map(|loose| (loose, items.len())));
+    found
+}
+
+impl Wif {
+    /// Counts, colors, floats and a structure guess, for showing a draft in a listing.
+    pub fn summary(&self) -> DraftSummary {
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        let warp_on_top = self.to_drawdown_matrix();
+        let (longest_warp_float, longest_weft_float) = longest_floats(&warp_on_top);
+        let shafts: BTreeSet<_> = self
+            .threading
+            .iter()
+            .flat_map(|t| t.values().flatten())
+            .collect();
+        let treadles: BTreeSet<_> = self
+            .treadling
+            .iter()
+            .flat_map(|t| t.values().flatten())
+            .collect();
+        let colors: BTreeSet<ColorIndex> = (1..=ends)
+            .filter_map(|end| self.warp_color_idx(Warp(end)))
+            .chain((1..=picks).filter_map(|pick| self.weft_color_idx(Weft(pick))))
+            .collect();
+        DraftSummary {
+            shafts_used: shafts.len() as u32,
+            shafts: self.shafts(),
+            treadles_used: treadles.len() as u32,
+            treadles: self.treadles(),
+            ends,
+            picks,
+            colors: colors.len(),
+            structure: structure(&warp_on_top),
+            longest_warp_float,
+            longest_weft_float,
+        }
+    }
+
+    /// Counts, color usage, floats, repeats and sett gathered in one place.
+    pub fn stats(&self) -> Result<DraftStats> {
+        let summary = self.summary();
+        let mut colors: BTreeMap<ColorIndex, ColorUsage> = BTreeMap::new();
+        let unused = |color| ColorUsage {
+            color,
+            ends: 0,
+            picks: 0,
+        };
+        for end in 1..=summary.ends {
+            if let Some(color) = self.warp_color_idx(Warp(end)) {
+                colors.entry(color).or_insert_with(|| unused(color)).ends += 1;
+            }
+        }
+        for pick in 1..=summary.picks {
+            if let Some(color) = self.weft_color_idx(Weft(pick)) {
+                colors.entry(color).or_insert_with(|| unused(color)).picks += 1;
+            }
+        }
+        let lifts: BTreeSet<_> = lift_sequence(self).into_iter().flatten().collect();
+        let floats = self.float_report()?;
+        let repeats = self.detect_repeats();
+        let dimensions = self.fabric_dimensions();
+        Ok(DraftStats {
+            ends: summary.ends,
+            picks: summary.picks,
+            shafts: summary.shafts,
+            shafts_used: summary.shafts_used,
+            treadles: summary.treadles,
+            treadles_used: summary.treadles_used,
+            distinct_lifts: lifts.len(),
+            colors: colors.into_values().collect(),
+            longest_warp_float: floats.face.warp.longest.max(floats.back.warp.longest),
+            longest_weft_float: floats.face.weft.longest.max(floats.back.weft.longest),
+            threading_repeat: repeats.threading.map(|r| r.length),
+            treadling_repeat: repeats.treadling.map(|r| r.length),
+            liftplan_repeat: repeats.liftplan.map(|r| r.length),
+            warp_color_repeat: repeats.warp_colors.map(|r| r.length),
+            weft_color_repeat: repeats.weft_colors.map(|r| r.length),
+            ends_per_inch: dimensions.warp.map(|sett| sett.per_inch()),
+            picks_per_inch: dimensions.weft.map(|sett| sett.per_inch()),
+        })
+    }
+
+    /// Splits the drawdown into rectangles along the places where the threading and the
+    /// liftplan change from one repeating unit to another, and guesses the structure of each,
+    /// so twill blocks framed by plain weave borders come out as separate regions. Regions
+    /// are listed pick band by pick band, each from end 1 across.
+    pub fn regions(&self) -> Vec<Region> {
+        let warp_on_top = self.to_drawdown_matrix();
+        let ends = warp_on_top.first().map_or(0, Vec::len);
+        let threading: Vec<_> = (1..=ends as u32)
+            .map(|end| self.threading.as_ref().and_then(|t| t.get(&Warp(end))))
+            .collect();
+        let mut lifts = lift_sequence(self).unwrap_or_default();
+        lifts.resize(warp_on_top.len(), Default::default());
+        let mut regions = vec![];
+        for (top, bottom) in segments(&lifts) {
+            for (left, right) in segments(&threading) {
+                let block: Vec<Vec<bool>> = warp_on_top[top..bottom]
+                    .iter()
+                    .map(|row| row[left..right].to_vec())
+                    .collect();
+                regions.push(Region {
+                    first_end: Warp(left as u32 + 1),
+                    last_end: Warp(right as u32),
+                    first_pick: Weft(top as u32 + 1),
+                    last_pick: Weft(bottom as u32),
+                    structure: structure(&block),
+                });
+            }
+        }
+        regions
+    }
+}
@@ -0,0 +1,140 @@
+use super::Structure;
+use crate::{Tieup, TieupMatrix, WifBuilder};
+
+fn lifted(lifts: &[&[u32]], ends: u32) -> crate::Wif {
+    WifBuilder::new()
+        .threading_from((0..ends).map(|end| end % lifts.len() as u32 + 1))
+        .liftplan(lifts.iter().cycle().take(ends as usize).map(|l| l.to_vec()))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn guesses_structure() {
+    assert_eq!(
+        lifted(&[&[1], &[2]], 8).summary().structure,
+        Structure::PlainWeave
+    );
+    assert_eq!(
+        lifted(&[&[1, 2], &[2, 3], &[3, 4], &[4, 1]], 8)
+            .summary()
+            .structure,
+        Structure::Twill
+    );
+    assert_eq!(
+        lifted(&[&[1], &[3], &[5], &[2], &[4]], 10)
+            .summary()
+            .structure,
+        Structure::Satin
+    );
+    assert_eq!(
+        lifted(&[&[1, 2], &[1, 2], &[3, 4], &[3, 4]], 8)
+            .summary()
+            .structure,
+        Structure::Other
+    );
+}
+
+#[test]
+fn displays_one_line() {
+    let wif = WifBuilder::new()
+        .shafts(8)
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4])
+        .tieup(Tieup::from_matrix(&[
+            vec![true, false, false, true],
+            vec![true, true, false, false],
+            vec![false, true, true, false],
+            vec![false, false, true, true],
+        ]))
+        .treadling([1, 2, 3, 4])
+        .build()
+        .unwrap();
+    let summary = wif.summary();
+    assert_eq!((summary.shafts_used, summary.shafts), (4, Some(8)));
+    assert_eq!(
+        summary.to_string(),
+        "twill, 8 ends × 4 picks, 4 of 8 shafts, 4 treadles, 0 colors, longest floats 2 warp / 2 weft"
+    );
+}
+
+#[test]
+fn stats_gather_counts_colors_and_repeats() {
+    use crate::Color;
+    let dark = Color {
+        red: 0,
+        green: 0,
+        blue: 0,
+    };
+    let light = Color {
+        red: 255,
+        green: 255,
+        blue: 255,
+    };
+    let wif = WifBuilder::new()
+        .shafts(8)
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4])
+        .liftplan([vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 1], vec![1, 2]])
+        .warp_colors([dark, dark, light, light].repeat(2))
+        .weft_colors([light; 5])
+        .build()
+        .unwrap();
+    let stats = wif.stats().unwrap();
+    assert_eq!((stats.ends, stats.picks), (8, 5));
+    assert_eq!((stats.shafts_used, stats.shafts), (4, Some(8)));
+    assert_eq!(stats.distinct_lifts, 4);
+    let counts: Vec<_> = stats.colors.iter().map(|c| (c.ends, c.picks)).collect();
+    assert_eq!(counts, [(4, 0), (4, 5)]);
+    assert_eq!((stats.longest_warp_float, stats.longest_weft_float), (2, 2));
+    assert_eq!(stats.threading_repeat, Some(4));
+    assert_eq!(stats.liftplan_repeat, Some(4));
+    assert_eq!(stats.warp_color_repeat, Some(4));
+    assert_eq!(stats.weft_color_repeat, Some(1));
+    assert_eq!(stats.ends_per_inch, None);
+
+    let sample = crate::parse(include_str!("../sample.wif"))
+        .unwrap()
+        .stats()
+        .unwrap();
+    let total: u32 = sample.colors.iter().map(|c| c.ends).sum();
+    assert_eq!(total, sample.ends);
+    assert!(sample.ends_per_inch.is_some());
+}
+
+#[test]
+fn regions_separate_twill_block_from_plain_border() {
+    use super::Region;
+    use crate::{Warp, Weft};
+    let plain = [[1, 3, 5], [2, 4, 6]];
+    let twill = [[1, 2, 5], [2, 3, 6], [3, 4, 5], [4, 1, 6]];
+    let lifts = plain
+        .repeat(2)
+        .into_iter()
+        .chain(twill.repeat(2))
+        .chain(plain.repeat(2));
+    let wif = WifBuilder::new()
+        .threading_from([5, 6, 5, 6, 1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 5, 6])
+        .liftplan(lifts)
+        .build()
+        .unwrap();
+    let regions = wif.regions();
+    assert_eq!(regions.len(), 9);
+    assert_eq!(
+        regions[4],
+        Region {
+            first_end: Warp(5),
+            last_end: Warp(12),
+            first_pick: Weft(5),
+            last_pick: Weft(12),
+            structure: Structure::Twill,
+        }
+    );
+    assert!(regions
+        .iter()
+        .enumerate()
+        .all(|(i, r)| i == 4 || r.structure == Structure::PlainWeave));
+    assert_eq!((regions[8].ends(), regions[8].picks()), (4, 4));
+
+    let sample = crate::parse(include_str!("../sample.wif")).unwrap();
+    let area: u32 = sample.regions().iter().map(|r| r.ends() * r.picks()).sum();
+    assert_eq!(area, sample.width().unwrap() * sample.height().unwrap());
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeSet;
+
+use crate::{Shaft, Table, Treadle};
+
+/// The shafts tied to each treadle.
+pub type Tieup = Table<Treadle, BTreeSet<Shaft>>;
+
+/// Conversions between a [`Tieup`] and the 0/1 grids most programs display, with one row per
+/// shaft (shaft 1 first) and one column per treadle (treadle 1 first).
+pub trait TieupMatrix: Sized {
+    /// A riser grid: `true` where the treadle lifts the shaft. Treadles with nothing tied
+    /// are left out.
+    fn from_matrix(rows: &[Vec<bool>]) -> Self;
+    /// A sinker grid: `true` where the treadle lowers the shaft, so every other shaft rises.
+    fn from_sinker_matrix(rows: &[Vec<bool>]) -> Self;
+    /// A riser grid of `shafts` rows and `treadles` columns. Shafts and treadles outside
+    /// that range are dropped.
+    fn to_matrix(&self, shafts: u32, treadles: u32) -> Vec<Vec<bool>>;
+}
+
+impl TieupMatrix for Tieup {
+    fn from_matrix(rows: &[Vec<bool>]) -> Self {
+        let treadles = rows.iter().map(Vec::len).max().unwrap_or(0);
+        (0..treadles)
+            .filter_map(|col| {
+                let shafts: BTreeSet<Shaft> = (1..)
+                    .zip(rows)
+                    .filter(|(_, row)| row.get(col).copied().unwrap_or(false))
+                    .map(|(shaft, _)| Shaft(shaft))
+                    .collect();
+                (!shafts.is_empty()).then_some((Treadle(col as u32 + 1), shafts))
+            })
+            .collect()
+    }
+
+    fn from_sinker_matrix(rows: &[Vec<bool>]) -> Self {
+        let treadles = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let risers: Vec<Vec<bool>> = rows
+            .iter()
+            .map(|row| {
+                (0..treadles)
+                    .map(|col| !row.get(col).copied().unwrap_or(false))
+                    .collect()
+            })
+            .collect();
+        Self::from_matrix(&risers)
+    }
+
+    fn to_matrix(&self, shafts: u32, treadles: u32) -> Vec<Vec<bool>> {
+        (1..=shafts)
+            .map(|shaft| {
+                (1..=treadles)
+                    .map(|treadle| {
+                        self.get(&Treadle(treadle))
+                            .is_some_and(|tied| tied.contains(&Shaft(shaft)))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
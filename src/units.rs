@@ -0,0 +1,85 @@
+use crate::wifparse::WifParse;
+
+/// The units of the `Spacing` and `Thickness` values in WARP and WEFT.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Units {
+    /// 1/720 of an inch.
+    Decipoints,
+    Inches,
+    Centimeters,
+    /// Units the spec doesn't define, kept as written. Lengths in them can't be converted.
+    #[cfg_attr(feature = "serde", serde(untagged))]
+    Other(String),
+}
+
+impl Units {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Units::Decipoints => "Decipoints",
+            Units::Inches => "Inches",
+            Units::Centimeters => "Centimeters",
+            Units::Other(units) => units,
+        }
+    }
+
+    fn per_inch(&self) -> Option<f64> {
+        match self {
+            Units::Decipoints => Some(720.),
+            Units::Inches => Some(1.),
+            Units::Centimeters => Some(2.54),
+            Units::Other(_) => None,
+        }
+    }
+
+    /// Converts `value` from these units to `to`, or `None` if either is
+    /// [`Other`](Units::Other).
+    pub fn convert(&self, value: f64, to: &Units) -> Option<f64> {
+        Some(value / self.per_inch()? * to.per_inch()?)
+    }
+}
+
+impl WifParse for Units {
+    fn parse(s: &str) -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        // Accept the singular and British spellings some programs write.
+        match s.trim().to_ascii_lowercase().as_str() {
+            "decipoints" | "decipoint" => Ok(Units::Decipoints),
+            "inches" | "inch" => Ok(Units::Inches),
+            "centimeters" | "centimeter" | "centimetres" | "centimetre" => Ok(Units::Centimeters),
+            _ => Ok(Units::Other(s.trim().into())),
+        }
+    }
+
+    fn unparse(&self) -> Option<String> {
+        Some(self.as_str().into())
+    }
+}
+
+/// A spacing or thickness along with its units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Length {
+    pub value: f64,
+    pub units: Units,
+}
+
+impl Length {
+    pub fn new(value: f64, units: Units) -> Self {
+        Length { value, units }
+    }
+
+    /// The length in `units`, or `None` if either these or those units are unknown.
+    pub fn to(&self, units: Units) -> Option<Length> {
+        Some(Length::new(self.units.convert(self.value, &units)?, units))
+    }
+
+    pub fn inches(&self) -> Option<f64> {
+        self.units.convert(self.value, &Units::Inches)
+    }
+
+    pub fn centimeters(&self) -> Option<f64> {
+        self.units.convert(self.value, &Units::Centimeters)
+    }
+}
@@ -1,53 +1,177 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+};
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "tokio")]
+mod async_read;
+mod canonical;
+mod cell;
+mod colors;
+mod compare;
+mod crop;
+mod dimensions;
+mod downgrade;
+mod drawdown;
+mod encoding;
+mod extract;
+mod fingerprint;
+mod gaps;
+#[cfg(feature = "flate2")]
+mod gz;
+mod ini;
+mod join;
+#[cfg(feature = "json")]
+mod json;
+mod layers;
+#[cfg(feature = "preserve-layout")]
+mod layout;
+mod matrix;
+mod provenance;
+mod prune;
+mod quirks;
+mod repair;
+mod report;
+mod revision;
+mod setters;
+mod shed;
+mod threads;
+mod treadling;
+mod turn;
+mod validate;
+mod views;
 mod wif_sections;
+mod write_options;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "tokio")]
+pub use async_read::parse_from_async_reader;
+pub use cell::CellInfo;
 use chrono::NaiveDate;
-use configparser::ini::Ini;
-use wif_sections::WifSection;
+pub use dimensions::{FabricDimensions, Sett, SettSection};
+pub use downgrade::VersionLoss;
+pub use drawdown::Drawdown;
+pub use extract::extract_and_parse;
+pub use gaps::{CompletenessReport, TableGaps};
+use ini::{Ini, Line};
+#[cfg(feature = "json")]
+pub(crate) use json::Document;
+#[cfg(feature = "json")]
+pub use json::{JSON_FORMAT_VERSION, JSON_SCHEMA};
+pub use layers::Layers;
+pub use provenance::ProvenanceEntry;
+pub use prune::Pruned;
+pub use quirks::{quirks_for, Quirk};
+pub use repair::{Repair, RepairOptions, RepairReport};
+#[cfg(feature = "json")]
+pub use report::VALIDATION_REPORT_VERSION;
+pub use report::{ReportEntry, ValidationReport};
+pub use revision::Revision;
+pub use threads::{WarpEnd, WeftPick};
+pub use validate::{IssueCode, Severity, Thresholds, ValidationIssue};
+pub use views::{LiftplanView, TableView, ThreadingView, TreadlingView};
+use wif_sections::{ReadContext, WifSection};
+pub(crate) use write_options::rescale;
+pub use write_options::{
+    ContentsStyle, LiftMode, LineEnding, SectionFilter, SectionOrder, TargetProgram, WriteOptions,
+};
 
 use crate::{
-    liftplan_from_threading_and_treadle, wifparse::WifParse, Color, Result, Shaft, Table, Treadle,
-    Warp, WarpOrWeft, Weft, WifContext, WifError,
+    liftplan_from_threading_and_treadle, progress, wifparse::WifParse, Color, ColorIndex, Length,
+    Progress, Result, Shaft, SymbolIndex, Table, Tieup, Treadle, Units, Warp, WarpOrWeft, Weft,
+    WifContext, WifError,
 };
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Wif {
     pub wif_header: WifHeader,
     pub color_palette: Option<ColorPalette>,
     pub warp_symbol_palette: Option<WarpSymbolPalette>,
-    pub weft_symbol_palette: Option<WarpSymbolPalette>,
+    pub weft_symbol_palette: Option<WeftSymbolPalette>,
     pub text: Option<Text>,
     pub weaving: Option<Weaving>,
     pub warp: Option<WarpS>,
     pub weft: Option<WeftS>,
-    pub color_table: Option<Table<u32, Color>>,
+    pub color_table: Option<Table<ColorIndex, Color>>,
     pub notes: Option<Table<u32, String>>,
-    pub tieup: Option<Table<Treadle, BTreeSet<Shaft>>>,
-    pub warp_symbol_table: Option<Table<u32, String>>,
-    pub weft_symbols_table: Option<Table<u32, String>>,
+    pub tieup: Option<Tieup>,
+    pub warp_symbol_table: Option<Table<SymbolIndex, Symbol>>,
+    pub weft_symbols_table: Option<Table<SymbolIndex, Symbol>>,
     pub threading: Option<Table<Warp, BTreeSet<Shaft>>>,
     pub warp_thickness: Option<Table<Warp, f64>>,
     pub warp_thickness_zoom: Option<Table<Warp, u32>>,
     pub warp_spacing: Option<Table<Warp, f64>>,
     pub warp_spacing_zoom: Option<Table<Warp, u32>>,
-    pub warp_colors: Option<Table<Warp, u32>>,
-    pub warp_symbols: Option<Table<Warp, u32>>,
+    pub warp_colors: Option<Table<Warp, ColorIndex>>,
+    pub warp_symbols: Option<Table<Warp, SymbolIndex>>,
     pub treadling: Option<Table<Weft, BTreeSet<Treadle>>>,
     pub liftplan: Option<Table<Weft, BTreeSet<Shaft>>>,
     pub weft_thickness: Option<Table<Weft, f64>>,
     pub weft_thickness_zoom: Option<Table<Weft, u32>>,
     pub weft_spacing: Option<Table<Weft, f64>>,
     pub weft_spacing_zoom: Option<Table<Weft, u32>>,
-    pub weft_colors: Option<Table<Weft, u32>>,
-    pub weft_symbols: Option<Table<Weft, u32>>,
+    pub weft_colors: Option<Table<Weft, ColorIndex>>,
+    pub weft_symbols: Option<Table<Weft, SymbolIndex>>,
+    pub contents: Contents,
+    /// Programs that previously saved the draft, oldest first.
+    pub provenance: Vec<ProvenanceEntry>,
     // Private code regions go here
+    #[cfg(feature = "preserve-layout")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    layout: Option<layout::Layout>,
+}
+
+/// The sections a file declares in its CONTENTS section, in file order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Contents {
+    pub entries: Vec<(String, bool)>,
+    /// When set, `write` emits `entries` verbatim instead of regenerating CONTENTS from the
+    /// sections actually written.
+    pub pinned: bool,
+}
+
+impl Contents {
+    pub fn declares(&self, section: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|(name, present)| *present && name.eq_ignore_ascii_case(section))
+    }
+
+    /// Entries that don't name any section defined by the spec.
+    pub fn unknown(&self) -> impl Iterator<Item = &(String, bool)> {
+        self.entries.iter().filter(|(name, _)| {
+            !sections::ALL
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(name))
+        })
+    }
 }
 
 impl Wif {
+    /// Reads and parses a WIF file, detecting its text encoding. Any error reports the path.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Wif> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|error| WifError::CouldNotReadFile {
+            path: path.to_owned(),
+            error: Arc::new(error),
+        })?;
+        parse(&encoding::decode(&bytes)).map_err(|err| WifError::InFile {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })
+    }
+
+    pub fn spec_version(&self) -> WifVersion {
+        WifVersion::from_header(&self.wif_header.version)
+    }
+
     pub fn shafts(&self) -> Option<u32> {
         self.weaving.as_ref().map(|w| w.shafts)
     }
@@ -61,6 +185,68 @@ impl Wif {
         self.weft.as_ref().map(|w| w.threads)
     }
 
+    /// An empty draft for a loom with `shafts` and `treadles`, with no threads yet. The header
+    /// is dated today and names this crate as the source program.
+    pub fn new(shafts: u32, treadles: u32) -> Wif {
+        let mut wif = Wif::blank(0, 0);
+        wif.weaving = Some(Weaving {
+            shafts,
+            treadles,
+            rising_shed: None,
+        });
+        wif
+    }
+
+    /// A draft with only a header and thread counts, dated today, for generators to fill in.
+    pub(crate) fn blank(ends: u32, picks: u32) -> Wif {
+        Wif {
+            wif_header: WifHeader {
+                version: WifVersion::V1_1.as_str().into(),
+                date: chrono::Local::now().date_naive(),
+                developers: "wif@mhsoft.com".into(),
+                source_program: env!("CARGO_PKG_NAME").into(),
+                source_version: Some(env!("CARGO_PKG_VERSION").into()),
+            },
+            color_palette: None,
+            warp_symbol_palette: None,
+            weft_symbol_palette: None,
+            text: None,
+            weaving: None,
+            warp: Some(WarpS {
+                threads: ends,
+                ..Default::default()
+            }),
+            weft: Some(WeftS {
+                threads: picks,
+                ..Default::default()
+            }),
+            color_table: None,
+            notes: None,
+            tieup: None,
+            warp_symbol_table: None,
+            weft_symbols_table: None,
+            threading: None,
+            warp_thickness: None,
+            warp_thickness_zoom: None,
+            warp_spacing: None,
+            warp_spacing_zoom: None,
+            warp_colors: None,
+            warp_symbols: None,
+            treadling: None,
+            liftplan: None,
+            weft_thickness: None,
+            weft_thickness_zoom: None,
+            weft_spacing: None,
+            weft_spacing_zoom: None,
+            weft_colors: None,
+            weft_symbols: None,
+            contents: Contents::default(),
+            provenance: Vec::new(),
+            #[cfg(feature = "preserve-layout")]
+            layout: None,
+        }
+    }
+
     pub fn build_or_validate_liftplan(&mut self) -> Result<()> {
         let liftplan =
             liftplan_from_threading_and_treadle(self.treadling.as_ref(), self.tieup.as_ref());
@@ -80,71 +266,150 @@ impl Wif {
         }
     }
 
-    fn get_ct(&self, color_idx: u32) -> Option<Color> {
+    fn get_ct(&self, color_idx: ColorIndex) -> Option<Color> {
         self.color_table
             .as_ref()
             .and_then(|ct| ct.get(&color_idx))
             .copied()
     }
 
-    fn get_default_weft_color(&self) -> Option<Color> {
-        self.get_ct(self.weft.as_ref()?.color?.idx)
+    /// Returns the color table index holding `color`, appending a new entry if needed.
+    pub(crate) fn find_or_add_color(&mut self, color: Color) -> ColorIndex {
+        let table = self.color_table.get_or_insert_with(Default::default);
+        if let Some((&idx, _)) = table.iter().find(|(_, c)| **c == color) {
+            return idx;
+        }
+        let idx = ColorIndex(table.keys().next_back().map_or(1, |idx| idx.0 + 1));
+        table.insert(idx, color);
+        let entries = palette_entries(table, |idx| idx.0);
+        let palette = self.color_palette.get_or_insert(ColorPalette {
+            entries,
+            range: (0, 999),
+        });
+        palette.entries = entries;
+        idx
     }
 
-    pub fn weft_color(&self, weft: impl Into<Weft>) -> Option<Color> {
-        let weft = weft.into();
+    /// Which rule decides the color of a warp end or weft pick, as used by
+    /// [`warp_color`](Self::warp_color) and [`weft_color`](Self::weft_color).
+    pub fn explain_color(&self, warp_or_weft: WarpOrWeft, index: u32) -> ColorResolution {
+        let (thread_index, default_index) = match warp_or_weft {
+            WarpOrWeft::Warp => (
+                self.warp_colors
+                    .as_ref()
+                    .and_then(|wc| wc.get(&Warp(index)))
+                    .copied(),
+                self.warp.as_ref().and_then(|w| Some(w.color?.idx)),
+            ),
+            WarpOrWeft::Weft => (
+                self.weft_colors
+                    .as_ref()
+                    .and_then(|wc| wc.get(&Weft(index)))
+                    .copied(),
+                self.weft.as_ref().and_then(|w| Some(w.color?.idx)),
+            ),
+        };
+        if let Some((index, color)) = thread_index.and_then(|i| Some((i, self.get_ct(i)?))) {
+            return ColorResolution::Thread { index, color };
+        }
+        if let Some((index, color)) = default_index.and_then(|i| Some((i, self.get_ct(i)?))) {
+            return ColorResolution::SectionDefault {
+                index,
+                color,
+                missing_thread_index: thread_index,
+            };
+        }
+        ColorResolution::Unresolved {
+            thread_index,
+            default_index,
+        }
+    }
+
+    /// The color table index used by a weft pick, falling back to the [WEFT] default.
+    pub(crate) fn weft_color_idx(&self, weft: Weft) -> Option<ColorIndex> {
         self.weft_colors
             .as_ref()
             .and_then(|wc| wc.get(&weft))
-            .and_then(|idx| self.get_ct(*idx))
-            .or_else(|| self.get_default_weft_color())
+            .copied()
+            .or_else(|| Some(self.weft.as_ref()?.color?.idx))
+    }
+
+    pub fn weft_color(&self, weft: impl Into<Weft>) -> Option<Color> {
+        self.explain_color(WarpOrWeft::Weft, weft.into().0).color()
     }
 
     pub fn weft_color_u8(&self, weft: impl Into<Weft>) -> Option<[u8; 3]> {
-        let range = self
+        self.weft_color(weft.into())
+            .map(|color| self.color_u8(color))
+    }
+
+    // Scales a color from the palette's range to 0..=255. Values outside the range are
+    // clamped to it rather than wrapping.
+    fn color_u8(&self, color: Color) -> [u8; 3] {
+        let (low, high) = self
             .color_palette
             .as_ref()
             .map(|cp| cp.range)
             .unwrap_or((0, 999));
-        let convert =
-            |old_value| ((old_value - range.0) as f64 / (range.1 - range.0) as f64 * 255.) as u8;
-        self.weft_color(weft.into()).map(|color| {
-            [
-                convert(color.red),
-                convert(color.green),
-                convert(color.blue),
-            ]
-        })
+        let span = high.saturating_sub(low).max(1) as f64;
+        let convert = |value: u32| (value.min(high).saturating_sub(low) as f64 / span * 255.) as u8;
+        [
+            convert(color.red),
+            convert(color.green),
+            convert(color.blue),
+        ]
     }
 
-    fn get_default_warp_color(&self) -> Option<Color> {
-        self.get_ct(self.warp.as_ref()?.color?.idx)
-    }
-
-    pub fn warp_color(&self, warp: impl Into<Warp>) -> Option<Color> {
-        let warp = warp.into();
+    /// The color table index used by a warp end, falling back to the [WARP] default.
+    pub(crate) fn warp_color_idx(&self, warp: Warp) -> Option<ColorIndex> {
         self.warp_colors
             .as_ref()
             .and_then(|wc| wc.get(&warp))
-            .and_then(|idx| self.get_ct(*idx))
-            .or_else(|| self.get_default_warp_color())
+            .copied()
+            .or_else(|| Some(self.warp.as_ref()?.color?.idx))
+    }
+
+    pub fn warp_color(&self, warp: impl Into<Warp>) -> Option<Color> {
+        self.explain_color(WarpOrWeft::Warp, warp.into().0).color()
     }
 
     pub fn warp_color_u8(&self, warp: impl Into<Warp>) -> Option<[u8; 3]> {
-        let range = self
-            .color_palette
-            .as_ref()
-            .map(|cp| cp.range)
-            .unwrap_or((0, 999));
-        let convert =
-            |old_value| ((old_value - range.0) as f64 / (range.1 - range.0) as f64 * 255.) as u8;
-        self.warp_color(warp.into()).map(|color| {
-            [
-                convert(color.red),
-                convert(color.green),
-                convert(color.blue),
-            ]
-        })
+        self.warp_color(warp.into())
+            .map(|color| self.color_u8(color))
+    }
+
+    /// Inverse of the threading: every warp end threaded on each shaft, in order.
+    pub fn shaft_usage(&self) -> BTreeMap<Shaft, Vec<Warp>> {
+        let mut usage: BTreeMap<Shaft, Vec<Warp>> = BTreeMap::new();
+        for (&warp, shafts) in self.threading.iter().flatten() {
+            for &shaft in shafts {
+                usage.entry(shaft).or_default().push(warp);
+            }
+        }
+        usage
+    }
+
+    /// Heddles needed on each shaft: one for every end threaded on it, so an end on two
+    /// shafts needs a heddle on both. Shafts declared in [WEAVING] but unused count 0.
+    pub fn heddles_per_shaft(&self) -> BTreeMap<Shaft, u32> {
+        let mut heddles: BTreeMap<Shaft, u32> = (1..=self.shafts().unwrap_or(0))
+            .map(|s| (Shaft(s), 0))
+            .collect();
+        for &shaft in self.threading.iter().flat_map(|t| t.values().flatten()) {
+            *heddles.entry(shaft).or_default() += 1;
+        }
+        heddles
+    }
+
+    /// Inverse of the treadling: every weft pick using each treadle, in order.
+    pub fn treadle_usage(&self) -> BTreeMap<Treadle, Vec<Weft>> {
+        let mut usage: BTreeMap<Treadle, Vec<Weft>> = BTreeMap::new();
+        for (&weft, treadles) in self.treadling.iter().flatten() {
+            for &treadle in treadles {
+                usage.entry(treadle).or_default().push(weft);
+            }
+        }
+        usage
     }
 
     pub fn warp_or_weft(&self, warp: impl Into<Warp>, weft: impl Into<Weft>) -> Option<WarpOrWeft> {
@@ -152,18 +417,22 @@ impl Wif {
         let weft = weft.into();
         let liftplan = self.liftplan.as_ref()?;
         let threading = self.threading.as_ref()?;
-        if let Some(shafts) = liftplan.get(&weft) {
-            if let Some(thread_shafts) = threading.get(&warp) {
-                if shafts.intersection(thread_shafts).next().is_some() {
-                    Some(WarpOrWeft::Warp)
-                } else {
-                    Some(WarpOrWeft::Weft)
-                }
-            } else {
-                Some(WarpOrWeft::Weft)
-            }
+        // Unthreaded ends never move, so the weft covers them.
+        let Some(thread_shafts) = threading.get(&warp) else {
+            return Some(WarpOrWeft::Weft);
+        };
+        // The liftplan lists the shafts that rise, or on a sinking shed the ones that sink.
+        let listed = liftplan
+            .get(&weft)
+            .is_some_and(|shafts| shafts.intersection(thread_shafts).next().is_some());
+        let rising_shed = self
+            .weaving
+            .as_ref()
+            .and_then(|w| w.rising_shed)
+            .unwrap_or(true);
+        if listed == rising_shed {
+            Some(WarpOrWeft::Warp)
         } else {
-            // TODO: Check if the loom is rising shed or not
             Some(WarpOrWeft::Weft)
         }
     }
@@ -171,20 +440,125 @@ impl Wif {
     where
         W: std::io::Write,
     {
-        let mut ini = configparser::ini::Ini::new_cs();
+        self.write_with_options(output, &WriteOptions::default())
+    }
 
-        wif_sections::Wif::write(&self.wif_header, &mut ini);
+    /// Writes only the sections `filter` allows, e.g. to share a draft without its [TEXT].
+    pub fn write_filtered<W>(&self, output: &mut W, filter: SectionFilter) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let options = WriteOptions {
+            sections: filter,
+            ..WriteOptions::default()
+        };
+        self.write_with_options(output, &options)
+    }
 
-        macro_rules! write_section {
-            ($($field:ident : $section:ident),*) => {
-                $(
-                    if let Some($field) = &self.$field {
-                        wif_sections::$section::write($field, &mut ini);
+    pub fn write_with_options<W>(
+        &self,
+        output: &mut W,
+        options: &WriteOptions,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let wif = options.adapt(self);
+        let mut writers = wif.section_writers();
+        writers.retain(|(name, _)| options.sections.allows(name));
+
+        // CONTENTS and the header are small, so they're built up front; every other section
+        // is generated and written one at a time.
+        let mut contents = Ini::new();
+        if wif.contents.pinned {
+            for (name, present) in &wif.contents.entries {
+                if options.sections.allows(name) {
+                    contents.set(sections::CONTENTS, name, present.unparse());
+                }
+            }
+        } else {
+            // Private sections aren't listed in CONTENTS
+            for (name, _) in writers
+                .iter()
+                .filter(|(name, _)| !name.starts_with("PRIVATE "))
+            {
+                contents.set(sections::CONTENTS, name, true.unparse());
+            }
+            if options.contents == ContentsStyle::Complete {
+                for &name in &sections::ALL[2..] {
+                    if contents.get(sections::CONTENTS, name).is_none() {
+                        contents.set(sections::CONTENTS, name, false.unparse());
+                    }
+                }
+            }
+        }
+        let mut header = Ini::new();
+        wif_sections::Wif::write(&wif.wif_header, &mut header);
+        header.set(
+            sections::WIF,
+            "Date",
+            Some(wif.wif_header.date.format(&options.date_format).to_string()),
+        );
+
+        let mut order: Vec<&str> = vec![sections::WIF];
+        if contents.has_section(sections::CONTENTS) {
+            order.push(sections::CONTENTS);
+        }
+        order.extend(writers.iter().map(|(name, _)| *name));
+        order.sort_by_key(|name| options.section_rank(name));
+
+        let eol = options.line_ending.as_str();
+        for (i, &name) in order.iter().enumerate() {
+            if options.blank_lines && i > 0 {
+                write!(output, "{eol}")?;
+            }
+            let ini = match name {
+                sections::WIF => &header,
+                sections::CONTENTS => &contents,
+                _ => {
+                    let mut ini = Ini::new();
+                    if let Some((_, write)) = writers.iter().find(|(n, _)| *n == name) {
+                        write(&mut ini);
                     }
-                )*
+                    write_section(output, &ini, name, eol)?;
+                    continue;
+                }
+            };
+            write_section(output, ini, name, eol)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the draft back in the layout it was parsed from, keeping key order, comments and
+    /// spacing. Only lines whose values have changed since parsing are rewritten, so an
+    /// unmodified draft reproduces its source exactly. Drafts that weren't parsed are written
+    /// as by [`write`](Self::write).
+    #[cfg(feature = "preserve-layout")]
+    pub fn write_preserving<W>(&self, output: &mut W) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        match &self.layout {
+            Some(layout) => layout.write(&self.to_ini(), output),
+            None => self.write(output),
+        }
+    }
+
+    // One writer per section present in the draft, in the order they're written by default.
+    fn section_writers(&self) -> Vec<(&'static str, SectionWriter<'_>)> {
+        macro_rules! writers {
+            ($($field:ident : $section:ident),*) => {
+                [$(
+                    self.$field.as_ref().map(|value| {
+                        let write: SectionWriter<'_> = Box::new(move |ini: &mut Ini<'static>| {
+                            wif_sections::$section::write(value, ini)
+                        });
+                        (wif_sections::$section::NAME, write)
+                    }),
+                )*]
             }
         }
-        write_section! {
+        writers! {
             color_palette: ColorPalette,
             warp_symbol_palette: WarpSymbolPalette,
             weft_symbol_palette: WeftSymbolPalette,
@@ -216,12 +590,61 @@ impl Wif {
             treadling: Treadling,
             liftplan: Liftplan
         }
-        output.write_all(ini.writes().as_bytes())?;
-        Ok(())
+        .into_iter()
+        .flatten()
+        .chain((!self.provenance.is_empty()).then(|| {
+            let write: SectionWriter<'_> =
+                Box::new(|ini: &mut Ini<'static>| provenance::write(&self.provenance, ini));
+            (sections::PROVENANCE, write)
+        }))
+        .collect()
+    }
+
+    fn to_ini(&self) -> Ini<'static> {
+        let mut ini = Ini::new();
+        wif_sections::Wif::write(&self.wif_header, &mut ini);
+        for (_, write) in self.section_writers() {
+            write(&mut ini);
+        }
+        if self.contents.pinned {
+            if let Some(contents) = ini.section_mut(sections::CONTENTS) {
+                contents.clear();
+            }
+            for (name, present) in &self.contents.entries {
+                ini.set(sections::CONTENTS, name, present.unparse());
+            }
+        }
+        ini
+    }
+}
+
+type SectionWriter<'a> = Box<dyn Fn(&mut Ini<'static>) + 'a>;
+
+fn write_section<W: std::io::Write>(
+    output: &mut W,
+    ini: &Ini,
+    name: &str,
+    eol: &str,
+) -> std::io::Result<()> {
+    let Some(section) = ini.section(name) else {
+        return Ok(());
+    };
+    write!(output, "[{}]{eol}", section.name)?;
+    for line in section.lines() {
+        match line {
+            Line::Entry {
+                key,
+                value: Some(value),
+            } => write!(output, "{key}={value}{eol}")?,
+            Line::Entry { key, value: None } => write!(output, "{key}{eol}")?,
+            Line::Comment(comment) => write!(output, "{comment}{eol}")?,
+        }
     }
+    Ok(())
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WifHeader {
     pub version: String,
     pub date: NaiveDate,
@@ -230,18 +653,52 @@ pub struct WifHeader {
     pub source_version: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WifVersion {
+    V1_0,
+    V1_1,
+}
+
+impl WifVersion {
+    /// Interprets a `[WIF] Version` value. Anything other than 1.0 is read as 1.1, the
+    /// latest version of the spec.
+    pub fn from_header(version: &str) -> WifVersion {
+        match version.trim() {
+            "1" | "1.0" | "1.00" => WifVersion::V1_0,
+            _ => WifVersion::V1_1,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WifVersion::V1_0 => "1.0",
+            WifVersion::V1_1 => "1.1",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorPalette {
     pub entries: usize,
     pub range: (u32, u32),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WarpSymbolPalette {
     pub entries: usize,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeftSymbolPalette {
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
     pub title: Option<String>,
     pub author: Option<String>,
@@ -252,38 +709,69 @@ pub struct Text {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Weaving {
     pub shafts: u32,
     pub treadles: u32,
     pub rising_shed: Option<bool>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WarpS {
     pub threads: u32,
     pub color: Option<BaseColor>,
-    pub symbol: Option<String>,
-    pub symbol_number: Option<usize>,
-    pub units: Option<String>,
+    pub symbol: Option<Symbol>,
+    pub symbol_number: Option<SymbolIndex>,
+    pub units: Option<Units>,
     pub spacing: Option<f64>,
     pub thickness: Option<f64>,
     pub spacing_zoom: Option<u32>,
     pub thickness_zoom: Option<u32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WeftS {
     pub threads: u32,
     pub color: Option<BaseColor>,
-    pub symbol: Option<String>,
-    pub symbol_number: Option<usize>,
-    pub units: Option<String>,
+    pub symbol: Option<Symbol>,
+    pub symbol_number: Option<SymbolIndex>,
+    pub units: Option<Units>,
     pub spacing: Option<f64>,
     pub thickness: Option<f64>,
     pub spacing_zoom: Option<u32>,
     pub thickness_zoom: Option<u32>,
 }
 
+macro_rules! thread_lengths {
+    ($ty:ident) => {
+        impl $ty {
+            /// The default spacing, if both it and the units are given.
+            pub fn spacing_length(&self) -> Option<Length> {
+                Some(Length::new(self.spacing?, self.units.clone()?))
+            }
+
+            /// The default thickness, if both it and the units are given.
+            pub fn thickness_length(&self) -> Option<Length> {
+                Some(Length::new(self.thickness?, self.units.clone()?))
+            }
+        }
+    };
+}
+
+thread_lengths!(WarpS);
+thread_lengths!(WeftS);
+
+// The `Entries` a palette needs for its table: the highest index, so that a table left with
+// gaps by removed entries still has every index in range.
+pub(crate) fn palette_entries<K, V>(table: &Table<K, V>, index: impl Fn(&K) -> u32) -> usize {
+    table
+        .keys()
+        .next_back()
+        .map_or(0, |key| index(key) as usize)
+}
+
 fn get_field<T>(ini: &Ini, section: &str, field: &str) -> Result<Option<T>>
 where
     T: WifParse,
@@ -308,44 +796,206 @@ where
         })
 }
 
+/// How the color of a single thread was arrived at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorResolution {
+    /// The thread's own entry in WARP COLORS or WEFT COLORS.
+    Thread { index: ColorIndex, color: Color },
+    /// The `Color` default in [WARP] or [WEFT]. If the thread had an entry of its own that
+    /// isn't in the COLOR TABLE, it is given as `missing_thread_index`.
+    SectionDefault {
+        index: ColorIndex,
+        color: Color,
+        missing_thread_index: Option<ColorIndex>,
+    },
+    /// Neither the thread's entry nor the section default named a COLOR TABLE entry.
+    Unresolved {
+        thread_index: Option<ColorIndex>,
+        default_index: Option<ColorIndex>,
+    },
+}
+
+impl ColorResolution {
+    pub fn color(&self) -> Option<Color> {
+        match *self {
+            ColorResolution::Thread { color, .. }
+            | ColorResolution::SectionDefault { color, .. } => Some(color),
+            ColorResolution::Unresolved { .. } => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BaseColor {
-    pub idx: u32,
+    pub idx: ColorIndex,
     pub alt: Option<Color>,
 }
 
-fn parse_base_color_opt(ini: &Ini, section: &str, field: &str) -> Result<Option<BaseColor>> {
-    let mut s = ini.get(section, field);
-    s.map(|s| {
-        if s.contains(',') {
-            todo!()
-        } else {
-            Ok(BaseColor {
-                idx: u32::parse(s)?,
-                alt: None,
-            })
+/// What to do with a section that is present in the file but not listed in CONTENTS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrphanSectionPolicy {
+    #[default]
+    Ignore,
+    Warn,
+    Read,
+}
+
+/// What to do with a CONTENTS entry whose section is not present in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingSectionPolicy {
+    #[default]
+    Error,
+    Warn,
+    Ignore,
+}
+
+/// What to do when a table section lists the same key more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeyPolicy {
+    Error,
+    FirstWins,
+    #[default]
+    LastWins,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    pub orphan_sections: OrphanSectionPolicy,
+    pub missing_sections: MissingSectionPolicy,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Normalize known bugs of the program named in the header's `Source Program`.
+    pub quirks: bool,
+    /// Keep the rest of a table when some of its rows can't be parsed, reporting each bad row
+    /// as a [`ParseWarning::BadRow`].
+    pub lenient_tables: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            orphan_sections: Default::default(),
+            missing_sections: Default::default(),
+            duplicate_keys: Default::default(),
+            quirks: true,
+            lenient_tables: false,
         }
-    })
-    .transpose()
+    }
 }
 
-fn parse_symbol_opt(ini: &Ini, section: &str, field: &str) -> Result<Option<String>> {
-    Ok(ini.get(section, field))
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    OrphanSection {
+        section: String,
+    },
+    MissingSection {
+        section: String,
+    },
+    QuirkApplied {
+        quirk: Quirk,
+        section: String,
+    },
+    DuplicateKey {
+        section: String,
+        key: String,
+        kept: String,
+        discarded: String,
+    },
+    /// A table row that was skipped because it couldn't be parsed.
+    BadRow {
+        section: String,
+        key: String,
+        value: String,
+        error: String,
+    },
 }
 
 pub fn parse(s: &str) -> Result<Wif, WifError> {
-    let mut ini = configparser::ini::Ini::new();
-    ini.read(s.into()).map_err(WifError::CouldNotParseWifFile)?;
-    macro_rules! read_section {
-        ($name:ident) => {
-            if has_section(&ini, wif_sections::$name::NAME)? {
-                Some(wif_sections::$name::read(&ini)?)
-            } else {
-                None
+    parse_with_options(s, &ParseOptions::default()).map(|(wif, _)| wif)
+}
+
+pub fn parse_with_options(
+    s: &str,
+    options: &ParseOptions,
+) -> Result<(Wif, Vec<ParseWarning>), WifError> {
+    parse_with_progress(s, options, &mut ())
+}
+
+/// Like [`parse_with_options`], reporting progress after each section is read.
+pub fn parse_with_progress(
+    s: &str,
+    options: &ParseOptions,
+    progress: &mut dyn Progress,
+) -> Result<(Wif, Vec<ParseWarning>), WifError> {
+    let mut ini = Ini::read(s).map_err(WifError::CouldNotParseWifFile)?;
+    let mut warnings = Vec::new();
+    let duplicates = std::mem::take(&mut ini.duplicates);
+    // Repeated named fields are left to the reader's last-wins behaviour
+    for duplicate in duplicates.into_iter().filter(|d| {
+        sections::TABLES
+            .iter()
+            .any(|table| table.eq_ignore_ascii_case(&d.section))
+    }) {
+        let (kept, discarded) = match options.duplicate_keys {
+            DuplicateKeyPolicy::Error => {
+                return Err(WifError::DuplicateKey {
+                    section: duplicate.section,
+                    key: duplicate.key,
+                })
+            }
+            DuplicateKeyPolicy::FirstWins => {
+                ini.set(
+                    &duplicate.section,
+                    &duplicate.key,
+                    Some(duplicate.first.clone()),
+                );
+                (duplicate.first, duplicate.last)
             }
+            DuplicateKeyPolicy::LastWins => (duplicate.last, duplicate.first),
         };
+        warnings.push(ParseWarning::DuplicateKey {
+            section: duplicate.section,
+            key: duplicate.key,
+            kept,
+            discarded,
+        });
+    }
+    if options.quirks {
+        quirks::apply_quirks(&mut ini, &mut warnings);
+    }
+    // Every spec section except WIF and CONTENTS is read through `read_section!`
+    let section_count = sections::ALL.len() - 2;
+    let mut sections_read = 0;
+    macro_rules! read_section {
+        ($name:ident) => {{
+            let section =
+                if should_read_section(&ini, wif_sections::$name::NAME, options, &mut warnings)? {
+                    let mut ctx = ReadContext {
+                        lenient_tables: options.lenient_tables,
+                        warnings: &mut warnings,
+                    };
+                    Some(wif_sections::$name::read(&ini, &mut ctx)?)
+                } else {
+                    None
+                };
+            sections_read += 1;
+            progress::report(progress, sections_read, section_count)?;
+            section
+        }};
     }
-    let wif_header = wif_sections::Wif::read(&ini)?;
+    let version: String = get_required_field(&ini, sections::WIF, "Version")?;
+    let wif_header = match WifVersion::from_header(&version) {
+        WifVersion::V1_1 => wif_sections::Wif::read(
+            &ini,
+            &mut ReadContext {
+                lenient_tables: options.lenient_tables,
+                warnings: &mut warnings,
+            },
+        )?,
+        WifVersion::V1_0 => read_v1_0_header(&ini, version)?,
+    };
+    let contents = read_contents(&ini)?;
+    let provenance = provenance::read(&ini)?;
     let color_palette = read_section!(ColorPalette);
     let color_table = read_section!(ColorTable);
     let warp_symbol_palette = read_section!(WarpSymbolPalette);
@@ -378,6 +1028,8 @@ pub fn parse(s: &str) -> Result<Wif, WifError> {
 
     let mut wif = Wif {
         wif_header,
+        contents,
+        provenance,
         color_palette,
         warp_symbol_palette,
         color_table,
@@ -409,15 +1061,86 @@ pub fn parse(s: &str) -> Result<Wif, WifError> {
         weft_spacing_zoom,
         weft_colors,
         weft_symbols,
+
+        #[cfg(feature = "preserve-layout")]
+        layout: None,
     };
     wif.build_or_validate_liftplan()?;
-    Ok(wif)
+    #[cfg(feature = "preserve-layout")]
+    {
+        wif.layout = Some(layout::Layout::new(s, wif.to_ini()));
+    }
+    Ok((wif, warnings))
 }
 
 fn has_section(ini: &Ini, section_name: &str) -> Result<bool, WifError> {
     Ok(get_field(ini, "CONTENTS", section_name)?.unwrap_or(false))
 }
 
+// WIF 1.0 only required the version; the rest of the header became mandatory in 1.1. A
+// missing date is taken as today, as for a new draft.
+fn read_v1_0_header(ini: &Ini, version: String) -> Result<WifHeader, WifError> {
+    Ok(WifHeader {
+        version,
+        date: get_field(ini, sections::WIF, "Date")?
+            .unwrap_or_else(|| chrono::Local::now().date_naive()),
+        developers: get_field(ini, sections::WIF, "Developers")?.unwrap_or_default(),
+        source_program: get_field(ini, sections::WIF, "Source Program")?.unwrap_or_default(),
+        source_version: get_field(ini, sections::WIF, "Source Version")?,
+    })
+}
+
+fn read_contents(ini: &Ini) -> Result<Contents, WifError> {
+    let mut contents = Contents::default();
+    let Some(section) = ini.section(sections::CONTENTS) else {
+        return Ok(contents);
+    };
+    for (name, value) in section.entries() {
+        let present = match value {
+            Some(value) => bool::parse(value).add_context(sections::CONTENTS, name)?,
+            None => false,
+        };
+        contents.entries.push((name.to_string(), present));
+    }
+    Ok(contents)
+}
+
+fn should_read_section(
+    ini: &Ini,
+    section_name: &str,
+    options: &ParseOptions,
+    warnings: &mut Vec<ParseWarning>,
+) -> Result<bool, WifError> {
+    let declared = has_section(ini, section_name)?;
+    let present = ini.has_section(section_name);
+    match (declared, present) {
+        (true, true) => Ok(true),
+        (false, false) => Ok(false),
+        (true, false) => match options.missing_sections {
+            MissingSectionPolicy::Error => Err(WifError::MissingSection {
+                section: section_name.into(),
+            }),
+            MissingSectionPolicy::Warn => {
+                warnings.push(ParseWarning::MissingSection {
+                    section: section_name.into(),
+                });
+                Ok(false)
+            }
+            MissingSectionPolicy::Ignore => Ok(false),
+        },
+        (false, true) => match options.orphan_sections {
+            OrphanSectionPolicy::Ignore => Ok(false),
+            OrphanSectionPolicy::Warn => {
+                warnings.push(ParseWarning::OrphanSection {
+                    section: section_name.into(),
+                });
+                Ok(false)
+            }
+            OrphanSectionPolicy::Read => Ok(true),
+        },
+    }
+}
+
 pub mod sections {
     pub const CONTENTS: &str = "CONTENTS";
     pub const WIF: &str = "WIF";
@@ -448,20 +1171,89 @@ pub mod sections {
     pub const WEFT_SPACING_ZOOM: &str = "WEFT SPACING ZOOM";
     pub const WEFT_COLORS: &str = "WEFT COLORS";
     pub const WEFT_SYMBOLS: &str = "WEFT SYMBOLS";
+    /// Where [`Wif::provenance`](super::Wif::provenance) is kept.
+    pub const PROVENANCE: &str = "PRIVATE WIF-RS PROVENANCE";
+
+    /// Sections holding numbered tables rather than named fields.
+    pub const TABLES: &[&str] = &[
+        COLOR_TABLE,
+        NOTES,
+        TIEUP,
+        WARP_SYMBOL_TABLE,
+        WEFT_SYMBOL_TABLE,
+        THREADING,
+        WARP_THICKNESS,
+        WARP_THICKNESS_ZOOM,
+        WARP_SPACING,
+        WARP_SPACING_ZOOM,
+        WARP_COLORS,
+        WARP_SYMBOLS,
+        TREADLING,
+        LIFTPLAN,
+        WEFT_THICKNESS,
+        WEFT_THICKNESS_ZOOM,
+        WEFT_SPACING,
+        WEFT_SPACING_ZOOM,
+        WEFT_COLORS,
+        WEFT_SYMBOLS,
+    ];
+
+    /// Every section defined by the spec, in the order the spec lists them.
+    pub const ALL: &[&str] = &[
+        WIF,
+        CONTENTS,
+        COLOR_PALETTE,
+        WARP_SYMBOL_PALETTE,
+        WEFT_SYMBOL_PALETTE,
+        TEXT,
+        WEAVING,
+        WARP,
+        WEFT,
+        NOTES,
+        TIEUP,
+        COLOR_TABLE,
+        WARP_SYMBOL_TABLE,
+        WEFT_SYMBOL_TABLE,
+        THREADING,
+        WARP_THICKNESS,
+        WARP_THICKNESS_ZOOM,
+        WARP_SPACING,
+        WARP_SPACING_ZOOM,
+        WARP_COLORS,
+        WARP_SYMBOLS,
+        TREADLING,
+        LIFTPLAN,
+        WEFT_THICKNESS,
+        WEFT_THICKNESS_ZOOM,
+        WEFT_SPACING,
+        WEFT_SPACING_ZOOM,
+        WEFT_COLORS,
+        WEFT_SYMBOLS,
+    ];
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Symbol {
     Char(char),
     Quoted(char),
     Code(char),
 }
 
-struct Section<'a> {
-    ini: &'a mut Ini,
+impl Symbol {
+    pub fn as_char(&self) -> char {
+        match *self {
+            Symbol::Char(c) | Symbol::Quoted(c) | Symbol::Code(c) => c,
+        }
+    }
+}
+
+struct Section<'a, 'b> {
+    ini: &'a mut Ini<'b>,
     name: String,
 }
-impl<'a> Section<'a> {
-    fn new(ini: &'a mut Ini, name: impl Into<String>) -> Self {
+impl<'a, 'b> Section<'a, 'b> {
+    fn new(ini: &'a mut Ini<'b>, name: impl Into<String>) -> Self {
         Self {
             ini,
             name: name.into(),
@@ -0,0 +1,101 @@
+use std::collections::BTreeSet;
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use super::{Weaving, Wif};
+use crate::{liftplan_from_threading_and_treadle, Color, Shaft, Treadle, Warp, Weft};
+
+const MAX_SHAFTS: u32 = 16;
+const MAX_TREADLES: u32 = 16;
+const MAX_THREADS: u32 = 64;
+const MAX_COLORS: u32 = 8;
+
+// A non-empty set of values from 1 to `max`.
+fn some_of<T: Ord>(
+    u: &mut Unstructured,
+    max: u32,
+    f: impl Fn(u32) -> T,
+) -> arbitrary::Result<BTreeSet<T>> {
+    let mut set = BTreeSet::from([f(u.int_in_range(1..=max)?)]);
+    for value in 1..=max {
+        if u.ratio(1u8, 4)? {
+            set.insert(f(value));
+        }
+    }
+    Ok(set)
+}
+
+/// A draft that can be written and read back: a threading, either a tieup and treadling or
+/// a liftplan, and a palette of up to eight colors on the ends and picks. Every thread,
+/// tieup entry and lift has at least one shaft or treadle, none beyond the [WEAVING]
+/// counts, and the liftplan always matches the treadling.
+impl<'a> Arbitrary<'a> for Wif {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let shafts = u.int_in_range(2..=MAX_SHAFTS)?;
+        let ends = u.int_in_range(1..=MAX_THREADS)?;
+        let picks = u.int_in_range(1..=MAX_THREADS)?;
+        let mut wif = Wif::blank(ends, picks);
+
+        wif.threading = Some(
+            (1..=ends)
+                .map(|end| Ok((Warp(end), [Shaft(u.int_in_range(1..=shafts)?)].into())))
+                .collect::<arbitrary::Result<_>>()?,
+        );
+        let treadles = if u.arbitrary()? {
+            let treadles = u.int_in_range(1..=MAX_TREADLES)?;
+            wif.tieup = Some(
+                (1..=treadles)
+                    .map(|treadle| Ok((Treadle(treadle), some_of(u, shafts, Shaft)?)))
+                    .collect::<arbitrary::Result<_>>()?,
+            );
+            let multiple = u.arbitrary()?;
+            wif.treadling = Some(
+                (1..=picks)
+                    .map(|pick| {
+                        let pressed = if multiple {
+                            some_of(u, treadles, Treadle)?
+                        } else {
+                            [Treadle(u.int_in_range(1..=treadles)?)].into()
+                        };
+                        Ok((Weft(pick), pressed))
+                    })
+                    .collect::<arbitrary::Result<_>>()?,
+            );
+            wif.liftplan =
+                liftplan_from_threading_and_treadle(wif.treadling.as_ref(), wif.tieup.as_ref());
+            treadles
+        } else {
+            wif.liftplan = Some(
+                (1..=picks)
+                    .map(|pick| Ok((Weft(pick), some_of(u, shafts, Shaft)?)))
+                    .collect::<arbitrary::Result<_>>()?,
+            );
+            0
+        };
+        wif.weaving = Some(Weaving {
+            shafts,
+            treadles,
+            rising_shed: Some(u.arbitrary()?),
+        });
+
+        let mut colors = vec![];
+        for _ in 0..u.int_in_range(1..=MAX_COLORS)? {
+            colors.push(wif.find_or_add_color(Color {
+                red: u.int_in_range(0..=999)?,
+                green: u.int_in_range(0..=999)?,
+                blue: u.int_in_range(0..=999)?,
+            }));
+        }
+        wif.warp_colors = Some(
+            (1..=ends)
+                .map(|end| Ok((Warp(end), *u.choose(&colors)?)))
+                .collect::<arbitrary::Result<_>>()?,
+        );
+        wif.weft_colors = Some(
+            (1..=picks)
+                .map(|pick| Ok((Weft(pick), *u.choose(&colors)?)))
+                .collect::<arbitrary::Result<_>>()?,
+        );
+        Ok(wif)
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{encoding, parse, Wif};
+use crate::{Result, WifError};
+
+/// Reads a WIF file from an async source without blocking the runtime. The text encoding is
+/// detected as in [`Wif::from_path`], and parsing runs on tokio's blocking thread pool.
+pub async fn parse_from_async_reader<R>(mut reader: R) -> Result<Wif>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|error| WifError::CouldNotRead(Arc::new(error)))?;
+    match tokio::task::spawn_blocking(move || parse(&encoding::decode(&bytes))).await {
+        Ok(result) => result,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        // The runtime is shutting down
+        Err(_) => Err(WifError::Cancelled),
+    }
+}
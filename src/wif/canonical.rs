@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use crate::Table;
+
+use super::{sections, Contents, Wif};
+
+// Renumbers the table's keys as 1, 2, 3, ... in their current order, returning old -> new.
+fn renumber<I: Ord + Copy + From<u32>, V>(table: &mut Table<I, V>) -> BTreeMap<I, I> {
+    let old = std::mem::take(table);
+    let mut mapping = BTreeMap::new();
+    for (new, (key, value)) in (1..).zip(old) {
+        let new = I::from(new);
+        mapping.insert(key, new);
+        table.insert(new, value);
+    }
+    mapping
+}
+
+// Gives each referenced index that has no entry in the table a number of its own after the
+// table's, so it can't end up pointing at an entry that was renumbered onto it. The new
+// numbers run on without gaps, so the highest is the mapping's length.
+fn number_dangling<I: Ord + Copy + From<u32>>(
+    mapping: &mut BTreeMap<I, I>,
+    referenced: impl IntoIterator<Item = I>,
+) {
+    for idx in referenced {
+        let fresh = I::from(mapping.len() as u32 + 1);
+        mapping.entry(idx).or_insert(fresh);
+    }
+}
+
+fn remap<K, I: Ord + Copy>(table: Option<&mut Table<K, I>>, mapping: &BTreeMap<I, I>) {
+    for idx in table.into_iter().flat_map(|t| t.values_mut()) {
+        if let Some(&new) = mapping.get(idx) {
+            *idx = new;
+        }
+    }
+}
+
+fn trim(s: &mut String) {
+    let trimmed = s.trim();
+    if trimmed.len() != s.len() {
+        *s = trimmed.to_string();
+    }
+}
+
+impl Wif {
+    /// Puts the draft into a normal form so that the output of [`write`](Self::write) only
+    /// changes when the draft does: color and symbol indices are renumbered from 1 without
+    /// gaps, indices with no entry are numbered after the last that has one, text is
+    /// trimmed, and CONTENTS lists exactly the sections present. Tables are always written
+    /// in key order and booleans as `true`/`false`.
+    pub fn canonicalize(&mut self) {
+        self.renumber_colors();
+        if let Some(table) = self.warp_symbol_table.as_mut() {
+            let mut mapping = renumber(table);
+            let default = self.warp.as_ref().and_then(|w| w.symbol_number);
+            let used = self.warp_symbols.iter().flat_map(|t| t.values().copied());
+            number_dangling(&mut mapping, used.chain(default));
+            remap(self.warp_symbols.as_mut(), &mapping);
+            if let Some(number) = self.warp.as_mut().and_then(|w| w.symbol_number.as_mut()) {
+                if let Some(&new) = mapping.get(number) {
+                    *number = new;
+                }
+            }
+            if let Some(palette) = self.warp_symbol_palette.as_mut() {
+                palette.entries = mapping.len();
+            }
+        }
+        if let Some(table) = self.weft_symbols_table.as_mut() {
+            let mut mapping = renumber(table);
+            let default = self.weft.as_ref().and_then(|w| w.symbol_number);
+            let used = self.weft_symbols.iter().flat_map(|t| t.values().copied());
+            number_dangling(&mut mapping, used.chain(default));
+            remap(self.weft_symbols.as_mut(), &mapping);
+            if let Some(number) = self.weft.as_mut().and_then(|w| w.symbol_number.as_mut()) {
+                if let Some(&new) = mapping.get(number) {
+                    *number = new;
+                }
+            }
+            if let Some(palette) = self.weft_symbol_palette.as_mut() {
+                palette.entries = mapping.len();
+            }
+        }
+
+        let header = &mut self.wif_header;
+        for field in [
+            &mut header.version,
+            &mut header.developers,
+            &mut header.source_program,
+        ] {
+            trim(field);
+        }
+        if let Some(version) = header.source_version.as_mut() {
+            trim(version);
+        }
+        if let Some(text) = self.text.as_mut() {
+            for field in [
+                &mut text.title,
+                &mut text.author,
+                &mut text.address,
+                &mut text.email,
+                &mut text.telephone,
+                &mut text.fax,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                trim(field);
+            }
+        }
+        for note in self.notes.iter_mut().flat_map(|n| n.values_mut()) {
+            trim(note);
+        }
+
+        let present = self.to_ini();
+        self.contents = Contents {
+            entries: sections::ALL
+                .iter()
+                .filter(|&&name| name != sections::WIF && name != sections::CONTENTS)
+                .filter(|&&name| present.has_section(name))
+                .map(|&name| (name.to_string(), true))
+                .collect(),
+            pinned: false,
+        };
+    }
+
+    /// Renumbers the color table from 1 without gaps, keeping its order. Colors with no
+    /// entry in the table are numbered after it.
+    pub(super) fn renumber_colors(&mut self) {
+        if let Some(table) = self.color_table.as_mut() {
+            let mut mapping = renumber(table);
+            let warp = self.warp_colors.iter().flat_map(|t| t.values().copied());
+            let weft = self.weft_colors.iter().flat_map(|t| t.values().copied());
+            let defaults = [
+                self.warp.as_ref().and_then(|w| w.color),
+                self.weft.as_ref().and_then(|w| w.color),
+            ];
+            let defaults = defaults.into_iter().flatten().map(|c| c.idx);
+            number_dangling(&mut mapping, warp.chain(weft).chain(defaults));
+            remap(self.warp_colors.as_mut(), &mapping);
+            remap(self.weft_colors.as_mut(), &mapping);
+            let defaults = [
+                self.warp.as_mut().and_then(|w| w.color.as_mut()),
+                self.weft.as_mut().and_then(|w| w.color.as_mut()),
+            ];
+            for color in defaults.into_iter().flatten() {
+                if let Some(&new) = mapping.get(&color.idx) {
+                    color.idx = new;
+                }
+            }
+            if let Some(palette) = self.color_palette.as_mut() {
+                palette.entries = mapping.len();
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use super::{Symbol, Wif};
+use crate::{Length, Warp, WarpOrWeft, Weft};
+
+/// Everything about one interlacement of the drawdown, from the thread on top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellInfo {
+    pub on_top: WarpOrWeft,
+    pub color: Option<[u8; 3]>,
+    pub symbol: Option<Symbol>,
+    pub thickness: Option<Length>,
+    pub spacing: Option<Length>,
+}
+
+impl Wif {
+    /// Which thread shows at an interlacement, and its resolved color, symbol, and zoomed
+    /// thickness and spacing. `None` if the draft has no threading or liftplan.
+    pub fn cell(&self, warp: impl Into<Warp>, weft: impl Into<Weft>) -> Option<CellInfo> {
+        let (warp, weft) = (warp.into(), weft.into());
+        let on_top = self.warp_or_weft(warp, weft)?;
+        Some(match on_top {
+            WarpOrWeft::Warp => CellInfo {
+                on_top,
+                color: self.warp_color_u8(warp),
+                symbol: self.warp_symbol(warp),
+                thickness: self.warp_thickness_at(warp),
+                spacing: self.warp_spacing_at(warp),
+            },
+            WarpOrWeft::Weft => CellInfo {
+                on_top,
+                color: self.weft_color_u8(weft),
+                symbol: self.weft_symbol(weft),
+                thickness: self.weft_thickness_at(weft),
+                spacing: self.weft_spacing_at(weft),
+            },
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use super::Wif;
+use crate::{Color, ColorIndex, Result, WifError};
+
+// CIE L*a*b* for an sRGB color, under the D65 white point.
+fn lab([red, green, blue]: [u8; 3]) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(red), linear(green), linear(blue));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 216. / 24389. {
+            t.cbrt()
+        } else {
+            (24389. / 27. * t + 16.) / 116.
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
+
+// The CIE76 color difference: the distance between two colors in L*a*b*.
+fn delta_e(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+impl Wif {
+    /// Makes every thread and [WARP]/[WEFT] default using color `old` use `new` instead,
+    /// and removes `old` from the color table. Fails, changing nothing, if `new` isn't in
+    /// the color table.
+    pub fn remap_color(&mut self, old: ColorIndex, new: ColorIndex) -> Result<()> {
+        if old == new {
+            return self.check_color(new);
+        }
+        self.swap_colorway(&[(old, new)].into())?;
+        if let Some(table) = &mut self.color_table {
+            table.remove(&old);
+            if let Some(palette) = &mut self.color_palette {
+                palette.entries = super::palette_entries(table, |idx| idx.0);
+            }
+        }
+        Ok(())
+    }
+
+    /// Changes the color at `idx` in the color table, recoloring every thread using it.
+    /// Fails if `idx` isn't in the color table.
+    pub fn replace_color(&mut self, idx: ColorIndex, color: Color) -> Result<()> {
+        self.check_color(idx)?;
+        if let Some(table) = &mut self.color_table {
+            table.insert(idx, color);
+        }
+        Ok(())
+    }
+
+    /// Recolors the threads and [WARP]/[WEFT] defaults through `map`, all at once, so
+    /// swapping two colors works. Colors `map` leaves out stay as they are, and the color
+    /// table keeps every entry. Fails, changing nothing, if `map` leads to a color that
+    /// isn't in the color table.
+    pub fn swap_colorway(&mut self, map: &BTreeMap<ColorIndex, ColorIndex>) -> Result<()> {
+        for &new in map.values() {
+            self.check_color(new)?;
+        }
+        let per_thread = self
+            .warp_colors
+            .iter_mut()
+            .flat_map(|c| c.values_mut())
+            .chain(self.weft_colors.iter_mut().flat_map(|c| c.values_mut()));
+        let defaults = [
+            self.warp.as_mut().and_then(|w| w.color.as_mut()),
+            self.weft.as_mut().and_then(|w| w.color.as_mut()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|color| &mut color.idx);
+        for idx in per_thread.chain(defaults) {
+            if let Some(&new) = map.get(idx) {
+                *idx = new;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_color(&self, idx: ColorIndex) -> Result<()> {
+        if self
+            .color_table
+            .as_ref()
+            .is_some_and(|table| table.contains_key(&idx))
+        {
+            Ok(())
+        } else {
+            Err(WifError::UnknownColor { idx: idx.0 })
+        }
+    }
+
+    /// Merges color table entries that look the same: each color within
+    /// `delta_e_threshold` (CIE76 ΔE, where about 2.3 is just noticeable) of an earlier
+    /// entry is removed, and the threads using it take the earlier one. A threshold of 0
+    /// merges only exact duplicates. Returns each removed entry with the one it merged into.
+    pub fn consolidate_palette(
+        &mut self,
+        delta_e_threshold: f64,
+    ) -> BTreeMap<ColorIndex, ColorIndex> {
+        let Some(table) = &self.color_table else {
+            return BTreeMap::new();
+        };
+        let mut kept: Vec<(ColorIndex, [f64; 3])> = vec![];
+        let mut merged = BTreeMap::new();
+        for (&idx, &color) in table {
+            let color = lab(self.color_u8(color));
+            match kept
+                .iter()
+                .find(|(_, other)| delta_e(*other, color) <= delta_e_threshold)
+            {
+                Some(&(into, _)) => {
+                    merged.insert(idx, into);
+                }
+                None => kept.push((idx, color)),
+            }
+        }
+        self.swap_colorway(&merged)
+            .expect("colors are only merged into entries in the color table");
+        if let Some(table) = &mut self.color_table {
+            table.retain(|idx, _| !merged.contains_key(idx));
+            if let Some(palette) = &mut self.color_palette {
+                palette.entries = super::palette_entries(table, |idx| idx.0);
+            }
+        }
+        merged
+    }
+}
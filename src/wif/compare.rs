@@ -0,0 +1,150 @@
+//! Comparing drafts by what they weave rather than how the file spells it. Both comparisons
+//! work from the drawdown and the RGB color of each thread, so shaft and treadle numbering,
+//! tieup versus liftplan, and color index order make no difference.
+
+use std::collections::BTreeMap;
+
+use super::Wif;
+use crate::{Warp, Weft};
+
+// The drawdown and thread colors.
+struct Woven {
+    cells: Vec<Vec<bool>>,
+    warp: Vec<Option<[u8; 3]>>,
+    weft: Vec<Option<[u8; 3]>>,
+}
+
+impl Woven {
+    fn new(wif: &Wif) -> Self {
+        Woven {
+            cells: wif.to_drawdown_matrix(),
+            warp: (1..=wif.width().unwrap_or(0))
+                .map(|end| wif.warp_color_u8(Warp(end)))
+                .collect(),
+            weft: (1..=wif.height().unwrap_or(0))
+                .map(|pick| wif.weft_color_u8(Weft(pick)))
+                .collect(),
+        }
+    }
+
+    fn warp_cells(&self) -> usize {
+        self.cells.iter().flatten().filter(|&&warp| warp).count()
+    }
+
+    // Whether `other` is this cloth started at end `dx + 1` and pick `dy + 1`.
+    fn matches_at(&self, other: &Woven, dx: usize, dy: usize) -> bool {
+        let (ends, picks) = (self.warp.len(), self.weft.len());
+        other.cells.iter().enumerate().all(|(y, row)| {
+            let shifted = &self.cells[(y + dy) % picks];
+            row.iter()
+                .enumerate()
+                .all(|(x, &cell)| shifted[(x + dx) % ends] == cell)
+        })
+    }
+
+    fn float_lengths(&self) -> BTreeMap<(bool, usize), usize> {
+        let mut lengths = BTreeMap::new();
+        let columns = (0..self.warp.len()).map(|x| runs(self.cells.iter().map(|row| row[x]), true));
+        for length in columns.flatten() {
+            *lengths.entry((true, length)).or_default() += 1;
+        }
+        let rows = self
+            .cells
+            .iter()
+            .map(|row| runs(row.iter().copied(), false));
+        for length in rows.flatten() {
+            *lengths.entry((false, length)).or_default() += 1;
+        }
+        lengths
+    }
+}
+
+// Every rotation of `other` that lines it up with `this`.
+fn rotations<T: PartialEq>(this: &[T], other: &[T]) -> Vec<usize> {
+    (0..this.len().max(1))
+        .filter(|&d| {
+            other
+                .iter()
+                .enumerate()
+                .all(|(i, item)| this[(i + d) % this.len()] == *item)
+        })
+        .collect()
+}
+
+// Lengths of the runs of `value` along a line.
+fn runs(line: impl Iterator<Item = bool>, value: bool) -> Vec<usize> {
+    let mut runs = vec![];
+    let mut run = 0;
+    for cell in line {
+        if cell == value {
+            run += 1;
+        } else if run > 0 {
+            runs.push(run);
+            run = 0;
+        }
+    }
+    if run > 0 {
+        runs.push(run);
+    }
+    runs
+}
+
+fn counts<T: Ord + Copy>(items: &[T]) -> BTreeMap<T, usize> {
+    let mut counts = BTreeMap::new();
+    for &item in items {
+        *counts.entry(item).or_default() += 1;
+    }
+    counts
+}
+
+// How much two distributions have in common, from 0 to 1.
+fn overlap<K: Ord>(a: &BTreeMap<K, usize>, b: &BTreeMap<K, usize>) -> f64 {
+    let (total_a, total_b) = (a.values().sum::<usize>(), b.values().sum::<usize>());
+    if total_a == 0 || total_b == 0 {
+        return if total_a == total_b { 1. } else { 0. };
+    }
+    a.iter()
+        .filter_map(|(key, &n)| Some((n, *b.get(key)?)))
+        .map(|(n, m)| (n as f64 / total_a as f64).min(m as f64 / total_b as f64))
+        .sum()
+}
+
+fn same_cloth(a: &Woven, b: &Woven) -> bool {
+    if a.warp.len() != b.warp.len()
+        || a.weft.len() != b.weft.len()
+        || a.warp_cells() != b.warp_cells()
+        || counts(&a.warp) != counts(&b.warp)
+        || counts(&a.weft) != counts(&b.weft)
+    {
+        return false;
+    }
+    if a.warp.is_empty() || a.weft.is_empty() {
+        return true;
+    }
+    let dxs = rotations(&a.warp, &b.warp);
+    rotations(&a.weft, &b.weft)
+        .into_iter()
+        .any(|dy| dxs.iter().any(|&dx| a.matches_at(b, dx, dy)))
+}
+
+impl Wif {
+    /// Whether the two drafts weave the same cloth in the same colors, allowing the repeat
+    /// to start at a different end or pick.
+    pub fn structurally_equal(&self, other: &Wif) -> bool {
+        same_cloth(&Woven::new(self), &Woven::new(other))
+    }
+
+    /// How alike the two drafts are, from 0 to 1: the average overlap of their float lengths
+    /// and of the proportion of ends and of picks in each color. Structurally equal drafts
+    /// score 1, as can drafts that only differ in size or in the order of their threads.
+    pub fn similarity(&self, other: &Wif) -> f64 {
+        let (a, b) = (Woven::new(self), Woven::new(other));
+        if same_cloth(&a, &b) {
+            return 1.;
+        }
+        let floats = overlap(&a.float_lengths(), &b.float_lengths());
+        let warp = overlap(&counts(&a.warp), &counts(&b.warp));
+        let weft = overlap(&counts(&a.weft), &counts(&b.weft));
+        (floats + warp + weft) / 3.
+    }
+}
@@ -0,0 +1,65 @@
+use std::ops::{Bound, RangeBounds};
+
+use super::setters::renumber;
+use super::Wif;
+use crate::{Warp, Weft};
+
+// The first and last thread a range covers, clamped to 1..=count.
+fn span(range: &impl RangeBounds<u32>, count: u32) -> (u32, u32) {
+    let first = match range.start_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n + 1,
+        Bound::Unbounded => 1,
+    };
+    let last = match range.end_bound() {
+        Bound::Included(&n) => n,
+        Bound::Excluded(&n) => n.saturating_sub(1),
+        Bound::Unbounded => count,
+    };
+    (first.max(1), last.min(count))
+}
+
+// Keeps threads first..=last, renumbered from 1.
+fn window(index: u32, (first, last): (u32, u32)) -> Option<u32> {
+    (first..=last).contains(&index).then(|| index - first + 1)
+}
+
+impl Wif {
+    /// A new draft holding only the ends in `warps` and the picks in `wefts`, both
+    /// renumbered from 1. Shafts no cropped end is threaded on and treadles no cropped pick
+    /// uses are removed and the rest renumbered in order; colors no longer used are dropped
+    /// from the color table.
+    pub fn crop(&self, warps: impl RangeBounds<u32>, wefts: impl RangeBounds<u32>) -> Wif {
+        let mut out = self.clone();
+        let ends = span(&warps, self.width().unwrap_or(0));
+        let picks = span(&wefts, self.height().unwrap_or(0));
+
+        let end_window = |w: Warp| window(w.0, ends).map(Warp);
+        renumber(&mut out.threading, end_window);
+        renumber(&mut out.warp_colors, end_window);
+        renumber(&mut out.warp_thickness, end_window);
+        renumber(&mut out.warp_thickness_zoom, end_window);
+        renumber(&mut out.warp_spacing, end_window);
+        renumber(&mut out.warp_spacing_zoom, end_window);
+        renumber(&mut out.warp_symbols, end_window);
+        let pick_window = |w: Weft| window(w.0, picks).map(Weft);
+        renumber(&mut out.treadling, pick_window);
+        renumber(&mut out.liftplan, pick_window);
+        renumber(&mut out.weft_colors, pick_window);
+        renumber(&mut out.weft_thickness, pick_window);
+        renumber(&mut out.weft_thickness_zoom, pick_window);
+        renumber(&mut out.weft_spacing, pick_window);
+        renumber(&mut out.weft_spacing_zoom, pick_window);
+        renumber(&mut out.weft_symbols, pick_window);
+        if let Some(warp) = &mut out.warp {
+            warp.threads = (ends.0..=ends.1).count() as u32;
+        }
+        if let Some(weft) = &mut out.weft {
+            weft.threads = (picks.0..=picks.1).count() as u32;
+        }
+
+        out.compact_shafts_and_treadles();
+        out.prune_colors();
+        out
+    }
+}
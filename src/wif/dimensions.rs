@@ -0,0 +1,105 @@
+use super::Wif;
+use crate::Length;
+
+/// Consecutive threads `first..=last` with the same spacing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettSection {
+    pub first: u32,
+    pub last: u32,
+    pub spacing: Length,
+}
+
+impl SettSection {
+    pub fn threads(&self) -> u32 {
+        self.last - self.first + 1
+    }
+
+    /// Threads per inch, or `None` if the spacing's units are unknown.
+    pub fn per_inch(&self) -> Option<f64> {
+        Some(1. / self.spacing.inches()?)
+    }
+
+    pub fn per_centimeter(&self) -> Option<f64> {
+        Some(1. / self.spacing.centimeters()?)
+    }
+}
+
+/// The sett along the warp or weft: how far the threads span and how closely they're set,
+/// section by section where the spacing varies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sett {
+    pub threads: u32,
+    /// The span of all the threads, in inches.
+    pub inches: f64,
+    pub sections: Vec<SettSection>,
+}
+
+impl Sett {
+    pub fn centimeters(&self) -> f64 {
+        self.inches * 2.54
+    }
+
+    /// The average number of threads per inch.
+    pub fn per_inch(&self) -> f64 {
+        if self.inches > 0. {
+            self.threads as f64 / self.inches
+        } else {
+            0.
+        }
+    }
+
+    pub fn per_centimeter(&self) -> f64 {
+        self.per_inch() / 2.54
+    }
+
+    /// Whether every thread has the same spacing.
+    pub fn is_uniform(&self) -> bool {
+        self.sections.len() <= 1
+    }
+
+    // Groups `spacings` into sections, or `None` if any thread has no spacing or its units
+    // are unknown.
+    fn new(spacings: impl Iterator<Item = Option<Length>>) -> Option<Sett> {
+        let mut sett = Sett {
+            threads: 0,
+            inches: 0.,
+            sections: vec![],
+        };
+        for (thread, spacing) in (1..).zip(spacings) {
+            let spacing = spacing?;
+            sett.threads = thread;
+            sett.inches += spacing.inches()?;
+            match sett.sections.last_mut() {
+                Some(section) if section.spacing == spacing => section.last = thread,
+                _ => sett.sections.push(SettSection {
+                    first: thread,
+                    last: thread,
+                    spacing,
+                }),
+            }
+        }
+        Some(sett)
+    }
+}
+
+/// The size of the cloth on the loom, from [`Wif::fabric_dimensions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FabricDimensions {
+    /// Across the ends, giving the width in the reed and the ends per inch.
+    pub warp: Option<Sett>,
+    /// Along the picks, giving the woven length and the picks per inch.
+    pub weft: Option<Sett>,
+}
+
+impl Wif {
+    /// The width and length of the woven cloth and its sett, from each thread's spacing with
+    /// zoom applied. A side is `None` if any of its threads has no spacing or the units are
+    /// missing. These are sizes on the loom, before take-up and shrinkage.
+    pub fn fabric_dimensions(&self) -> FabricDimensions {
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        FabricDimensions {
+            warp: Sett::new((1..=ends).map(|end| self.warp_spacing_at(end))),
+            weft: Sett::new((1..=picks).map(|pick| self.weft_spacing_at(pick))),
+        }
+    }
+}
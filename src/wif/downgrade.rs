@@ -0,0 +1,93 @@
+use super::{sections, Wif, WifVersion};
+
+/// Data dropped by [`Wif::write_as_version`] because the target version can't express it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionLoss {
+    Section(&'static str),
+    Field {
+        section: &'static str,
+        field: &'static str,
+    },
+}
+
+// The zoom fields and tables were added in WIF 1.1.
+fn strip_1_1(wif: &mut Wif) -> Vec<VersionLoss> {
+    let mut lost = Vec::new();
+    let tables = [
+        (
+            sections::WARP_THICKNESS_ZOOM,
+            wif.warp_thickness_zoom.take().is_some(),
+        ),
+        (
+            sections::WARP_SPACING_ZOOM,
+            wif.warp_spacing_zoom.take().is_some(),
+        ),
+        (
+            sections::WEFT_THICKNESS_ZOOM,
+            wif.weft_thickness_zoom.take().is_some(),
+        ),
+        (
+            sections::WEFT_SPACING_ZOOM,
+            wif.weft_spacing_zoom.take().is_some(),
+        ),
+    ];
+    lost.extend(
+        tables
+            .into_iter()
+            .filter(|&(_, present)| present)
+            .map(|(section, _)| VersionLoss::Section(section)),
+    );
+    let fields = [
+        wif.warp.as_mut().map(|w| {
+            (
+                sections::WARP,
+                w.spacing_zoom.take().is_some(),
+                w.thickness_zoom.take().is_some(),
+            )
+        }),
+        wif.weft.as_mut().map(|w| {
+            (
+                sections::WEFT,
+                w.spacing_zoom.take().is_some(),
+                w.thickness_zoom.take().is_some(),
+            )
+        }),
+    ];
+    for (section, spacing, thickness) in fields.into_iter().flatten() {
+        if spacing {
+            lost.push(VersionLoss::Field {
+                section,
+                field: "Spacing Zoom",
+            });
+        }
+        if thickness {
+            lost.push(VersionLoss::Field {
+                section,
+                field: "Thickness Zoom",
+            });
+        }
+    }
+    lost
+}
+
+impl Wif {
+    /// Writes the draft for readers of an older spec version, leaving out anything that version
+    /// doesn't define. Returns what was left out.
+    pub fn write_as_version<W>(
+        &self,
+        version: WifVersion,
+        output: &mut W,
+    ) -> std::io::Result<Vec<VersionLoss>>
+    where
+        W: std::io::Write,
+    {
+        let mut wif = self.clone();
+        let lost = match version {
+            WifVersion::V1_0 => strip_1_1(&mut wif),
+            WifVersion::V1_1 => Vec::new(),
+        };
+        wif.wif_header.version = version.as_str().into();
+        wif.write(output)?;
+        Ok(lost)
+    }
+}
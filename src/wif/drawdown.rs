@@ -0,0 +1,128 @@
+use super::Wif;
+use crate::{progress, Progress, Result, Warp, WarpOrWeft, Weft, WifError};
+
+/// Which thread is on top at every interlacement, stored row by row, one row per pick (pick
+/// 1 first) and one cell per end, `true` where the warp shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drawdown {
+    ends: u32,
+    picks: u32,
+    cells: Vec<bool>,
+}
+
+impl Drawdown {
+    pub fn ends(&self) -> u32 {
+        self.ends
+    }
+
+    pub fn picks(&self) -> u32 {
+        self.picks
+    }
+
+    // The offset of a cell in `cells`, if it's inside the drawdown.
+    fn index(&self, warp: Warp, weft: Weft) -> Option<usize> {
+        let inside = (1..=self.ends).contains(&warp.0) && (1..=self.picks).contains(&weft.0);
+        if !inside {
+            return None;
+        }
+        let row = (weft.0 as usize - 1).checked_mul(self.ends as usize)?;
+        row.checked_add(warp.0 as usize - 1)
+    }
+
+    /// Which thread is on top where an end crosses a pick, or `None` outside the drawdown.
+    pub fn get(&self, warp: impl Into<Warp>, weft: impl Into<Weft>) -> Option<WarpOrWeft> {
+        let index = self.index(warp.into(), weft.into())?;
+        Some(if self.cells[index] {
+            WarpOrWeft::Warp
+        } else {
+            WarpOrWeft::Weft
+        })
+    }
+
+    /// One pick's cells, one per end.
+    pub fn row(&self, weft: impl Into<Weft>) -> Option<&[bool]> {
+        let weft = weft.into();
+        let start = self.index(Warp(1), weft)?;
+        Some(&self.cells[start..start + self.ends as usize])
+    }
+
+    /// Every pick's cells, pick 1 first.
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> + '_ {
+        let ends = self.ends as usize;
+        (0..self.picks as usize).map(move |pick| &self.cells[pick * ends..(pick + 1) * ends])
+    }
+
+    /// All the cells, row after row.
+    pub fn as_slice(&self) -> &[bool] {
+        &self.cells
+    }
+
+    pub fn to_matrix(&self) -> Vec<Vec<bool>> {
+        self.rows().map(<[bool]>::to_vec).collect()
+    }
+}
+
+impl Wif {
+    // Fills in each pick's row of cells, one per end, `true` where the warp shows.
+    fn interlace(&self) -> impl Fn(u32, &mut [bool]) + '_ {
+        let rising_shed = self
+            .weaving
+            .as_ref()
+            .and_then(|w| w.rising_shed)
+            .unwrap_or(true);
+        // Each end's shafts, looked up once rather than per cell.
+        let threaded: Vec<_> = (1..=self.width().unwrap_or(0))
+            .map(|end| self.threading.as_ref()?.get(&Warp(end)))
+            .collect();
+        move |pick, row| {
+            let Some(liftplan) = &self.liftplan else {
+                return;
+            };
+            let lift = liftplan.get(&Weft(pick));
+            for (cell, shafts) in row.iter_mut().zip(&threaded) {
+                // Unthreaded ends never move, so the weft covers them.
+                *cell = shafts.is_some_and(|shafts| {
+                    let listed = lift.is_some_and(|lift| !lift.is_disjoint(shafts));
+                    listed == rising_shed
+                });
+            }
+        }
+    }
+
+    /// The drawdown for every end and pick, as [`warp_or_weft`](Self::warp_or_weft) would
+    /// give it cell by cell. Without a threading or liftplan, the weft shows everywhere.
+    ///
+    /// Fails with [`WifError::DrawdownTooLarge`] if there are more cells than fit in memory's
+    /// address space.
+    pub fn drawdown(&self) -> Result<Drawdown> {
+        self.drawdown_with_progress(&mut ())
+    }
+
+    /// Like [`drawdown`](Self::drawdown), reporting progress after each pick.
+    pub fn drawdown_with_progress(&self, progress: &mut dyn Progress) -> Result<Drawdown> {
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        let size = (ends as usize)
+            .checked_mul(picks as usize)
+            .ok_or(WifError::DrawdownTooLarge { ends, picks })?;
+        let mut cells = vec![false; size];
+        let interlace = self.interlace();
+        for (pick, row) in (1..=picks).zip(cells.chunks_mut(ends.max(1) as usize)) {
+            interlace(pick, row);
+            progress::report(progress, pick as usize, picks as usize)?;
+        }
+        Ok(Drawdown { ends, picks, cells })
+    }
+
+    /// The drawdown, one row per pick and one column per end, `true` where the warp is on top.
+    pub fn to_drawdown_matrix(&self) -> Vec<Vec<bool>> {
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        let interlace = self.interlace();
+        (1..=picks)
+            .map(|pick| {
+                let mut row = vec![false; ends as usize];
+                interlace(pick, &mut row);
+                row
+            })
+            .collect()
+    }
+}
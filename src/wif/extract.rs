@@ -0,0 +1,66 @@
+use std::ops::Range;
+
+use crate::{Result, Wif, WifError};
+
+fn strip_quoting(line: &str) -> &str {
+    let mut line = line.trim_start();
+    while let Some(rest) = line.strip_prefix('>') {
+        line = rest.trim_start();
+    }
+    line
+}
+
+fn is_wif_line(line: &str) -> bool {
+    let line = strip_quoting(line).trim_end();
+    if line.starts_with(';') {
+        return true;
+    }
+    if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        return !name.is_empty() && !name.contains(['[', ']']);
+    }
+    match line.split_once('=') {
+        Some((key, _)) => {
+            let key = key.trim();
+            !key.is_empty()
+                && key.len() <= 40
+                && key.chars().all(|c| c.is_alphanumeric() || c == ' ')
+        }
+        None => false,
+    }
+}
+
+/// Finds a WIF file embedded in other text, such as an email or forum post, and parses it.
+/// Returns the draft along with the byte range of `text` it was read from. Lines may be quoted
+/// with `>` as in email replies.
+pub fn extract_and_parse(text: &str) -> Result<(Wif, Range<usize>)> {
+    let mut offset = 0;
+    let mut start = None;
+    let mut end = 0;
+    let mut body = String::new();
+    for line in text.split_inclusive('\n') {
+        let line_range = offset..offset + line.len();
+        offset += line.len();
+        let content = strip_quoting(line).trim_end();
+        if start.is_none() {
+            if content.eq_ignore_ascii_case("[WIF]") {
+                start = Some(line_range.start);
+            } else {
+                continue;
+            }
+        }
+        if content.is_empty() {
+            // Blank lines separate sections; only a following WIF line extends the region
+            body.push('\n');
+            continue;
+        }
+        if !is_wif_line(line) {
+            break;
+        }
+        body.push_str(content);
+        body.push('\n');
+        end = line_range.start + line.trim_end().len();
+    }
+    let start = start.ok_or(WifError::NoWifSection)?;
+    let wif = super::parse(&body)?;
+    Ok((wif, start..end))
+}
@@ -0,0 +1,39 @@
+use std::hash::{DefaultHasher, Hasher};
+
+use super::Wif;
+
+impl Wif {
+    // The size and drawdown, eight cells to a byte, row by row. This depends only on what the
+    // draft weaves, not on how shafts and treadles are numbered or how the threads are colored.
+    fn structure_bytes(&self) -> Vec<u8> {
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        let mut bytes = [ends.to_le_bytes(), picks.to_le_bytes()].concat();
+        for row in self.to_drawdown_matrix() {
+            bytes.extend(row.chunks(8).map(|cells| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, &warp)| byte | (u8::from(warp) << i))
+            }));
+        }
+        bytes
+    }
+
+    /// A hash of the draft's interlacement structure, for spotting duplicates. Drafts that
+    /// weave the same cloth get the same fingerprint whatever their shaft order, tieup or
+    /// colors. The value is only stable within one build of the program; store
+    /// [`fingerprint_sha256`](Self::fingerprint_sha256) instead.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&self.structure_bytes());
+        hasher.finish()
+    }
+
+    /// The SHA-256 of the same structure as [`fingerprint`](Self::fingerprint), stable across
+    /// builds and platforms.
+    #[cfg(feature = "sha2")]
+    pub fn fingerprint_sha256(&self) -> [u8; 32] {
+        use sha2::{Digest, Sha256};
+        Sha256::digest(self.structure_bytes()).into()
+    }
+}
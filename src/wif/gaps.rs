@@ -0,0 +1,220 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::{sections, Wif};
+use crate::{Table, Warp, Weft};
+
+/// The thread numbers missing from one per-thread table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableGaps {
+    pub section: &'static str,
+    /// Ascending, each within `1..=` the [WARP] or [WEFT] thread count.
+    pub missing: Vec<u32>,
+}
+
+/// Written as runs, e.g. `[THREADING] missing 41-44, 50`.
+impl fmt::Display for TableGaps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] missing ", self.section)?;
+        let mut runs = vec![];
+        for &n in &self.missing {
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == n => *end = n,
+                _ => runs.push((n, n)),
+            }
+        }
+        for (i, (start, end)) in runs.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            if start == end {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{end}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every per-thread table with gaps, warp tables first, in file order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompletenessReport {
+    pub tables: Vec<TableGaps>,
+}
+
+impl CompletenessReport {
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    pub fn get(&self, section: &str) -> Option<&TableGaps> {
+        self.tables.iter().find(|t| t.section == section)
+    }
+
+    fn push(&mut self, section: &'static str, missing: Vec<u32>) {
+        if !missing.is_empty() {
+            self.tables.push(TableGaps { section, missing });
+        }
+    }
+}
+
+/// One line per table.
+impl fmt::Display for CompletenessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for table in &self.tables {
+            writeln!(f, "{table}")?;
+        }
+        Ok(())
+    }
+}
+
+// Numbers in `1..=threads` with no entry. Without a thread count, the table's last key stands
+// in for it.
+fn gaps<K, V>(
+    table: Option<&Table<K, V>>,
+    threads: Option<u32>,
+    index: impl Fn(&K) -> u32,
+) -> Vec<u32> {
+    let Some(table) = table else {
+        return vec![];
+    };
+    let present: BTreeSet<u32> = table.keys().map(index).collect();
+    let threads = threads.or_else(|| present.last().copied()).unwrap_or(0);
+    (1..=threads).filter(|n| !present.contains(n)).collect()
+}
+
+// Calls `$m!` for each per-thread table with its section, the thread count of its side, its
+// key type, and the value from [WARP] or [WEFT] that stands in for a missing entry, if any.
+macro_rules! per_thread_tables {
+    ($wif:expr, $m:ident) => {
+        $m!(threading, THREADING, $wif.width(), Warp);
+        $m!(
+            warp_thickness,
+            WARP_THICKNESS,
+            $wif.width(),
+            Warp,
+            $wif.warp.as_ref().and_then(|w| w.thickness)
+        );
+        $m!(
+            warp_thickness_zoom,
+            WARP_THICKNESS_ZOOM,
+            $wif.width(),
+            Warp,
+            $wif.warp.as_ref().and_then(|w| w.thickness_zoom)
+        );
+        $m!(
+            warp_spacing,
+            WARP_SPACING,
+            $wif.width(),
+            Warp,
+            $wif.warp.as_ref().and_then(|w| w.spacing)
+        );
+        $m!(
+            warp_spacing_zoom,
+            WARP_SPACING_ZOOM,
+            $wif.width(),
+            Warp,
+            $wif.warp.as_ref().and_then(|w| w.spacing_zoom)
+        );
+        $m!(
+            warp_colors,
+            WARP_COLORS,
+            $wif.width(),
+            Warp,
+            $wif.warp.as_ref().and_then(|w| w.color).map(|c| c.idx)
+        );
+        $m!(
+            warp_symbols,
+            WARP_SYMBOLS,
+            $wif.width(),
+            Warp,
+            $wif.warp.as_ref().and_then(|w| w.symbol_number)
+        );
+        $m!(treadling, TREADLING, $wif.height(), Weft);
+        $m!(liftplan, LIFTPLAN, $wif.height(), Weft);
+        $m!(
+            weft_thickness,
+            WEFT_THICKNESS,
+            $wif.height(),
+            Weft,
+            $wif.weft.as_ref().and_then(|w| w.thickness)
+        );
+        $m!(
+            weft_thickness_zoom,
+            WEFT_THICKNESS_ZOOM,
+            $wif.height(),
+            Weft,
+            $wif.weft.as_ref().and_then(|w| w.thickness_zoom)
+        );
+        $m!(
+            weft_spacing,
+            WEFT_SPACING,
+            $wif.height(),
+            Weft,
+            $wif.weft.as_ref().and_then(|w| w.spacing)
+        );
+        $m!(
+            weft_spacing_zoom,
+            WEFT_SPACING_ZOOM,
+            $wif.height(),
+            Weft,
+            $wif.weft.as_ref().and_then(|w| w.spacing_zoom)
+        );
+        $m!(
+            weft_colors,
+            WEFT_COLORS,
+            $wif.height(),
+            Weft,
+            $wif.weft.as_ref().and_then(|w| w.color).map(|c| c.idx)
+        );
+        $m!(
+            weft_symbols,
+            WEFT_SYMBOLS,
+            $wif.height(),
+            Weft,
+            $wif.weft.as_ref().and_then(|w| w.symbol_number)
+        );
+    };
+}
+
+impl Wif {
+    /// Thread numbers missing from each per-thread table that's present. A gap is read as the
+    /// [WARP] or [WEFT] default, or as unthreaded or unlifted, but tools that read tables as
+    /// lists shift every later thread into it.
+    pub fn find_gaps(&self) -> CompletenessReport {
+        let mut report = CompletenessReport::default();
+        macro_rules! find {
+            ($field:ident, $name:ident, $threads:expr, $key:ident $(, $default:expr)?) => {
+                report.push(
+                    sections::$name,
+                    gaps(self.$field.as_ref(), $threads, |k| k.0),
+                );
+            };
+        }
+        per_thread_tables!(self, find);
+        report
+    }
+
+    /// Fills the gaps [`find_gaps`](Self::find_gaps) reports with the [WARP] or [WEFT]
+    /// default for the table, and returns what was filled. Tables without a default, including
+    /// the threading, treadling and liftplan, are left as they are.
+    pub fn fill_gaps(&mut self) -> CompletenessReport {
+        let mut report = CompletenessReport::default();
+        macro_rules! fill {
+            ($field:ident, $name:ident, $threads:expr, $key:ident $(, $default:expr)?) => {
+                $(
+                    let missing = gaps(self.$field.as_ref(), $threads, |k| k.0);
+                    if let (Some(table), Some(default)) = (&mut self.$field, $default) {
+                        for &n in &missing {
+                            table.insert($key(n), default);
+                        }
+                        report.push(sections::$name, missing);
+                    }
+                )?
+            };
+        }
+        per_thread_tables!(self, fill);
+        report
+    }
+}
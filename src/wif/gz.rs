@@ -0,0 +1,36 @@
+use std::{io::Read, path::Path, sync::Arc};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use super::{encoding, parse, Wif};
+use crate::{Result, WifError};
+
+impl Wif {
+    /// Like [`from_path`](Self::from_path), for a gzip-compressed file.
+    pub fn from_gz_path(path: impl AsRef<Path>) -> Result<Wif> {
+        let path = path.as_ref();
+        let read_error = |error| WifError::CouldNotReadFile {
+            path: path.to_owned(),
+            error: Arc::new(error),
+        };
+        let file = std::fs::File::open(path).map_err(read_error)?;
+        let mut bytes = Vec::new();
+        GzDecoder::new(file)
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        parse(&encoding::decode(&bytes)).map_err(|err| WifError::InFile {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })
+    }
+
+    /// Writes the draft gzip-compressed, streaming it through the encoder.
+    pub fn write_gz<W>(&self, output: W) -> std::io::Result<W>
+    where
+        W: std::io::Write,
+    {
+        let mut encoder = GzEncoder::new(output, Compression::default());
+        self.write(&mut encoder)?;
+        encoder.finish()
+    }
+}
@@ -0,0 +1,240 @@
+//! A single-pass reader for the subset of INI that WIF uses.
+//!
+//! Values borrow from the input wherever possible, sections and keys keep their original
+//! spelling and order, and comments are kept in place so that they can be written back out.
+//! Lookups are case-insensitive, as the spec requires.
+
+use std::{borrow::Cow, collections::HashMap};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Line<'a> {
+    Entry {
+        key: Cow<'a, str>,
+        value: Option<Cow<'a, str>>,
+    },
+    Comment(Cow<'a, str>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IniSection<'a> {
+    pub name: Cow<'a, str>,
+    lines: Vec<Line<'a>>,
+    index: HashMap<Cow<'a, str>, usize>,
+}
+
+/// A key that was given more than once in the same section. Lookups see `last`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Duplicate {
+    pub section: String,
+    pub key: String,
+    pub first: String,
+    pub last: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Ini<'a> {
+    sections: Vec<IniSection<'a>>,
+    index: HashMap<Cow<'a, str>, usize>,
+    /// Comments that appear before the first section.
+    pub preamble: Vec<Cow<'a, str>>,
+    pub duplicates: Vec<Duplicate>,
+}
+
+// Lowercasing allocates, so only do it for names that aren't lowercase already. Table keys are
+// numbers, so in practice this only allocates for section names and named fields.
+fn fold(name: Cow<'_, str>) -> Cow<'_, str> {
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        name
+    }
+}
+
+fn fold_ref(name: &str) -> Cow<'_, str> {
+    fold(Cow::Borrowed(name))
+}
+
+impl<'a> IniSection<'a> {
+    fn new(name: Cow<'a, str>) -> Self {
+        Self {
+            name,
+            lines: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let &idx = self.index.get(&fold_ref(key))?;
+        match &self.lines[idx] {
+            Line::Entry { value, .. } => value.as_deref(),
+            Line::Comment(_) => None,
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(&fold_ref(key))
+    }
+
+    /// Sets a value, returning the previous one if the key was already present.
+    fn set_entry(
+        &mut self,
+        key: Cow<'a, str>,
+        value: Option<Cow<'a, str>>,
+    ) -> Option<Option<Cow<'a, str>>> {
+        if let Some(&idx) = self.index.get(&fold_ref(&key)) {
+            if let Line::Entry { value: old, .. } = &mut self.lines[idx] {
+                return Some(std::mem::replace(old, value));
+            }
+        }
+        self.index.insert(fold(key.clone()), self.lines.len());
+        self.lines.push(Line::Entry { key, value });
+        None
+    }
+
+    pub fn set(&mut self, key: &str, value: Option<String>) {
+        self.set_entry(Cow::Owned(key.to_owned()), value.map(Cow::Owned));
+    }
+
+    pub fn push_comment(&mut self, comment: impl Into<Cow<'a, str>>) {
+        self.lines.push(Line::Comment(comment.into()));
+    }
+
+    /// Key/value pairs in order, skipping comments.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value } => Some((key.as_ref(), value.as_deref())),
+            Line::Comment(_) => None,
+        })
+    }
+
+    pub fn lines(&self) -> &[Line<'a>] {
+        &self.lines
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.index.clear();
+    }
+
+    /// Renames keys in place, keeping their order and values.
+    pub fn rename_keys(&mut self, mut rename: impl FnMut(&str) -> Option<String>) {
+        self.index.clear();
+        for (idx, line) in self.lines.iter_mut().enumerate() {
+            if let Line::Entry { key, .. } = line {
+                if let Some(new_key) = rename(key) {
+                    *key = Cow::Owned(new_key);
+                }
+                self.index.insert(fold(key.clone()), idx);
+            }
+        }
+    }
+}
+
+impl<'a> Ini<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read(input: &'a str) -> Result<Self, String> {
+        let mut ini = Ini::new();
+        let mut current: Option<usize> = None;
+        for (line_no, raw) in input.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with(';') || line.starts_with('#') {
+                match current {
+                    Some(idx) => ini.sections[idx].push_comment(line),
+                    None => ini.preamble.push(Cow::Borrowed(line)),
+                }
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix('[') {
+                let Some(name) = rest.strip_suffix(']') else {
+                    return Err(format!(
+                        "line {}: Found opening bracket for section name but no closing bracket",
+                        line_no + 1
+                    ));
+                };
+                current = Some(ini.section_idx_or_insert(Cow::Borrowed(name.trim())));
+                continue;
+            }
+            let Some(idx) = current else {
+                return Err(format!(
+                    "line {}: Found a value outside of any section",
+                    line_no + 1
+                ));
+            };
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(Cow::Borrowed(value.trim()))),
+                None => (line, None),
+            };
+            let section = &mut ini.sections[idx];
+            let last = value.as_deref().unwrap_or_default().to_string();
+            let Some(old) = section.set_entry(Cow::Borrowed(key), value) else {
+                continue;
+            };
+            let section = &section.name;
+            match ini
+                .duplicates
+                .iter_mut()
+                .find(|d| d.section == *section && d.key.eq_ignore_ascii_case(key))
+            {
+                Some(duplicate) => duplicate.last = last,
+                None => ini.duplicates.push(Duplicate {
+                    section: section.to_string(),
+                    key: key.to_string(),
+                    first: old.as_deref().unwrap_or_default().to_string(),
+                    last,
+                }),
+            }
+        }
+        Ok(ini)
+    }
+
+    fn section_idx_or_insert(&mut self, name: Cow<'a, str>) -> usize {
+        if let Some(&idx) = self.index.get(&fold_ref(&name)) {
+            return idx;
+        }
+        let idx = self.sections.len();
+        self.index.insert(fold(name.clone()), idx);
+        self.sections.push(IniSection::new(name));
+        idx
+    }
+
+    pub fn section(&self, name: &str) -> Option<&IniSection<'a>> {
+        let &idx = self.index.get(&fold_ref(name))?;
+        Some(&self.sections[idx])
+    }
+
+    pub fn section_mut(&mut self, name: &str) -> Option<&mut IniSection<'a>> {
+        let &idx = self.index.get(&fold_ref(name))?;
+        Some(&mut self.sections[idx])
+    }
+
+    pub fn section_or_insert(&mut self, name: &str) -> &mut IniSection<'a> {
+        let idx = match self.index.get(&fold_ref(name)) {
+            Some(&idx) => idx,
+            None => self.section_idx_or_insert(Cow::Owned(name.to_owned())),
+        };
+        &mut self.sections[idx]
+    }
+
+    #[cfg(feature = "preserve-layout")]
+    pub fn sections(&self) -> impl Iterator<Item = &IniSection<'a>> {
+        self.sections.iter()
+    }
+
+    pub fn has_section(&self, name: &str) -> bool {
+        self.index.contains_key(&fold_ref(name))
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.section(section)?.get(key)
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: Option<String>) {
+        self.section_or_insert(section).set(key, value);
+    }
+}
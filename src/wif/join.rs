@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{sections, Wif};
+use crate::analysis::lift_sequence;
+use crate::{ColorIndex, Shaft, Table, Warp, Weft};
+
+// Adds `other`'s entries to `table`, under the keys `shift` gives them.
+fn append<K: Ord, V: Clone>(
+    table: &mut Option<Table<K, V>>,
+    other: Option<&Table<K, V>>,
+    shift: impl Fn(&K) -> K,
+) {
+    let Some(other) = other else {
+        return;
+    };
+    let table = table.get_or_insert_with(Default::default);
+    for (key, value) in other {
+        table.insert(shift(key), value.clone());
+    }
+}
+
+impl Wif {
+    /// Adds `other`'s picks after this draft's, on a warp threaded for both, as for a
+    /// sampler of treadlings. See [`join_warpwise`](Self::join_warpwise) for how the two are
+    /// made into one.
+    pub fn join_weftwise(&mut self, other: &Wif) {
+        self.join(other, false);
+    }
+
+    /// Adds `other`'s ends beside this draft's, woven with both liftplans at once, as for a
+    /// gamp of threadings. The joined draft is wide and long enough for both, and the
+    /// shorter liftplan is repeated to the length of the longer.
+    ///
+    /// Shafts are kept apart and then merged wherever one shaft can do the work of two, and
+    /// the shafts and treadles are numbered from 1 as in [`optimize_shafts`]. Colors from
+    /// `other` are added to the color table, and thread thickness and spacing come along
+    /// with its threads. If both drafts are treadled, a tieup and treadling for the joined
+    /// draft is built with [`derive_treadling`] on as many treadles as the larger loom;
+    /// otherwise, or if that doesn't fit, the joined draft is woven from a liftplan alone.
+    /// The joined draft keeps this draft's shed direction, and `other` is adapted to it.
+    ///
+    /// [`optimize_shafts`]: Self::optimize_shafts
+    /// [`derive_treadling`]: Self::derive_treadling
+    pub fn join_warpwise(&mut self, other: &Wif) {
+        self.join(other, true);
+    }
+
+    fn join(&mut self, other: &Wif, side_by_side: bool) {
+        // The two liftplans are merged as they are, so `other`'s has to list shafts the way
+        // this draft's shed does.
+        let rising_shed = |wif: &Wif| {
+            wif.weaving
+                .as_ref()
+                .and_then(|w| w.rising_shed)
+                .unwrap_or(true)
+        };
+        let inverted;
+        let other = if rising_shed(self) == rising_shed(other) {
+            other
+        } else {
+            let mut flipped = other.clone();
+            flipped.invert_shed();
+            inverted = flipped;
+            &inverted
+        };
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        let (other_ends, other_picks) = (other.width().unwrap_or(0), other.height().unwrap_or(0));
+        let shafts = self.shafts().unwrap_or(0);
+        let treadles = self.treadles().max(other.treadles()).unwrap_or(0);
+        let treadled = self.treadling.is_some() && other.treadling.is_some();
+        let (end_offset, pick_offset) = if side_by_side { (ends, 0) } else { (0, picks) };
+        let moved = |lift: &BTreeSet<Shaft>| {
+            lift.iter()
+                .map(|shaft| Shaft(shaft.0 + shafts))
+                .collect::<Vec<_>>()
+        };
+
+        let threading = self.threading.get_or_insert_with(Default::default);
+        for (end, threaded) in other.threading.iter().flatten() {
+            threading
+                .entry(Warp(end.0 + end_offset))
+                .or_default()
+                .extend(moved(threaded));
+        }
+        let (mut lifts, mut other_lifts) = (
+            lift_sequence(self).unwrap_or_default(),
+            lift_sequence(other).unwrap_or_default(),
+        );
+        if side_by_side {
+            // The shorter liftplan is woven again until the longer one ends, so neither
+            // side's ends are left down.
+            let height = lifts.len().max(other_lifts.len());
+            lifts = lifts.into_iter().cycle().take(height).collect();
+            other_lifts = other_lifts.into_iter().cycle().take(height).collect();
+        }
+        let mut liftplan: Table<Weft, BTreeSet<Shaft>> = (1..).map(Weft).zip(lifts).collect();
+        for (pick, lift) in (1..).zip(other_lifts) {
+            liftplan
+                .entry(Weft(pick + pick_offset))
+                .or_default()
+                .extend(moved(&lift));
+        }
+        self.liftplan = Some(liftplan);
+        self.treadling = None;
+        self.tieup = None;
+        let weaving = self.weaving_mut();
+        weaving.shafts = shafts + other.shafts().unwrap_or(0);
+        weaving.treadles = 0;
+        if side_by_side {
+            self.set_ends(ends + other_ends);
+            self.set_picks(picks.max(other_picks));
+        } else {
+            self.set_ends(ends.max(other_ends));
+            self.set_picks(picks + other_picks);
+        }
+
+        let colors: BTreeMap<ColorIndex, ColorIndex> = other
+            .color_table
+            .iter()
+            .flatten()
+            .map(|(&idx, &color)| (idx, self.find_or_add_color(color)))
+            .collect();
+        // Threads across the join take their color from `other`, and so do threads along it
+        // that only `other` has.
+        let other_warp = if side_by_side { 1 } else { ends + 1 };
+        for end in other_warp..=other_ends {
+            if let Some(&idx) = other.warp_color_idx(Warp(end)).and_then(|i| colors.get(&i)) {
+                let warp_colors = self.warp_colors.get_or_insert_with(Default::default);
+                warp_colors.insert(Warp(end + end_offset), idx);
+            }
+        }
+        let other_weft = if side_by_side { picks + 1 } else { 1 };
+        for pick in other_weft..=other_picks {
+            if let Some(&idx) = other
+                .weft_color_idx(Weft(pick))
+                .and_then(|i| colors.get(&i))
+            {
+                let weft_colors = self.weft_colors.get_or_insert_with(Default::default);
+                weft_colors.insert(Weft(pick + pick_offset), idx);
+            }
+        }
+        if side_by_side {
+            let shift = |end: &Warp| Warp(end.0 + ends);
+            append(
+                &mut self.warp_thickness,
+                other.warp_thickness.as_ref(),
+                shift,
+            );
+            append(
+                &mut self.warp_thickness_zoom,
+                other.warp_thickness_zoom.as_ref(),
+                shift,
+            );
+            append(&mut self.warp_spacing, other.warp_spacing.as_ref(), shift);
+            append(
+                &mut self.warp_spacing_zoom,
+                other.warp_spacing_zoom.as_ref(),
+                shift,
+            );
+        } else {
+            let shift = |pick: &Weft| Weft(pick.0 + picks);
+            append(
+                &mut self.weft_thickness,
+                other.weft_thickness.as_ref(),
+                shift,
+            );
+            append(
+                &mut self.weft_thickness_zoom,
+                other.weft_thickness_zoom.as_ref(),
+                shift,
+            );
+            append(&mut self.weft_spacing, other.weft_spacing.as_ref(), shift);
+            append(
+                &mut self.weft_spacing_zoom,
+                other.weft_spacing_zoom.as_ref(),
+                shift,
+            );
+        }
+
+        if !side_by_side {
+            self.merge_threaded_alike();
+        }
+        self.optimize_shafts(false);
+        self.mark_present(sections::THREADING);
+        self.mark_present(sections::LIFTPLAN);
+        if !(treadled && self.derive_treadling(treadles).is_ok()) {
+            self.mark_absent(sections::TREADLING);
+            self.mark_absent(sections::TIEUP);
+        }
+    }
+
+    // Makes shafts threaded on exactly the same ends into one, lifted whenever any of them
+    // was. Every end on one of them is on all of them, so the cloth doesn't change.
+    fn merge_threaded_alike(&mut self) {
+        let mut threaded: BTreeMap<Shaft, BTreeSet<Warp>> = BTreeMap::new();
+        for (&end, shafts) in self.threading.iter().flatten() {
+            for &shaft in shafts {
+                threaded.entry(shaft).or_default().insert(end);
+            }
+        }
+        let mut first = BTreeMap::new();
+        let merged: BTreeMap<Shaft, Shaft> = threaded
+            .iter()
+            .map(|(&shaft, ends)| (shaft, *first.entry(ends).or_insert(shaft)))
+            .collect();
+        let sets = self.threading.iter_mut().flat_map(|t| t.values_mut());
+        let sets = sets.chain(self.liftplan.iter_mut().flat_map(|l| l.values_mut()));
+        for set in sets {
+            *set = set
+                .iter()
+                .map(|shaft| *merged.get(shaft).unwrap_or(shaft))
+                .collect();
+        }
+    }
+}
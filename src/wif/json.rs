@@ -0,0 +1,422 @@
+//! A versioned JSON form of a draft for tools that don't read WIF.
+//!
+//! The document is described by [`JSON_SCHEMA`]. Field names are fixed for a given
+//! [`JSON_FORMAT_VERSION`] and don't follow renames in this crate. Tables are objects keyed by
+//! the same 1-based warp, weft, treadle and color numbers used in the WIF file.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    BaseColor, ColorPalette, Contents, ProvenanceEntry, Symbol, Text, WarpS, WarpSymbolPalette,
+    Weaving, WeftS, WeftSymbolPalette, Wif, WifHeader,
+};
+use crate::{
+    Color, ColorIndex, Result, Shaft, SymbolIndex, Table, Treadle, Units, Warp, Weft, WifError,
+};
+
+/// The version written to `formatVersion`. Bumped whenever a field changes meaning.
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+/// JSON Schema (draft 2020-12) for the documents written by [`Wif::to_json`].
+pub const JSON_SCHEMA: &str = include_str!("json.schema.json");
+
+const FORMAT: &str = "wif-json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Document {
+    format: String,
+    format_version: u32,
+    header: Header,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    provenance: Vec<Provenance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<TextJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weaving: Option<WeavingJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_palette: Option<Palette>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    colors: Option<BTreeMap<ColorIndex, [u32; 3]>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_symbol_palette: Option<Palette>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_symbol_table: Option<BTreeMap<SymbolIndex, char>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_symbol_palette: Option<Palette>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_symbol_table: Option<BTreeMap<SymbolIndex, char>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<BTreeMap<u32, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp: Option<Threads>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft: Option<Threads>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tieup: Option<Table<Treadle, BTreeSet<Shaft>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    threading: Option<Table<Warp, BTreeSet<Shaft>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    treadling: Option<Table<Weft, BTreeSet<Treadle>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    liftplan: Option<Table<Weft, BTreeSet<Shaft>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_colors: Option<Table<Warp, ColorIndex>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_symbols: Option<Table<Warp, SymbolIndex>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_thickness: Option<Table<Warp, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_thickness_zoom: Option<Table<Warp, u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_spacing: Option<Table<Warp, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    warp_spacing_zoom: Option<Table<Warp, u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_colors: Option<Table<Weft, ColorIndex>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_symbols: Option<Table<Weft, SymbolIndex>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_thickness: Option<Table<Weft, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_thickness_zoom: Option<Table<Weft, u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_spacing: Option<Table<Weft, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weft_spacing_zoom: Option<Table<Weft, u32>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    wif_version: String,
+    date: NaiveDate,
+    developers: String,
+    source_program: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Provenance {
+    date: NaiveDate,
+    source_program: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TextJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    telephone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fax: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WeavingJson {
+    shafts: u32,
+    treadles: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rising_shed: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Palette {
+    entries: usize,
+    /// Only meaningful for the color palette.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    range: Option<[u32; 2]>,
+}
+
+/// Defaults shared by every warp or weft thread.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Threads {
+    threads: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<ColorIndex>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_rgb: Option<[u32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    symbol: Option<char>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    symbol_number: Option<SymbolIndex>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    units: Option<Units>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spacing: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thickness: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spacing_zoom: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thickness_zoom: Option<u32>,
+}
+
+fn rgb(color: &Color) -> [u32; 3] {
+    [color.red, color.green, color.blue]
+}
+
+fn from_rgb([red, green, blue]: [u32; 3]) -> Color {
+    Color { red, green, blue }
+}
+
+// JSON only carries the character, so pick the WIF spelling that can hold it.
+fn symbol(c: char) -> Symbol {
+    if c.is_alphanumeric() {
+        Symbol::Char(c)
+    } else {
+        Symbol::Quoted(c)
+    }
+}
+
+fn symbol_table(table: &Table<SymbolIndex, Symbol>) -> BTreeMap<SymbolIndex, char> {
+    table.iter().map(|(&k, v)| (k, v.as_char())).collect()
+}
+
+macro_rules! threads {
+    ($ty:ident) => {
+        impl From<&$ty> for Threads {
+            fn from(value: &$ty) -> Self {
+                Threads {
+                    threads: value.threads,
+                    color: value.color.as_ref().map(|c| c.idx),
+                    color_rgb: value.color.as_ref().and_then(|c| c.alt.as_ref()).map(rgb),
+                    symbol: value.symbol.as_ref().map(Symbol::as_char),
+                    symbol_number: value.symbol_number,
+                    units: value.units.clone(),
+                    spacing: value.spacing,
+                    thickness: value.thickness,
+                    spacing_zoom: value.spacing_zoom,
+                    thickness_zoom: value.thickness_zoom,
+                }
+            }
+        }
+
+        impl From<Threads> for $ty {
+            fn from(value: Threads) -> Self {
+                $ty {
+                    threads: value.threads,
+                    color: value.color.map(|idx| BaseColor {
+                        idx,
+                        alt: value.color_rgb.map(from_rgb),
+                    }),
+                    symbol: value.symbol.map(symbol),
+                    symbol_number: value.symbol_number,
+                    units: value.units,
+                    spacing: value.spacing,
+                    thickness: value.thickness,
+                    spacing_zoom: value.spacing_zoom,
+                    thickness_zoom: value.thickness_zoom,
+                }
+            }
+        }
+    };
+}
+
+threads!(WarpS);
+threads!(WeftS);
+
+impl Document {
+    pub(crate) fn new(wif: &Wif) -> Self {
+        let header = &wif.wif_header;
+        Document {
+            format: FORMAT.into(),
+            format_version: JSON_FORMAT_VERSION,
+            header: Header {
+                wif_version: header.version.clone(),
+                date: header.date,
+                developers: header.developers.clone(),
+                source_program: header.source_program.clone(),
+                source_version: header.source_version.clone(),
+            },
+            provenance: wif
+                .provenance
+                .iter()
+                .map(|entry| Provenance {
+                    date: entry.date,
+                    source_program: entry.source_program.clone(),
+                    source_version: entry.source_version.clone(),
+                })
+                .collect(),
+            text: wif.text.as_ref().map(|text| TextJson {
+                title: text.title.clone(),
+                author: text.author.clone(),
+                address: text.address.clone(),
+                email: text.email.clone(),
+                telephone: text.telephone.clone(),
+                fax: text.fax.clone(),
+            }),
+            weaving: wif.weaving.as_ref().map(|weaving| WeavingJson {
+                shafts: weaving.shafts,
+                treadles: weaving.treadles,
+                rising_shed: weaving.rising_shed,
+            }),
+            color_palette: wif.color_palette.as_ref().map(|palette| Palette {
+                entries: palette.entries,
+                range: Some([palette.range.0, palette.range.1]),
+            }),
+            colors: wif
+                .color_table
+                .as_ref()
+                .map(|table| table.iter().map(|(&k, v)| (k, rgb(v))).collect()),
+            warp_symbol_palette: wif.warp_symbol_palette.as_ref().map(|palette| Palette {
+                entries: palette.entries,
+                range: None,
+            }),
+            warp_symbol_table: wif.warp_symbol_table.as_ref().map(symbol_table),
+            weft_symbol_palette: wif.weft_symbol_palette.as_ref().map(|palette| Palette {
+                entries: palette.entries,
+                range: None,
+            }),
+            weft_symbol_table: wif.weft_symbols_table.as_ref().map(symbol_table),
+            notes: wif.notes.clone(),
+            warp: wif.warp.as_ref().map(Threads::from),
+            weft: wif.weft.as_ref().map(Threads::from),
+            tieup: wif.tieup.clone(),
+            threading: wif.threading.clone(),
+            treadling: wif.treadling.clone(),
+            liftplan: wif.liftplan.clone(),
+            warp_colors: wif.warp_colors.clone(),
+            warp_symbols: wif.warp_symbols.clone(),
+            warp_thickness: wif.warp_thickness.clone(),
+            warp_thickness_zoom: wif.warp_thickness_zoom.clone(),
+            warp_spacing: wif.warp_spacing.clone(),
+            warp_spacing_zoom: wif.warp_spacing_zoom.clone(),
+            weft_colors: wif.weft_colors.clone(),
+            weft_symbols: wif.weft_symbols.clone(),
+            weft_thickness: wif.weft_thickness.clone(),
+            weft_thickness_zoom: wif.weft_thickness_zoom.clone(),
+            weft_spacing: wif.weft_spacing.clone(),
+            weft_spacing_zoom: wif.weft_spacing_zoom.clone(),
+        }
+    }
+
+    fn into_wif(self) -> Wif {
+        let symbols = |table: BTreeMap<SymbolIndex, char>| {
+            table
+                .into_iter()
+                .map(|(k, c)| (k, symbol(c)))
+                .collect::<Table<SymbolIndex, Symbol>>()
+        };
+        Wif {
+            wif_header: WifHeader {
+                version: self.header.wif_version,
+                date: self.header.date,
+                developers: self.header.developers,
+                source_program: self.header.source_program,
+                source_version: self.header.source_version,
+            },
+            provenance: self
+                .provenance
+                .into_iter()
+                .map(|entry| ProvenanceEntry {
+                    source_program: entry.source_program,
+                    source_version: entry.source_version,
+                    date: entry.date,
+                })
+                .collect(),
+            text: self.text.map(|text| Text {
+                title: text.title,
+                author: text.author,
+                address: text.address,
+                email: text.email,
+                telephone: text.telephone,
+                fax: text.fax,
+            }),
+            weaving: self.weaving.map(|weaving| Weaving {
+                shafts: weaving.shafts,
+                treadles: weaving.treadles,
+                rising_shed: weaving.rising_shed,
+            }),
+            color_palette: self.color_palette.map(|palette| {
+                let [low, high] = palette.range.unwrap_or([0, 255]);
+                ColorPalette {
+                    entries: palette.entries,
+                    range: (low, high),
+                }
+            }),
+            color_table: self
+                .colors
+                .map(|table| table.into_iter().map(|(k, v)| (k, from_rgb(v))).collect()),
+            warp_symbol_palette: self.warp_symbol_palette.map(|palette| WarpSymbolPalette {
+                entries: palette.entries,
+            }),
+            warp_symbol_table: self.warp_symbol_table.map(symbols),
+            weft_symbol_palette: self.weft_symbol_palette.map(|palette| WeftSymbolPalette {
+                entries: palette.entries,
+            }),
+            weft_symbols_table: self.weft_symbol_table.map(symbols),
+            notes: self.notes,
+            warp: self.warp.map(WarpS::from),
+            weft: self.weft.map(WeftS::from),
+            tieup: self.tieup,
+            threading: self.threading,
+            treadling: self.treadling,
+            liftplan: self.liftplan,
+            warp_colors: self.warp_colors,
+            warp_symbols: self.warp_symbols,
+            warp_thickness: self.warp_thickness,
+            warp_thickness_zoom: self.warp_thickness_zoom,
+            warp_spacing: self.warp_spacing,
+            warp_spacing_zoom: self.warp_spacing_zoom,
+            weft_colors: self.weft_colors,
+            weft_symbols: self.weft_symbols,
+            weft_thickness: self.weft_thickness,
+            weft_thickness_zoom: self.weft_thickness_zoom,
+            weft_spacing: self.weft_spacing,
+            weft_spacing_zoom: self.weft_spacing_zoom,
+            contents: Contents::default(),
+            #[cfg(feature = "preserve-layout")]
+            layout: None,
+        }
+    }
+}
+
+impl Wif {
+    /// Writes the draft as a version [`JSON_FORMAT_VERSION`] JSON document.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&Document::new(self))
+            .expect("JSON documents only contain strings, numbers and string-keyed maps")
+    }
+
+    /// Reads a document written by [`to_json`](Self::to_json). CONTENTS is regenerated from
+    /// the sections present, and the liftplan is built or checked as in [`parse`](super::parse).
+    pub fn from_json(s: &str) -> Result<Wif> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Version {
+            format: String,
+            format_version: u32,
+        }
+
+        let invalid = |err: serde_json::Error| WifError::InvalidJson(err.to_string());
+        let version: Version = serde_json::from_str(s).map_err(invalid)?;
+        if version.format != FORMAT || version.format_version != JSON_FORMAT_VERSION {
+            return Err(WifError::UnsupportedJsonFormat {
+                format: version.format,
+                version: version.format_version,
+            });
+        }
+        let document: Document = serde_json::from_str(s).map_err(invalid)?;
+        let mut wif = document.into_wif();
+        wif.build_or_validate_liftplan()?;
+        Ok(wif)
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Wif;
+use crate::analysis::raised_sequence;
+use crate::{Result, Shaft, Warp, Weft, WifError};
+
+/// How a doubleweave draft divides into two cloths, from [`Wif::detect_layers`].
+///
+/// The top layer's picks lift only top shafts, leaving the bottom layer down, and the bottom
+/// layer's picks lift every top shaft, so the top layer stays up out of the way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layers {
+    pub top_shafts: BTreeSet<Shaft>,
+    pub bottom_shafts: BTreeSet<Shaft>,
+    /// Ascending. Unthreaded ends belong to neither layer.
+    pub top_ends: Vec<Warp>,
+    pub bottom_ends: Vec<Warp>,
+    pub top_picks: Vec<Weft>,
+    pub bottom_picks: Vec<Weft>,
+}
+
+// Whether `lifts` weave `shafts` into cloth: some lift raises part of them but not all.
+fn interlaces<'a>(
+    lifts: impl IntoIterator<Item = &'a BTreeSet<Shaft>>,
+    shafts: &BTreeSet<Shaft>,
+) -> bool {
+    lifts.into_iter().any(|lift| {
+        let raised = lift.intersection(shafts).count();
+        raised > 0 && raised < shafts.len()
+    })
+}
+
+impl Wif {
+    /// The two layers of a doubleweave draft, or `None` if it weaves a single cloth.
+    ///
+    /// Every top pick's lift is contained in every bottom pick's, so the top shafts are found
+    /// by trying each lift size as the dividing line. Each layer must interlace on its own,
+    /// and no end may be threaded on shafts from both.
+    pub fn detect_layers(&self) -> Option<Layers> {
+        let lifts = raised_sequence(self)?;
+        let threading = self.threading.as_ref()?;
+        let threaded: BTreeSet<Shaft> = threading.values().flatten().copied().collect();
+        let sizes: BTreeSet<usize> = lifts.iter().map(BTreeSet::len).collect();
+        sizes.into_iter().find_map(|size| {
+            let (top, bottom): (Vec<_>, Vec<_>) = lifts.iter().partition(|l| l.len() <= size);
+            let top_shafts: BTreeSet<Shaft> = top.iter().copied().flatten().copied().collect();
+            let bottom_shafts: BTreeSet<Shaft> =
+                threaded.difference(&top_shafts).copied().collect();
+            if top_shafts.is_empty()
+                || bottom_shafts.is_empty()
+                || bottom.iter().any(|l| !l.is_superset(&top_shafts))
+                || !interlaces(top.iter().copied(), &top_shafts)
+                || !interlaces(bottom.iter().copied(), &bottom_shafts)
+            {
+                return None;
+            }
+
+            let mut top_ends = vec![];
+            let mut bottom_ends = vec![];
+            for (&end, shafts) in threading {
+                if shafts.is_empty() {
+                    continue;
+                } else if shafts.is_subset(&top_shafts) {
+                    top_ends.push(end);
+                } else if shafts.is_subset(&bottom_shafts) {
+                    bottom_ends.push(end);
+                } else {
+                    return None;
+                }
+            }
+            let (top_picks, bottom_picks) = (1..)
+                .map(Weft)
+                .zip(&lifts)
+                .partition::<Vec<_>, _>(|(_, lift)| lift.len() <= size);
+            Some(Layers {
+                top_shafts,
+                bottom_shafts,
+                top_ends,
+                bottom_ends,
+                top_picks: top_picks.into_iter().map(|(pick, _)| pick).collect(),
+                bottom_picks: bottom_picks.into_iter().map(|(pick, _)| pick).collect(),
+            })
+        })
+    }
+
+    /// Each layer of a doubleweave draft as a draft of its own, top first: its ends and picks
+    /// renumbered from 1 with their colors, thickness, spacing and symbols, a liftplan
+    /// lifting only its own shafts, and the shafts and colors it doesn't use pruned. The
+    /// treadling and tieup are dropped, as they lift both layers.
+    ///
+    /// Fails with [`WifError::NotDoubleweave`] if [`detect_layers`](Self::detect_layers)
+    /// finds no layers.
+    pub fn split_layers(&self) -> Result<(Wif, Wif)> {
+        let layers = self.detect_layers().ok_or(WifError::NotDoubleweave)?;
+        let top = self.layer(&layers.top_ends, &layers.top_picks, &layers.top_shafts);
+        let bottom = self.layer(
+            &layers.bottom_ends,
+            &layers.bottom_picks,
+            &layers.bottom_shafts,
+        );
+        Ok((top, bottom))
+    }
+
+    // Keeps only `ends` and `picks`, renumbered in order, lifting only `shafts`.
+    fn layer(&self, ends: &[Warp], picks: &[Weft], shafts: &BTreeSet<Shaft>) -> Wif {
+        let mut out = self.clone();
+        let ends: BTreeMap<u32, u32> = ends.iter().map(|w| w.0).zip(1..).collect();
+        let picks: BTreeMap<u32, u32> = picks.iter().map(|w| w.0).zip(1..).collect();
+        out.renumber_ends(|end| ends.get(&end).copied());
+        out.renumber_picks(|pick| picks.get(&pick).copied());
+        out.treadling = None;
+        out.tieup = None;
+        for lift in out.liftplan.iter_mut().flat_map(|l| l.values_mut()) {
+            lift.retain(|shaft| shafts.contains(shaft));
+        }
+        if let Some(warp) = &mut out.warp {
+            warp.threads = ends.len() as u32;
+        }
+        if let Some(weft) = &mut out.weft {
+            weft.threads = picks.len() as u32;
+        }
+        if let Some(weaving) = &mut out.weaving {
+            weaving.treadles = 0;
+        }
+        out.prune_unused();
+        out
+    }
+
+    /// A drawdown of a double-width cloth opened out flat, as rows of `true` where the warp
+    /// shows. Each row is a top pick across the top layer followed by the matching bottom
+    /// pick across the bottom layer, seen from underneath and mirrored about the fold at the
+    /// last end. There are as many rows as the layer with fewer picks has.
+    ///
+    /// Fails with [`WifError::NotDoubleweave`] if [`detect_layers`](Self::detect_layers)
+    /// finds no layers.
+    pub fn unfold(&self) -> Result<Vec<Vec<bool>>> {
+        let layers = self.detect_layers().ok_or(WifError::NotDoubleweave)?;
+        let (Some(threading), Some(lifts)) = (&self.threading, raised_sequence(self)) else {
+            return Err(WifError::NotDoubleweave);
+        };
+        let raised = |end: &Warp, pick: &Weft| {
+            let lift = lifts.get(pick.0 as usize - 1);
+            threading[end]
+                .iter()
+                .any(|shaft| lift.is_some_and(|l| l.contains(shaft)))
+        };
+        Ok(layers
+            .top_picks
+            .iter()
+            .zip(&layers.bottom_picks)
+            .map(|(top, bottom)| {
+                let face = layers.top_ends.iter().map(|end| raised(end, top));
+                let back = layers.bottom_ends.iter().rev();
+                face.chain(back.map(|end| !raised(end, bottom))).collect()
+            })
+            .collect())
+    }
+}
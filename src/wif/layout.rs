@@ -0,0 +1,177 @@
+use std::{collections::BTreeSet, io::Write};
+
+use super::ini::{Ini, IniSection, Line};
+
+/// The text a draft was parsed from, and the draft as it would have been written at the time.
+/// Comparing the two against a fresh write shows what has changed since parsing.
+#[derive(Debug, Clone)]
+pub(crate) struct Layout {
+    source: String,
+    baseline: Ini<'static>,
+}
+
+enum RawLine<'a> {
+    Header(&'a str),
+    Entry(&'a str),
+    Other,
+}
+
+fn classify(raw: &str) -> RawLine<'_> {
+    let line = raw.trim();
+    if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+        RawLine::Other
+    } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+        RawLine::Header(name.trim())
+    } else {
+        RawLine::Entry(line.split_once('=').map_or(line, |(key, _)| key.trim()))
+    }
+}
+
+fn line_ending(raw: &str) -> &str {
+    if raw.ends_with("\r\n") {
+        "\r\n"
+    } else if raw.ends_with('\n') {
+        "\n"
+    } else {
+        ""
+    }
+}
+
+fn write_entry<W: Write>(
+    output: &mut W,
+    key: &str,
+    value: Option<&str>,
+    eol: &str,
+) -> std::io::Result<()> {
+    match value {
+        Some(value) => write!(output, "{key}={value}{eol}"),
+        None => write!(output, "{key}{eol}"),
+    }
+}
+
+fn same_section(a: Option<&IniSection>, b: Option<&IniSection>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.entries().eq(b.entries()),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+impl Layout {
+    pub fn new(source: &str, baseline: Ini<'static>) -> Self {
+        Self {
+            source: source.to_string(),
+            baseline,
+        }
+    }
+
+    /// Writes the source text, changing only the lines whose values differ between the
+    /// baseline and `current`. New keys go at the end of their section and new sections at
+    /// the end of the file.
+    pub fn write<W: Write>(&self, current: &Ini, output: &mut W) -> std::io::Result<()> {
+        let eol = self
+            .source
+            .split_inclusive('\n')
+            .map(line_ending)
+            .next()
+            .filter(|eol| !eol.is_empty())
+            .unwrap_or("\n");
+        let mut seen_sections = BTreeSet::new();
+        let mut section: Option<&str> = None;
+        // Lines of the current section, held back so new keys can go before trailing blanks
+        let mut pending: Vec<&str> = Vec::new();
+        for raw in self.source.split_inclusive('\n') {
+            if let RawLine::Header(name) = classify(raw) {
+                self.flush_section(section, &mut pending, current, eol, output)?;
+                seen_sections.insert(name.to_ascii_lowercase());
+                section = Some(name);
+            }
+            pending.push(raw);
+        }
+        self.flush_section(section, &mut pending, current, eol, output)?;
+
+        let mut needs_eol = !self.source.is_empty() && line_ending(&self.source).is_empty();
+        for new in current.sections() {
+            if seen_sections.contains(&new.name.to_ascii_lowercase())
+                || same_section(self.baseline.section(&new.name), Some(new))
+            {
+                continue;
+            }
+            if std::mem::take(&mut needs_eol) {
+                write!(output, "{eol}")?;
+            }
+            write!(output, "[{}]{eol}", new.name)?;
+            for line in new.lines() {
+                if let Line::Entry { key, value } = line {
+                    write_entry(output, key, value.as_deref(), eol)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_section<W: Write>(
+        &self,
+        name: Option<&str>,
+        lines: &mut Vec<&str>,
+        current: &Ini,
+        eol: &str,
+        output: &mut W,
+    ) -> std::io::Result<()> {
+        let raw_lines = std::mem::take(lines);
+        let (Some(name), Some(baseline)) = (name, name.and_then(|n| self.baseline.section(n)))
+        else {
+            // The preamble, or a section the draft doesn't model
+            return raw_lines
+                .iter()
+                .try_for_each(|raw| output.write_all(raw.as_bytes()));
+        };
+        let Some(section) = current.section(name) else {
+            return Ok(());
+        };
+        if same_section(Some(baseline), Some(section)) {
+            return raw_lines
+                .iter()
+                .try_for_each(|raw| output.write_all(raw.as_bytes()));
+        }
+        let end = raw_lines
+            .iter()
+            .rposition(|raw| !raw.trim().is_empty())
+            .map_or(raw_lines.len(), |i| i + 1);
+        let mut written = BTreeSet::new();
+        for raw in &raw_lines[..end] {
+            let RawLine::Entry(key) = classify(raw) else {
+                output.write_all(raw.as_bytes())?;
+                continue;
+            };
+            written.insert(key.to_ascii_lowercase());
+            if !baseline.contains_key(key) {
+                output.write_all(raw.as_bytes())?;
+            } else if section.contains_key(key) {
+                if baseline.get(key) == section.get(key) {
+                    output.write_all(raw.as_bytes())?;
+                } else {
+                    let indent = &raw[..raw.len() - raw.trim_start().len()];
+                    let raw_eol = line_ending(raw);
+                    write!(output, "{indent}")?;
+                    write_entry(output, key, section.get(key), raw_eol)?;
+                }
+            }
+        }
+        let mut needs_eol = raw_lines[..end]
+            .last()
+            .is_some_and(|raw| line_ending(raw).is_empty());
+        for (key, value) in section.entries() {
+            let changed = !baseline.contains_key(key) || baseline.get(key) != value;
+            if changed && !written.contains(&key.to_ascii_lowercase()) {
+                if std::mem::take(&mut needs_eol) {
+                    write!(output, "{eol}")?;
+                }
+                write_entry(output, key, value, eol)?;
+            }
+        }
+        raw_lines[end..]
+            .iter()
+            .try_for_each(|raw| output.write_all(raw.as_bytes()))
+    }
+}
@@ -0,0 +1,172 @@
+//! Dense grids of the threading, tieup, treadling and liftplan. Grids are laid out as most
+//! programs draw them: the threading and tieup have one row per shaft (shaft 1 first), and the
+//! treadling, liftplan and drawdown one row per pick (pick 1 first).
+
+use std::collections::BTreeSet;
+
+use super::Wif;
+use crate::{Shaft, Table, Tieup, TieupMatrix, Treadle, Warp, Weft};
+
+// A grid with one row per key and a column for each value index.
+fn rows<K, V>(
+    table: Option<&Table<K, BTreeSet<V>>>,
+    rows: u32,
+    columns: u32,
+    key: impl Fn(u32) -> K,
+    value: impl Fn(u32) -> V,
+) -> Vec<Vec<bool>>
+where
+    K: Ord,
+    V: Ord,
+{
+    (1..=rows)
+        .map(|row| {
+            let set = table.and_then(|t| t.get(&key(row)));
+            (1..=columns)
+                .map(|col| set.is_some_and(|s| s.contains(&value(col))))
+                .collect()
+        })
+        .collect()
+}
+
+// The inverse of `rows`. Empty rows are left out of the table.
+fn from_rows<K, V>(
+    grid: &[Vec<bool>],
+    key: impl Fn(u32) -> K,
+    value: impl Fn(u32) -> V,
+) -> Table<K, BTreeSet<V>>
+where
+    K: Ord,
+    V: Ord,
+{
+    (1..)
+        .zip(grid)
+        .filter_map(|(row, cells)| {
+            let set: BTreeSet<V> = (1..)
+                .zip(cells)
+                .filter(|(_, &on)| on)
+                .map(|(col, _)| value(col))
+                .collect();
+            (!set.is_empty()).then(|| (key(row), set))
+        })
+        .collect()
+}
+
+fn transpose(grid: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+    (0..columns)
+        .map(|col| {
+            grid.iter()
+                .map(|row| row.get(col).copied().unwrap_or(false))
+                .collect()
+        })
+        .collect()
+}
+
+fn highest<'a, T: 'a>(items: impl Iterator<Item = &'a T>, index: impl Fn(&T) -> u32) -> u32 {
+    items.map(index).max().unwrap_or(0)
+}
+
+impl Wif {
+    // Declared counts, widened to cover anything the tables use.
+    fn shaft_count(&self) -> u32 {
+        let threaded = self.threading.iter().flat_map(|t| t.values());
+        let lifted = self.liftplan.iter().flat_map(|l| l.values());
+        let tied = self.tieup.iter().flat_map(|t| t.values());
+        let used = threaded.chain(lifted).chain(tied).flatten();
+        self.shafts().unwrap_or(0).max(highest(used, |s| s.0))
+    }
+
+    fn treadle_count(&self) -> u32 {
+        let used = self.treadling.iter().flat_map(|t| t.values().flatten());
+        let tied = self.tieup.iter().flat_map(|t| t.keys());
+        self.treadles()
+            .unwrap_or(0)
+            .max(highest(used.chain(tied), |t| t.0))
+    }
+
+    fn end_count(&self) -> u32 {
+        let threaded = self.threading.iter().flat_map(|t| t.keys());
+        self.width().unwrap_or(0).max(highest(threaded, |w| w.0))
+    }
+
+    fn pick_count(&self) -> u32 {
+        let treadled = self.treadling.iter().flat_map(|t| t.keys());
+        let lifted = self.liftplan.iter().flat_map(|l| l.keys());
+        self.height()
+            .unwrap_or(0)
+            .max(highest(treadled.chain(lifted), |w| w.0))
+    }
+
+    /// One row per shaft, one column per end.
+    pub fn to_threading_matrix(&self) -> Vec<Vec<bool>> {
+        let by_end = rows(
+            self.threading.as_ref(),
+            self.end_count(),
+            self.shaft_count(),
+            Warp,
+            Shaft,
+        );
+        let mut grid = transpose(&by_end);
+        grid.resize(self.shaft_count() as usize, Vec::new());
+        grid
+    }
+
+    /// One row per shaft, one column per treadle, `true` where the treadle lifts the shaft.
+    pub fn to_tieup_matrix(&self) -> Vec<Vec<bool>> {
+        let tieup = self.tieup.clone().unwrap_or_default();
+        tieup.to_matrix(self.shaft_count(), self.treadle_count())
+    }
+
+    /// One row per pick, one column per treadle.
+    pub fn to_treadling_matrix(&self) -> Vec<Vec<bool>> {
+        rows(
+            self.treadling.as_ref(),
+            self.pick_count(),
+            self.treadle_count(),
+            Weft,
+            Treadle,
+        )
+    }
+
+    /// One row per pick, one column per shaft.
+    pub fn to_liftplan_matrix(&self) -> Vec<Vec<bool>> {
+        rows(
+            self.liftplan.as_ref(),
+            self.pick_count(),
+            self.shaft_count(),
+            Weft,
+            Shaft,
+        )
+    }
+
+    /// Replaces the threading from a grid laid out as in
+    /// [`to_threading_matrix`](Self::to_threading_matrix), as if by
+    /// [`set_threading`](Self::set_threading), but with one end per column, so unthreaded
+    /// ends at the right are kept.
+    pub fn set_threading_matrix(&mut self, grid: &[Vec<bool>]) {
+        self.set_threading(from_rows(&transpose(grid), Warp, Shaft));
+        if let Some(row) = grid.first() {
+            self.set_ends(row.len() as u32);
+        }
+    }
+
+    /// Replaces the tieup from a riser grid, as if by [`set_tieup`](Self::set_tieup).
+    pub fn set_tieup_matrix(&mut self, grid: &[Vec<bool>]) {
+        self.set_tieup(Tieup::from_matrix(grid));
+    }
+
+    /// Replaces the treadling from a grid, as if by [`set_treadling`](Self::set_treadling),
+    /// but with one pick per row, so empty picks at the end are kept.
+    pub fn set_treadling_matrix(&mut self, grid: &[Vec<bool>]) {
+        self.set_treadling(from_rows(grid, Weft, Treadle));
+        self.set_picks(grid.len() as u32);
+    }
+
+    /// Replaces the liftplan from a grid, as if by [`set_liftplan`](Self::set_liftplan),
+    /// but with one pick per row, so empty picks at the end are kept.
+    pub fn set_liftplan_matrix(&mut self, grid: &[Vec<bool>]) {
+        self.set_liftplan(from_rows(grid, Weft, Shaft));
+        self.set_picks(grid.len() as u32);
+    }
+}
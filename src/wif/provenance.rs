@@ -0,0 +1,94 @@
+use chrono::{Local, NaiveDate};
+
+use super::{ini::Ini, sections, Wif};
+use crate::{wifparse::WifParse, Result, WifContext, WifError};
+
+/// A program that saved the draft before the current one, as recorded by
+/// [`Wif::stamp_header_keeping_history`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProvenanceEntry {
+    pub source_program: String,
+    pub source_version: Option<String>,
+    pub date: NaiveDate,
+}
+
+impl WifParse for ProvenanceEntry {
+    fn parse(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, '|');
+        let (Some(date), Some(program), version) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(WifError::CouldNotParseWifFile(format!(
+                "Expected date|program|version, but saw {s}"
+            )));
+        };
+        Ok(ProvenanceEntry {
+            date: NaiveDate::parse(date)?,
+            source_program: program.to_string(),
+            source_version: version.filter(|v| !v.is_empty()).map(str::to_string),
+        })
+    }
+
+    fn unparse(&self) -> Option<String> {
+        Some(format!(
+            "{}|{}|{}",
+            self.date.unparse()?,
+            self.source_program,
+            self.source_version.as_deref().unwrap_or_default()
+        ))
+    }
+}
+
+pub(crate) fn read(ini: &Ini) -> Result<Vec<ProvenanceEntry>> {
+    let Some(section) = ini.section(sections::PROVENANCE) else {
+        return Ok(vec![]);
+    };
+    let mut entries: Vec<(u32, ProvenanceEntry)> = Vec::new();
+    for (key, value) in section.entries() {
+        let Some(value) = value else {
+            continue;
+        };
+        let idx = key.parse().map_err(|_| WifError::CouldNotParseTableKey {
+            section: sections::PROVENANCE.into(),
+            key: key.into(),
+        })?;
+        entries.push((
+            idx,
+            ProvenanceEntry::parse(value).add_context(sections::PROVENANCE, key)?,
+        ));
+    }
+    entries.sort_by_key(|(idx, _)| *idx);
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+pub(crate) fn write(entries: &[ProvenanceEntry], ini: &mut Ini) {
+    for (idx, entry) in (1..).zip(entries) {
+        ini.set(sections::PROVENANCE, &idx.to_string(), entry.unparse());
+    }
+}
+
+impl Wif {
+    /// Marks the draft as saved by `program` today. Call this before writing a draft that has
+    /// been modified.
+    pub fn stamp_header(&mut self, program: &str, version: &str) {
+        let header = &mut self.wif_header;
+        header.source_program = program.to_string();
+        header.source_version = Some(version.to_string());
+        header.date = Local::now().date_naive();
+    }
+
+    /// Like [`stamp_header`](Self::stamp_header), first recording the previous program in
+    /// [`provenance`](Self::provenance), which is written to a private section.
+    pub fn stamp_header_keeping_history(&mut self, program: &str, version: &str) {
+        let header = &self.wif_header;
+        let previous = ProvenanceEntry {
+            source_program: header.source_program.clone(),
+            source_version: header.source_version.clone(),
+            date: header.date,
+        };
+        if !previous.source_program.is_empty() && self.provenance.last() != Some(&previous) {
+            self.provenance.push(previous);
+        }
+        self.stamp_header(program, version);
+    }
+}
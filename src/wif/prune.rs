@@ -0,0 +1,368 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Wif;
+use crate::{ColorIndex, Shaft, Table, Treadle, Warp, Weft};
+
+/// What [`Wif::prune_unused`] removed, by the numbers they had before.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pruned {
+    pub shafts: Vec<Shaft>,
+    pub treadles: Vec<Treadle>,
+    pub colors: Vec<ColorIndex>,
+}
+
+impl Pruned {
+    pub fn is_empty(&self) -> bool {
+        self.shafts.is_empty() && self.treadles.is_empty() && self.colors.is_empty()
+    }
+}
+
+// Numbers the items still in use from 1, in their original order.
+fn compact<T: Ord + Copy>(used: impl IntoIterator<Item = T>) -> BTreeMap<T, u32> {
+    let used: BTreeSet<T> = used.into_iter().collect();
+    used.into_iter().zip(1..).collect()
+}
+
+fn remap<T: Ord>(set: &BTreeSet<T>, map: &BTreeMap<T, u32>, new: fn(u32) -> T) -> BTreeSet<T> {
+    set.iter()
+        .filter_map(|v| map.get(v))
+        .map(|&n| new(n))
+        .collect()
+}
+
+fn remap_values<K, T: Ord>(
+    table: &mut Option<Table<K, BTreeSet<T>>>,
+    map: &BTreeMap<T, u32>,
+    new: fn(u32) -> T,
+) {
+    for set in table.iter_mut().flat_map(|t| t.values_mut()) {
+        *set = remap(set, map, new);
+    }
+}
+
+// Everything numbered 1..=declared or appearing in `seen` that isn't in `kept`.
+fn dropped<T: Ord + Copy>(
+    declared: u32,
+    new: fn(u32) -> T,
+    seen: impl IntoIterator<Item = T>,
+    kept: &BTreeMap<T, u32>,
+) -> Vec<T> {
+    let known: BTreeSet<T> = (1..=declared).map(new).chain(seen).collect();
+    known
+        .into_iter()
+        .filter(|v| !kept.contains_key(v))
+        .collect()
+}
+
+// Largest number of treadles to try every order of; more are improved by swaps instead.
+const EXHAUSTIVE_TREADLES: usize = 8;
+
+// How far the feet travel over the treadling, with `weights[a][b]` the number of times
+// treadle `b` follows treadle `a`.
+fn walk(order: &[usize], weights: &[Vec<u32>]) -> u32 {
+    let mut position = vec![0; order.len()];
+    for (at, &treadle) in order.iter().enumerate() {
+        position[treadle] = at;
+    }
+    let mut distance = 0;
+    for (a, row) in weights.iter().enumerate() {
+        for (b, &times) in row.iter().enumerate() {
+            distance += times * position[a].abs_diff(position[b]) as u32;
+        }
+    }
+    distance
+}
+
+// Tries every order, keeping the first with the shortest walk, so an order that's already
+// as good as any stays as it is.
+fn arrange(
+    placed: &mut Vec<usize>,
+    distance: u32,
+    weights: &[Vec<u32>],
+    best: &mut (u32, Vec<usize>),
+) {
+    let count = weights.len();
+    if distance >= best.0 {
+        return;
+    }
+    if placed.len() == count {
+        *best = (distance, placed.clone());
+        return;
+    }
+    let at = placed.len();
+    for next in 0..count {
+        if placed.contains(&next) {
+            continue;
+        }
+        let added: u32 = placed
+            .iter()
+            .enumerate()
+            .map(|(i, &other)| (weights[next][other] + weights[other][next]) * (at - i) as u32)
+            .sum();
+        placed.push(next);
+        arrange(placed, distance + added, weights, best);
+        placed.pop();
+    }
+}
+
+// An order of the treadles giving a short walk, starting from their current order.
+fn walking_order(weights: &[Vec<u32>]) -> Vec<usize> {
+    let count = weights.len();
+    let mut order: Vec<usize> = (0..count).collect();
+    let mut distance = walk(&order, weights);
+    if count <= EXHAUSTIVE_TREADLES {
+        let mut best = (distance + 1, order);
+        arrange(&mut vec![], 0, weights, &mut best);
+        return best.1;
+    }
+    loop {
+        let swaps = (0..count).flat_map(|a| (a + 1..count).map(move |b| (a, b)));
+        let better = swaps
+            .map(|(a, b)| {
+                let mut swapped = order.clone();
+                swapped.swap(a, b);
+                (walk(&swapped, weights), swapped)
+            })
+            .filter(|(shorter, _)| *shorter < distance)
+            .min_by_key(|(shorter, _)| *shorter);
+        match better {
+            Some((shorter, swapped)) => (distance, order) = (shorter, swapped),
+            None => return order,
+        }
+    }
+}
+
+impl Wif {
+    /// Removes shafts no end is threaded on, treadles no pick uses and colors no thread
+    /// uses, then renumbers the shafts, treadles and color table from 1 in their original
+    /// order. The tieup, liftplan, [WEAVING] counts and palette size follow.
+    pub fn prune_unused(&mut self) -> Pruned {
+        let (shafts, treadles) = self.compact_shafts_and_treadles();
+        let colors = self.prune_colors();
+        self.renumber_colors();
+        Pruned {
+            shafts,
+            treadles,
+            colors,
+        }
+    }
+
+    /// Keeps only the shafts the threading uses and the treadles the treadling uses,
+    /// renumbered from 1, and returns the ones removed.
+    pub(super) fn compact_shafts_and_treadles(&mut self) -> (Vec<Shaft>, Vec<Treadle>) {
+        let shafts = compact(
+            self.threading
+                .iter()
+                .flat_map(|t| t.values().flatten().copied()),
+        );
+        let treadles = compact(
+            self.treadling
+                .iter()
+                .flat_map(|t| t.values().flatten().copied()),
+        );
+        let removed_shafts = dropped(
+            self.shafts().unwrap_or(0),
+            Shaft,
+            self.liftplan
+                .iter()
+                .flat_map(|l| l.values().flatten())
+                .chain(self.tieup.iter().flat_map(|t| t.values().flatten()))
+                .copied(),
+            &shafts,
+        );
+        let removed_treadles = dropped(
+            self.treadles().unwrap_or(0),
+            Treadle,
+            self.tieup.iter().flat_map(|t| t.keys()).copied(),
+            &treadles,
+        );
+
+        remap_values(&mut self.threading, &shafts, Shaft);
+        remap_values(&mut self.liftplan, &shafts, Shaft);
+        remap_values(&mut self.treadling, &treadles, Treadle);
+        if let Some(tieup) = &mut self.tieup {
+            *tieup = std::mem::take(tieup)
+                .into_iter()
+                .filter_map(|(treadle, lifted)| {
+                    let treadle = Treadle(*treadles.get(&treadle)?);
+                    Some((treadle, remap(&lifted, &shafts, Shaft)))
+                })
+                .collect();
+        }
+        if let Some(weaving) = &mut self.weaving {
+            weaving.shafts = shafts.len() as u32;
+            weaving.treadles = treadles.len() as u32;
+        }
+        (removed_shafts, removed_treadles)
+    }
+
+    /// Reduces the shafts to the fewest that weave the same cloth. Shafts no end is threaded
+    /// on are removed, and shafts lifted on exactly the same picks and tied to exactly the
+    /// same treadles become one. A draft with neither a liftplan nor a tieup doesn't say how
+    /// its shafts are lifted, so none are merged. The rest are numbered from 1 in their original order, or,
+    /// with `reorder`, in the order the threading first uses them, so a straight draw
+    /// threads 1, 2, 3... The threading, tieup, liftplan and [WEAVING] follow. Returns each
+    /// old shaft still in use with its new number.
+    pub fn optimize_shafts(&mut self, reorder: bool) -> BTreeMap<Shaft, Shaft> {
+        let mut first_end: BTreeMap<Shaft, Warp> = BTreeMap::new();
+        for (&end, shafts) in self.threading.iter().flatten() {
+            for &shaft in shafts {
+                first_end.entry(shaft).or_insert(end);
+            }
+        }
+        // What a shaft does: the picks it's lifted on and the treadles it's tied to.
+        let behavior = |shaft: &Shaft| {
+            let picks: BTreeSet<Weft> = self
+                .liftplan
+                .iter()
+                .flatten()
+                .filter(|(_, lift)| lift.contains(shaft))
+                .map(|(&pick, _)| pick)
+                .collect();
+            let treadles: BTreeSet<Treadle> = self
+                .tieup
+                .iter()
+                .flatten()
+                .filter(|(_, tied)| tied.contains(shaft))
+                .map(|(&treadle, _)| treadle)
+                .collect();
+            (picks, treadles)
+        };
+        let lifted = self.liftplan.is_some() || self.tieup.is_some();
+        let mut groups: Vec<(Warp, Vec<Shaft>)> = vec![];
+        let mut behaviors = vec![];
+        for (&shaft, &end) in &first_end {
+            let does = behavior(&shaft);
+            let same = behaviors.iter().position(|other| *other == does);
+            match same.filter(|_| lifted) {
+                Some(group) => {
+                    let (first, shafts) = &mut groups[group];
+                    *first = (*first).min(end);
+                    shafts.push(shaft);
+                }
+                None => {
+                    behaviors.push(does);
+                    groups.push((end, vec![shaft]));
+                }
+            }
+        }
+        if reorder {
+            groups.sort_by_key(|(end, _)| *end);
+        }
+        let renumbered: BTreeMap<Shaft, u32> = groups
+            .iter()
+            .zip(1..)
+            .flat_map(|((_, shafts), new)| shafts.iter().map(move |&shaft| (shaft, new)))
+            .collect();
+
+        remap_values(&mut self.threading, &renumbered, Shaft);
+        remap_values(&mut self.liftplan, &renumbered, Shaft);
+        remap_values(&mut self.tieup, &renumbered, Shaft);
+        if let Some(weaving) = &mut self.weaving {
+            weaving.shafts = groups.len() as u32;
+        }
+        renumbered
+            .into_iter()
+            .map(|(old, new)| (old, Shaft(new)))
+            .collect()
+    }
+
+    /// Makes treadles tied to exactly the same shafts into one, then puts the treadles in
+    /// the order that keeps the feet moving least from pick to pick, so the treadling walks
+    /// across neighbouring treadles where it can. The treadling, tieup and [WEAVING] follow.
+    /// Returns each old treadle with its new number.
+    pub fn optimize_treadles(&mut self) -> BTreeMap<Treadle, Treadle> {
+        let declared = (1..=self.treadles().unwrap_or(0)).map(Treadle);
+        let used: BTreeSet<Treadle> = declared
+            .chain(self.tieup.iter().flat_map(|t| t.keys().copied()))
+            .chain(
+                self.treadling
+                    .iter()
+                    .flat_map(|t| t.values().flatten().copied()),
+            )
+            .collect();
+        let empty = BTreeSet::new();
+        let mut ties: Vec<&BTreeSet<Shaft>> = vec![];
+        let mut group = BTreeMap::new();
+        for &treadle in &used {
+            let tied = self
+                .tieup
+                .as_ref()
+                .and_then(|t| t.get(&treadle))
+                .unwrap_or(&empty);
+            let index = ties
+                .iter()
+                .position(|&other| other == tied)
+                .unwrap_or_else(|| {
+                    ties.push(tied);
+                    ties.len() - 1
+                });
+            group.insert(treadle, index);
+        }
+
+        let mut weights = vec![vec![0; ties.len()]; ties.len()];
+        let picks: Vec<&BTreeSet<Treadle>> =
+            self.treadling.iter().flat_map(|t| t.values()).collect();
+        for pair in picks.windows(2) {
+            for a in pair[0] {
+                for b in pair[1] {
+                    weights[group[a]][group[b]] += 1;
+                }
+            }
+        }
+        let order = walking_order(&weights);
+        let mut position = vec![0; order.len()];
+        for (at, &index) in (1..).zip(&order) {
+            position[index] = at;
+        }
+        let renumbered: BTreeMap<Treadle, u32> = group
+            .into_iter()
+            .map(|(treadle, index)| (treadle, position[index]))
+            .collect();
+
+        remap_values(&mut self.treadling, &renumbered, Treadle);
+        if let Some(tieup) = &mut self.tieup {
+            *tieup = std::mem::take(tieup)
+                .into_iter()
+                .filter_map(|(treadle, tied)| Some((Treadle(*renumbered.get(&treadle)?), tied)))
+                .collect();
+        }
+        if let Some(weaving) = &mut self.weaving {
+            weaving.treadles = order.len() as u32;
+        }
+        renumbered
+            .into_iter()
+            .map(|(old, new)| (old, Treadle(new)))
+            .collect()
+    }
+
+    /// Drops colors no thread or [WARP]/[WEFT] default uses, returning them.
+    pub(super) fn prune_colors(&mut self) -> Vec<ColorIndex> {
+        let per_thread = self.warp_colors.iter().flat_map(|c| c.values());
+        let per_thread = per_thread.chain(self.weft_colors.iter().flat_map(|c| c.values()));
+        let warp_default = self.warp.as_ref().and_then(|w| w.color);
+        let weft_default = self.weft.as_ref().and_then(|w| w.color);
+        let used: BTreeSet<ColorIndex> = per_thread
+            .copied()
+            .chain(
+                [warp_default, weft_default]
+                    .into_iter()
+                    .flatten()
+                    .map(|c| c.idx),
+            )
+            .collect();
+        let mut removed = vec![];
+        if let Some(table) = &mut self.color_table {
+            removed = table
+                .keys()
+                .filter(|idx| !used.contains(idx))
+                .copied()
+                .collect();
+            table.retain(|idx, _| used.contains(idx));
+            if let Some(palette) = &mut self.color_palette {
+                palette.entries = super::palette_entries(table, |idx| idx.0);
+            }
+        }
+        removed
+    }
+}
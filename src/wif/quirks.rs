@@ -0,0 +1,148 @@
+use super::{ini::Ini, sections, ParseWarning};
+use chrono::NaiveDate;
+
+/// A known deviation from the spec in files written by a particular program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quirk {
+    /// The header date is numeric or abbreviated rather than "Month DD, YYYY".
+    NonStandardDate,
+    /// Thread-indexed tables are numbered from 0 rather than 1.
+    ZeroBasedTables,
+    /// COLOR PALETTE has no Entries count.
+    MissingPaletteEntries,
+}
+
+struct KnownQuirk {
+    program: &'static str,
+    version_prefix: Option<&'static str>,
+    quirk: Quirk,
+}
+
+const KNOWN_QUIRKS: &[KnownQuirk] = &[
+    KnownQuirk {
+        program: "fiberworks",
+        version_prefix: None,
+        quirk: Quirk::NonStandardDate,
+    },
+    KnownQuirk {
+        program: "pixeloom",
+        version_prefix: None,
+        quirk: Quirk::ZeroBasedTables,
+    },
+    KnownQuirk {
+        program: "weaveit",
+        version_prefix: None,
+        quirk: Quirk::MissingPaletteEntries,
+    },
+];
+
+const ALTERNATE_DATE_FORMATS: &[&str] = &["%m/%d/%Y", "%Y-%m-%d", "%b %d, %Y", "%d %B %Y"];
+
+const THREAD_TABLES: &[&str] = &[
+    sections::THREADING,
+    sections::WARP_THICKNESS,
+    sections::WARP_THICKNESS_ZOOM,
+    sections::WARP_SPACING,
+    sections::WARP_SPACING_ZOOM,
+    sections::WARP_COLORS,
+    sections::WARP_SYMBOLS,
+    sections::TREADLING,
+    sections::LIFTPLAN,
+    sections::WEFT_THICKNESS,
+    sections::WEFT_THICKNESS_ZOOM,
+    sections::WEFT_SPACING,
+    sections::WEFT_SPACING_ZOOM,
+    sections::WEFT_COLORS,
+    sections::WEFT_SYMBOLS,
+];
+
+/// The quirks known for a given `Source Program` and `Source Version`.
+pub fn quirks_for(program: &str, version: Option<&str>) -> Vec<Quirk> {
+    let program = program.to_lowercase().replace(' ', "");
+    KNOWN_QUIRKS
+        .iter()
+        .filter(|known| program.contains(known.program))
+        .filter(|known| match (known.version_prefix, version) {
+            (None, _) => true,
+            (Some(prefix), Some(version)) => version.trim().starts_with(prefix),
+            (Some(_), None) => false,
+        })
+        .map(|known| known.quirk)
+        .collect()
+}
+
+pub(crate) fn apply_quirks(ini: &mut Ini, warnings: &mut Vec<ParseWarning>) {
+    let Some(program) = ini.get(sections::WIF, "Source Program") else {
+        return;
+    };
+    let quirks = quirks_for(program, ini.get(sections::WIF, "Source Version"));
+    for quirk in quirks {
+        let sections = match quirk {
+            Quirk::NonStandardDate => fix_date(ini),
+            Quirk::ZeroBasedTables => fix_zero_based_tables(ini),
+            Quirk::MissingPaletteEntries => fix_palette_entries(ini),
+        };
+        warnings.extend(
+            sections
+                .into_iter()
+                .map(|section| ParseWarning::QuirkApplied { quirk, section }),
+        );
+    }
+}
+
+fn fix_date(ini: &mut Ini) -> Vec<String> {
+    let Some(date) = ini.get(sections::WIF, "Date") else {
+        return vec![];
+    };
+    if NaiveDate::parse_from_str(date, "%B %d, %Y").is_ok() {
+        return vec![];
+    }
+    let Some(parsed) = ALTERNATE_DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+    else {
+        return vec![];
+    };
+    ini.set(
+        sections::WIF,
+        "Date",
+        Some(parsed.format("%B %d, %Y").to_string()),
+    );
+    vec![sections::WIF.into()]
+}
+
+fn fix_zero_based_tables(ini: &mut Ini) -> Vec<String> {
+    let mut fixed = vec![];
+    for &name in THREAD_TABLES {
+        let Some(section) = ini.section_mut(name) else {
+            continue;
+        };
+        if !section.contains_key("0") {
+            continue;
+        }
+        section.rename_keys(|key| key.parse::<u32>().ok().map(|idx| (idx + 1).to_string()));
+        fixed.push(name.into());
+    }
+    fixed
+}
+
+fn fix_palette_entries(ini: &mut Ini) -> Vec<String> {
+    if !ini.has_section(sections::COLOR_PALETTE)
+        || ini.get(sections::COLOR_PALETTE, "Entries").is_some()
+    {
+        return vec![];
+    }
+    let entries = ini.section(sections::COLOR_TABLE).map_or(0, |table| {
+        table
+            .entries()
+            .filter_map(|(key, _)| key.parse::<u32>().ok())
+            .max()
+            .unwrap_or(0)
+    });
+    ini.set(
+        sections::COLOR_PALETTE,
+        "Entries",
+        Some(entries.to_string()),
+    );
+    vec![sections::COLOR_PALETTE.into()]
+}
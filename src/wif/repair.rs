@@ -0,0 +1,641 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::{
+    rescale, sections, ColorPalette, IssueCode, WarpS, WarpSymbolPalette, Weaving, WeftS,
+    WeftSymbolPalette, Wif,
+};
+use crate::{liftplan_from_threading_and_treadle, Shaft, Symbol, SymbolIndex, Table, Treadle};
+
+/// Which fixes [`Wif::repair`] may make. All but `rescale_colors` are on by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepairOptions {
+    /// Raise the shaft, treadle and thread counts to cover everything the draft uses. When
+    /// off, shafts, treadles and table entries beyond the counts are dropped instead.
+    /// Missing [WEAVING], [WARP] and [WEFT] sections are added either way.
+    pub grow_counts: bool,
+    /// Remove color and symbol numbers that aren't in their tables, both per thread and as
+    /// the [WARP] and [WEFT] defaults.
+    pub drop_dangling: bool,
+    /// Set each palette's `Entries` to the size of its table, adding the palette if it's
+    /// missing, and bring colors outside the palette's range into it.
+    pub fix_palettes: bool,
+    /// Rebuild liftplan picks that disagree with the treadling and tieup.
+    pub regenerate_liftplan: bool,
+    /// With `fix_palettes`, scale every color in the table down by the same amount when some
+    /// lie outside the palette's range, keeping them distinct, rather than clamping just
+    /// those.
+    pub rescale_colors: bool,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        Self {
+            grow_counts: true,
+            drop_dangling: true,
+            fix_palettes: true,
+            regenerate_liftplan: true,
+            rescale_colors: false,
+        }
+    }
+}
+
+/// One change made by [`Wif::repair`], with the kind of problem it fixed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub code: IssueCode,
+    pub section: &'static str,
+    pub key: Option<u32>,
+    pub description: String,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.section)?;
+        if let Some(key) = self.key {
+            write!(f, " {key}")?;
+        }
+        write!(f, ": {}", self.description)
+    }
+}
+
+/// Every change made by [`Wif::repair`], in the order they were made.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    pub repairs: Vec<Repair>,
+}
+
+impl RepairReport {
+    pub fn is_empty(&self) -> bool {
+        self.repairs.is_empty()
+    }
+
+    fn push(
+        &mut self,
+        code: IssueCode,
+        section: &'static str,
+        key: Option<u32>,
+        description: String,
+    ) {
+        self.repairs.push(Repair {
+            code,
+            section,
+            key,
+            description,
+        });
+    }
+}
+
+/// One line per repair.
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for repair in &self.repairs {
+            writeln!(f, "{repair}")?;
+        }
+        Ok(())
+    }
+}
+
+// Runs `$body` once for each per-thread table of one side, with `$section` naming it.
+macro_rules! for_each_table {
+    ($wif:expr, [$($field:ident: $name:ident),* $(,)?], |$section:ident, $table:ident| $body:block) => {
+        $({
+            let ($section, $table) = (sections::$name, &mut $wif.$field);
+            $body
+        })*
+    };
+}
+
+macro_rules! warp_tables {
+    ($wif:expr, |$section:ident, $table:ident| $body:block) => {
+        for_each_table!($wif, [
+            threading: THREADING,
+            warp_thickness: WARP_THICKNESS,
+            warp_thickness_zoom: WARP_THICKNESS_ZOOM,
+            warp_spacing: WARP_SPACING,
+            warp_spacing_zoom: WARP_SPACING_ZOOM,
+            warp_colors: WARP_COLORS,
+            warp_symbols: WARP_SYMBOLS,
+        ], |$section, $table| $body)
+    };
+}
+
+macro_rules! weft_tables {
+    ($wif:expr, |$section:ident, $table:ident| $body:block) => {
+        for_each_table!($wif, [
+            treadling: TREADLING,
+            liftplan: LIFTPLAN,
+            weft_thickness: WEFT_THICKNESS,
+            weft_thickness_zoom: WEFT_THICKNESS_ZOOM,
+            weft_spacing: WEFT_SPACING,
+            weft_spacing_zoom: WEFT_SPACING_ZOOM,
+            weft_colors: WEFT_COLORS,
+            weft_symbols: WEFT_SYMBOLS,
+        ], |$section, $table| $body)
+    };
+}
+
+// Removes the entries whose key fails `keep`, reporting each one.
+fn retain_keys<K: Ord + Copy, V>(
+    table: &mut Option<Table<K, V>>,
+    section: &'static str,
+    key: impl Fn(K) -> u32,
+    keep: impl Fn(u32) -> bool,
+    report: &mut RepairReport,
+) {
+    let Some(t) = table else {
+        return;
+    };
+    t.retain(|&k, _| {
+        let k = key(k);
+        if !keep(k) {
+            report.push(
+                IssueCode::ThreadOutOfRange,
+                section,
+                Some(k),
+                "removed, being outside the threads".into(),
+            );
+        }
+        keep(k)
+    });
+}
+
+// Drops values beyond `max` from each set, and any entry left empty.
+fn clamp_sets<K: Ord + Copy, T: Ord>(
+    table: &mut Option<Table<K, BTreeSet<T>>>,
+    code: IssueCode,
+    section: &'static str,
+    max: u32,
+    key: impl Fn(K) -> u32,
+    value: impl Fn(&T) -> u32,
+    report: &mut RepairReport,
+) {
+    let what = match code {
+        IssueCode::ShaftOutOfRange => "shafts",
+        _ => "treadles",
+    };
+    let Some(t) = table else {
+        return;
+    };
+    t.retain(|&k, set| {
+        let before = set.len();
+        set.retain(|v| value(v) <= max);
+        if set.len() != before {
+            report.push(
+                code,
+                section,
+                Some(key(k)),
+                format!("dropped {what} above {max}"),
+            );
+        }
+        !set.is_empty()
+    });
+}
+
+fn highest<K: Copy, V>(table: &Option<Table<K, V>>, key: impl Fn(K) -> u32) -> Option<u32> {
+    table.as_ref()?.keys().next_back().map(|&k| key(k))
+}
+
+fn dangling<K: Ord + Copy, I: Copy + fmt::Display>(
+    table: &mut Option<Table<K, I>>,
+    code: IssueCode,
+    section: &'static str,
+    key: impl Fn(K) -> u32,
+    exists: impl Fn(I) -> bool,
+    report: &mut RepairReport,
+) {
+    if let Some(t) = table {
+        t.retain(|&k, &mut idx| {
+            if !exists(idx) {
+                report.push(
+                    code,
+                    section,
+                    Some(key(k)),
+                    format!("removed missing {idx}"),
+                );
+            }
+            exists(idx)
+        });
+    }
+}
+
+fn fix_entries(section: &'static str, entries: &mut usize, size: usize, report: &mut RepairReport) {
+    if *entries != size {
+        report.push(
+            IssueCode::PaletteSizeMismatch,
+            section,
+            None,
+            format!("Entries changed from {entries} to {size}"),
+        );
+        *entries = size;
+    }
+}
+
+impl Wif {
+    /// Fixes what [`validate`](Self::validate) finds where that can be done without
+    /// guessing, as chosen by `options`, and reports every change. Treadles with nothing
+    /// tied to them and treadling without a tieup are left alone.
+    pub fn repair(&mut self, options: RepairOptions) -> RepairReport {
+        let mut report = RepairReport::default();
+        self.repair_looms(options, &mut report);
+        self.repair_threads(options, &mut report);
+        if options.drop_dangling {
+            self.drop_dangling(&mut report);
+        }
+        if options.regenerate_liftplan {
+            self.regenerate_liftplan(&mut report);
+        }
+        if options.fix_palettes {
+            self.fix_palettes(options, &mut report);
+        }
+        report
+    }
+
+    fn repair_looms(&mut self, options: RepairOptions, report: &mut RepairReport) {
+        let shafts_used = self
+            .threading
+            .iter()
+            .flat_map(|t| t.values().flatten())
+            .chain(self.liftplan.iter().flat_map(|l| l.values().flatten()))
+            .chain(self.tieup.iter().flat_map(|t| t.values().flatten()))
+            .map(|s| s.0)
+            .max();
+        let treadles_used = self
+            .treadling
+            .iter()
+            .flat_map(|t| t.values().flatten())
+            .chain(self.tieup.iter().flat_map(|t| t.keys()))
+            .map(|t| t.0)
+            .max();
+        let Some(weaving) = &mut self.weaving else {
+            if shafts_used.is_some() || treadles_used.is_some() {
+                let (shafts, treadles) = (shafts_used.unwrap_or(0), treadles_used.unwrap_or(0));
+                self.weaving = Some(Weaving {
+                    shafts,
+                    treadles,
+                    rising_shed: None,
+                });
+                report.push(
+                    IssueCode::MissingSection,
+                    sections::WEAVING,
+                    None,
+                    format!("added, with {shafts} shafts and {treadles} treadles"),
+                );
+            }
+            return;
+        };
+        if options.grow_counts {
+            if let Some(used) = shafts_used.filter(|&used| used > weaving.shafts) {
+                report.push(
+                    IssueCode::ShaftOutOfRange,
+                    sections::WEAVING,
+                    None,
+                    format!("Shafts raised from {} to {used}", weaving.shafts),
+                );
+                weaving.shafts = used;
+            }
+            if let Some(used) = treadles_used.filter(|&used| used > weaving.treadles) {
+                report.push(
+                    IssueCode::TreadleOutOfRange,
+                    sections::WEAVING,
+                    None,
+                    format!("Treadles raised from {} to {used}", weaving.treadles),
+                );
+                weaving.treadles = used;
+            }
+            return;
+        }
+        let (shafts, treadles) = (weaving.shafts, weaving.treadles);
+        let code = IssueCode::ShaftOutOfRange;
+        let shaft = |s: &Shaft| s.0;
+        clamp_sets(
+            &mut self.threading,
+            code,
+            sections::THREADING,
+            shafts,
+            |w| w.0,
+            shaft,
+            report,
+        );
+        clamp_sets(
+            &mut self.tieup,
+            code,
+            sections::TIEUP,
+            shafts,
+            |t| t.0,
+            shaft,
+            report,
+        );
+        clamp_sets(
+            &mut self.liftplan,
+            code,
+            sections::LIFTPLAN,
+            shafts,
+            |w| w.0,
+            shaft,
+            report,
+        );
+        let code = IssueCode::TreadleOutOfRange;
+        let treadle = |t: &Treadle| t.0;
+        clamp_sets(
+            &mut self.treadling,
+            code,
+            sections::TREADLING,
+            treadles,
+            |w| w.0,
+            treadle,
+            report,
+        );
+        if let Some(tieup) = &mut self.tieup {
+            tieup.retain(|t, _| {
+                if t.0 > treadles {
+                    report.push(
+                        code,
+                        sections::TIEUP,
+                        Some(t.0),
+                        format!("removed, being above {treadles}"),
+                    );
+                }
+                t.0 <= treadles
+            });
+        }
+    }
+
+    fn repair_threads(&mut self, options: RepairOptions, report: &mut RepairReport) {
+        warp_tables!(self, |section, table| {
+            retain_keys(table, section, |w| w.0, |k| k > 0, report);
+        });
+        weft_tables!(self, |section, table| {
+            retain_keys(table, section, |w| w.0, |k| k > 0, report);
+        });
+
+        let mut ends_used = None;
+        warp_tables!(self, |_section, table| {
+            ends_used = ends_used.max(highest(table, |w| w.0));
+        });
+        let mut picks_used = None;
+        weft_tables!(self, |_section, table| {
+            picks_used = picks_used.max(highest(table, |w| w.0));
+        });
+        match (&mut self.warp, ends_used) {
+            (None, Some(ends)) => {
+                self.warp = Some(WarpS {
+                    threads: ends,
+                    ..Default::default()
+                });
+                report.push(
+                    IssueCode::MissingSection,
+                    sections::WARP,
+                    None,
+                    format!("added, with {ends} threads"),
+                );
+            }
+            (Some(warp), Some(ends)) if options.grow_counts && ends > warp.threads => {
+                report.push(
+                    IssueCode::ThreadOutOfRange,
+                    sections::WARP,
+                    None,
+                    format!("Threads raised from {} to {ends}", warp.threads),
+                );
+                warp.threads = ends;
+            }
+            (Some(warp), _) => {
+                let ends = warp.threads;
+                warp_tables!(self, |section, table| {
+                    retain_keys(table, section, |w| w.0, |k| k <= ends, report);
+                });
+            }
+            _ => {}
+        }
+        match (&mut self.weft, picks_used) {
+            (None, Some(picks)) => {
+                self.weft = Some(WeftS {
+                    threads: picks,
+                    ..Default::default()
+                });
+                report.push(
+                    IssueCode::MissingSection,
+                    sections::WEFT,
+                    None,
+                    format!("added, with {picks} threads"),
+                );
+            }
+            (Some(weft), Some(picks)) if options.grow_counts && picks > weft.threads => {
+                report.push(
+                    IssueCode::ThreadOutOfRange,
+                    sections::WEFT,
+                    None,
+                    format!("Threads raised from {} to {picks}", weft.threads),
+                );
+                weft.threads = picks;
+            }
+            (Some(weft), _) => {
+                let picks = weft.threads;
+                weft_tables!(self, |section, table| {
+                    retain_keys(table, section, |w| w.0, |k| k <= picks, report);
+                });
+            }
+            _ => {}
+        }
+    }
+
+    fn drop_dangling(&mut self, report: &mut RepairReport) {
+        let colors = self.color_table.clone().unwrap_or_default();
+        let warp_symbols = self.warp_symbol_table.clone().unwrap_or_default();
+        let weft_symbols = self.weft_symbols_table.clone().unwrap_or_default();
+        let (color, symbol) = (IssueCode::MissingColor, IssueCode::MissingSymbol);
+        let has_color = |idx| colors.contains_key(&idx);
+        dangling(
+            &mut self.warp_colors,
+            color,
+            sections::WARP_COLORS,
+            |w| w.0,
+            has_color,
+            report,
+        );
+        dangling(
+            &mut self.weft_colors,
+            color,
+            sections::WEFT_COLORS,
+            |w| w.0,
+            has_color,
+            report,
+        );
+        let has_symbol = |idx| warp_symbols.contains_key(&idx);
+        dangling(
+            &mut self.warp_symbols,
+            symbol,
+            sections::WARP_SYMBOLS,
+            |w| w.0,
+            has_symbol,
+            report,
+        );
+        let has_symbol = |idx| weft_symbols.contains_key(&idx);
+        dangling(
+            &mut self.weft_symbols,
+            symbol,
+            sections::WEFT_SYMBOLS,
+            |w| w.0,
+            has_symbol,
+            report,
+        );
+
+        let defaults = [
+            (
+                sections::WARP,
+                self.warp
+                    .as_mut()
+                    .map(|w| (&mut w.color, &mut w.symbol_number)),
+                &warp_symbols,
+            ),
+            (
+                sections::WEFT,
+                self.weft
+                    .as_mut()
+                    .map(|w| (&mut w.color, &mut w.symbol_number)),
+                &weft_symbols,
+            ),
+        ];
+        for (section, fields, symbols) in defaults {
+            let Some((default_color, default_symbol)) = fields else {
+                continue;
+            };
+            if let Some(c) = default_color.filter(|c| !has_color(c.idx)) {
+                report.push(
+                    color,
+                    section,
+                    None,
+                    format!("removed missing default color {}", c.idx),
+                );
+                *default_color = None;
+            }
+            if let Some(idx) = default_symbol.filter(|idx| !symbols.contains_key(idx)) {
+                report.push(
+                    symbol,
+                    section,
+                    None,
+                    format!("removed missing default symbol {idx}"),
+                );
+                *default_symbol = None;
+            }
+        }
+    }
+
+    fn regenerate_liftplan(&mut self, report: &mut RepairReport) {
+        if self.liftplan_is_inverted() {
+            report.push(
+                IssueCode::InvertedLiftplan,
+                sections::LIFTPLAN,
+                None,
+                "inverted to match the treadling and tieup".into(),
+            );
+            self.invert_liftplan();
+            return;
+        }
+        let (Some(liftplan), Some(built)) = (
+            &mut self.liftplan,
+            liftplan_from_threading_and_treadle(self.treadling.as_ref(), self.tieup.as_ref()),
+        ) else {
+            return;
+        };
+        let empty = BTreeSet::new();
+        for (weft, lift) in built {
+            if liftplan.get(&weft).unwrap_or(&empty) != &lift {
+                report.push(
+                    IssueCode::LiftplanMismatch,
+                    sections::LIFTPLAN,
+                    Some(weft.0),
+                    "rebuilt from the treadling and tieup".into(),
+                );
+                if lift.is_empty() {
+                    liftplan.remove(&weft);
+                } else {
+                    liftplan.insert(weft, lift);
+                }
+            }
+        }
+    }
+
+    fn fix_palettes(&mut self, options: RepairOptions, report: &mut RepairReport) {
+        if let Some(table) = &mut self.color_table {
+            let palette = match &mut self.color_palette {
+                Some(palette) => palette,
+                None => {
+                    let high = table
+                        .values()
+                        .flat_map(|c| [c.red, c.green, c.blue])
+                        .fold(999, u32::max);
+                    report.push(
+                        IssueCode::PaletteSizeMismatch,
+                        sections::COLOR_PALETTE,
+                        None,
+                        format!("added, with range 0,{high}"),
+                    );
+                    self.color_palette.insert(ColorPalette {
+                        entries: super::palette_entries(table, |idx| idx.0),
+                        range: (0, high),
+                    })
+                }
+            };
+            let (low, high) = palette.range;
+            let components = || table.values().flat_map(|c| [c.red, c.green, c.blue]);
+            let from = (
+                components().fold(low, u32::min),
+                components().fold(high, u32::max),
+            );
+            let rescaling = options.rescale_colors && from != (low, high);
+            for (idx, c) in table.iter_mut() {
+                let before = [c.red, c.green, c.blue];
+                let after = if rescaling {
+                    before.map(|v| rescale(v, from, (low, high)))
+                } else {
+                    before.map(|v| v.clamp(low, high))
+                };
+                if after != before {
+                    let how = if rescaling { "rescaled" } else { "brought" };
+                    report.push(
+                        IssueCode::ColorOutOfRange,
+                        sections::COLOR_TABLE,
+                        Some(idx.0),
+                        format!("{how} into the range {low},{high}"),
+                    );
+                    [c.red, c.green, c.blue] = after;
+                }
+            }
+            fix_entries(
+                sections::COLOR_PALETTE,
+                &mut palette.entries,
+                super::palette_entries(table, |idx| idx.0),
+                report,
+            );
+        }
+
+        let symbols = |t: &Table<SymbolIndex, Symbol>| super::palette_entries(t, |idx| idx.0);
+        if let Some(size) = self.warp_symbol_table.as_ref().map(symbols) {
+            let section = sections::WARP_SYMBOL_PALETTE;
+            let palette = self.warp_symbol_palette.get_or_insert_with(|| {
+                report.push(
+                    IssueCode::PaletteSizeMismatch,
+                    section,
+                    None,
+                    "added".into(),
+                );
+                WarpSymbolPalette { entries: size }
+            });
+            fix_entries(section, &mut palette.entries, size, report);
+        }
+        if let Some(size) = self.weft_symbols_table.as_ref().map(symbols) {
+            let section = sections::WEFT_SYMBOL_PALETTE;
+            let palette = self.weft_symbol_palette.get_or_insert_with(|| {
+                report.push(
+                    IssueCode::PaletteSizeMismatch,
+                    section,
+                    None,
+                    "added".into(),
+                );
+                WeftSymbolPalette { entries: size }
+            });
+            fix_entries(section, &mut palette.entries, size, report);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+
+use super::{Severity, Thresholds, ValidationIssue, Wif};
+use crate::loom::{LoomIssue, LoomProfile};
+
+/// The version written to `formatVersion` by [`ValidationReport::to_json`]. Bumped whenever
+/// a field changes meaning.
+#[cfg(feature = "json")]
+pub const VALIDATION_REPORT_VERSION: u32 = 1;
+
+/// One problem in a [`ValidationReport`], from either [`Wif::validate`] or
+/// [`Wif::check_against_loom`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportEntry {
+    pub severity: Severity,
+    /// The issue's stable name, such as `shaft-out-of-range` or `too-many-shafts`.
+    pub code: &'static str,
+    pub section: &'static str,
+    pub key: Option<u32>,
+    pub message: String,
+}
+
+impl From<ValidationIssue> for ReportEntry {
+    fn from(issue: ValidationIssue) -> Self {
+        Self {
+            severity: issue.severity,
+            code: issue.code.as_str(),
+            section: issue.section,
+            key: issue.key,
+            message: issue.message,
+        }
+    }
+}
+
+/// A draft that doesn't fit the loom is broken for it, but one written for the other shed
+/// direction can still be woven by inverting its tieup, so that's only stylistic.
+impl From<&LoomIssue> for ReportEntry {
+    fn from(issue: &LoomIssue) -> Self {
+        let severity = match issue {
+            LoomIssue::ShedDirection { .. } => Severity::Stylistic,
+            _ => Severity::BrokenDraft,
+        };
+        Self {
+            severity,
+            code: issue.as_str(),
+            section: issue.section(),
+            key: None,
+            message: issue.to_string(),
+        }
+    }
+}
+
+/// Written like a [`ValidationIssue`].
+impl fmt::Display for ReportEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} [{}]", self.severity, self.code, self.section)?;
+        if let Some(key) = self.key {
+            write!(f, " {key}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// What [`Wif::validate`] and, given a loom, [`Wif::check_against_loom`] found at or above
+/// the reporting threshold, for tools that gate on the result.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub entries: Vec<ReportEntry>,
+    pub thresholds: Thresholds,
+}
+
+impl ValidationReport {
+    /// The entries that reach the failing threshold.
+    pub fn errors(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| self.thresholds.fails(e.severity))
+            .count()
+    }
+
+    /// The entries reported but below the failing threshold.
+    pub fn warnings(&self) -> usize {
+        self.entries.len() - self.errors()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.severity == severity)
+            .count()
+    }
+
+    /// Whether no entry reaches the failing threshold.
+    pub fn passed(&self) -> bool {
+        self.errors() == 0
+    }
+
+    /// The report as a JSON object with `format` set to `"wif-validation"`,
+    /// `formatVersion`, `passed`, `errors` and `warnings` counts, the `report` and `fail`
+    /// thresholds, a `counts` object keyed by severity, and an `issues` array of objects with `severity`, `code`, `section`,
+    /// `key` (a number or `null`) and `message`. Severities are written as
+    /// `"stylistic"`, `"broken-draft"` or `"spec-violation"`.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        use std::collections::BTreeMap;
+
+        use serde::Serialize;
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Report<'a> {
+            format: &'static str,
+            format_version: u32,
+            passed: bool,
+            errors: usize,
+            warnings: usize,
+            report: String,
+            fail: String,
+            counts: BTreeMap<String, usize>,
+            issues: Vec<Issue<'a>>,
+        }
+
+        #[derive(Serialize)]
+        struct Issue<'a> {
+            severity: String,
+            code: &'static str,
+            section: &'static str,
+            key: Option<u32>,
+            message: &'a str,
+        }
+
+        let severities = [
+            Severity::Stylistic,
+            Severity::BrokenDraft,
+            Severity::SpecViolation,
+        ];
+        let report = Report {
+            format: "wif-validation",
+            format_version: VALIDATION_REPORT_VERSION,
+            passed: self.passed(),
+            errors: self.errors(),
+            warnings: self.warnings(),
+            report: self.thresholds.report.to_string(),
+            fail: self.thresholds.fail.to_string(),
+            counts: severities
+                .into_iter()
+                .map(|s| (s.to_string(), self.count(s)))
+                .collect(),
+            issues: self
+                .entries
+                .iter()
+                .map(|e| Issue {
+                    severity: e.severity.to_string(),
+                    code: e.code,
+                    section: e.section,
+                    key: e.key,
+                    message: &e.message,
+                })
+                .collect(),
+        };
+        serde_json::to_string_pretty(&report).expect("reports only contain strings and numbers")
+    }
+}
+
+/// One line per entry.
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Wif {
+    /// [`validate`](Self::validate) and, if `loom` is given,
+    /// [`check_against_loom`](Self::check_against_loom), combined in one report and filtered
+    /// by `thresholds`.
+    pub fn validation_report(
+        &self,
+        loom: Option<&LoomProfile>,
+        thresholds: Thresholds,
+    ) -> ValidationReport {
+        let mut entries: Vec<ReportEntry> = self.validate().into_iter().map(Into::into).collect();
+        if let Some(loom) = loom {
+            entries.extend(self.check_against_loom(loom).iter().map(ReportEntry::from));
+        }
+        entries.retain(|e| thresholds.reports(e.severity));
+        ValidationReport {
+            entries,
+            thresholds,
+        }
+    }
+}
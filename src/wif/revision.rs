@@ -0,0 +1,37 @@
+use std::hash::{DefaultHasher, Hasher};
+
+use super::Wif;
+
+/// A snapshot of a draft's contents, for telling whether it has changed since.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Revision(u64);
+
+struct HashWriter(DefaultHasher);
+
+impl std::io::Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Wif {
+    /// A digest of everything [`write`](Self::write) would output. The fields are public, so
+    /// rather than tracking mutations this hashes the draft as it is written, streaming it
+    /// through the hasher without building the file in memory.
+    pub fn revision(&self) -> Revision {
+        let mut hasher = HashWriter(DefaultHasher::new());
+        self.write(&mut hasher)
+            .expect("writing to a hasher can't fail");
+        Revision(hasher.0.finish())
+    }
+
+    /// Whether the draft has changed in any way that would show up in its written form.
+    pub fn is_modified_since(&self, revision: Revision) -> bool {
+        self.revision() != revision
+    }
+}
@@ -0,0 +1,405 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use super::{sections, Weaving, Wif};
+use crate::{
+    liftplan_from_threading_and_treadle, ColorIndex, Shaft, Table, Tieup, Treadle, Warp, Weft,
+};
+
+// Moves every entry to the key `renumber` gives it, dropping entries it maps to `None`.
+pub(super) fn renumber<K: Ord + Copy, V>(
+    table: &mut Option<Table<K, V>>,
+    renumber: impl Fn(K) -> Option<K>,
+) {
+    if let Some(table) = table {
+        *table = std::mem::take(table)
+            .into_iter()
+            .filter_map(|(key, value)| Some((renumber(key)?, value)))
+            .collect();
+    }
+}
+
+// The keys of `table` beyond `last`.
+fn keys_after<K: Ord + Copy, V>(
+    table: &Option<Table<K, V>>,
+    last: K,
+) -> impl Iterator<Item = K> + '_ {
+    table
+        .iter()
+        .flat_map(move |t| t.keys().filter(move |&&k| k > last).copied())
+}
+
+// Makes room for a new thread at `at` by moving it and everything after it up one.
+fn open_gap(index: u32, at: u32) -> Option<u32> {
+    Some(if index >= at { index + 1 } else { index })
+}
+
+// Drops the thread at `at` and moves everything after it down one.
+fn close_gap(index: u32, at: u32) -> Option<u32> {
+    match index.cmp(&at) {
+        Ordering::Less => Some(index),
+        Ordering::Equal => None,
+        Ordering::Greater => Some(index - 1),
+    }
+}
+
+// Setters that keep thread counts, shaft and treadle counts, the liftplan and a pinned
+// CONTENTS in step with the tables they change.
+impl Wif {
+    pub(super) fn mark_present(&mut self, section: &str) {
+        let contents = &mut self.contents;
+        if !contents.pinned {
+            return;
+        }
+        match contents
+            .entries
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(section))
+        {
+            Some((_, present)) => *present = true,
+            None => contents.entries.push((section.into(), true)),
+        }
+    }
+
+    pub(super) fn mark_absent(&mut self, section: &str) {
+        let contents = &mut self.contents;
+        if !contents.pinned {
+            return;
+        }
+        if let Some((_, present)) = contents
+            .entries
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(section))
+        {
+            *present = false;
+        }
+    }
+
+    pub(super) fn weaving_mut(&mut self) -> &mut Weaving {
+        self.weaving.get_or_insert(Weaving {
+            shafts: 0,
+            treadles: 0,
+            rising_shed: None,
+        })
+    }
+
+    fn fit_shafts<'a>(&mut self, used: impl IntoIterator<Item = &'a Shaft>) {
+        if let Some(highest) = used.into_iter().map(|s| s.0).max() {
+            let weaving = self.weaving_mut();
+            weaving.shafts = weaving.shafts.max(highest);
+            self.mark_present(sections::WEAVING);
+        }
+    }
+
+    fn fit_treadles<'a>(&mut self, used: impl IntoIterator<Item = &'a Treadle>) {
+        if let Some(highest) = used.into_iter().map(|t| t.0).max() {
+            let weaving = self.weaving_mut();
+            weaving.treadles = weaving.treadles.max(highest);
+            self.mark_present(sections::WEAVING);
+        }
+    }
+
+    pub(super) fn set_ends(&mut self, ends: u32) {
+        self.warp.get_or_insert_with(Default::default).threads = ends;
+        self.mark_present(sections::WARP);
+    }
+
+    pub(super) fn set_picks(&mut self, picks: u32) {
+        self.weft.get_or_insert_with(Default::default).threads = picks;
+        self.mark_present(sections::WEFT);
+    }
+
+    // Rebuilds the liftplan from treadling and tieup when both are present.
+    fn refresh_liftplan(&mut self) {
+        if let Some(liftplan) =
+            liftplan_from_threading_and_treadle(self.treadling.as_ref(), self.tieup.as_ref())
+        {
+            self.liftplan = Some(liftplan);
+            self.mark_present(sections::LIFTPLAN);
+        }
+    }
+
+    // Removes the treadling and tieup, leaving the draft woven from its liftplan alone.
+    pub(crate) fn drop_treadling(&mut self) {
+        self.treadling = None;
+        self.tieup = None;
+        if let Some(weaving) = &mut self.weaving {
+            weaving.treadles = 0;
+        }
+        self.mark_present(sections::LIFTPLAN);
+        self.mark_absent(sections::TREADLING);
+        self.mark_absent(sections::TIEUP);
+    }
+
+    // Moves a treadled draft onto its liftplan before a pick is added that no treadle makes.
+    fn untreadle(&mut self) {
+        if self.treadling.is_some() || self.tieup.is_some() {
+            self.refresh_liftplan();
+            self.drop_treadling();
+        }
+    }
+
+    /// Replaces the threading. The warp gets one end per threading entry, up to the highest
+    /// end threaded, and the shaft count grows to cover every shaft used.
+    pub fn set_threading(&mut self, threading: Table<Warp, BTreeSet<Shaft>>) {
+        let ends = threading.keys().next_back().map_or(0, |w| w.0);
+        self.fit_shafts(threading.values().flatten());
+        self.set_ends(ends);
+        self.threading = Some(threading);
+        self.mark_present(sections::THREADING);
+    }
+
+    /// Replaces the tieup and rebuilds the liftplan from the treadling.
+    pub fn set_tieup(&mut self, tieup: Tieup) {
+        self.fit_shafts(tieup.values().flatten());
+        self.fit_treadles(tieup.keys());
+        self.tieup = Some(tieup);
+        self.mark_present(sections::TIEUP);
+        self.refresh_liftplan();
+    }
+
+    /// Replaces the treadling, sets the pick count to the last pick treadled, and rebuilds
+    /// the liftplan if there is a tieup.
+    pub fn set_treadling(&mut self, treadling: Table<Weft, BTreeSet<Treadle>>) {
+        let picks = treadling.keys().next_back().map_or(0, |w| w.0);
+        self.fit_treadles(treadling.values().flatten());
+        self.set_picks(picks);
+        self.treadling = Some(treadling);
+        self.mark_present(sections::TREADLING);
+        self.refresh_liftplan();
+    }
+
+    /// Replaces the liftplan and sets the pick count to its last pick. Treadling and tieup
+    /// that no longer produce this liftplan are removed.
+    pub fn set_liftplan(&mut self, liftplan: Table<Weft, BTreeSet<Shaft>>) {
+        let picks = liftplan.keys().next_back().map_or(0, |w| w.0);
+        self.fit_shafts(liftplan.values().flatten());
+        self.set_picks(picks);
+        let derived =
+            liftplan_from_threading_and_treadle(self.treadling.as_ref(), self.tieup.as_ref());
+        if derived.is_some_and(|derived| derived != liftplan) {
+            self.drop_treadling();
+        }
+        self.liftplan = Some(liftplan);
+        self.mark_present(sections::LIFTPLAN);
+    }
+
+    /// Adds an end after the last one, threaded on `shafts`, returning its number.
+    pub fn push_warp_end(
+        &mut self,
+        shafts: impl IntoIterator<Item = u32>,
+        color: Option<ColorIndex>,
+    ) -> Warp {
+        let end = Warp(self.width().unwrap_or(0) + 1);
+        let shafts: BTreeSet<Shaft> = shafts.into_iter().map(Shaft).collect();
+        self.fit_shafts(&shafts);
+        self.set_ends(end.0);
+        self.threading
+            .get_or_insert_with(Default::default)
+            .insert(end, shafts);
+        self.mark_present(sections::THREADING);
+        if let Some(color) = color {
+            self.warp_colors
+                .get_or_insert_with(Default::default)
+                .insert(end, color);
+            self.mark_present(sections::WARP_COLORS);
+        }
+        end
+    }
+
+    /// Adds a pick after the last one, woven with `treadles`, returning its number. The
+    /// liftplan gains the matching lift if there is a tieup.
+    pub fn push_weft_pick(
+        &mut self,
+        treadles: impl IntoIterator<Item = u32>,
+        color: Option<ColorIndex>,
+    ) -> Weft {
+        let pick = self.push_pick(color);
+        let treadles: BTreeSet<Treadle> = treadles.into_iter().map(Treadle).collect();
+        self.fit_treadles(&treadles);
+        self.treadling
+            .get_or_insert_with(Default::default)
+            .insert(pick, treadles);
+        self.mark_present(sections::TREADLING);
+        self.refresh_liftplan();
+        pick
+    }
+
+    /// Adds a pick after the last one, lifting `shafts`, returning its number. Use this for
+    /// drafts woven from a liftplan rather than treadling: a treadled draft is first turned
+    /// into its liftplan, and its treadling and tieup removed.
+    pub fn push_lift(
+        &mut self,
+        shafts: impl IntoIterator<Item = u32>,
+        color: Option<ColorIndex>,
+    ) -> Weft {
+        self.untreadle();
+        let pick = self.push_pick(color);
+        let shafts: BTreeSet<Shaft> = shafts.into_iter().map(Shaft).collect();
+        self.fit_shafts(&shafts);
+        self.liftplan
+            .get_or_insert_with(Default::default)
+            .insert(pick, shafts);
+        self.mark_present(sections::LIFTPLAN);
+        pick
+    }
+
+    // Renumbers every per-end table together.
+    pub(super) fn renumber_ends(&mut self, f: impl Fn(u32) -> Option<u32> + Copy) {
+        let f = |w: Warp| f(w.0).map(Warp);
+        renumber(&mut self.threading, f);
+        renumber(&mut self.warp_colors, f);
+        renumber(&mut self.warp_thickness, f);
+        renumber(&mut self.warp_thickness_zoom, f);
+        renumber(&mut self.warp_spacing, f);
+        renumber(&mut self.warp_spacing_zoom, f);
+        renumber(&mut self.warp_symbols, f);
+    }
+
+    // Renumbers every per-pick table together.
+    pub(super) fn renumber_picks(&mut self, f: impl Fn(u32) -> Option<u32> + Copy) {
+        let f = |w: Weft| f(w.0).map(Weft);
+        renumber(&mut self.treadling, f);
+        renumber(&mut self.liftplan, f);
+        renumber(&mut self.weft_colors, f);
+        renumber(&mut self.weft_thickness, f);
+        renumber(&mut self.weft_thickness_zoom, f);
+        renumber(&mut self.weft_spacing, f);
+        renumber(&mut self.weft_spacing_zoom, f);
+        renumber(&mut self.weft_symbols, f);
+    }
+
+    /// Inserts an end threaded on `shafts` at `at`, moving that end and every later one up by
+    /// one in the threading, colors, thickness, spacing and symbol tables. Inserting past the
+    /// last end leaves unthreaded ends in between.
+    ///
+    /// Panics if `at` is 0.
+    pub fn insert_warp_end(&mut self, at: impl Into<Warp>, shafts: impl IntoIterator<Item = u32>) {
+        let at = at.into();
+        assert!(at.0 > 0, "ends are numbered from 1");
+        let ends = self.width().unwrap_or(0).max(at.0 - 1) + 1;
+        self.renumber_ends(|i| open_gap(i, at.0));
+        let shafts: BTreeSet<Shaft> = shafts.into_iter().map(Shaft).collect();
+        self.fit_shafts(&shafts);
+        self.set_ends(ends);
+        self.threading
+            .get_or_insert_with(Default::default)
+            .insert(at, shafts);
+        self.mark_present(sections::THREADING);
+    }
+
+    /// Removes the end at `at` from every per-end table, moving later ends down by one.
+    /// Does nothing if there is no such end.
+    pub fn remove_warp_end(&mut self, at: impl Into<Warp>) {
+        let at = at.into();
+        let ends = self.width().unwrap_or(0);
+        if at.0 == 0 || at.0 > ends {
+            return;
+        }
+        self.renumber_ends(|i| close_gap(i, at.0));
+        self.set_ends(ends - 1);
+    }
+
+    /// Inserts a pick woven with `treadles` at `at`, moving that pick and every later one up
+    /// by one in the treadling, liftplan, colors, thickness, spacing and symbol tables. The
+    /// liftplan gains the matching lift if there is a tieup.
+    pub fn insert_weft_pick(
+        &mut self,
+        at: impl Into<Weft>,
+        treadles: impl IntoIterator<Item = u32>,
+    ) {
+        let at = at.into();
+        self.open_pick(at);
+        let treadles: BTreeSet<Treadle> = treadles.into_iter().map(Treadle).collect();
+        self.fit_treadles(&treadles);
+        self.treadling
+            .get_or_insert_with(Default::default)
+            .insert(at, treadles);
+        self.mark_present(sections::TREADLING);
+        self.refresh_liftplan();
+    }
+
+    /// Inserts a pick lifting `shafts` at `at`, as [`insert_weft_pick`](Self::insert_weft_pick)
+    /// does for treadled picks. As with [`push_lift`](Self::push_lift), a treadled draft
+    /// loses its treadling and tieup to its liftplan first.
+    pub fn insert_lift(&mut self, at: impl Into<Weft>, shafts: impl IntoIterator<Item = u32>) {
+        self.untreadle();
+        let at = at.into();
+        self.open_pick(at);
+        let shafts: BTreeSet<Shaft> = shafts.into_iter().map(Shaft).collect();
+        self.fit_shafts(&shafts);
+        self.liftplan
+            .get_or_insert_with(Default::default)
+            .insert(at, shafts);
+        self.mark_present(sections::LIFTPLAN);
+    }
+
+    /// Removes the pick at `at` from every per-pick table, moving later picks down by one.
+    /// Does nothing if there is no such pick.
+    pub fn remove_weft_pick(&mut self, at: impl Into<Weft>) {
+        let at = at.into();
+        let picks = self.height().unwrap_or(0);
+        if at.0 == 0 || at.0 > picks {
+            return;
+        }
+        self.renumber_picks(|i| close_gap(i, at.0));
+        self.set_picks(picks - 1);
+    }
+
+    /// Reverses the order of the ends, so end 1 becomes the last, in every per-end table:
+    /// the mirror image of the draft left to right. Entries for ends past [WARP] `Threads`
+    /// have nowhere to go, so they're removed, and their ends returned.
+    pub fn flip_horizontal(&mut self) -> Vec<Warp> {
+        let ends = self.width().unwrap_or(0);
+        let last = Warp(ends);
+        let dropped: BTreeSet<Warp> = keys_after(&self.threading, last)
+            .chain(keys_after(&self.warp_colors, last))
+            .chain(keys_after(&self.warp_thickness, last))
+            .chain(keys_after(&self.warp_thickness_zoom, last))
+            .chain(keys_after(&self.warp_spacing, last))
+            .chain(keys_after(&self.warp_spacing_zoom, last))
+            .chain(keys_after(&self.warp_symbols, last))
+            .collect();
+        self.renumber_ends(|i| (i <= ends).then(|| ends + 1 - i));
+        dropped.into_iter().collect()
+    }
+
+    /// Reverses the order of the picks, so pick 1 becomes the last, in every per-pick table:
+    /// the mirror image of the draft top to bottom. As with
+    /// [`flip_horizontal`](Self::flip_horizontal), entries for picks past [WEFT] `Threads`
+    /// are removed, and their picks returned.
+    pub fn flip_vertical(&mut self) -> Vec<Weft> {
+        let picks = self.height().unwrap_or(0);
+        let last = Weft(picks);
+        let dropped: BTreeSet<Weft> = keys_after(&self.treadling, last)
+            .chain(keys_after(&self.liftplan, last))
+            .chain(keys_after(&self.weft_colors, last))
+            .chain(keys_after(&self.weft_thickness, last))
+            .chain(keys_after(&self.weft_thickness_zoom, last))
+            .chain(keys_after(&self.weft_spacing, last))
+            .chain(keys_after(&self.weft_spacing_zoom, last))
+            .chain(keys_after(&self.weft_symbols, last))
+            .collect();
+        self.renumber_picks(|i| (i <= picks).then(|| picks + 1 - i));
+        dropped.into_iter().collect()
+    }
+
+    fn open_pick(&mut self, at: Weft) {
+        assert!(at.0 > 0, "picks are numbered from 1");
+        let picks = self.height().unwrap_or(0).max(at.0 - 1) + 1;
+        self.renumber_picks(|i| open_gap(i, at.0));
+        self.set_picks(picks);
+    }
+
+    fn push_pick(&mut self, color: Option<ColorIndex>) -> Weft {
+        let pick = Weft(self.height().unwrap_or(0) + 1);
+        self.set_picks(pick.0);
+        if let Some(color) = color {
+            self.weft_colors
+                .get_or_insert_with(Default::default)
+                .insert(pick, color);
+            self.mark_present(sections::WEFT_COLORS);
+        }
+        pick
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use super::Wif;
+use crate::{liftplan_from_threading_and_treadle, Shaft, Treadle, Weft};
+
+// The shafts in `1..=shafts` not in `lift`.
+fn complement(lift: &BTreeSet<Shaft>, shafts: u32) -> BTreeSet<Shaft> {
+    (1..=shafts)
+        .map(Shaft)
+        .filter(|shaft| !lift.contains(shaft))
+        .collect()
+}
+
+impl Wif {
+    /// Whether the liftplan disagrees with the treadling and tieup on some pick, but lists
+    /// exactly the other shafts on every pick, as a draft converted for the opposite shed
+    /// direction without updating the tieup would.
+    pub(super) fn liftplan_is_inverted(&self) -> bool {
+        let (Some(shafts), Some(liftplan), Some(built)) = (
+            self.shafts(),
+            self.liftplan.as_ref(),
+            liftplan_from_threading_and_treadle(self.treadling.as_ref(), self.tieup.as_ref()),
+        ) else {
+            return false;
+        };
+        let empty = BTreeSet::new();
+        let listed = |weft| liftplan.get(weft).unwrap_or(&empty);
+        built.iter().any(|(weft, lift)| listed(weft) != lift)
+            && built
+                .iter()
+                .all(|(weft, lift)| *listed(weft) == complement(lift, shafts))
+    }
+
+    /// Swaps the lifted and unlifted shafts of every pick in the liftplan, turning a liftplan
+    /// for a rising shed into one for a sinking shed or back. Picks the liftplan leaves out
+    /// lift every shaft afterwards. Does nothing without a liftplan or [WEAVING] section.
+    pub fn invert_liftplan(&mut self) {
+        let (Some(shafts), Some(liftplan)) = (self.shafts(), &self.liftplan) else {
+            return;
+        };
+        let picks = self
+            .height()
+            .or_else(|| liftplan.keys().next_back().map(|w| w.0))
+            .unwrap_or(0);
+        let empty = BTreeSet::new();
+        let inverted = (1..=picks)
+            .map(Weft)
+            .map(|weft| {
+                (
+                    weft,
+                    complement(liftplan.get(&weft).unwrap_or(&empty), shafts),
+                )
+            })
+            .filter(|(_, lift)| !lift.is_empty())
+            .collect();
+        self.liftplan = Some(inverted);
+    }
+
+    /// Adapts the draft for a loom with the opposite shed: each treadle is tied to the
+    /// shafts it wasn't, the liftplan lists the shafts it didn't, and [WEAVING] is marked
+    /// for the other shed direction, so the cloth comes out the same. Does nothing without a
+    /// [WEAVING] section.
+    pub fn invert_shed(&mut self) {
+        let Some(shafts) = self.shafts() else {
+            return;
+        };
+        if let Some(tieup) = &mut self.tieup {
+            let treadles = self
+                .weaving
+                .as_ref()
+                .map_or(0, |w| w.treadles)
+                .max(tieup.keys().next_back().map_or(0, |t| t.0));
+            let empty = BTreeSet::new();
+            *tieup = (1..=treadles)
+                .map(Treadle)
+                .map(|treadle| {
+                    (
+                        treadle,
+                        complement(tieup.get(&treadle).unwrap_or(&empty), shafts),
+                    )
+                })
+                .filter(|(_, tied)| !tied.is_empty())
+                .collect();
+        }
+        self.invert_liftplan();
+        if let Some(weaving) = &mut self.weaving {
+            weaving.rising_shed = Some(!weaving.rising_shed.unwrap_or(true));
+        }
+    }
+}
@@ -3,3 +3,2361 @@ fn test() {
     let s = include_str!("../sample.wif");
     super::parse(s).unwrap();
 }
+
+#[test]
+fn usage_maps_invert_threading_and_treadling() {
+    let s = include_str!("../sample.wif");
+    let wif = super::parse(s).unwrap();
+    let shaft_usage = wif.shaft_usage();
+    for (warp, shafts) in wif.threading.as_ref().unwrap() {
+        for shaft in shafts {
+            assert!(shaft_usage[shaft].contains(warp));
+        }
+    }
+    let treadle_usage = wif.treadle_usage();
+    let picks: usize = treadle_usage.values().map(Vec::len).sum();
+    let expected: usize = wif
+        .treadling
+        .as_ref()
+        .unwrap()
+        .values()
+        .map(|t| t.len())
+        .sum();
+    assert_eq!(picks, expected);
+}
+
+#[test]
+fn orphan_and_missing_sections() {
+    use super::{MissingSectionPolicy, OrphanSectionPolicy, ParseOptions, ParseWarning};
+    let s = include_str!("../sample.wif").replace("THREADING=true\n", "");
+    let (wif, warnings) = super::parse_with_options(&s, &ParseOptions::default()).unwrap();
+    assert!(wif.threading.is_none());
+    assert!(warnings.is_empty());
+
+    let options = ParseOptions {
+        orphan_sections: OrphanSectionPolicy::Warn,
+        ..Default::default()
+    };
+    let (wif, warnings) = super::parse_with_options(&s, &options).unwrap();
+    assert!(wif.threading.is_none());
+    assert_eq!(
+        warnings,
+        vec![ParseWarning::OrphanSection {
+            section: "THREADING".into()
+        }]
+    );
+
+    let options = ParseOptions {
+        orphan_sections: OrphanSectionPolicy::Read,
+        ..Default::default()
+    };
+    let (wif, _) = super::parse_with_options(&s, &options).unwrap();
+    assert!(wif.threading.is_some());
+
+    let s = include_str!("../sample.wif").replace("[THREADING]", "[UNRELATED]");
+    assert!(super::parse(&s).is_err());
+    let options = ParseOptions {
+        missing_sections: MissingSectionPolicy::Warn,
+        ..Default::default()
+    };
+    let (wif, warnings) = super::parse_with_options(&s, &options).unwrap();
+    assert!(wif.threading.is_none());
+    assert_eq!(
+        warnings,
+        vec![ParseWarning::MissingSection {
+            section: "THREADING".into()
+        }]
+    );
+}
+
+#[test]
+fn symbols_round_trip() {
+    use crate::{wifparse::WifParse, Symbol};
+    for (text, symbol) in [
+        ("x", Symbol::Char('x')),
+        ("';'", Symbol::Quoted(';')),
+        ("#65", Symbol::Code('A')),
+    ] {
+        let parsed = Symbol::parse(text).unwrap();
+        assert_eq!(parsed, symbol);
+        assert_eq!(parsed.unparse().as_deref(), Some(text));
+    }
+    for bad in ["", "xy", "'x", "'xy'", "#", "#1114112"] {
+        assert!(Symbol::parse(bad).is_err(), "{bad:?} should not parse");
+    }
+}
+
+#[test]
+fn random_colorway_is_reproducible() {
+    use crate::{generators::StripeRuns, Color};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let palette = [
+        Color {
+            red: 999,
+            green: 0,
+            blue: 0,
+        },
+        Color {
+            red: 0,
+            green: 0,
+            blue: 999,
+        },
+    ];
+    let runs = StripeRuns { min: 2, max: 4 };
+    wif.random_colorway(&palette, &[3, 1], 42, runs).unwrap();
+    let first = wif.warp_colors.clone();
+    wif.random_colorway(&palette, &[3, 1], 42, runs).unwrap();
+    assert_eq!(first, wif.warp_colors);
+    assert_eq!(first.unwrap().len(), wif.width().unwrap() as usize);
+    assert!(wif.random_colorway(&palette, &[1], 42, runs).is_err());
+}
+
+#[test]
+fn stripes_repeat_across_the_threads() {
+    use crate::{Color, WifError};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let blue = Color {
+        red: 0,
+        green: 0,
+        blue: 999,
+    };
+    let white = Color {
+        red: 999,
+        green: 999,
+        blue: 999,
+    };
+    let colors = wif.color_table.as_ref().map_or(0, |t| t.len());
+    wif.stripe_warp(&[(blue, 3), (white, 1)]).unwrap();
+    let warp: Vec<_> = (1..=5).map(|end| wif.warp_color(end).unwrap()).collect();
+    assert_eq!(warp, [blue, blue, blue, white, blue]);
+    assert_eq!(
+        wif.warp_colors.as_ref().unwrap().len(),
+        wif.width().unwrap() as usize
+    );
+    wif.stripe_weft(&[(white, 2), (blue, 0), (blue, 1)])
+        .unwrap();
+    let weft: Vec<_> = (1..=4).map(|pick| wif.weft_color(pick).unwrap()).collect();
+    assert_eq!(weft, [white, white, blue, white]);
+    let added = wif.color_table.as_ref().unwrap().len() - colors;
+    assert!(added <= 2);
+    assert!(matches!(
+        wif.stripe_weft(&[(blue, 0)]),
+        Err(WifError::EmptyStripes)
+    ));
+    assert!(matches!(wif.stripe_warp(&[]), Err(WifError::EmptyStripes)));
+}
+
+#[test]
+fn decodes_common_encodings() {
+    use super::encoding::decode;
+    assert_eq!(decode(b"\xEF\xBB\xBF[WIF]"), "[WIF]");
+    assert_eq!(decode(b"\xFF\xFE[\0W\0]\0"), "[W]");
+    assert_eq!(decode(b"\xFE\xFF\0[\0W\0]"), "[W]");
+    assert_eq!(
+        decode(b"Title=\x93Caf\xE9\x94"),
+        "Title=\u{201C}Caf\u{E9}\u{201D}"
+    );
+}
+
+#[test]
+fn from_path_reports_path() {
+    let err = super::Wif::from_path("does/not/exist.wif").unwrap_err();
+    assert!(err.to_string().contains("does/not/exist.wif"));
+}
+
+#[test]
+fn contents_is_exposed_and_pinnable() {
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    assert_eq!(wif.contents.entries[0], ("COLOR PALETTE".into(), true));
+    assert!(wif.contents.declares("threading"));
+    assert_eq!(wif.contents.unknown().count(), 0);
+
+    wif.contents.entries.push(("PRIVATE EXAMPLE".into(), true));
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    let reparsed = super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    assert_eq!(reparsed.contents.unknown().count(), 0);
+    // Until the contents are pinned, they follow the sections rather than edits to them.
+    wif.mark_absent(super::sections::THREADING);
+    assert!(wif.contents.declares("threading"));
+
+    wif.contents.pinned = true;
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    let reparsed = super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    assert_eq!(reparsed.contents.entries, wif.contents.entries);
+}
+
+#[test]
+fn wif_1_0_header_is_lenient() {
+    use super::WifVersion;
+    let s = include_str!("../sample.wif")
+        .replace("Version=1.1", "Version=1.0")
+        .replace("Developers=wif@mhsoft.com\n", "");
+    let wif = super::parse(&s).unwrap();
+    assert_eq!(wif.spec_version(), WifVersion::V1_0);
+    assert_eq!(wif.wif_header.developers, "");
+    let undated = super::parse(&s.replace("Date=April 20, 1997\n", "")).unwrap();
+    assert_ne!(undated.wif_header.date, chrono::NaiveDate::default());
+
+    let s = include_str!("../sample.wif").replace("Developers=wif@mhsoft.com\n", "");
+    assert!(super::parse(&s).is_err());
+}
+
+#[test]
+fn quirks_are_normalized() {
+    use super::{ParseOptions, ParseWarning, Quirk};
+    let s = include_str!("../sample.wif").replace("Date=April 20, 1997", "Date=04/20/1997");
+    let (wif, warnings) = super::parse_with_options(&s, &ParseOptions::default()).unwrap();
+    assert_eq!(
+        Some(wif.wif_header.date),
+        chrono::NaiveDate::from_ymd_opt(1997, 4, 20)
+    );
+    assert!(warnings.contains(&ParseWarning::QuirkApplied {
+        quirk: Quirk::NonStandardDate,
+        section: "WIF".into()
+    }));
+    let options = ParseOptions {
+        quirks: false,
+        ..Default::default()
+    };
+    assert!(super::parse_with_options(&s, &options).is_err());
+
+    let s = include_str!("../sample.wif")
+        .replace("Source Program=Fiberworks PCW", "Source Program=WeaveIt")
+        .replace("Entries=82\n", "");
+    let wif = super::parse(&s).unwrap();
+    assert_eq!(wif.color_palette.unwrap().entries, 82);
+
+    let s = s.replace("\n3=121,0,607\n", "\n");
+    let wif = super::parse(&s).unwrap();
+    assert_eq!(wif.color_palette.unwrap().entries, 82);
+}
+
+#[test]
+fn drawin_plan_tallies_heddles() {
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let plan = wif.drawin_plan();
+    assert_eq!(plan.steps.len(), wif.threading.as_ref().unwrap().len());
+    let usage = wif.shaft_usage();
+    for (shaft, ends) in usage {
+        let last = plan
+            .steps
+            .iter()
+            .rev()
+            .find_map(|step| step.heddles.iter().find(|(s, _)| *s == shaft))
+            .unwrap();
+        assert_eq!(last.1 as usize, ends.len());
+    }
+    let csv = plan.to_csv();
+    assert_eq!(csv.lines().count(), plan.steps.len() + 1);
+    assert!(plan.to_text(100).starts_with("Page 1 of 5\n"));
+}
+
+#[test]
+fn weaving_plan_has_legend_and_shuttle_changes() {
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let names = [(crate::ColorIndex(79), "Navy 8/2 cotton".to_string())].into();
+    let plan = wif.weaving_plan(&names);
+    assert_eq!(plan.steps.len() as u32, wif.height().unwrap());
+    let total: usize = plan.legend.iter().map(|entry| entry.picks).sum();
+    assert_eq!(total, plan.steps.len());
+    let navy = plan
+        .legend
+        .iter()
+        .find(|e| e.color == Some(crate::ColorIndex(79)))
+        .unwrap();
+    assert_eq!(navy.yarn.as_deref(), Some("Navy 8/2 cotton"));
+    assert!(!plan.steps[0].shuttle_change);
+    for pair in plan.steps.windows(2) {
+        assert_eq!(pair[1].shuttle_change, pair[0].color != pair[1].color);
+    }
+    let text = plan.to_text(50);
+    assert!(text.starts_with("Legend\n"));
+    assert!(text.contains("-- change shuttle: color 79 (Navy 8/2 cotton)"));
+}
+
+#[test]
+fn duplicate_table_keys() {
+    use super::{DuplicateKeyPolicy, ParseOptions, ParseWarning};
+    use crate::{Shaft, Warp};
+    let s = include_str!("../sample.wif").replace("[THREADING]\n", "[THREADING]\n12=7\n");
+    let original = super::parse(include_str!("../sample.wif")).unwrap();
+    let original_shafts = original.threading.as_ref().unwrap()[&Warp(12)].clone();
+
+    let (wif, warnings) = super::parse_with_options(&s, &ParseOptions::default()).unwrap();
+    assert_eq!(wif.threading.unwrap()[&Warp(12)], original_shafts);
+    assert!(matches!(
+        &warnings[..],
+        [ParseWarning::DuplicateKey { section, key, discarded, .. }]
+            if section == "THREADING" && key == "12" && discarded == "7"
+    ));
+
+    let options = ParseOptions {
+        duplicate_keys: DuplicateKeyPolicy::FirstWins,
+        ..Default::default()
+    };
+    let (wif, _) = super::parse_with_options(&s, &options).unwrap();
+    assert_eq!(wif.threading.unwrap()[&Warp(12)], [Shaft(7)].into());
+
+    let options = ParseOptions {
+        duplicate_keys: DuplicateKeyPolicy::Error,
+        ..Default::default()
+    };
+    assert!(super::parse_with_options(&s, &options).is_err());
+}
+
+#[test]
+fn parse_progress_and_cancellation() {
+    use super::ParseOptions;
+    let s = include_str!("../sample.wif");
+    let mut reports = vec![];
+    let mut record = |fraction: f64| {
+        reports.push(fraction);
+        true
+    };
+    super::parse_with_progress(s, &ParseOptions::default(), &mut record).unwrap();
+    assert!(reports.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(reports.last(), Some(&1.0));
+
+    let mut cancel = |fraction: f64| fraction < 0.5;
+    let err = super::parse_with_progress(s, &ParseOptions::default(), &mut cancel).unwrap_err();
+    assert!(matches!(err, crate::WifError::Cancelled));
+}
+
+#[test]
+fn write_profiles() {
+    use super::{TargetProgram, WriteOptions};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let mut out = vec![];
+    wif.write_with_options(&mut out, &WriteOptions::profile(TargetProgram::PixeLoom))
+        .unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let headers: Vec<&str> = text.lines().filter(|l| l.starts_with('[')).collect();
+    assert_eq!(&headers[..3], ["[WIF]", "[CONTENTS]", "[COLOR PALETTE]"]);
+    assert!(text.contains("TREADLING=false"));
+    assert!(text.contains("\r\n\r\n[CONTENTS]\r\n"));
+    let reparsed = super::parse(&text).unwrap();
+    assert_eq!(reparsed.color_palette.as_ref().unwrap().range, (0, 255));
+    assert!(reparsed.treadling.is_none());
+    assert_eq!(reparsed.liftplan, wif.liftplan);
+    let (before, after) = (
+        wif.warp_color_u8(1).unwrap(),
+        reparsed.warp_color_u8(1).unwrap(),
+    );
+    assert!(before.iter().zip(after).all(|(&a, b)| a.abs_diff(b) <= 1));
+
+    let mut out = vec![];
+    wif.write_with_options(&mut out, &WriteOptions::profile(TargetProgram::Fiberworks))
+        .unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("Date=04/20/1997"));
+}
+
+#[test]
+fn color_range_needs_a_usable_palette() {
+    use super::WriteOptions;
+    let options = WriteOptions {
+        color_range: Some((0, 255)),
+        ..WriteOptions::default()
+    };
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+
+    // Without a palette, colors are taken as 0-999 and a palette is written for the new range
+    let mut unpaletted = wif.clone();
+    unpaletted.color_palette = None;
+    let mut out = vec![];
+    unpaletted.write_with_options(&mut out, &options).unwrap();
+    let reparsed = super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    let palette = reparsed.color_palette.as_ref().unwrap();
+    assert_eq!((palette.entries, palette.range), (82, (0, 255)));
+    let (before, after) = (
+        wif.warp_color_u8(1).unwrap(),
+        reparsed.warp_color_u8(1).unwrap(),
+    );
+    assert!(before.iter().zip(after).all(|(&a, b)| a.abs_diff(b) <= 1));
+
+    // A reversed range can't be rescaled from, so the colors are written as they are
+    let mut reversed = wif.clone();
+    reversed.color_palette.as_mut().unwrap().range = (999, 0);
+    let mut out = vec![];
+    reversed.write_with_options(&mut out, &options).unwrap();
+    let reparsed = super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    assert_eq!(reparsed.color_table, wif.color_table);
+}
+
+#[test]
+fn extracts_embedded_wif() {
+    let draft = include_str!("../sample.wif");
+    let quoted: String = draft.lines().map(|l| format!("> {l}\n")).collect();
+    let text =
+        format!("Hi all,\n\nHere's the draft I promised:\n\n{quoted}\nHappy weaving!\n-- Sam\n");
+    let (wif, range) = super::extract_and_parse(&text).unwrap();
+    assert!(text[range.clone()].starts_with("> [WIF]"));
+    assert!(text[range.clone()].ends_with("Entries=82"));
+    assert_eq!(wif.width(), Some(459));
+    assert!(super::extract_and_parse("no draft here").is_err());
+}
+
+#[test]
+fn ini_reader_keeps_comments_and_case() {
+    use super::ini::{Ini, Line};
+    let ini = Ini::read("; preamble\n[Weaving]\nShafts=4\n; a note\nshafts=8\n").unwrap();
+    assert_eq!(ini.preamble, vec!["; preamble"]);
+    let weaving = ini.section("WEAVING").unwrap();
+    assert_eq!(weaving.name, "Weaving");
+    assert_eq!(weaving.get("SHAFTS"), Some("8"));
+    assert!(matches!(weaving.lines()[1], Line::Comment(ref c) if c == "; a note"));
+    assert_eq!(ini.duplicates.len(), 1);
+    assert_eq!(ini.duplicates[0].first, "4");
+    assert!(Ini::read("[WIF\nVersion=1.1\n").is_err());
+}
+
+#[test]
+fn explain_color_follows_defaulting_chain() {
+    use super::ColorResolution;
+    use crate::{Warp, WarpOrWeft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    assert!(matches!(
+        wif.explain_color(WarpOrWeft::Warp, 2),
+        ColorResolution::Thread {
+            index: crate::ColorIndex(1),
+            ..
+        }
+    ));
+    assert!(matches!(
+        wif.explain_color(WarpOrWeft::Warp, 1),
+        ColorResolution::SectionDefault {
+            index: crate::ColorIndex(3),
+            missing_thread_index: None,
+            ..
+        }
+    ));
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(1), crate::ColorIndex(9999));
+    let resolution = wif.explain_color(WarpOrWeft::Warp, 1);
+    assert!(matches!(
+        resolution,
+        ColorResolution::SectionDefault {
+            missing_thread_index: Some(crate::ColorIndex(9999)),
+            ..
+        }
+    ));
+    assert_eq!(resolution.color(), wif.warp_color(1));
+    wif.warp.as_mut().unwrap().color = None;
+    assert_eq!(wif.warp_color(1), None);
+}
+
+#[cfg(feature = "preserve-layout")]
+#[test]
+fn write_preserving_round_trips() {
+    use crate::Warp;
+    let source = include_str!("../sample.wif").replace('\n', "\r\n");
+    let mut wif = super::parse(&source).unwrap();
+    let mut out = vec![];
+    wif.write_preserving(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), source);
+
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(2), crate::ColorIndex(5));
+    wif.text.as_mut().unwrap().author = Some("Someone".into());
+    let mut out = vec![];
+    wif.write_preserving(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let expected = source
+        .replace("[WARP COLORS]\r\n2=1\r\n", "[WARP COLORS]\r\n2=5\r\n")
+        .replace("12:55\r\n", "12:55\r\nAuthor=Someone\r\n");
+    assert_eq!(text, expected);
+    assert_eq!(
+        super::parse(&text).unwrap().warp_color_idx(Warp(2)),
+        Some(crate::ColorIndex(5))
+    );
+}
+
+#[test]
+fn canonicalize_renumbers_and_trims() {
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let table = wif.color_table.as_mut().unwrap();
+    let removed = table.remove(&crate::ColorIndex(2)).unwrap();
+    table.insert(crate::ColorIndex(500), removed);
+    let dangling = crate::ColorIndex(2);
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(crate::Warp(1), dangling);
+    wif.text.as_mut().unwrap().title = Some("  Shadow weave ".into());
+    wif.contents.entries.push(("NOTES".into(), false));
+    wif.contents.pinned = true;
+    let warp: Vec<_> = (1..=wif.width().unwrap())
+        .map(|w| wif.warp_color(w))
+        .collect();
+    let weft: Vec<_> = (1..=wif.height().unwrap())
+        .map(|w| wif.weft_color(w))
+        .collect();
+
+    wif.canonicalize();
+    let table = wif.color_table.as_ref().unwrap();
+    assert!(table.keys().map(|idx| idx.0).eq(1..=table.len() as u32));
+    let idx = wif.warp_colors.as_ref().unwrap()[&crate::Warp(1)];
+    assert!(!table.contains_key(&idx));
+    assert_eq!(idx.0 as usize, table.len() + 1);
+    assert_eq!(wif.color_palette.as_ref().unwrap().entries, idx.0 as usize);
+    assert_eq!(
+        wif.warp.as_ref().unwrap().color.unwrap().idx,
+        crate::ColorIndex(2)
+    );
+    assert!((1..=wif.width().unwrap())
+        .map(|w| wif.warp_color(w))
+        .eq(warp));
+    assert!((1..=wif.height().unwrap())
+        .map(|w| wif.weft_color(w))
+        .eq(weft));
+    assert_eq!(
+        wif.text.as_ref().unwrap().title.as_deref(),
+        Some("Shadow weave")
+    );
+    assert!(!wif.contents.pinned);
+    assert!(wif.contents.entries.iter().all(|(_, present)| *present));
+    assert!(!wif.contents.declares("NOTES"));
+}
+
+#[test]
+fn write_filtered_keeps_contents_consistent() {
+    use super::{sections, SectionFilter};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let mut out = vec![];
+    let filter = SectionFilter::only(&[sections::THREADING, sections::TIEUP, sections::TREADLING]);
+    wif.write_filtered(&mut out, filter).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(!text.contains("[TEXT]"));
+    assert!(!text.contains("TEXT="));
+    let reparsed = super::parse(&text).unwrap();
+    assert!(reparsed.text.is_none());
+    assert_eq!(reparsed.treadling, wif.treadling);
+    assert_eq!(reparsed.contents.entries.len(), 3);
+
+    let mut out = vec![];
+    let filter = SectionFilter::all().without(sections::TEXT);
+    wif.write_filtered(&mut out, filter).unwrap();
+    let reparsed = super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+    assert!(reparsed.text.is_none());
+    assert!(reparsed.color_table.is_some());
+}
+
+#[test]
+fn revision_tracks_changes() {
+    use crate::{Color, Warp};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let revision = wif.revision();
+    assert_eq!(wif.clone().revision(), revision);
+    assert!(!wif.is_modified_since(revision));
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(1), crate::ColorIndex(2));
+    assert!(wif.is_modified_since(revision));
+    let revision = wif.revision();
+    wif.find_or_add_color(Color {
+        red: 1,
+        green: 2,
+        blue: 3,
+    });
+    assert!(wif.is_modified_since(revision));
+    let table = wif.color_table.as_mut().unwrap();
+    let first = *table.keys().next().unwrap();
+    table.remove(&first);
+    let added = wif.find_or_add_color(Color {
+        red: 4,
+        green: 5,
+        blue: 6,
+    });
+    assert_eq!(wif.color_palette.unwrap().entries, added.0 as usize);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn gz_round_trip() {
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let compressed = wif.write_gz(vec![]).unwrap();
+    let path = std::env::temp_dir().join(format!("wif-gz-test-{}.wif.gz", std::process::id()));
+    std::fs::write(&path, compressed).unwrap();
+    let reread = super::Wif::from_gz_path(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(reread.unwrap().revision(), wif.revision());
+}
+
+#[test]
+fn stamp_header_records_provenance() {
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    wif.stamp_header("wif-rs", "0.1.0");
+    assert!(wif.provenance.is_empty());
+    assert_eq!(wif.wif_header.source_program, "wif-rs");
+
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    wif.stamp_header_keeping_history("wif-rs", "0.1.0");
+    assert_eq!(wif.wif_header.source_version.as_deref(), Some("0.1.0"));
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("[PRIVATE WIF-RS PROVENANCE]\n1=April 20, 1997|Fiberworks PCW|4.2\n"));
+    assert!(!text.contains("PRIVATE WIF-RS PROVENANCE=true"));
+    let reparsed = super::parse(&text).unwrap();
+    assert_eq!(reparsed.provenance, wif.provenance);
+    assert_eq!(reparsed.provenance[0].source_program, "Fiberworks PCW");
+}
+
+#[test]
+fn lenient_tables_skip_bad_rows() {
+    use super::{ParseOptions, ParseWarning};
+    use crate::Warp;
+    let s = include_str!("../sample.wif").replace("[THREADING]\n", "[THREADING]\nx=1\n");
+    let s = s.replace("\n12=3\n", "\n12=3,x\n");
+    assert!(super::parse(&s).is_err());
+    let options = ParseOptions {
+        lenient_tables: true,
+        ..Default::default()
+    };
+    let (wif, warnings) = super::parse_with_options(&s, &options).unwrap();
+    let bad: Vec<_> = warnings
+        .iter()
+        .filter_map(|w| match w {
+            ParseWarning::BadRow {
+                section,
+                key,
+                value,
+                ..
+            } => Some((section.as_str(), key.as_str(), value.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert!(bad.contains(&("THREADING", "x", "1")));
+    assert_eq!(bad.len(), 2, "{bad:?}");
+    assert!(bad.contains(&("THREADING", "12", "3,x")));
+    let threading = wif.threading.unwrap();
+    assert!(threading.contains_key(&Warp(11)) && !threading.contains_key(&Warp(12)));
+}
+
+#[test]
+fn tieup_matrix_round_trip() {
+    use crate::{Shaft, Tieup, TieupMatrix, Treadle};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let tieup = wif.tieup.as_ref().unwrap();
+    let (shafts, treadles) = (wif.shafts().unwrap(), wif.treadles().unwrap());
+    let matrix = tieup.to_matrix(shafts, treadles);
+    assert_eq!(matrix.len() as u32, shafts);
+    assert_eq!(&Tieup::from_matrix(&matrix), tieup);
+
+    // Row is shaft, column is treadle
+    let grid = vec![vec![true, false], vec![false, false], vec![true, true]];
+    let tieup = Tieup::from_matrix(&grid);
+    assert_eq!(tieup[&Treadle(1)], [Shaft(1), Shaft(3)].into());
+    assert_eq!(tieup[&Treadle(2)], [Shaft(3)].into());
+    let sinkers = Tieup::from_sinker_matrix(&grid);
+    assert_eq!(sinkers[&Treadle(2)], [Shaft(1), Shaft(2)].into());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_round_trip() {
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let json = wif.to_json();
+    assert!(json.contains("\"formatVersion\": 1"));
+    assert!(json.contains("\"threading\": {\n    \"1\": ["));
+
+    let mut expected = vec![];
+    wif.write(&mut expected).unwrap();
+    let mut out = vec![];
+    super::Wif::from_json(&json)
+        .unwrap()
+        .write(&mut out)
+        .unwrap();
+    assert_eq!(String::from_utf8(out), String::from_utf8(expected));
+
+    let newer = json.replace("\"formatVersion\": 1", "\"formatVersion\": 2");
+    assert!(matches!(
+        super::Wif::from_json(&newer),
+        Err(crate::WifError::UnsupportedJsonFormat { version: 2, .. })
+    ));
+}
+
+#[test]
+fn log_cabin_flips_parity_between_blocks() {
+    use crate::{generators::log_cabin, Warp, WarpOrWeft, Weft};
+    let order = log_cabin(&[4, 4, 3], (crate::ColorIndex(1), crate::ColorIndex(2)));
+    let order_idxs: Vec<u32> = order.warp.iter().map(|idx| idx.0).collect();
+    assert_eq!(order_idxs, [1, 2, 1, 2, 2, 1, 2, 1, 1, 2, 1]);
+    assert_eq!(order.weft, order.warp);
+
+    let wif = order.plain_weave();
+    assert_eq!((wif.width(), wif.height()), (Some(11), Some(11)));
+    assert_eq!(wif.warp_color_idx(Warp(5)), Some(crate::ColorIndex(2)));
+    // Plain weave: ends and picks alternate on top
+    assert_eq!(wif.warp_or_weft(1, 1), Some(WarpOrWeft::Warp));
+    assert_eq!(wif.warp_or_weft(2, 1), Some(WarpOrWeft::Weft));
+    assert_eq!(
+        wif.weft_colors.as_ref().unwrap()[&Weft(11)],
+        crate::ColorIndex(1)
+    );
+
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+}
+
+#[test]
+fn write_as_1_0_drops_zoom_data() {
+    use super::{sections, VersionLoss, WifVersion};
+    use crate::Warp;
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    wif.warp_spacing_zoom = Some([(Warp(1), 3)].into());
+    wif.weft.as_mut().unwrap().thickness_zoom = Some(2);
+
+    let mut out = vec![];
+    let lost = wif.write_as_version(WifVersion::V1_0, &mut out).unwrap();
+    assert_eq!(
+        lost,
+        [
+            VersionLoss::Section(sections::WARP_SPACING_ZOOM),
+            VersionLoss::Field {
+                section: sections::WEFT,
+                field: "Thickness Zoom"
+            },
+        ]
+    );
+    let text = String::from_utf8(out).unwrap();
+    assert!(!text.contains("ZOOM") && !text.contains("Zoom"));
+    let reread = super::parse(&text).unwrap();
+    assert_eq!(reread.spec_version(), WifVersion::V1_0);
+
+    let mut out = vec![];
+    let lost = wif.write_as_version(WifVersion::V1_1, &mut out).unwrap();
+    assert!(lost.is_empty());
+}
+
+#[test]
+fn new_draft_writes_and_parses() {
+    let wif = super::Wif::new(8, 10);
+    assert_eq!((wif.shafts(), wif.treadles()), (Some(8), Some(10)));
+    assert_eq!((wif.width(), wif.height()), (Some(0), Some(0)));
+    assert_eq!(wif.wif_header.source_program, env!("CARGO_PKG_NAME"));
+
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("[WARP]") && text.contains("Threads=0"));
+    let reread = super::parse(&text).unwrap();
+    assert_eq!(reread.shafts(), Some(8));
+}
+
+#[test]
+fn units_parse_and_convert() {
+    use crate::{wifparse::WifParse, Length, Units};
+    assert_eq!(Units::parse("centimetres").unwrap(), Units::Centimeters);
+    assert_eq!(Units::parse(" INCHES").unwrap(), Units::Inches);
+    assert_eq!(Units::Decipoints.unparse().as_deref(), Some("Decipoints"));
+    let cubits = Units::parse("Cubits").unwrap();
+    assert_eq!(cubits, Units::Other("Cubits".into()));
+    assert_eq!(cubits.unparse().as_deref(), Some("Cubits"));
+
+    let length = Length::new(720., Units::Decipoints);
+    assert!((length.inches().unwrap() - 1.).abs() < 1e-9);
+    assert!((length.centimeters().unwrap() - 2.54).abs() < 1e-9);
+    assert_eq!(Length::new(2., cubits).inches(), None);
+
+    let text = include_str!("../sample.wif").replacen("Units=centimeters", "Units=Cubits", 1);
+    let wif = super::parse(&text).unwrap();
+    assert_eq!(
+        wif.warp.as_ref().unwrap().units,
+        Some(Units::Other("Cubits".into()))
+    );
+    assert_eq!(wif.fabric_dimensions().warp, None);
+    let issue = wif.validate().into_iter().next().unwrap();
+    assert_eq!(issue.code, super::IssueCode::UnknownUnits);
+    assert_eq!(issue.severity, super::Severity::Stylistic);
+
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let warp = wif.warp.as_ref().unwrap();
+    assert_eq!(warp.units, Some(Units::Centimeters));
+    assert_eq!(
+        warp.spacing_length(),
+        Some(Length::new(warp.spacing.unwrap(), Units::Centimeters))
+    );
+}
+
+#[test]
+fn setters_keep_counts_and_liftplan_in_sync() {
+    use crate::{Shaft, Tieup, TieupMatrix, Treadle, Warp, Weft};
+    let mut wif = super::Wif::new(2, 2);
+    for shaft in [1, 2, 3, 4] {
+        wif.push_warp_end([shaft], Some(crate::ColorIndex(1)));
+    }
+    assert_eq!((wif.width(), wif.shafts()), (Some(4), Some(4)));
+    assert_eq!(
+        wif.warp_colors.as_ref().unwrap()[&Warp(4)],
+        crate::ColorIndex(1)
+    );
+
+    wif.set_tieup(Tieup::from_matrix(&[
+        vec![true, false],
+        vec![false, true],
+        vec![true, false],
+        vec![false, true],
+    ]));
+    wif.push_weft_pick([1], None);
+    wif.push_weft_pick([2], None);
+    assert_eq!(wif.height(), Some(2));
+    let liftplan = wif.liftplan.as_ref().unwrap();
+    assert_eq!(liftplan[&Weft(2)], [Shaft(2), Shaft(4)].into());
+
+    // A liftplan the treadling can't produce drops the treadling and tieup
+    wif.contents.pinned = true;
+    wif.set_liftplan([(Weft(1), [Shaft(1)].into())].into());
+    assert_eq!((wif.treadling.as_ref(), wif.tieup.as_ref()), (None, None));
+    assert_eq!(wif.height(), Some(1));
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+
+    wif.set_tieup(Tieup::from_matrix(&[
+        vec![true, false],
+        vec![false, true],
+        vec![true, false],
+        vec![false, true],
+    ]));
+    wif.set_treadling([(Weft(3), [Treadle(1)].into())].into());
+    assert_eq!(wif.height(), Some(3));
+    assert_eq!(wif.liftplan.as_ref().unwrap().len(), 1);
+    assert!(wif.contents.declares("TREADLING"));
+    let mut out = vec![];
+    wif.write(&mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("TREADLING=true"));
+
+    // A pick no treadle makes moves the draft onto its liftplan.
+    wif.push_lift([2, 3], None);
+    assert_eq!((wif.treadling.as_ref(), wif.tieup.as_ref()), (None, None));
+    assert!(!wif.contents.declares("TREADLING"));
+    assert!(!wif.contents.declares("TIEUP"));
+    let liftplan = wif.liftplan.as_ref().unwrap();
+    assert_eq!(liftplan[&Weft(3)], [Shaft(1), Shaft(3)].into());
+    assert_eq!(liftplan[&Weft(4)], [Shaft(2), Shaft(3)].into());
+}
+
+#[test]
+fn matrix_views_round_trip() {
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let threading = wif.to_threading_matrix();
+    let shafts = wif.shafts().unwrap() as usize;
+    assert_eq!(threading.len(), shafts);
+    assert!(threading
+        .iter()
+        .all(|row| row.len() == wif.width().unwrap() as usize));
+    let liftplan = wif.to_liftplan_matrix();
+    assert_eq!(liftplan.len(), wif.height().unwrap() as usize);
+    assert_eq!(wif.to_tieup_matrix().len(), shafts);
+
+    let mut copy = super::Wif::new(0, 0);
+    copy.set_threading_matrix(&threading);
+    copy.set_tieup_matrix(&wif.to_tieup_matrix());
+    copy.set_treadling_matrix(&wif.to_treadling_matrix());
+    assert_eq!(copy.threading, wif.threading);
+    assert_eq!(copy.treadling, wif.treadling);
+    assert_eq!(copy.liftplan, wif.liftplan);
+    assert_eq!(copy.to_liftplan_matrix(), liftplan);
+
+    // Empty rows and columns at the end still count as picks and ends
+    let mut copy = super::Wif::new(0, 0);
+    let mut threading = threading.clone();
+    threading.iter_mut().for_each(|row| row.push(false));
+    copy.set_threading_matrix(&threading);
+    assert_eq!(copy.width(), Some(wif.width().unwrap() + 1));
+    let mut liftplan = liftplan.clone();
+    liftplan.push(vec![false; shafts]);
+    copy.set_liftplan_matrix(&liftplan);
+    assert_eq!(copy.height(), Some(wif.height().unwrap() + 1));
+    assert_eq!(copy.to_liftplan_matrix(), liftplan);
+}
+
+#[test]
+fn cell_resolves_thread_on_top() {
+    use crate::{Length, Units, Warp, WarpOrWeft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    wif.warp_thickness = Some([(Warp(1), 0.5)].into());
+    let pick = (1..=wif.height().unwrap())
+        .find(|&pick| wif.warp_or_weft(1, pick) == Some(WarpOrWeft::Warp))
+        .unwrap();
+    let cell = wif.cell(1, pick).unwrap();
+    assert_eq!(cell.on_top, WarpOrWeft::Warp);
+    assert_eq!(cell.color, wif.warp_color_u8(1));
+    assert_eq!(cell.thickness, Some(Length::new(0.5, Units::Centimeters)));
+    assert_eq!(cell.spacing, Some(Length::new(0.212, Units::Centimeters)));
+    assert_eq!(cell.symbol, None);
+
+    let pick = (1..=wif.height().unwrap())
+        .find(|&pick| wif.warp_or_weft(2, pick) == Some(WarpOrWeft::Weft))
+        .unwrap();
+    let cell = wif.cell(2, pick).unwrap();
+    assert_eq!(cell.color, wif.weft_color_u8(pick));
+    assert_eq!(cell.thickness, Some(Length::new(0.212, Units::Centimeters)));
+
+    wif.weft.as_mut().unwrap().thickness_zoom = Some(2);
+    let cell = wif.cell(2, pick).unwrap();
+    assert_eq!(cell.thickness, Some(Length::new(0.424, Units::Centimeters)));
+}
+
+#[test]
+fn thread_iterators_resolve_defaults() {
+    use crate::{Length, Units, Warp};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    wif.warp_spacing = Some([(Warp(2), 0.3)].into());
+    let ends: Vec<_> = wif.warp_ends().collect();
+    assert_eq!(ends.len() as u32, wif.width().unwrap());
+    assert_eq!(ends[0].shafts, wif.threading.as_ref().unwrap()[&Warp(1)]);
+    assert_eq!(ends[0].color, wif.warp_color(1));
+    assert_eq!(
+        ends[0].spacing,
+        Some(Length::new(0.212, Units::Centimeters))
+    );
+    assert_eq!(ends[1].spacing, Some(Length::new(0.3, Units::Centimeters)));
+    wif.warp_spacing_zoom = Some([(Warp(2), 3)].into());
+    let spacing = wif.warp_ends().nth(1).unwrap().spacing.unwrap();
+    assert!((spacing.value - 0.9).abs() < 1e-9);
+
+    let picks: Vec<_> = wif.weft_picks().collect();
+    assert_eq!(picks.len() as u32, wif.height().unwrap());
+    assert_eq!(
+        picks[0].lift,
+        wif.liftplan.as_ref().unwrap()[&picks[0].index]
+    );
+    assert_eq!(picks[4].color, wif.weft_color(5));
+}
+
+#[test]
+fn thickness_and_spacing_apply_zoom() {
+    use crate::{Length, Units, Warp, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let warp = wif.warp.as_mut().unwrap();
+    warp.units = Some(Units::Inches);
+    warp.thickness = Some(0.01);
+    warp.thickness_zoom = Some(2);
+    wif.warp_thickness = Some([(Warp(3), 0.25)].into());
+    wif.warp_thickness_zoom = Some([(Warp(3), 3)].into());
+    assert_eq!(
+        wif.warp_thickness_at(1),
+        Some(Length::new(0.02, Units::Inches))
+    );
+    assert_eq!(
+        wif.warp_thickness_at(3),
+        Some(Length::new(0.75, Units::Inches))
+    );
+
+    let weft = wif.weft.as_mut().unwrap();
+    weft.spacing = Some(0.5);
+    weft.spacing_zoom = None;
+    weft.units = Some(Units::Centimeters);
+    wif.weft_spacing = None;
+    wif.weft_spacing_zoom = Some([(Weft(2), 4)].into());
+    assert_eq!(
+        wif.weft_spacing_at(1),
+        Some(Length::new(0.5, Units::Centimeters))
+    );
+    assert_eq!(
+        wif.weft_spacing_at(2),
+        Some(Length::new(2.0, Units::Centimeters))
+    );
+}
+
+#[test]
+fn table_views_index_with_empty_fallback() {
+    use crate::{Warp, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let threading = wif.threading_view();
+    assert_eq!(
+        threading[Warp(1)],
+        wif.threading.as_ref().unwrap()[&Warp(1)]
+    );
+    assert!(threading[Warp(100_000)].is_empty());
+    assert_eq!(
+        wif.liftplan_view()[Weft(1)],
+        wif.liftplan.as_ref().unwrap()[&Weft(1)]
+    );
+
+    wif.treadling = None;
+    let treadling = wif.treadling_view();
+    assert!(!treadling.is_present());
+    assert!(treadling[Weft(1)].is_empty());
+    assert_eq!(treadling.get(Weft(1)), None);
+}
+
+#[test]
+fn insert_and_remove_renumber_every_table() {
+    use crate::{Shaft, Warp, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let width = wif.width().unwrap();
+    let height = wif.height().unwrap();
+    let end_5 = wif.threading_view()[Warp(5)].clone();
+    let color_5 = wif.warp_colors.as_ref().unwrap().get(&Warp(5)).copied();
+    let original = wif.clone();
+
+    wif.insert_warp_end(3, [2]);
+    assert_eq!(wif.width(), Some(width + 1));
+    assert_eq!(wif.threading_view()[Warp(3)], [Shaft(2)].into());
+    assert_eq!(wif.threading_view()[Warp(6)], end_5);
+    assert_eq!(
+        wif.warp_colors.as_ref().unwrap().get(&Warp(6)).copied(),
+        color_5
+    );
+    wif.remove_warp_end(3);
+    assert_eq!(wif.threading, original.threading);
+    assert_eq!(wif.warp_colors, original.warp_colors);
+    assert_eq!(wif.width(), Some(width));
+
+    let pick_2 = wif.liftplan_view()[Weft(2)].clone();
+    let spacing_3 = wif.weft_spacing.as_ref().unwrap().get(&Weft(3)).copied();
+    wif.remove_weft_pick(1);
+    assert_eq!(wif.height(), Some(height - 1));
+    assert_eq!(wif.liftplan_view()[Weft(1)], pick_2);
+    assert_eq!(
+        wif.weft_spacing.as_ref().unwrap().get(&Weft(2)).copied(),
+        spacing_3
+    );
+
+    wif.remove_weft_pick(height + 10);
+    assert_eq!(wif.height(), Some(height - 1));
+}
+
+#[test]
+fn crop_keeps_region_and_prunes() {
+    use crate::{Warp, Weft};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let cropped = wif.crop(5..=12, 3..11);
+    assert_eq!(cropped.width(), Some(8));
+    assert_eq!(cropped.height(), Some(8));
+    for end in 1..=8 {
+        for pick in 1..=8 {
+            assert_eq!(
+                cropped.warp_or_weft(Warp(end), Weft(pick)),
+                wif.warp_or_weft(Warp(end + 4), Weft(pick + 2))
+            );
+            assert_eq!(cropped.warp_color(end), wif.warp_color(end + 4));
+        }
+    }
+    let threaded: std::collections::BTreeSet<_> = cropped
+        .threading
+        .as_ref()
+        .unwrap()
+        .values()
+        .flatten()
+        .collect();
+    assert_eq!(cropped.shafts(), Some(threaded.len() as u32));
+    assert!(threaded.iter().all(|s| s.0 <= threaded.len() as u32));
+    let table = cropped.color_table.as_ref().unwrap();
+    assert!(table.len() <= wif.color_table.as_ref().unwrap().len());
+    let highest = table.keys().next_back().unwrap().0 as usize;
+    assert_eq!(cropped.color_palette.as_ref().unwrap().entries, highest);
+}
+
+#[test]
+fn structural_equality_ignores_numbering_and_phase() {
+    use crate::{Color, WifBuilder};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let mut recolored = wif.clone();
+    recolored.canonicalize();
+    assert!(wif.structurally_equal(&recolored));
+
+    let rgb = |red, green, blue| Color { red, green, blue };
+    let (red, green, blue) = (rgb(999, 0, 0), rgb(0, 999, 0), rgb(0, 0, 999));
+    let threading = [1, 2, 3, 4, 3, 2];
+    let lifts = [[1, 2], [2, 3], [3, 4], [4, 1], [3, 4], [2, 3]];
+    let warp = [red, blue, blue, green, green, green];
+    let weft = [blue, red, red, red, green, red];
+    let draft = |threading: &[u32], lifts: &[[u32; 2]], warp: &[Color], weft: &[Color]| {
+        WifBuilder::new()
+            .shafts(4)
+            .threading_from(threading.to_vec())
+            .liftplan(lifts.to_vec())
+            .warp_colors(warp.to_vec())
+            .weft_colors(weft.to_vec())
+            .build()
+            .unwrap()
+    };
+    fn rotated<T: Clone>(items: &[T], n: usize) -> Vec<T> {
+        let mut items = items.to_vec();
+        items.rotate_left(n);
+        items
+    }
+    let original = draft(&threading, &lifts, &warp, &weft);
+
+    // The same cloth started two ends and three picks in, with the shafts numbered backwards
+    // and the colors added to the table in a different order.
+    let flip = |shaft: u32| 5 - shaft;
+    let shifted = draft(
+        &rotated(&threading.map(flip), 2),
+        &rotated(&lifts.map(|lift| lift.map(flip)), 3),
+        &rotated(&warp, 2),
+        &rotated(&weft, 3),
+    );
+    assert_ne!(shifted.threading, original.threading);
+    assert_ne!(shifted.color_table, original.color_table);
+    assert!(shifted.structurally_equal(&original));
+    assert!(original.structurally_equal(&shifted));
+
+    // Shifting the warp colors without the threading moves the stripes across the weave.
+    let slipped = draft(&threading, &lifts, &rotated(&warp, 1), &weft);
+    assert!(!slipped.structurally_equal(&original));
+
+    let mut changed = wif.clone();
+    changed
+        .liftplan
+        .as_mut()
+        .unwrap()
+        .insert(crate::Weft(1), Default::default());
+    assert!(!wif.structurally_equal(&changed));
+    let score = wif.similarity(&changed);
+    assert!(score > 0.5 && score < 1., "{score}");
+}
+
+#[test]
+fn fingerprint_ignores_shaft_order_and_color() {
+    use crate::{Shaft, Weft};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let swap = |shaft: &Shaft| match shaft.0 {
+        1 => Shaft(2),
+        2 => Shaft(1),
+        n => Shaft(n),
+    };
+    let mut permuted = wif.clone();
+    for shafts in permuted.threading.iter_mut().flat_map(|t| t.values_mut()) {
+        *shafts = shafts.iter().map(swap).collect();
+    }
+    for shafts in permuted.liftplan.iter_mut().flat_map(|l| l.values_mut()) {
+        *shafts = shafts.iter().map(swap).collect();
+    }
+    permuted.tieup = None;
+    permuted.treadling = None;
+    permuted.warp_colors = None;
+    assert_eq!(wif.fingerprint(), permuted.fingerprint());
+
+    let mut changed = wif.clone();
+    changed
+        .liftplan
+        .as_mut()
+        .unwrap()
+        .insert(Weft(1), Default::default());
+    assert_ne!(wif.fingerprint(), changed.fingerprint());
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_drafts_round_trip() {
+    use arbitrary::{Arbitrary, Unstructured};
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..50 {
+        let bytes: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let wif = super::Wif::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert_eq!(wif.validate(), vec![]);
+        let mut out = vec![];
+        wif.write(&mut out).unwrap();
+        let parsed = super::parse(std::str::from_utf8(&out).unwrap()).unwrap();
+        assert!(crate::diff(&wif, &parsed).is_empty());
+        assert!(parsed.structurally_equal(&wif));
+        assert!(super::Wif::from(crate::Draft::from(&parsed)).structurally_equal(&wif));
+    }
+}
+
+#[test]
+fn validate_reports_dangling_references() {
+    use super::{IssueCode, Severity};
+    use crate::{ColorIndex, Shaft, Treadle, Warp, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    assert_eq!(wif.validate(), vec![]);
+
+    let shafts = wif.shafts().unwrap();
+    let ends = wif.width().unwrap();
+    wif.threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(3), [Shaft(shafts + 1)].into());
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(ends + 1), ColorIndex(1));
+    wif.weft_colors
+        .as_mut()
+        .unwrap()
+        .insert(Weft(2), ColorIndex(9999));
+    wif.treadling
+        .as_mut()
+        .unwrap()
+        .insert(Weft(1), [Treadle(1), Treadle(99)].into());
+    wif.liftplan
+        .as_mut()
+        .unwrap()
+        .insert(Weft(4), Default::default());
+    wif.color_palette.as_mut().unwrap().entries += 1;
+
+    let issues = wif.validate();
+    let found: Vec<_> = issues.iter().map(|i| (i.code, i.key)).collect();
+    assert!(found.contains(&(IssueCode::ShaftOutOfRange, Some(3))));
+    assert!(found.contains(&(IssueCode::ThreadOutOfRange, Some(ends + 1))));
+    assert!(found.contains(&(IssueCode::MissingColor, Some(2))));
+    assert!(found.contains(&(IssueCode::TreadleOutOfRange, Some(1))));
+    assert!(found.contains(&(IssueCode::UntiedTreadle, Some(99))));
+    assert!(found.contains(&(IssueCode::LiftplanMismatch, Some(4))));
+    assert!(found.contains(&(IssueCode::PaletteSizeMismatch, None)));
+    let palette = issues
+        .iter()
+        .find(|i| i.code == IssueCode::PaletteSizeMismatch)
+        .unwrap();
+    assert_eq!(palette.severity, Severity::Stylistic);
+    assert_eq!(
+        palette.to_string(),
+        "stylistic palette-size-mismatch [COLOR PALETTE]: declares 83 entries, but the table goes up to 82"
+    );
+}
+
+fn defective() -> super::Wif {
+    use crate::{ColorIndex, Shaft, Warp, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let shafts = wif.shafts().unwrap();
+    let ends = wif.width().unwrap();
+    wif.threading
+        .as_mut()
+        .unwrap()
+        .insert(Warp(3), [Shaft(1), Shaft(shafts + 1)].into());
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(ends + 2), ColorIndex(1));
+    wif.weft_colors
+        .as_mut()
+        .unwrap()
+        .insert(Weft(2), ColorIndex(9999));
+    wif.liftplan
+        .as_mut()
+        .unwrap()
+        .insert(Weft(4), Default::default());
+    wif.color_palette.as_mut().unwrap().entries += 1;
+    wif
+}
+
+#[test]
+fn repair_grows_counts_and_fixes_references() {
+    use super::{IssueCode, RepairOptions};
+    let mut wif = defective();
+    let (shafts, ends) = (wif.shafts().unwrap(), wif.width().unwrap());
+    let report = wif.repair(RepairOptions::default());
+    assert_eq!(wif.validate(), vec![]);
+    assert_eq!(wif.shafts(), Some(shafts + 1));
+    assert_eq!(wif.width(), Some(ends + 2));
+    let codes: Vec<_> = report.repairs.iter().map(|r| (r.code, r.key)).collect();
+    assert_eq!(
+        codes,
+        [
+            (IssueCode::ShaftOutOfRange, None),
+            (IssueCode::ThreadOutOfRange, None),
+            (IssueCode::MissingColor, Some(2)),
+            (IssueCode::LiftplanMismatch, Some(4)),
+            (IssueCode::PaletteSizeMismatch, None),
+        ]
+    );
+    assert_eq!(
+        report.repairs[1].to_string(),
+        format!("[WARP]: Threads raised from {ends} to {}", ends + 2)
+    );
+    assert!(wif.repair(RepairOptions::default()).is_empty());
+
+    // A table with gaps needs entries up to its highest index, not one per color.
+    let color = wif.color_table.as_ref().unwrap()[&crate::ColorIndex(1)];
+    wif.color_table
+        .as_mut()
+        .unwrap()
+        .insert(crate::ColorIndex(90), color);
+    assert_eq!(wif.repair(RepairOptions::default()).repairs.len(), 1);
+    assert_eq!(wif.color_palette.as_ref().unwrap().entries, 90);
+    assert_eq!(wif.validate(), vec![]);
+}
+
+#[test]
+fn repair_can_drop_what_is_out_of_range() {
+    use super::{IssueCode, RepairOptions};
+    use crate::{Shaft, Warp};
+    let mut wif = defective();
+    let (shafts, ends) = (wif.shafts().unwrap(), wif.width().unwrap());
+    let report = wif.repair(RepairOptions {
+        grow_counts: false,
+        ..Default::default()
+    });
+    assert_eq!(wif.validate(), vec![]);
+    assert_eq!(wif.shafts(), Some(shafts));
+    assert_eq!(wif.width(), Some(ends));
+    assert_eq!(wif.threading.as_ref().unwrap()[&Warp(3)], [Shaft(1)].into());
+    assert!(report
+        .repairs
+        .iter()
+        .any(|r| r.code == IssueCode::ThreadOutOfRange && r.key == Some(ends + 2)));
+}
+
+#[test]
+fn prune_unused_renumbers_what_is_left() {
+    use crate::{Color, ColorIndex, Shaft, Treadle, Warp};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    // Move every end on shaft 2 to shaft 3, leaving shaft 2 empty.
+    for shafts in wif.threading.as_mut().unwrap().values_mut() {
+        if shafts.remove(&Shaft(2)) {
+            shafts.insert(Shaft(3));
+        }
+    }
+    let shafts = wif.shafts().unwrap();
+    let treadles = wif.treadles().unwrap();
+    wif.weaving.as_mut().unwrap().treadles = treadles + 1;
+    let unused = wif.find_or_add_color(Color {
+        red: 1,
+        green: 2,
+        blue: 3,
+    });
+    let before = wif.warp_color(Warp(1)).unwrap();
+
+    let pruned = wif.prune_unused();
+    assert_eq!(pruned.shafts, [Shaft(2)]);
+    assert_eq!(pruned.treadles, [Treadle(treadles + 1)]);
+    assert!(pruned.colors.contains(&unused));
+    assert_eq!(wif.shafts(), Some(shafts - 1));
+    assert_eq!(wif.treadles(), Some(treadles));
+    assert_eq!(wif.validate(), vec![]);
+    assert_eq!(wif.warp_color(Warp(1)), Some(before));
+    let table = wif.color_table.as_ref().unwrap();
+    assert!(table
+        .keys()
+        .copied()
+        .eq((1..=table.len() as u32).map(ColorIndex)));
+    assert!(wif.prune_unused().is_empty());
+}
+
+#[test]
+fn find_and_fill_gaps() {
+    use super::sections;
+    use crate::{ColorIndex, Warp, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    // The sample leaves every other thread's color and spacing to the defaults.
+    let gaps = wif.find_gaps();
+    assert_eq!(
+        gaps.get(sections::WEFT_COLORS).unwrap().missing[..2],
+        [2, 4]
+    );
+    assert!(gaps.get(sections::THREADING).is_none());
+
+    for end in [41, 42, 43, 44, 50] {
+        wif.threading.as_mut().unwrap().remove(&Warp(end));
+    }
+    let gaps = wif.find_gaps();
+    let threading = gaps.get(sections::THREADING).unwrap();
+    assert_eq!(threading.to_string(), "[THREADING] missing 41-44, 50");
+
+    let filled = wif.fill_gaps();
+    assert_eq!(
+        filled.get(sections::WARP_COLORS),
+        gaps.get(sections::WARP_COLORS)
+    );
+    assert!(filled.get(sections::THREADING).is_none());
+    assert_eq!(wif.warp_colors.as_ref().unwrap()[&Warp(1)], ColorIndex(3));
+    assert_eq!(wif.weft_colors.as_ref().unwrap()[&Weft(2)], ColorIndex(6));
+    assert_eq!(wif.find_gaps().tables, std::slice::from_ref(threading));
+}
+
+#[test]
+fn colors_outside_the_palette_range() {
+    use super::{IssueCode, RepairOptions};
+    use crate::{Color, ColorIndex, Warp};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let bright = Color {
+        red: 1998,
+        green: 999,
+        blue: 0,
+    };
+    wif.color_table
+        .as_mut()
+        .unwrap()
+        .insert(ColorIndex(1), bright);
+    wif.warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(1), ColorIndex(1));
+    let issues = wif.validate();
+    assert!(issues
+        .iter()
+        .any(|i| i.code == IssueCode::ColorOutOfRange && i.key == Some(1)));
+    assert_eq!(wif.warp_color_u8(1), Some([255, 255, 0]));
+
+    let mut rescaled = wif.clone();
+    rescaled.repair(RepairOptions {
+        rescale_colors: true,
+        ..Default::default()
+    });
+    assert_eq!(rescaled.validate(), vec![]);
+    assert_eq!(
+        rescaled.warp_color(1),
+        Some(Color {
+            red: 999,
+            green: 500,
+            blue: 0
+        })
+    );
+
+    wif.color_palette.as_mut().unwrap().range = (100, 999);
+    assert_eq!(wif.warp_color_u8(1), Some([255, 255, 0]));
+    wif.color_table
+        .as_mut()
+        .unwrap()
+        .get_mut(&ColorIndex(1))
+        .unwrap()
+        .red = 50;
+    assert_eq!(wif.warp_color_u8(1), Some([0, 255, 0]));
+}
+
+#[test]
+fn shed_direction() {
+    use super::{IssueCode, RepairOptions};
+    use crate::{Shaft, Warp, WarpOrWeft, Weft};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let threaded = wif.threading.as_ref().unwrap()[&Warp(1)].clone();
+    let lifted = |wif: &super::Wif, pick| {
+        wif.liftplan.as_ref().unwrap()[&Weft(pick)]
+            .intersection(&threaded)
+            .next()
+            .is_some()
+    };
+    let pick = (1..=wif.height().unwrap())
+        .find(|&pick| lifted(&wif, pick))
+        .unwrap();
+    assert_eq!(wif.warp_or_weft(1, pick), Some(WarpOrWeft::Warp));
+    wif.weaving.as_mut().unwrap().rising_shed = Some(false);
+    assert_eq!(wif.warp_or_weft(1, pick), Some(WarpOrWeft::Weft));
+    wif.weaving.as_mut().unwrap().rising_shed = Some(true);
+
+    let original = wif.liftplan.clone();
+    wif.invert_liftplan();
+    assert!(!lifted(&wif, pick));
+    let issues = wif.validate();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].code, IssueCode::InvertedLiftplan);
+    assert!(issues[0].to_string().contains("sinking shed"));
+
+    let report = wif.repair(RepairOptions::default());
+    assert_eq!(report.repairs.len(), 1);
+    assert_eq!(report.repairs[0].code, IssueCode::InvertedLiftplan);
+    assert_eq!(wif.liftplan, original);
+    assert!(wif.validate().is_empty());
+
+    // A single wrong pick is a mismatch, not an inversion.
+    let picks = wif.liftplan.as_mut().unwrap();
+    picks.insert(Weft(1), [Shaft(1)].into());
+    assert!(wif
+        .validate()
+        .iter()
+        .all(|i| i.code != IssueCode::InvertedLiftplan));
+}
+
+#[test]
+fn validation_report_combines_loom_issues() {
+    use super::{Severity, Thresholds};
+    use crate::loom::LoomProfile;
+    let wif = defective();
+    let issues = wif.validate().len();
+    let report = wif.validation_report(None, Thresholds::default());
+    assert_eq!(report.entries.len(), issues);
+    assert!(!report.passed());
+
+    let clean = super::parse(include_str!("../sample.wif")).unwrap();
+    let loom = LoomProfile {
+        rising_shed: false,
+        ..Default::default()
+    };
+    let report = clean.validation_report(Some(&loom), Thresholds::default());
+    let codes: Vec<_> = report.entries.iter().map(|e| e.code).collect();
+    assert!(codes.contains(&"too-many-shafts"));
+    let shed = report
+        .entries
+        .iter()
+        .find(|e| e.code == "shed-direction")
+        .unwrap();
+    assert_eq!(shed.severity, Severity::Stylistic);
+    assert_eq!(
+        shed.to_string(),
+        "stylistic shed-direction [WEAVING]: is written for a rising shed, but the loom has a sinking shed"
+    );
+}
+
+#[test]
+fn thresholds_filter_and_fail() {
+    use super::{Severity, Thresholds};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    wif.color_palette.as_mut().unwrap().entries += 1;
+
+    let strict = wif.validation_report(None, Thresholds::STRICT);
+    assert_eq!(strict.count(Severity::Stylistic), 1);
+    assert_eq!((strict.errors(), strict.warnings()), (1, 0));
+    assert!(!strict.passed());
+    let default = wif.validation_report(None, Thresholds::default());
+    assert_eq!(default.entries.len(), 1);
+    assert_eq!((default.errors(), default.warnings()), (0, 1));
+    assert!(default.passed());
+    let forgiving = wif.validation_report(None, Thresholds::FORGIVING);
+    assert!(forgiving.entries.is_empty());
+
+    // A liftplan that disagrees with the treadling is broken, but still follows the spec.
+    wif.invert_liftplan();
+    let forgiving = wif.validation_report(None, Thresholds::FORGIVING);
+    assert_eq!(forgiving.count(Severity::BrokenDraft), 1);
+    assert!(forgiving.passed());
+    assert!(!wif.validation_report(None, Thresholds::default()).passed());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn validation_report_json() {
+    let json = defective()
+        .validation_report(None, super::Thresholds::default())
+        .to_json();
+    assert!(json.starts_with("{\n  \"format\": \"wif-validation\",\n  \"formatVersion\": 1,"));
+    assert!(json.contains("\"passed\": false"));
+    assert!(json.contains("\"warnings\": 1"));
+    assert!(json.contains("\"fail\": \"broken-draft\""));
+    assert!(json.contains("\"stylistic\": 1"));
+    assert!(json.contains("\"code\": \"palette-size-mismatch\",\n      \"section\": \"COLOR PALETTE\",\n      \"key\": null,"));
+}
+
+#[test]
+fn drawdown_matches_warp_or_weft() {
+    use crate::WarpOrWeft;
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    for rising_shed in [true, false] {
+        wif.weaving.as_mut().unwrap().rising_shed = Some(rising_shed);
+        let drawdown = wif.drawdown().unwrap();
+        assert_eq!(drawdown.ends(), wif.width().unwrap());
+        assert_eq!(drawdown.picks(), wif.height().unwrap());
+        for pick in [1, 2, 17, drawdown.picks()] {
+            for end in 1..=drawdown.ends() {
+                assert_eq!(drawdown.get(end, pick), wif.warp_or_weft(end, pick));
+            }
+            let row = drawdown.row(pick).unwrap();
+            assert_eq!(row.len() as u32, drawdown.ends());
+            assert_eq!(row[0], drawdown.get(1, pick) == Some(WarpOrWeft::Warp));
+        }
+        assert_eq!(drawdown.rows().count() as u32, drawdown.picks());
+        assert_eq!(drawdown.get(0, 1), None);
+        assert_eq!(drawdown.row(drawdown.picks() + 1), None);
+    }
+}
+
+#[test]
+fn heddles_per_shaft_counts_every_shaft_an_end_is_on() {
+    use crate::{Shaft, Warp};
+    let mut wif = super::Wif::new(4, 4);
+    wif.set_threading(
+        [
+            (Warp(1), [Shaft(1)].into()),
+            (Warp(2), [Shaft(1), Shaft(2)].into()),
+            (Warp(3), [Shaft(2), Shaft(5)].into()),
+        ]
+        .into(),
+    );
+    let heddles = wif.heddles_per_shaft();
+    assert_eq!(
+        heddles,
+        [
+            (Shaft(1), 2),
+            (Shaft(2), 2),
+            (Shaft(3), 0),
+            (Shaft(4), 0),
+            (Shaft(5), 1)
+        ]
+        .into()
+    );
+
+    let sample = super::parse(include_str!("../sample.wif")).unwrap();
+    let heddles = sample.heddles_per_shaft();
+    for (shaft, ends) in sample.shaft_usage() {
+        assert_eq!(heddles[&shaft] as usize, ends.len());
+    }
+}
+
+#[test]
+fn color_orders_are_run_length_encoded() {
+    use crate::instructions::ColorRun;
+    use crate::{ColorIndex, Warp};
+    let mut wif = super::Wif::blank(13, 0);
+    let (a, b) = (ColorIndex(3), ColorIndex(1));
+    wif.warp_colors = Some(
+        (1..=12)
+            .map(|end| (Warp(end), if (9..=10).contains(&end) { b } else { a }))
+            .collect(),
+    );
+    let order = wif.warp_color_order();
+    assert_eq!(
+        order.runs,
+        [
+            ColorRun {
+                color: Some(a),
+                count: 8
+            },
+            ColorRun {
+                color: Some(b),
+                count: 2
+            },
+            ColorRun {
+                color: Some(a),
+                count: 2
+            },
+            ColorRun {
+                color: None,
+                count: 1
+            },
+        ]
+    );
+    assert_eq!(
+        order.totals,
+        [(None, 1), (Some(b), 2), (Some(a), 10)].into()
+    );
+    assert_eq!(order.to_string(), "8×3, 2×1, 2×3, 1×-");
+
+    let sample = super::parse(include_str!("../sample.wif")).unwrap();
+    let weft = sample.weft_color_order();
+    let picks: u32 = weft.runs.iter().map(|r| r.count).sum();
+    assert_eq!(picks, sample.height().unwrap());
+    assert_eq!(weft.totals.values().sum::<u32>(), picks);
+}
+
+#[test]
+fn fabric_dimensions_follow_variable_spacing() {
+    use crate::{Units, Warp};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let dimensions = wif.fabric_dimensions();
+    let warp = dimensions.warp.unwrap();
+    assert_eq!(warp.threads, wif.width().unwrap());
+    let reed = crate::analysis::check_reed_fit(&wif, &Default::default()).unwrap();
+    assert!((warp.inches - reed.weaving_width).abs() < 1e-9);
+    assert!((warp.per_inch() - reed.sett).abs() < 1e-9);
+    let weft = dimensions.weft.unwrap();
+    assert_eq!(weft.threads, wif.height().unwrap());
+
+    // Sley the first ten ends at half the spacing.
+    let warp_section = wif.warp.as_mut().unwrap();
+    warp_section.units = Some(Units::Inches);
+    warp_section.spacing = Some(0.05);
+    wif.warp_spacing = Some((1..=10).map(|end| (Warp(end), 0.025)).collect());
+    let warp = wif.fabric_dimensions().warp.unwrap();
+    assert!(!warp.is_uniform());
+    assert_eq!(warp.sections.len(), 2);
+    assert_eq!((warp.sections[0].first, warp.sections[0].last), (1, 10));
+    assert!((warp.sections[0].per_inch().unwrap() - 40.).abs() < 1e-9);
+    assert!((warp.sections[1].per_inch().unwrap() - 20.).abs() < 1e-9);
+    let ends = wif.width().unwrap() as f64;
+    assert!((warp.inches - (10. * 0.025 + (ends - 10.) * 0.05)).abs() < 1e-9);
+
+    wif.warp.as_mut().unwrap().spacing = None;
+    assert_eq!(wif.fabric_dimensions().warp, None);
+}
+
+#[test]
+fn doubleweave_splits_into_two_plain_weave_layers() {
+    use crate::{Color, Shaft, Warp, Weft, WifBuilder, WifError};
+    let red = Color {
+        red: 255,
+        green: 0,
+        blue: 0,
+    };
+    let blue = Color {
+        red: 0,
+        green: 0,
+        blue: 255,
+    };
+    // Shafts 1 and 2 weave the top layer, 3 and 4 the bottom.
+    let wif = WifBuilder::new()
+        .threading_from([1, 3, 2, 4, 1, 3, 2, 4])
+        .liftplan([vec![1], vec![1, 2, 3], vec![2], vec![1, 2, 4]])
+        .warp_colors([red, blue, red, blue, red, blue, red, blue])
+        .weft_colors([red, blue, red, blue])
+        .build()
+        .unwrap();
+    let layers = wif.detect_layers().unwrap();
+    assert_eq!(layers.top_shafts, [Shaft(1), Shaft(2)].into());
+    assert_eq!(layers.bottom_shafts, [Shaft(3), Shaft(4)].into());
+    assert_eq!(layers.top_ends, [1, 3, 5, 7].map(Warp));
+    assert_eq!(layers.bottom_ends, [2, 4, 6, 8].map(Warp));
+    assert_eq!(layers.top_picks, [Weft(1), Weft(3)]);
+    assert_eq!(layers.bottom_picks, [Weft(2), Weft(4)]);
+
+    let (top, bottom) = wif.split_layers().unwrap();
+    let plain_weave = vec![[true, false].repeat(2), [false, true].repeat(2)];
+    for (layer, color) in [(&top, red), (&bottom, blue)] {
+        assert_eq!(layer.width(), Some(4));
+        assert_eq!(layer.height(), Some(2));
+        assert_eq!(layer.shafts(), Some(2));
+        assert_eq!(layer.to_drawdown_matrix(), plain_weave);
+        assert_eq!(layer.warp_color(Warp(1)), Some(color));
+        assert_eq!(layer.weft_color(Weft(2)), Some(color));
+    }
+
+    // Opened out, the bottom layer carries the plain weave on across the fold.
+    let unfolded = wif.unfold().unwrap();
+    assert_eq!(unfolded.len(), 2);
+    assert_eq!(unfolded[0], [true, false].repeat(4));
+    assert_eq!(unfolded[1], [false, true].repeat(4));
+
+    // On a sinking shed the liftplan lists the shafts that go down instead
+    let sinking = WifBuilder::new()
+        .threading_from([1, 3, 2, 4, 1, 3, 2, 4])
+        .liftplan([vec![2, 3, 4], vec![4], vec![1, 3, 4], vec![3]])
+        .rising_shed(false)
+        .build()
+        .unwrap();
+    assert_eq!(sinking.detect_layers(), Some(layers));
+    assert_eq!(sinking.unfold().unwrap(), unfolded);
+    let (top, bottom) = sinking.split_layers().unwrap();
+    assert_eq!(top.to_drawdown_matrix(), plain_weave);
+    assert_eq!(bottom.to_drawdown_matrix(), plain_weave);
+
+    let single = WifBuilder::new()
+        .threading_from([1, 2, 3, 4])
+        .liftplan([vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 1]])
+        .build()
+        .unwrap();
+    assert_eq!(single.detect_layers(), None);
+    assert!(matches!(
+        single.split_layers(),
+        Err(WifError::NotDoubleweave)
+    ));
+}
+
+#[test]
+fn flips_mirror_the_drawdown() {
+    use crate::{Warp, Weft};
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let drawdown = wif.to_drawdown_matrix();
+    let (ends, picks) = (wif.width().unwrap(), wif.height().unwrap());
+
+    let mut flipped = wif.clone();
+    assert!(flipped.flip_horizontal().is_empty());
+    let mirrored: Vec<Vec<bool>> = drawdown
+        .iter()
+        .map(|row| row.iter().rev().copied().collect())
+        .collect();
+    assert_eq!(flipped.to_drawdown_matrix(), mirrored);
+    assert_eq!(flipped.warp_color(Warp(1)), wif.warp_color(Warp(ends)));
+    assert_eq!(
+        flipped.threading.as_ref().unwrap().get(&Warp(ends)),
+        wif.threading.as_ref().unwrap().get(&Warp(1))
+    );
+
+    assert!(flipped.flip_vertical().is_empty());
+    let upside_down: Vec<Vec<bool>> = mirrored.into_iter().rev().collect();
+    assert_eq!(flipped.to_drawdown_matrix(), upside_down);
+    assert_eq!(flipped.weft_color(Weft(picks)), wif.weft_color(Weft(1)));
+    assert_eq!(
+        flipped.treadling.as_ref().unwrap().get(&Weft(1)),
+        wif.treadling.as_ref().unwrap().get(&Weft(picks))
+    );
+
+    flipped.flip_horizontal();
+    flipped.flip_vertical();
+    assert_eq!(flipped.to_drawdown_matrix(), drawdown);
+
+    // Colors past the last thread can't be mirrored, so they're handed back.
+    let color = wif.warp_color_idx(Warp(1)).unwrap();
+    flipped
+        .warp_colors
+        .as_mut()
+        .unwrap()
+        .insert(Warp(ends + 2), color);
+    flipped
+        .weft_colors
+        .as_mut()
+        .unwrap()
+        .insert(Weft(picks + 1), color);
+    assert_eq!(flipped.flip_horizontal(), [Warp(ends + 2)]);
+    assert_eq!(flipped.flip_vertical(), [Weft(picks + 1)]);
+    assert!(!flipped.warp_colors.unwrap().contains_key(&Warp(ends + 2)));
+}
+
+#[test]
+fn turning_swaps_warp_and_weft_keeping_the_face() {
+    use super::sections;
+    use crate::{Treadle, Warp, Weft, WifBuilder};
+    // The drawdown turned a quarter, with warp and weft trading places on top.
+    let turned = |drawdown: &[Vec<bool>]| -> Vec<Vec<bool>> {
+        (0..drawdown[0].len())
+            .map(|end| drawdown.iter().map(|row| !row[end]).collect())
+            .collect()
+    };
+
+    let wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let mut turn = wif.clone();
+    turn.turn().unwrap();
+    assert_eq!(turn.width(), wif.height());
+    assert_eq!(turn.height(), wif.width());
+    assert_eq!(turn.shafts(), wif.treadles());
+    assert!(turn.treadling.is_some());
+    assert_eq!(turn.to_drawdown_matrix(), turned(&wif.to_drawdown_matrix()));
+    assert_eq!(turn.warp_color(Warp(3)), wif.weft_color(Weft(3)));
+    assert_eq!(turn.weft_color(Weft(2)), wif.warp_color(Warp(2)));
+    turn.turn().unwrap();
+    assert_eq!(turn.to_drawdown_matrix(), wif.to_drawdown_matrix());
+
+    // Picks lifting several shafts turn into a liftplan draft.
+    let lifted = WifBuilder::new()
+        .threading_from([1, 2, 3, 4, 1, 2, 3, 4])
+        .liftplan([vec![1, 2], vec![2, 3], vec![3, 4], vec![4, 1], vec![1, 2]])
+        .build()
+        .unwrap();
+    let mut turn = lifted.clone();
+    turn.turn().unwrap();
+    assert!(turn.treadling.is_none());
+    assert_eq!(turn.shafts(), Some(4));
+    assert_eq!(
+        turn.to_drawdown_matrix(),
+        turned(&lifted.to_drawdown_matrix())
+    );
+
+    // A treadled draft pressing two treadles at once has its liftplan built to turn.
+    let mut treadled = wif.clone();
+    treadled
+        .treadling
+        .as_mut()
+        .unwrap()
+        .insert(Weft(1), [Treadle(1), Treadle(2)].into());
+    treadled.liftplan = None;
+    treadled.contents.pinned = true;
+    let mut turn = treadled.clone();
+    turn.turn().unwrap();
+    assert!(turn.treadling.is_none() && turn.tieup.is_none());
+    assert!(!turn.contents.declares(sections::TREADLING));
+    assert!(!turn.contents.declares(sections::TIEUP));
+    assert!(turn.contents.declares(sections::LIFTPLAN));
+    treadled.build_or_validate_liftplan().unwrap();
+    assert_eq!(
+        turn.to_drawdown_matrix(),
+        turned(&treadled.to_drawdown_matrix())
+    );
+}
+
+#[test]
+fn inverting_the_shed_keeps_the_cloth() {
+    use crate::{Shaft, Treadle};
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let drawdown = wif.to_drawdown_matrix();
+    let shafts = wif.shafts().unwrap();
+    let tied = |wif: &super::Wif, treadle| {
+        wif.tieup
+            .as_ref()
+            .unwrap()
+            .get(&Treadle(treadle))
+            .cloned()
+            .unwrap_or_default()
+    };
+    let before = tied(&wif, 1);
+
+    wif.invert_shed();
+    assert_eq!(wif.weaving.as_ref().unwrap().rising_shed, Some(false));
+    assert_eq!(wif.to_drawdown_matrix(), drawdown);
+    let after = tied(&wif, 1);
+    assert!(before.is_disjoint(&after));
+    assert_eq!(before.len() + after.len(), shafts as usize);
+    assert!(after.contains(&Shaft(shafts)) || before.contains(&Shaft(shafts)));
+    assert_eq!(wif.validate(), vec![]);
+
+    wif.invert_shed();
+    assert_eq!(wif.weaving.as_ref().unwrap().rising_shed, Some(true));
+    assert_eq!(tied(&wif, 1), before);
+}
+
+#[test]
+fn liftplan_and_treadling_convert_both_ways() {
+    use crate::WifError;
+    let mut wif = super::parse(include_str!("../sample.wif")).unwrap();
+    let drawdown = wif.to_drawdown_matrix();
+    let liftplan = wif.liftplan.clone();
+
+    wif.to_liftplan().unwrap();
+    assert_eq!(wif.treadling, None);
+    assert_eq!(wif.tieup, None);
+    assert_eq!(wif.weaving.as_ref().unwrap().treadles, 0);
+    assert_eq!(wif.liftplan, liftplan);
+    assert_eq!(wif.to_drawdown_matrix(), drawdown);
+
+    wif.derive_treadling(u32::MAX).unwrap();
+    let treadles = wif.weaving.as_ref().unwrap().treadles;
+    assert!(wif
+        .treadling
+        .as_ref()
+        .unwrap()
+        .values()
+        .all(|pressed| pressed.len() <= 1));
+    wif.liftplan = None;
+    wif.build_or_validate_liftplan().unwrap();
+    assert_eq!(wif.liftplan, liftplan);
+    assert_eq!(wif.to_drawdown_matrix(), drawdown);
+
+    assert!(treadles > 0);
+    assert!(matches!(
+        wif.derive_treadling(0),
+        Err(WifError::LiftplanNeedsMoreTreadles { available: 0, .. })
+    ));
+}
+
+#[test]
+fn derived_treadling_combines_lifts_when_treadles_run_short() {
+    use crate::{Shaft, Treadle, Weft, WifBuilder, WifError};
+    use std::collections::BTreeSet;
+    let mut wif = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4])
+        .liftplan([
+            vec![1],
+            vec![2],
+            vec![1, 2],
+            vec![3, 4],
+            vec![1, 3, 4],
+            vec![],
+        ])
+        .build()
+        .unwrap();
+
+    wif.derive_treadling(3).unwrap();
+    let tieup = wif.tieup.clone().unwrap();
+    assert_eq!(tieup.len(), 3);
+    assert_eq!(tieup[&Treadle(3)], [Shaft(3), Shaft(4)].into());
+    let treadling = wif.treadling.clone().unwrap();
+    assert_eq!(treadling[&Weft(3)], [Treadle(1), Treadle(2)].into());
+    assert_eq!(treadling[&Weft(5)], [Treadle(1), Treadle(3)].into());
+    assert_eq!(treadling[&Weft(6)], BTreeSet::new());
+    wif.build_or_validate_liftplan().unwrap();
+
+    assert!(matches!(
+        wif.derive_treadling(2),
+        Err(WifError::LiftplanNeedsMoreTreadles {
+            needed: 3,
+            available: 2
+        })
+    ));
+}
+
+#[test]
+fn skeleton_tieup_presses_two_treadles_for_overshot() {
+    use crate::{Shaft, Treadle, WifBuilder, WifError};
+    use std::collections::BTreeSet;
+    let lifts = [[1, 2], [2, 3], [3, 4], [1, 4], [1, 3], [2, 4]];
+    let mut wif = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4])
+        .liftplan(lifts)
+        .build()
+        .unwrap();
+
+    wif.derive_treadling(4).unwrap();
+    let singles: Vec<BTreeSet<Shaft>> = (1..=4).map(|s| [Shaft(s)].into()).collect();
+    assert_eq!(
+        wif.tieup.clone().unwrap().into_values().collect::<Vec<_>>(),
+        singles
+    );
+    let treadling = wif.treadling.clone().unwrap();
+    for (pressed, lift) in treadling.values().zip(lifts) {
+        assert_eq!(*pressed, lift.map(Treadle).into());
+    }
+    wif.build_or_validate_liftplan().unwrap();
+
+    assert!(matches!(
+        wif.derive_treadling(3),
+        Err(WifError::LiftplanNeedsMoreTreadles {
+            needed: 4,
+            available: 3
+        })
+    ));
+}
+
+#[test]
+fn skeleton_search_gives_up_on_many_lifts() {
+    use crate::{WifBuilder, WifError};
+    let mut seed = 7u32;
+    let lifts: Vec<Vec<u32>> = (0..80)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (1..=12).filter(|s| seed >> (s + 4) & 1 == 1).collect()
+        })
+        .collect();
+    let mut wif = WifBuilder::new()
+        .shafts(12)
+        .threading_from(1..=12)
+        .liftplan(lifts)
+        .build()
+        .unwrap();
+    let liftplan = wif.liftplan.clone();
+
+    wif.derive_treadling(12).unwrap();
+    assert!(wif.tieup.as_ref().unwrap().len() <= 12);
+    wif.liftplan = None;
+    wif.build_or_validate_liftplan().unwrap();
+    assert_eq!(wif.liftplan, liftplan);
+
+    assert!(matches!(
+        wif.derive_treadling(6),
+        Err(WifError::LiftplanNeedsMoreTreadles { available: 6, .. })
+    ));
+}
+
+#[test]
+fn optimize_shafts_merges_and_reorders() {
+    use crate::{Shaft, Warp, Weft, WifBuilder};
+    use std::collections::BTreeSet;
+    let mut wif = WifBuilder::new()
+        .shafts(5)
+        .threading_from([4, 1, 3, 1, 4])
+        .liftplan([vec![1, 3], vec![4], vec![1, 3, 4]])
+        .build()
+        .unwrap();
+    let drawdown = wif.to_drawdown_matrix();
+
+    let mut plain = wif.clone();
+    let renumbered = plain.optimize_shafts(false);
+    assert_eq!(
+        renumbered,
+        [(1, 1), (3, 1), (4, 2)]
+            .map(|(old, new)| (Shaft(old), Shaft(new)))
+            .into()
+    );
+    assert_eq!(plain.shafts(), Some(2));
+    assert_eq!(plain.to_drawdown_matrix(), drawdown);
+
+    wif.optimize_shafts(true);
+    let threading: Vec<u32> = (1..=5)
+        .map(|end| {
+            wif.threading.as_ref().unwrap()[&Warp(end)]
+                .first()
+                .unwrap()
+                .0
+        })
+        .collect();
+    assert_eq!(threading, [1, 2, 2, 2, 1]);
+    assert_eq!(
+        wif.liftplan.as_ref().unwrap()[&Weft(1)],
+        BTreeSet::from([Shaft(2)])
+    );
+    assert_eq!(wif.to_drawdown_matrix(), drawdown);
+
+    let mut sample = super::parse(include_str!("../sample.wif")).unwrap();
+    let drawdown = sample.to_drawdown_matrix();
+    sample.optimize_shafts(true);
+    assert_eq!(sample.to_drawdown_matrix(), drawdown);
+    sample.build_or_validate_liftplan().unwrap();
+
+    let mut threaded = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4])
+        .build()
+        .unwrap();
+    assert_eq!(threaded.optimize_shafts(true).len(), 4);
+    assert_eq!(threaded.shafts(), Some(4));
+}
+
+#[test]
+fn optimize_treadles_merges_and_walks() {
+    use crate::{Shaft, Tieup, Treadle, Weft, WifBuilder};
+    let tieup: Tieup = [[1, 2], [3, 4], [2, 3], [1, 2]]
+        .into_iter()
+        .zip(1..)
+        .map(|(tied, treadle)| (Treadle(treadle), tied.map(Shaft).into()))
+        .collect();
+    let mut wif = WifBuilder::new()
+        .shafts(4)
+        .treadles(4)
+        .threading_from([1, 2, 3, 4])
+        .tieup(tieup)
+        .treadling([1, 3, 2, 4, 3, 1])
+        .build()
+        .unwrap();
+    let drawdown = wif.to_drawdown_matrix();
+
+    let renumbered = wif.optimize_treadles();
+    assert_eq!(
+        renumbered,
+        [(1, 1), (2, 3), (3, 2), (4, 1)]
+            .map(|(old, new)| (Treadle(old), Treadle(new)))
+            .into()
+    );
+    let treadling: Vec<u32> = (1..=6)
+        .map(|pick| {
+            wif.treadling.as_ref().unwrap()[&Weft(pick)]
+                .first()
+                .unwrap()
+                .0
+        })
+        .collect();
+    assert_eq!(treadling, [1, 2, 3, 1, 2, 1]);
+    assert_eq!(wif.treadles(), Some(3));
+    assert_eq!(wif.to_drawdown_matrix(), drawdown);
+    wif.build_or_validate_liftplan().unwrap();
+
+    let renumbered = wif.optimize_treadles();
+    assert!(renumbered.iter().all(|(old, new)| old == new));
+
+    let mut sample = super::parse(include_str!("../sample.wif")).unwrap();
+    let drawdown = sample.to_drawdown_matrix();
+    sample.optimize_treadles();
+    assert_eq!(sample.to_drawdown_matrix(), drawdown);
+    sample.build_or_validate_liftplan().unwrap();
+}
+
+#[test]
+fn treadling_as_drawn_in_follows_the_threading() {
+    use crate::{Shaft, Treadle, Weft, WifBuilder};
+    use std::collections::BTreeSet;
+    let shafts = |s: &[u32]| s.iter().map(|&s| Shaft(s)).collect::<BTreeSet<_>>();
+
+    let mut wif = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4, 3, 2])
+        .build()
+        .unwrap();
+    wif.treadle_as_drawn_in().unwrap();
+    let treadling = wif.treadling.as_ref().unwrap();
+    let treadled: Vec<u32> = treadling.values().map(|t| t.first().unwrap().0).collect();
+    assert_eq!(treadled, [1, 2, 3, 4, 3, 2]);
+    assert_eq!(wif.tieup.as_ref().unwrap()[&Treadle(4)], shafts(&[4, 1]));
+    assert_eq!(wif.liftplan.as_ref().unwrap()[&Weft(3)], shafts(&[3, 4]));
+    assert_eq!(wif.height(), Some(6));
+
+    let mut dobby = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4])
+        .liftplan([[1]])
+        .build()
+        .unwrap();
+    dobby.treadle_as_drawn_in().unwrap();
+    assert_eq!(dobby.treadling, None);
+    assert_eq!(dobby.liftplan.as_ref().unwrap()[&Weft(4)], shafts(&[1, 4]));
+
+    let mut sample = super::parse(include_str!("../sample.wif")).unwrap();
+    let tieup = sample.tieup.clone();
+    sample.treadle_as_drawn_in().unwrap();
+    assert_eq!(sample.tieup, tieup);
+    for (end, threaded) in sample.threading.as_ref().unwrap() {
+        let pressed = &sample.treadling.as_ref().unwrap()[&Weft(end.0)];
+        assert!(threaded
+            .iter()
+            .map(|s| Treadle(s.0))
+            .eq(pressed.iter().copied()));
+    }
+    sample.build_or_validate_liftplan().unwrap();
+
+    let mut short = WifBuilder::new()
+        .shafts(4)
+        .treadles(2)
+        .threading_from([1, 2, 3, 4])
+        .tieup([(Treadle(1), shafts(&[1, 3])), (Treadle(2), shafts(&[2, 4]))].into())
+        .build()
+        .unwrap();
+    assert!(matches!(
+        short.treadle_as_drawn_in(),
+        Err(crate::WifError::OutOfRange {
+            value: 3,
+            max: 2,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn colors_remap_replace_and_swap() {
+    use crate::{Color, ColorIndex, Warp, WifBuilder, WifError};
+    let red = Color {
+        red: 999,
+        green: 0,
+        blue: 0,
+    };
+    let blue = Color {
+        red: 0,
+        green: 0,
+        blue: 999,
+    };
+    let mut wif = WifBuilder::new()
+        .shafts(2)
+        .threading_from([1, 2, 1, 2])
+        .liftplan([[1], [2]])
+        .warp_colors([red, blue, red, blue])
+        .weft_colors([blue, blue])
+        .build()
+        .unwrap();
+    let idx = |wif: &crate::Wif, color| {
+        *wif.color_table
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|(_, c)| **c == color)
+            .unwrap()
+            .0
+    };
+    let (r, b) = (idx(&wif, red), idx(&wif, blue));
+
+    wif.swap_colorway(&[(r, b), (b, r)].into()).unwrap();
+    assert_eq!(wif.warp_color(Warp(1)), Some(blue));
+    assert_eq!(wif.weft_color(1), Some(red));
+
+    let green = Color {
+        red: 0,
+        green: 999,
+        blue: 0,
+    };
+    wif.replace_color(r, green).unwrap();
+    assert_eq!(wif.weft_color(2), Some(green));
+    assert!(matches!(
+        wif.replace_color(ColorIndex(99), green),
+        Err(WifError::UnknownColor { idx: 99 })
+    ));
+
+    wif.remap_color(r, b).unwrap();
+    assert!((1..=4).all(|end| wif.warp_color(end) == Some(blue)));
+    assert_eq!(wif.weft_color(1), Some(blue));
+    let table = wif.color_table.as_ref().unwrap();
+    assert!(!table.contains_key(&r));
+    let highest = table.keys().next_back().unwrap().0 as usize;
+    assert_eq!(wif.color_palette.as_ref().unwrap().entries, highest);
+
+    let before = wif.warp_colors.clone();
+    assert!(wif.swap_colorway(&[(b, ColorIndex(99))].into()).is_err());
+    assert_eq!(wif.warp_colors, before);
+}
+
+#[test]
+fn consolidate_palette_merges_near_whites() {
+    use crate::{Color, Warp, WifBuilder};
+    let gray = |level| Color {
+        red: level,
+        green: level,
+        blue: level,
+    };
+    let black = gray(0);
+    let mut wif = WifBuilder::new()
+        .shafts(2)
+        .threading_from([1, 2, 1, 2])
+        .liftplan([[1], [2]])
+        .warp_colors([gray(999), gray(995), black, gray(990)])
+        .weft_colors([gray(995), black])
+        .build()
+        .unwrap();
+    let colors = wif.color_table.as_ref().unwrap().len();
+
+    assert!(wif.consolidate_palette(0.).is_empty());
+    let merged = wif.consolidate_palette(2.);
+    assert_eq!(merged.len(), 2);
+    assert_eq!(wif.color_table.as_ref().unwrap().len(), colors - 2);
+    assert_eq!(wif.color_palette.as_ref().unwrap().entries, 3);
+    let white = wif.warp_color(Warp(1));
+    assert_eq!(wif.warp_color(Warp(2)), white);
+    assert_eq!(wif.warp_color(Warp(4)), white);
+    assert_eq!(wif.weft_color(1), white);
+    assert_eq!(wif.weft_color(2), Some(black));
+}
+
+#[test]
+fn joined_drafts_weave_both_cloths() {
+    use crate::{Color, Warp, WifBuilder};
+    let red = Color {
+        red: 999,
+        green: 0,
+        blue: 0,
+    };
+    let twill = || {
+        WifBuilder::new()
+            .shafts(4)
+            .threading_from([1, 2, 3, 4])
+            .liftplan([[1, 2], [2, 3], [3, 4], [4, 1]])
+            .build()
+            .unwrap()
+    };
+    let point = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4, 3, 2])
+        .liftplan([[1, 2], [2, 3], [3, 4], [4, 1]])
+        .warp_colors([red; 6])
+        .build()
+        .unwrap();
+
+    let mut gamp = twill();
+    gamp.join_warpwise(&point);
+    assert_eq!((gamp.width(), gamp.height()), (Some(10), Some(4)));
+    assert_eq!(gamp.shafts(), Some(4));
+    let (left, right) = (twill().to_drawdown_matrix(), point.to_drawdown_matrix());
+    for (row, (l, r)) in gamp
+        .to_drawdown_matrix()
+        .iter()
+        .zip(left.iter().zip(&right))
+    {
+        assert_eq!(*row, [l.as_slice(), r.as_slice()].concat());
+    }
+    assert_eq!(gamp.warp_color(Warp(5)), Some(red));
+
+    // A taller draft beside it keeps the twill repeating.
+    let mut tall = twill();
+    let mut point = point;
+    point.join_weftwise(&point.clone());
+    tall.join_warpwise(&point);
+    assert_eq!(tall.height(), Some(8));
+    for (pick, row) in tall.to_drawdown_matrix().iter().enumerate() {
+        assert_eq!(row[..4], left[pick % 4]);
+    }
+
+    let mut sampler = twill();
+    let broken = WifBuilder::new()
+        .shafts(4)
+        .threading_from([1, 2, 3, 4])
+        .liftplan([[1, 2], [2, 3], [1, 4], [3, 4]])
+        .build()
+        .unwrap();
+    sampler.join_weftwise(&broken);
+    assert_eq!((sampler.width(), sampler.height()), (Some(4), Some(8)));
+    assert_eq!(sampler.shafts(), Some(4));
+    let rows = [twill().to_drawdown_matrix(), broken.to_drawdown_matrix()].concat();
+    assert_eq!(sampler.to_drawdown_matrix(), rows);
+
+    // A draft for a sinking shed is adapted to the rising shed before it's joined on.
+    let mut sinking = broken.clone();
+    sinking.invert_shed();
+    let mut sampler = twill();
+    sampler.join_weftwise(&sinking);
+    assert_eq!(sampler.to_drawdown_matrix(), rows);
+    let mut gamp = twill();
+    gamp.join_warpwise(&sinking);
+    let joined: Vec<_> = left
+        .iter()
+        .zip(broken.to_drawdown_matrix())
+        .map(|(l, r)| [l.clone(), r].concat())
+        .collect();
+    assert_eq!(gamp.to_drawdown_matrix(), joined);
+
+    let sample = super::parse(include_str!("../sample.wif")).unwrap();
+    let mut doubled = sample.clone();
+    doubled.join_weftwise(&sample);
+    let rows = sample.to_drawdown_matrix();
+    assert_eq!(doubled.to_drawdown_matrix(), [rows.clone(), rows].concat());
+    assert_eq!(doubled.shafts(), sample.shafts());
+    assert!(doubled.treadling.is_some());
+    doubled.build_or_validate_liftplan().unwrap();
+
+    let mut wide = sample.clone();
+    wide.join_warpwise(&sample);
+    let rows = sample.to_drawdown_matrix();
+    let joined: Vec<_> = rows
+        .iter()
+        .map(|row| [row.clone(), row.clone()].concat())
+        .collect();
+    assert_eq!(wide.to_drawdown_matrix(), joined);
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeSet;
+
+use super::{Symbol, Wif};
+use crate::{Color, Length, Shaft, SymbolIndex, Table, Treadle, Units, Warp, Weft};
+
+/// One warp end, with per-thread tables falling back to the [WARP] defaults, and thickness
+/// and spacing zoomed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarpEnd {
+    pub index: Warp,
+    pub shafts: BTreeSet<Shaft>,
+    pub color: Option<Color>,
+    pub thickness: Option<Length>,
+    pub spacing: Option<Length>,
+    pub symbol: Option<Symbol>,
+}
+
+/// One weft pick, with per-thread tables falling back to the [WEFT] defaults, and thickness
+/// and spacing zoomed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeftPick {
+    pub index: Weft,
+    pub treadles: BTreeSet<Treadle>,
+    /// The shafts raised, from the liftplan.
+    pub lift: BTreeSet<Shaft>,
+    pub color: Option<Color>,
+    pub thickness: Option<Length>,
+    pub spacing: Option<Length>,
+    pub symbol: Option<Symbol>,
+}
+
+// A per-thread value from its table, falling back to the section default, with units.
+fn length<K: Ord>(
+    table: Option<&Table<K, f64>>,
+    key: &K,
+    default: Option<f64>,
+    units: Option<&Units>,
+) -> Option<Length> {
+    let value = table.and_then(|t| t.get(key)).copied().or(default)?;
+    Some(Length::new(value, units?.clone()))
+}
+
+// A per-thread zoom, falling back to the section zoom and then to 1.
+fn zoom<K: Ord>(table: Option<&Table<K, u32>>, key: &K, default: Option<u32>) -> f64 {
+    let zoom = table.and_then(|t| t.get(key)).copied().or(default);
+    f64::from(zoom.unwrap_or(1))
+}
+
+fn zoomed(length: Option<Length>, zoom: f64) -> Option<Length> {
+    length.map(|l| Length::new(l.value * zoom, l.units))
+}
+
+fn symbol<K: Ord>(
+    per_thread: Option<&Table<K, SymbolIndex>>,
+    key: &K,
+    default_number: Option<SymbolIndex>,
+    symbols: Option<&Table<SymbolIndex, Symbol>>,
+    default: Option<Symbol>,
+) -> Option<Symbol> {
+    let number = per_thread
+        .and_then(|t| t.get(key))
+        .copied()
+        .or(default_number);
+    number.and_then(|n| symbols?.get(&n).copied()).or(default)
+}
+
+impl Wif {
+    pub(crate) fn warp_thickness_length(&self, warp: Warp) -> Option<Length> {
+        let section = self.warp.as_ref()?;
+        length(
+            self.warp_thickness.as_ref(),
+            &warp,
+            section.thickness,
+            section.units.as_ref(),
+        )
+    }
+
+    pub(crate) fn warp_spacing_length(&self, warp: Warp) -> Option<Length> {
+        let section = self.warp.as_ref()?;
+        length(
+            self.warp_spacing.as_ref(),
+            &warp,
+            section.spacing,
+            section.units.as_ref(),
+        )
+    }
+
+    pub(crate) fn warp_symbol(&self, warp: Warp) -> Option<Symbol> {
+        let section = self.warp.as_ref()?;
+        symbol(
+            self.warp_symbols.as_ref(),
+            &warp,
+            section.symbol_number,
+            self.warp_symbol_table.as_ref(),
+            section.symbol,
+        )
+    }
+
+    pub(crate) fn weft_thickness_length(&self, weft: Weft) -> Option<Length> {
+        let section = self.weft.as_ref()?;
+        length(
+            self.weft_thickness.as_ref(),
+            &weft,
+            section.thickness,
+            section.units.as_ref(),
+        )
+    }
+
+    pub(crate) fn weft_spacing_length(&self, weft: Weft) -> Option<Length> {
+        let section = self.weft.as_ref()?;
+        length(
+            self.weft_spacing.as_ref(),
+            &weft,
+            section.spacing,
+            section.units.as_ref(),
+        )
+    }
+
+    pub(crate) fn weft_symbol(&self, weft: Weft) -> Option<Symbol> {
+        let section = self.weft.as_ref()?;
+        symbol(
+            self.weft_symbols.as_ref(),
+            &weft,
+            section.symbol_number,
+            self.weft_symbols_table.as_ref(),
+            section.symbol,
+        )
+    }
+
+    /// The effective thickness of an end: its [WARP THICKNESS] entry or the [WARP] default,
+    /// multiplied by its [WARP THICKNESS ZOOM] entry or the default zoom.
+    pub fn warp_thickness_at(&self, warp: impl Into<Warp>) -> Option<Length> {
+        let warp = warp.into();
+        let section = self.warp.as_ref()?;
+        let zoom = zoom(
+            self.warp_thickness_zoom.as_ref(),
+            &warp,
+            section.thickness_zoom,
+        );
+        zoomed(self.warp_thickness_length(warp), zoom)
+    }
+
+    /// The effective spacing of an end, with [WARP SPACING ZOOM] applied.
+    pub fn warp_spacing_at(&self, warp: impl Into<Warp>) -> Option<Length> {
+        let warp = warp.into();
+        let section = self.warp.as_ref()?;
+        let zoom = zoom(self.warp_spacing_zoom.as_ref(), &warp, section.spacing_zoom);
+        zoomed(self.warp_spacing_length(warp), zoom)
+    }
+
+    /// The effective thickness of a pick, with [WEFT THICKNESS ZOOM] applied.
+    pub fn weft_thickness_at(&self, weft: impl Into<Weft>) -> Option<Length> {
+        let weft = weft.into();
+        let section = self.weft.as_ref()?;
+        let zoom = zoom(
+            self.weft_thickness_zoom.as_ref(),
+            &weft,
+            section.thickness_zoom,
+        );
+        zoomed(self.weft_thickness_length(weft), zoom)
+    }
+
+    /// The effective spacing of a pick, with [WEFT SPACING ZOOM] applied.
+    pub fn weft_spacing_at(&self, weft: impl Into<Weft>) -> Option<Length> {
+        let weft = weft.into();
+        let section = self.weft.as_ref()?;
+        let zoom = zoom(self.weft_spacing_zoom.as_ref(), &weft, section.spacing_zoom);
+        zoomed(self.weft_spacing_length(weft), zoom)
+    }
+
+    /// Every warp end from 1 to [`width`](Self::width).
+    pub fn warp_ends(&self) -> impl Iterator<Item = WarpEnd> + '_ {
+        (1..=self.width().unwrap_or(0))
+            .map(Warp)
+            .map(|index| WarpEnd {
+                index,
+                shafts: self
+                    .threading
+                    .as_ref()
+                    .and_then(|t| t.get(&index))
+                    .cloned()
+                    .unwrap_or_default(),
+                color: self.warp_color(index),
+                thickness: self.warp_thickness_at(index),
+                spacing: self.warp_spacing_at(index),
+                symbol: self.warp_symbol(index),
+            })
+    }
+
+    /// Every weft pick from 1 to [`height`](Self::height).
+    pub fn weft_picks(&self) -> impl Iterator<Item = WeftPick> + '_ {
+        (1..=self.height().unwrap_or(0))
+            .map(Weft)
+            .map(|index| WeftPick {
+                index,
+                treadles: self
+                    .treadling
+                    .as_ref()
+                    .and_then(|t| t.get(&index))
+                    .cloned()
+                    .unwrap_or_default(),
+                lift: self
+                    .liftplan
+                    .as_ref()
+                    .and_then(|l| l.get(&index))
+                    .cloned()
+                    .unwrap_or_default(),
+                color: self.weft_color(index),
+                thickness: self.weft_thickness_at(index),
+                spacing: self.weft_spacing_at(index),
+                symbol: self.weft_symbol(index),
+            })
+    }
+}
@@ -0,0 +1,340 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use super::{sections, Wif};
+use crate::{Result, Shaft, Table, Treadle, Weft, WifError};
+
+// The lifts that aren't the union of the smaller lifts inside them. Every lift is the union
+// of some of these.
+fn irreducible(lifts: &[&BTreeSet<Shaft>]) -> Vec<BTreeSet<Shaft>> {
+    lifts
+        .iter()
+        .filter(|&&lift| {
+            let inside: BTreeSet<&Shaft> = lifts
+                .iter()
+                .filter(|&&other| other != lift && other.is_subset(lift))
+                .flat_map(|&other| other)
+                .collect();
+            inside.len() < lift.len()
+        })
+        .map(|&lift| lift.clone())
+        .collect()
+}
+
+// How much work the skeleton search does, counted in sets compared, over every number of
+// presses it tries before settling for the lifts no others combine to make or one shaft per treadle.
+const SEARCH_STEPS: usize = 100_000;
+
+// Treadles, no more than `presses`, whose tied shafts together are exactly `lift`. They're
+// chosen greedily, each adding the most shafts not yet lifted, and then any the others make
+// redundant are dropped, so it's quick but not always the fewest.
+fn cover(lift: &BTreeSet<Shaft>, tieup: &[BTreeSet<Shaft>], presses: usize) -> Option<Vec<usize>> {
+    let inside: Vec<usize> = (0..tieup.len())
+        .filter(|&t| !tieup[t].is_empty() && tieup[t].is_subset(lift))
+        .collect();
+    let mut chosen = vec![];
+    let mut covered = BTreeSet::new();
+    while covered.len() < lift.len() {
+        let &best = inside
+            .iter()
+            .max_by_key(|&&t| (tieup[t].difference(&covered).count(), Reverse(t)))?;
+        if tieup[best].is_subset(&covered) {
+            return None;
+        }
+        covered.extend(&tieup[best]);
+        chosen.push(best);
+    }
+    let mut i = 0;
+    while i < chosen.len() {
+        let others: BTreeSet<&Shaft> = chosen
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .flat_map(|(_, &t)| &tieup[t])
+            .collect();
+        if others.len() == lift.len() {
+            chosen.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    chosen.sort();
+    (chosen.len() <= presses).then_some(chosen)
+}
+
+// A depth-first search for a tieup of at most `treadles` treadles that makes every lift with
+// at most `presses` treadles pressed together.
+struct Skeleton<'a> {
+    lifts: Vec<&'a BTreeSet<Shaft>>,
+    presses: usize,
+    treadles: usize,
+    steps: usize,
+}
+
+impl Skeleton<'_> {
+    fn search(&mut self, tieup: &mut Vec<BTreeSet<Shaft>>, next: usize) -> bool {
+        self.steps += 1;
+        if self.steps > SEARCH_STEPS {
+            return false;
+        }
+        let Some(&lift) = self.lifts.get(next) else {
+            return true;
+        };
+        self.steps += tieup.len();
+        if cover(lift, tieup, self.presses).is_some() {
+            return self.search(tieup, next + 1);
+        }
+        if tieup.len() == self.treadles {
+            return false;
+        }
+        for piece in self.pieces(lift, tieup) {
+            tieup.push(piece);
+            if self.search(tieup, next) {
+                return true;
+            }
+            tieup.pop();
+        }
+        false
+    }
+
+    // Shafts worth tying to one treadle on the way to making `lift`: the parts it shares
+    // with other lifts, what's left of it beside a treadle already tied, single shafts and
+    // the whole lift. Parts that more lifts share come first.
+    fn pieces(
+        &mut self,
+        lift: &BTreeSet<Shaft>,
+        tieup: &[BTreeSet<Shaft>],
+    ) -> Vec<BTreeSet<Shaft>> {
+        let shared = self.lifts.iter().map(|&other| lift & other);
+        let left = tieup
+            .iter()
+            .filter(|tied| tied.is_subset(lift))
+            .map(|tied| lift - tied);
+        let single = lift.iter().map(|&shaft| [shaft].into());
+        let mut pieces: Vec<BTreeSet<Shaft>> = shared
+            .chain(left)
+            .chain(single)
+            .filter(|piece| !piece.is_empty() && !tieup.contains(piece))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        self.steps += (pieces.len() + 1) * self.lifts.len();
+        let sharing = |piece: &BTreeSet<Shaft>| {
+            self.lifts
+                .iter()
+                .filter(|other| piece.is_subset(other))
+                .count()
+        };
+        pieces.sort_by_cached_key(|piece| Reverse((sharing(piece), piece.len())));
+        pieces
+    }
+}
+
+// A skeleton tieup for more lifts than treadles, needing as few treadles pressed together as
+// the search can manage. Failing that, the lifts no others combine to make, or one shaft per
+// treadle, whichever is fewer. If neither of those fits, it fails without searching.
+fn skeleton(lifts: &[&BTreeSet<Shaft>], treadles: u32) -> Result<Vec<BTreeSet<Shaft>>> {
+    let combined = irreducible(lifts);
+    let used: BTreeSet<&Shaft> = lifts.iter().flat_map(|&lift| lift).collect();
+    let single: Vec<BTreeSet<Shaft>> = used.into_iter().map(|&shaft| [shaft].into()).collect();
+    let fewest = if combined.len() <= single.len() {
+        combined
+    } else {
+        single
+    };
+    if fewest.len() > treadles as usize {
+        return Err(WifError::LiftplanNeedsMoreTreadles {
+            needed: fewest.len() as u32,
+            available: treadles,
+        });
+    }
+
+    let mut by_size = lifts.to_vec();
+    by_size.sort_by_key(|lift| Reverse(lift.len()));
+    let largest = by_size.first().map_or(0, |lift| lift.len());
+    let mut search = Skeleton {
+        lifts: by_size,
+        presses: 0,
+        treadles: treadles as usize,
+        steps: 0,
+    };
+    for presses in 2..=largest {
+        search.presses = presses;
+        let mut tieup = vec![];
+        if search.search(&mut tieup, 0) {
+            tieup.sort();
+            return Ok(tieup);
+        }
+        if search.steps > SEARCH_STEPS {
+            break;
+        }
+    }
+    Ok(fewest)
+}
+
+// The usual tieup for treading as drawn in: each treadle lifts half the shafts, starting at
+// the shaft with its number and wrapping round, giving plain weave on 2 shafts and a 2/2
+// twill on 4.
+fn standard_lift(shaft: Shaft, shafts: u32) -> BTreeSet<Shaft> {
+    let half = (shafts / 2).max(1);
+    (0..half)
+        .map(|i| Shaft((shaft.0 - 1 + i) % shafts + 1))
+        .collect()
+}
+
+impl Wif {
+    /// Replaces the treadling and tieup with the liftplan they make, as for a dobby loom.
+    /// The liftplan is built first if the draft doesn't have one. Fails if the draft has no
+    /// way to make a liftplan, or its liftplan doesn't match the treadling.
+    pub fn to_liftplan(&mut self) -> Result<()> {
+        self.build_or_validate_liftplan()?;
+        if self.liftplan.is_none() {
+            return Err(WifError::MissingLiftplan);
+        }
+        self.drop_treadling();
+        Ok(())
+    }
+
+    /// Builds a tieup and treadling that weave the liftplan on at most `max_treadles`
+    /// treadles, replacing any there were. If there are few enough different lifts, each
+    /// gets its own treadle, in order of first use. Otherwise a skeleton tieup is built,
+    /// with picks pressing several treadles at once and as few together as it can find in a
+    /// bounded search, and each pick pressing treadles that make its lift, chosen greedily.
+    /// The liftplan is kept.
+    ///
+    /// Fails with [`WifError::LiftplanNeedsMoreTreadles`] when no tieup is found that fits.
+    /// If neither the lifts no others combine to make nor one shaft per treadle would fit,
+    /// it fails straight away without searching.
+    pub fn derive_treadling(&mut self, max_treadles: u32) -> Result<()> {
+        let liftplan = self.liftplan.as_ref().ok_or(WifError::MissingLiftplan)?;
+        let mut lifts: Vec<&BTreeSet<Shaft>> = vec![];
+        for lift in liftplan.values().filter(|lift| !lift.is_empty()) {
+            if !lifts.contains(&lift) {
+                lifts.push(lift);
+            }
+        }
+        let tieup = if lifts.len() <= max_treadles as usize {
+            lifts.iter().map(|&lift| lift.clone()).collect()
+        } else {
+            skeleton(&lifts, max_treadles)?
+        };
+        let treadling = liftplan
+            .iter()
+            .map(|(&weft, lift)| {
+                let pressed = cover(lift, &tieup, tieup.len()).unwrap_or_default();
+                (
+                    weft,
+                    pressed.iter().map(|&t| Treadle(t as u32 + 1)).collect(),
+                )
+            })
+            .collect();
+
+        self.weaving_mut().treadles = tieup.len() as u32;
+        self.mark_present(sections::WEAVING);
+        self.tieup = Some((1..).map(Treadle).zip(tieup).collect());
+        self.treadling = Some(treadling);
+        self.mark_present(sections::TIEUP);
+        self.mark_present(sections::TREADLING);
+        Ok(())
+    }
+
+    /// Weaves the draft as drawn in ("tromp as writ"): each pick uses the treadles numbered
+    /// like the shafts the end of the same number is threaded on, so the weft follows the
+    /// threading. An existing tieup is kept and the liftplan rebuilt from it. A draft woven
+    /// from a liftplan alone gets each pick lifting the standard tieup's shafts, and a draft
+    /// with neither gets the standard tieup: each treadle lifts half the shafts, starting
+    /// at its own, as for plain weave on 2 shafts or a 2/2 twill on 4.
+    ///
+    /// Fails if the threading uses a shaft with no treadle in the existing tieup.
+    pub fn treadle_as_drawn_in(&mut self) -> Result<()> {
+        let threading = self.threading.clone().unwrap_or_default();
+        let shafts = threading
+            .values()
+            .flatten()
+            .map(|s| s.0)
+            .chain(self.shafts())
+            .max()
+            .unwrap_or(0);
+        let treadling: Table<Weft, BTreeSet<Treadle>> = threading
+            .iter()
+            .map(|(end, shafts)| (Weft(end.0), shafts.iter().map(|s| Treadle(s.0)).collect()))
+            .collect();
+
+        if let Some(tieup) = &self.tieup {
+            let treadles = tieup
+                .keys()
+                .map(|t| t.0)
+                .chain(self.treadles())
+                .max()
+                .unwrap_or(0);
+            if let Some(treadle) = treadling.values().flatten().find(|t| t.0 > treadles) {
+                return Err(WifError::OutOfRange {
+                    section: sections::TIEUP.into(),
+                    what: "treadle",
+                    value: treadle.0,
+                    max: treadles,
+                });
+            }
+            self.set_treadling(treadling);
+        } else if self.liftplan.is_some() && self.treadling.is_none() {
+            let liftplan = treadling
+                .into_iter()
+                .map(|(pick, treadles)| {
+                    let lift = treadles
+                        .iter()
+                        .flat_map(|t| standard_lift(Shaft(t.0), shafts))
+                        .collect();
+                    (pick, lift)
+                })
+                .collect();
+            self.set_liftplan(liftplan);
+        } else {
+            self.set_tieup(
+                (1..=shafts)
+                    .map(|shaft| (Treadle(shaft), standard_lift(Shaft(shaft), shafts)))
+                    .collect(),
+            );
+            self.set_treadling(treadling);
+        }
+        Ok(())
+    }
+
+    /// Converts a skeleton tieup, where some picks press several treadles at once, into a full
+    /// tieup with one treadle per distinct combination, so every pick uses a single treadle.
+    /// Returns the number of treadles the new tieup uses. Drafts without treadling are left
+    /// alone.
+    pub fn expand_skeleton_tieup(&mut self) -> u32 {
+        let (Some(treadling), Some(tieup)) = (self.treadling.as_ref(), self.tieup.as_ref()) else {
+            return 0;
+        };
+        let mut combinations: BTreeMap<&BTreeSet<Treadle>, Treadle> = BTreeMap::new();
+        let mut new_tieup = Table::new();
+        let mut new_treadling = Table::new();
+        for (&pick, treadles) in treadling {
+            if treadles.is_empty() {
+                continue;
+            }
+            let next = Treadle(combinations.len() as u32 + 1);
+            let treadle = *combinations.entry(treadles).or_insert_with(|| {
+                let shafts = treadles
+                    .iter()
+                    .filter_map(|t| tieup.get(t))
+                    .flatten()
+                    .copied()
+                    .collect();
+                new_tieup.insert(next, shafts);
+                next
+            });
+            new_treadling.insert(pick, BTreeSet::from([treadle]));
+        }
+        let used = combinations.len() as u32;
+        self.tieup = Some(new_tieup);
+        self.treadling = Some(new_treadling);
+        if let Some(weaving) = self.weaving.as_mut() {
+            weaving.treadles = used;
+        }
+        used
+    }
+}
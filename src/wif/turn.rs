@@ -0,0 +1,182 @@
+use std::collections::BTreeSet;
+
+use super::{sections, WarpS, WarpSymbolPalette, Weaving, WeftS, WeftSymbolPalette, Wif};
+use crate::{Result, Shaft, Table, Treadle, Warp, Weft};
+
+// Moves a per-thread table from one side of the cloth to the other.
+fn rekey<K: Ord + Copy, J: Ord, V>(
+    table: Option<Table<K, V>>,
+    key: impl Fn(K) -> J,
+) -> Option<Table<J, V>> {
+    table.map(|t| t.into_iter().map(|(k, v)| (key(k), v)).collect())
+}
+
+// The only item in `set`, if it has exactly one.
+fn single<T: Copy + Ord>(set: Option<&BTreeSet<T>>) -> Option<T> {
+    match set {
+        Some(set) if set.len() == 1 => set.first().copied(),
+        _ => None,
+    }
+}
+
+// [WARP] and [WEFT] hold the same fields.
+macro_rules! swap_thread_section {
+    ($from:expr, $to:ident) => {
+        $from.map(|s| $to {
+            threads: s.threads,
+            color: s.color,
+            symbol: s.symbol,
+            symbol_number: s.symbol_number,
+            units: s.units,
+            spacing: s.spacing,
+            thickness: s.thickness,
+            spacing_zoom: s.spacing_zoom,
+            thickness_zoom: s.thickness_zoom,
+        })
+    };
+}
+
+impl Wif {
+    /// Turns the draft a quarter turn, so the picks become ends and the ends picks, with
+    /// the same face showing: wherever the warp was on top, the weft now is, and the other
+    /// way round. Colors, thickness, spacing and symbols swap sides with their threads.
+    ///
+    /// If every end is threaded on one shaft and every pick uses one treadle, the old
+    /// treadling becomes the threading and the old threading the treadling, with the tieup
+    /// flipped to match. Otherwise each different lift becomes a shaft, in order of first
+    /// use, and the turned draft is woven from a liftplan alone, built from the treadling
+    /// and tieup if the draft had none.
+    ///
+    /// Fails, leaving the draft as it was, if the liftplan doesn't match the treadling.
+    pub fn turn(&mut self) -> Result<()> {
+        self.build_or_validate_liftplan()?;
+        let (ends, picks) = (self.width().unwrap_or(0), self.height().unwrap_or(0));
+        let threaded: Option<Vec<Shaft>> = (1..=ends)
+            .map(|end| single(self.threading.as_ref().and_then(|t| t.get(&Warp(end)))))
+            .collect();
+        let treadled: Option<Vec<Treadle>> = (1..=picks)
+            .map(|pick| single(self.treadling.as_ref().and_then(|t| t.get(&Weft(pick)))))
+            .collect();
+        let rising_shed = self.weaving.as_ref().and_then(|w| w.rising_shed);
+
+        if let (Some(threaded), Some(treadled), Some(tieup)) = (threaded, treadled, &self.tieup) {
+            let shafts = self.shafts().unwrap_or(0);
+            let treadles = self.treadles().unwrap_or(0);
+            let turned_tieup = (1..=shafts)
+                .map(|shaft| {
+                    let lifted: BTreeSet<Shaft> = (1..=treadles)
+                        .filter(|&t| {
+                            !tieup
+                                .get(&Treadle(t))
+                                .is_some_and(|s| s.contains(&Shaft(shaft)))
+                        })
+                        .map(Shaft)
+                        .collect();
+                    (Treadle(shaft), lifted)
+                })
+                .filter(|(_, lifted)| !lifted.is_empty())
+                .collect();
+            self.threading = Some(
+                (1..)
+                    .map(Warp)
+                    .zip(treadled)
+                    .map(|(end, treadle)| (end, [Shaft(treadle.0)].into()))
+                    .collect(),
+            );
+            self.treadling = Some(
+                (1..)
+                    .map(Weft)
+                    .zip(threaded)
+                    .map(|(pick, shaft)| (pick, [Treadle(shaft.0)].into()))
+                    .collect(),
+            );
+            self.tieup = Some(turned_tieup);
+            self.liftplan = None;
+            self.weaving = Some(Weaving {
+                shafts: treadles,
+                treadles: shafts,
+                rising_shed,
+            });
+            self.build_or_validate_liftplan()
+                .expect("a draft without a liftplan can always have one built");
+        } else {
+            let liftplan = self.liftplan.take().unwrap_or_default();
+            let lifts: Vec<BTreeSet<Shaft>> = (1..=picks)
+                .map(|pick| liftplan.get(&Weft(pick)).cloned().unwrap_or_default())
+                .collect();
+            let mut distinct: Vec<&BTreeSet<Shaft>> = vec![];
+            for lift in &lifts {
+                if !distinct.contains(&lift) {
+                    distinct.push(lift);
+                }
+            }
+            let shaft_of = |lift: &BTreeSet<Shaft>| {
+                let index = distinct.iter().position(|l| *l == lift)?;
+                Some(Shaft(index as u32 + 1))
+            };
+            let threading = (1..)
+                .map(Warp)
+                .zip(&lifts)
+                .filter_map(|(end, lift)| Some((end, [shaft_of(lift)?].into())))
+                .collect();
+            let empty = BTreeSet::new();
+            let turned_liftplan = (1..=ends)
+                .map(|end| {
+                    let shafts = self
+                        .threading
+                        .as_ref()
+                        .and_then(|t| t.get(&Warp(end)))
+                        .unwrap_or(&empty);
+                    let lifted = (1..)
+                        .zip(&distinct)
+                        .filter(|(_, lift)| lift.is_disjoint(shafts))
+                        .map(|(s, _)| Shaft(s))
+                        .collect();
+                    (Weft(end), lifted)
+                })
+                .collect();
+            self.weaving = Some(Weaving {
+                shafts: distinct.len() as u32,
+                treadles: 0,
+                rising_shed,
+            });
+            self.threading = Some(threading);
+            self.liftplan = Some(turned_liftplan);
+            self.treadling = None;
+            self.tieup = None;
+            self.mark_present(sections::LIFTPLAN);
+            self.mark_absent(sections::TREADLING);
+            self.mark_absent(sections::TIEUP);
+        }
+
+        let warp = self.warp.take();
+        self.warp = swap_thread_section!(self.weft.take(), WarpS);
+        self.weft = swap_thread_section!(warp, WeftS);
+        let warp_palette = self.warp_symbol_palette.take();
+        self.warp_symbol_palette = self
+            .weft_symbol_palette
+            .take()
+            .map(|p| WarpSymbolPalette { entries: p.entries });
+        self.weft_symbol_palette = warp_palette.map(|p| WeftSymbolPalette { entries: p.entries });
+        std::mem::swap(&mut self.warp_symbol_table, &mut self.weft_symbols_table);
+
+        macro_rules! swap_tables {
+            ($($warp:ident <-> $weft:ident),*) => {
+                $(
+                    let warp = self.$warp.take();
+                    self.$warp = rekey(self.$weft.take(), |w: Weft| Warp(w.0));
+                    self.$weft = rekey(warp, |w: Warp| Weft(w.0));
+                )*
+            };
+        }
+        swap_tables!(
+            warp_colors <-> weft_colors,
+            warp_thickness <-> weft_thickness,
+            warp_thickness_zoom <-> weft_thickness_zoom,
+            warp_spacing <-> weft_spacing,
+            warp_spacing_zoom <-> weft_spacing_zoom,
+            warp_symbols <-> weft_symbols
+        );
+        Ok(())
+    }
+}
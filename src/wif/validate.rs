@@ -0,0 +1,565 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::{sections, Wif};
+use crate::{liftplan_from_threading_and_treadle, Shaft, Table, Treadle, Units, Warp, Weft};
+
+/// How serious a [`ValidationIssue`] is, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file follows the spec and the draft can be woven, but other programs may show it
+    /// differently.
+    Stylistic,
+    /// The file follows the spec, but the draft contradicts itself or can't be woven as
+    /// written.
+    BrokenDraft,
+    /// The file breaks a rule of the WIF spec.
+    SpecViolation,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Stylistic => "stylistic",
+            Severity::BrokenDraft => "broken-draft",
+            Severity::SpecViolation => "spec-violation",
+        })
+    }
+}
+
+/// Which issues to show and which to fail on, so one validator can serve both strict and
+/// forgiving tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// Issues less serious than this are left out.
+    pub report: Severity,
+    /// Issues at least this serious fail validation.
+    pub fail: Severity,
+}
+
+impl Thresholds {
+    /// Reports everything and fails on anything, for archives that only accept clean files.
+    pub const STRICT: Thresholds = Thresholds {
+        report: Severity::Stylistic,
+        fail: Severity::Stylistic,
+    };
+    /// Hides stylistic issues and fails only on spec violations, for editors that should open
+    /// whatever they're given.
+    pub const FORGIVING: Thresholds = Thresholds {
+        report: Severity::BrokenDraft,
+        fail: Severity::SpecViolation,
+    };
+
+    pub fn reports(&self, severity: Severity) -> bool {
+        severity >= self.report
+    }
+
+    pub fn fails(&self, severity: Severity) -> bool {
+        severity >= self.fail
+    }
+}
+
+/// Reports everything and fails on broken drafts and spec violations.
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            report: Severity::Stylistic,
+            fail: Severity::BrokenDraft,
+        }
+    }
+}
+
+/// What a [`ValidationIssue`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueCode {
+    /// Threads, shafts or treadles are used without the section that counts them.
+    MissingSection,
+    /// A shaft beyond the [WEAVING] `Shafts` count.
+    ShaftOutOfRange,
+    /// A treadle beyond the [WEAVING] `Treadles` count.
+    TreadleOutOfRange,
+    /// Treadling without a tieup.
+    MissingTieup,
+    /// A treadle pressed in the treadling that has nothing tied to it.
+    UntiedTreadle,
+    /// A table entry for a thread outside `1..=Threads`.
+    ThreadOutOfRange,
+    /// A color index with no entry in the color table.
+    MissingColor,
+    /// A color table entry outside the palette's range.
+    ColorOutOfRange,
+    /// A symbol number with no entry in the symbol table.
+    MissingSymbol,
+    /// A palette's `Entries` differs from the highest index in its table.
+    PaletteSizeMismatch,
+    /// The liftplan disagrees with the treadling and tieup.
+    LiftplanMismatch,
+    /// The liftplan lists the shafts the treadling and tieup leave down on every pick, as if
+    /// written for the opposite shed direction.
+    InvertedLiftplan,
+    /// WARP or WEFT `Units` other than the spec's Decipoints, Inches or Centimeters, so its
+    /// lengths can't be converted.
+    UnknownUnits,
+}
+
+impl IssueCode {
+    pub fn severity(&self) -> Severity {
+        match self {
+            IssueCode::PaletteSizeMismatch | IssueCode::UnknownUnits => Severity::Stylistic,
+            IssueCode::MissingTieup
+            | IssueCode::UntiedTreadle
+            | IssueCode::LiftplanMismatch
+            | IssueCode::InvertedLiftplan => Severity::BrokenDraft,
+            _ => Severity::SpecViolation,
+        }
+    }
+
+    /// A stable name for the code, such as `shaft-out-of-range`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueCode::MissingSection => "missing-section",
+            IssueCode::ShaftOutOfRange => "shaft-out-of-range",
+            IssueCode::TreadleOutOfRange => "treadle-out-of-range",
+            IssueCode::MissingTieup => "missing-tieup",
+            IssueCode::UntiedTreadle => "untied-treadle",
+            IssueCode::ThreadOutOfRange => "thread-out-of-range",
+            IssueCode::MissingColor => "missing-color",
+            IssueCode::ColorOutOfRange => "color-out-of-range",
+            IssueCode::MissingSymbol => "missing-symbol",
+            IssueCode::PaletteSizeMismatch => "palette-size-mismatch",
+            IssueCode::LiftplanMismatch => "liftplan-mismatch",
+            IssueCode::InvertedLiftplan => "inverted-liftplan",
+            IssueCode::UnknownUnits => "unknown-units",
+        }
+    }
+}
+
+/// A problem found by [`Wif::validate`]. `key` is the table entry or thread it was found
+/// at, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: IssueCode,
+    pub section: &'static str,
+    pub key: Option<u32>,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} [{}]",
+            self.severity,
+            self.code.as_str(),
+            self.section
+        )?;
+        if let Some(key) = self.key {
+            write!(f, " {key}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[derive(Default)]
+struct Issues(Vec<ValidationIssue>);
+
+impl Issues {
+    fn push(&mut self, code: IssueCode, section: &'static str, key: Option<u32>, message: String) {
+        self.0.push(ValidationIssue {
+            severity: code.severity(),
+            code,
+            section,
+            key,
+            message,
+        });
+    }
+
+    // Entries of a table of sets that use a shaft or treadle beyond `max`.
+    fn beyond<K, T>(
+        &mut self,
+        code: IssueCode,
+        section: &'static str,
+        table: Option<&Table<K, BTreeSet<T>>>,
+        max: u32,
+        key: impl Fn(&K) -> u32,
+        value: impl Fn(&T) -> u32,
+    ) {
+        let what = match code {
+            IssueCode::ShaftOutOfRange => "shaft",
+            _ => "treadle",
+        };
+        for (k, set) in table.into_iter().flatten() {
+            if let Some(highest) = set.iter().map(&value).filter(|&v| v > max).max() {
+                self.push(
+                    code,
+                    section,
+                    Some(key(k)),
+                    format!("uses {what} {highest}, but the draft has {max}"),
+                );
+            }
+        }
+    }
+
+    // Keys of a per-thread table outside `1..=threads`.
+    fn threads<K, V>(
+        &mut self,
+        section: &'static str,
+        table: Option<&Table<K, V>>,
+        threads: u32,
+        key: impl Fn(&K) -> u32,
+    ) {
+        for k in table.into_iter().flat_map(|t| t.keys()).map(key) {
+            if k == 0 || k > threads {
+                self.push(
+                    IssueCode::ThreadOutOfRange,
+                    section,
+                    Some(k),
+                    format!("is outside the {threads} threads"),
+                );
+            }
+        }
+    }
+
+    // Entries of a per-thread table whose color or symbol index doesn't exist.
+    fn dangling<K, I: Copy + fmt::Display>(
+        &mut self,
+        code: IssueCode,
+        section: &'static str,
+        table: Option<&Table<K, I>>,
+        key: impl Fn(&K) -> u32,
+        exists: impl Fn(I) -> bool,
+    ) {
+        let what = match code {
+            IssueCode::MissingColor => "color",
+            _ => "symbol",
+        };
+        for (k, &idx) in table.into_iter().flatten() {
+            if !exists(idx) {
+                self.push(
+                    code,
+                    section,
+                    Some(key(k)),
+                    format!("uses {what} {idx}, which is not in the {what} table"),
+                );
+            }
+        }
+    }
+
+    fn palette(&mut self, section: &'static str, entries: Option<usize>, table: Option<usize>) {
+        if let (Some(entries), Some(highest)) = (entries, table) {
+            if entries != highest {
+                self.push(
+                    IssueCode::PaletteSizeMismatch,
+                    section,
+                    None,
+                    format!("declares {entries} entries, but the table goes up to {highest}"),
+                );
+            }
+        }
+    }
+}
+
+impl Wif {
+    /// Checks the draft for references to shafts, treadles, threads, colors and symbols that
+    /// don't exist, for palettes whose sizes disagree with their tables, for units the spec
+    /// doesn't define, and for a liftplan that disagrees with the treadling. An empty list means no problems were found.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        use sections::*;
+        let mut issues = Issues::default();
+
+        match &self.weaving {
+            Some(weaving) => {
+                let (shafts, treadles) = (weaving.shafts, weaving.treadles);
+                let shaft = |s: &Shaft| s.0;
+                let treadle = |t: &Treadle| t.0;
+                let code = IssueCode::ShaftOutOfRange;
+                issues.beyond(
+                    code,
+                    THREADING,
+                    self.threading.as_ref(),
+                    shafts,
+                    |w| w.0,
+                    shaft,
+                );
+                issues.beyond(code, TIEUP, self.tieup.as_ref(), shafts, |t| t.0, shaft);
+                issues.beyond(
+                    code,
+                    LIFTPLAN,
+                    self.liftplan.as_ref(),
+                    shafts,
+                    |w| w.0,
+                    shaft,
+                );
+                let code = IssueCode::TreadleOutOfRange;
+                issues.beyond(
+                    code,
+                    TREADLING,
+                    self.treadling.as_ref(),
+                    treadles,
+                    |w| w.0,
+                    treadle,
+                );
+                for &t in self.tieup.iter().flat_map(|t| t.keys()) {
+                    if t.0 > treadles {
+                        issues.push(
+                            code,
+                            TIEUP,
+                            Some(t.0),
+                            format!("is beyond the {treadles} treadles"),
+                        );
+                    }
+                }
+            }
+            None if self.threading.is_some()
+                || self.tieup.is_some()
+                || self.treadling.is_some()
+                || self.liftplan.is_some() =>
+            {
+                issues.push(
+                    IssueCode::MissingSection,
+                    WEAVING,
+                    None,
+                    "is missing, so shaft and treadle counts can't be checked".into(),
+                );
+            }
+            None => {}
+        }
+
+        match (&self.treadling, &self.tieup) {
+            (Some(_), None) => issues.push(
+                IssueCode::MissingTieup,
+                TREADLING,
+                None,
+                "is given without a tieup".into(),
+            ),
+            (Some(treadling), Some(tieup)) => {
+                let untied: BTreeSet<_> = treadling
+                    .values()
+                    .flatten()
+                    .filter(|t| tieup.get(t).is_none_or(BTreeSet::is_empty))
+                    .collect();
+                for treadle in untied {
+                    issues.push(
+                        IssueCode::UntiedTreadle,
+                        TIEUP,
+                        Some(treadle.0),
+                        "is pressed in the treadling but has nothing tied to it".into(),
+                    );
+                }
+                let built = liftplan_from_threading_and_treadle(Some(treadling), Some(tieup));
+                let inverted = self.liftplan_is_inverted();
+                if inverted {
+                    let shed = match self.weaving.as_ref().and_then(|w| w.rising_shed) {
+                        Some(false) => "rising",
+                        _ => "sinking",
+                    };
+                    issues.push(
+                        IssueCode::InvertedLiftplan,
+                        LIFTPLAN,
+                        None,
+                        format!("is the inverse of the treadling and tieup, as for a {shed} shed"),
+                    );
+                }
+                let empty = BTreeSet::new();
+                for (weft, lift) in built.iter().flatten().filter(|_| !inverted) {
+                    let listed = self
+                        .liftplan
+                        .as_ref()
+                        .map(|l| l.get(weft).unwrap_or(&empty));
+                    if listed.is_some_and(|listed| listed != lift) {
+                        issues.push(
+                            IssueCode::LiftplanMismatch,
+                            LIFTPLAN,
+                            Some(weft.0),
+                            "differs from the treadling and tieup".into(),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let thread_tables = [
+            (WARP, self.width(), self.threading.is_some()),
+            (
+                WEFT,
+                self.height(),
+                self.treadling.is_some() || self.liftplan.is_some(),
+            ),
+        ];
+        for (section, threads, used) in thread_tables {
+            if threads.is_none() && used {
+                issues.push(
+                    IssueCode::MissingSection,
+                    section,
+                    None,
+                    "is missing, so thread counts can't be checked".into(),
+                );
+            }
+        }
+        let units = [
+            (WARP, self.warp.as_ref().and_then(|w| w.units.as_ref())),
+            (WEFT, self.weft.as_ref().and_then(|w| w.units.as_ref())),
+        ];
+        for (section, units) in units {
+            if let Some(Units::Other(units)) = units {
+                issues.push(
+                    IssueCode::UnknownUnits,
+                    section,
+                    None,
+                    format!("gives its lengths in {units:?}, which the spec doesn't define"),
+                );
+            }
+        }
+        if let Some(ends) = self.width() {
+            let end = |w: &Warp| w.0;
+            issues.threads(THREADING, self.threading.as_ref(), ends, end);
+            issues.threads(WARP_THICKNESS, self.warp_thickness.as_ref(), ends, end);
+            issues.threads(
+                WARP_THICKNESS_ZOOM,
+                self.warp_thickness_zoom.as_ref(),
+                ends,
+                end,
+            );
+            issues.threads(WARP_SPACING, self.warp_spacing.as_ref(), ends, end);
+            issues.threads(
+                WARP_SPACING_ZOOM,
+                self.warp_spacing_zoom.as_ref(),
+                ends,
+                end,
+            );
+            issues.threads(WARP_COLORS, self.warp_colors.as_ref(), ends, end);
+            issues.threads(WARP_SYMBOLS, self.warp_symbols.as_ref(), ends, end);
+        }
+        if let Some(picks) = self.height() {
+            let pick = |w: &Weft| w.0;
+            issues.threads(TREADLING, self.treadling.as_ref(), picks, pick);
+            issues.threads(LIFTPLAN, self.liftplan.as_ref(), picks, pick);
+            issues.threads(WEFT_THICKNESS, self.weft_thickness.as_ref(), picks, pick);
+            issues.threads(
+                WEFT_THICKNESS_ZOOM,
+                self.weft_thickness_zoom.as_ref(),
+                picks,
+                pick,
+            );
+            issues.threads(WEFT_SPACING, self.weft_spacing.as_ref(), picks, pick);
+            issues.threads(
+                WEFT_SPACING_ZOOM,
+                self.weft_spacing_zoom.as_ref(),
+                picks,
+                pick,
+            );
+            issues.threads(WEFT_COLORS, self.weft_colors.as_ref(), picks, pick);
+            issues.threads(WEFT_SYMBOLS, self.weft_symbols.as_ref(), picks, pick);
+        }
+
+        let has_color = |idx| {
+            self.color_table
+                .as_ref()
+                .is_some_and(|t| t.contains_key(&idx))
+        };
+        let has_warp_symbol =
+            |idx| (self.warp_symbol_table.as_ref()).is_some_and(|t| t.contains_key(&idx));
+        let has_weft_symbol =
+            |idx| (self.weft_symbols_table.as_ref()).is_some_and(|t| t.contains_key(&idx));
+        let (end, pick) = (|w: &Warp| w.0, |w: &Weft| w.0);
+        let (color, symbol) = (IssueCode::MissingColor, IssueCode::MissingSymbol);
+        issues.dangling(
+            color,
+            WARP_COLORS,
+            self.warp_colors.as_ref(),
+            end,
+            has_color,
+        );
+        issues.dangling(
+            color,
+            WEFT_COLORS,
+            self.weft_colors.as_ref(),
+            pick,
+            has_color,
+        );
+        issues.dangling(
+            symbol,
+            WARP_SYMBOLS,
+            self.warp_symbols.as_ref(),
+            end,
+            has_warp_symbol,
+        );
+        issues.dangling(
+            symbol,
+            WEFT_SYMBOLS,
+            self.weft_symbols.as_ref(),
+            pick,
+            has_weft_symbol,
+        );
+        let defaults = [
+            (WARP, self.warp.as_ref().and_then(|w| w.color)),
+            (WEFT, self.weft.as_ref().and_then(|w| w.color)),
+        ];
+        for (section, default) in defaults {
+            if let Some(default) = default.filter(|c| !has_color(c.idx)) {
+                issues.push(
+                    color,
+                    section,
+                    None,
+                    format!("default color {} is not in the color table", default.idx),
+                );
+            }
+        }
+        let warp_symbol = self.warp.as_ref().and_then(|w| w.symbol_number);
+        let weft_symbol = self.weft.as_ref().and_then(|w| w.symbol_number);
+        let defaults = [
+            (WARP, warp_symbol.filter(|&idx| !has_warp_symbol(idx))),
+            (WEFT, weft_symbol.filter(|&idx| !has_weft_symbol(idx))),
+        ];
+        for (section, default) in defaults {
+            if let Some(default) = default {
+                issues.push(
+                    symbol,
+                    section,
+                    None,
+                    format!("default symbol {default} is not in the symbol table"),
+                );
+            }
+        }
+        if let (Some(table), Some(palette)) = (&self.color_table, &self.color_palette) {
+            let (low, high) = palette.range;
+            for (idx, c) in table {
+                if [c.red, c.green, c.blue]
+                    .iter()
+                    .any(|&c| c < low || c > high)
+                {
+                    issues.push(
+                        IssueCode::ColorOutOfRange,
+                        COLOR_TABLE,
+                        Some(idx.0),
+                        format!("is outside the palette range {low},{high}"),
+                    );
+                }
+            }
+        }
+        issues.palette(
+            COLOR_PALETTE,
+            self.color_palette.as_ref().map(|p| p.entries),
+            self.color_table
+                .as_ref()
+                .map(|t| super::palette_entries(t, |idx| idx.0)),
+        );
+        issues.palette(
+            WARP_SYMBOL_PALETTE,
+            self.warp_symbol_palette.as_ref().map(|p| p.entries),
+            self.warp_symbol_table
+                .as_ref()
+                .map(|t| super::palette_entries(t, |idx| idx.0)),
+        );
+        issues.palette(
+            WEFT_SYMBOL_PALETTE,
+            self.weft_symbol_palette.as_ref().map(|p| p.entries),
+            self.weft_symbols_table
+                .as_ref()
+                .map(|t| super::palette_entries(t, |idx| idx.0)),
+        );
+        issues.0
+    }
+}
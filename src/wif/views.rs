@@ -0,0 +1,78 @@
+use std::collections::BTreeSet;
+use std::ops::Index;
+
+use super::Wif;
+use crate::{Shaft, Table, Treadle, Warp, Weft};
+
+static NO_SHAFTS: BTreeSet<Shaft> = BTreeSet::new();
+static NO_TREADLES: BTreeSet<Treadle> = BTreeSet::new();
+
+/// A read-only view of one of the draft's tables. Indexing a thread that isn't in the table
+/// (or a draft without the table) gives an empty set.
+#[derive(Debug, Clone, Copy)]
+pub struct TableView<'a, K, V> {
+    table: Option<&'a Table<K, BTreeSet<V>>>,
+}
+
+/// Shafts by end, from [`Wif::threading_view`].
+pub type ThreadingView<'a> = TableView<'a, Warp, Shaft>;
+/// Treadles by pick, from [`Wif::treadling_view`].
+pub type TreadlingView<'a> = TableView<'a, Weft, Treadle>;
+/// Shafts by pick, from [`Wif::liftplan_view`].
+pub type LiftplanView<'a> = TableView<'a, Weft, Shaft>;
+
+impl<'a, K: Ord, V> TableView<'a, K, V> {
+    /// The entry for a thread, if the table has one.
+    pub fn get(&self, key: K) -> Option<&'a BTreeSet<V>> {
+        self.table?.get(&key)
+    }
+
+    /// Whether the draft has this table at all.
+    pub fn is_present(&self) -> bool {
+        self.table.is_some()
+    }
+}
+
+impl Index<Warp> for ThreadingView<'_> {
+    type Output = BTreeSet<Shaft>;
+
+    fn index(&self, warp: Warp) -> &BTreeSet<Shaft> {
+        self.get(warp).unwrap_or(&NO_SHAFTS)
+    }
+}
+
+impl Index<Weft> for TreadlingView<'_> {
+    type Output = BTreeSet<Treadle>;
+
+    fn index(&self, weft: Weft) -> &BTreeSet<Treadle> {
+        self.get(weft).unwrap_or(&NO_TREADLES)
+    }
+}
+
+impl Index<Weft> for LiftplanView<'_> {
+    type Output = BTreeSet<Shaft>;
+
+    fn index(&self, weft: Weft) -> &BTreeSet<Shaft> {
+        self.get(weft).unwrap_or(&NO_SHAFTS)
+    }
+}
+
+impl Wif {
+    pub fn threading_view(&self) -> ThreadingView<'_> {
+        TableView {
+            table: self.threading.as_ref(),
+        }
+    }
+
+    pub fn treadling_view(&self) -> TreadlingView<'_> {
+        TableView {
+            table: self.treadling.as_ref(),
+        }
+    }
+
+    pub fn liftplan_view(&self) -> LiftplanView<'_> {
+        TableView {
+            table: self.liftplan.as_ref(),
+        }
+    }
+}
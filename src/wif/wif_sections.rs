@@ -1,16 +1,25 @@
 use std::{collections::BTreeSet, str::FromStr};
 
-use configparser::ini::Ini;
+use crate::{
+    wifparse::WifParse, Color, ColorIndex, Shaft, SymbolIndex, Table, Treadle, WifContext, WifError,
+};
 
-use crate::{wifparse::WifParse, Color, Shaft, Table, Treadle, WifContext, WifError};
+use super::{
+    get_field, get_required_field, ini::Ini, sections, ParseWarning, Section, Symbol, WifHeader,
+};
 
-use super::{get_field, get_required_field, sections, Section, WifHeader};
+/// State shared by every section as it is read.
+pub(crate) struct ReadContext<'w> {
+    /// Skip table rows that fail to parse instead of failing the whole file.
+    pub lenient_tables: bool,
+    pub warnings: &'w mut Vec<ParseWarning>,
+}
 
 pub(crate) trait WifSection {
     const NAME: &str;
     type Output;
     fn write(value: &Self::Output, ini: &mut Ini);
-    fn read(ini: &Ini) -> Result<Self::Output, crate::WifError>;
+    fn read(ini: &Ini, ctx: &mut ReadContext) -> Result<Self::Output, crate::WifError>;
 }
 
 macro_rules! read_fields {
@@ -64,7 +73,7 @@ macro_rules! wr_fields {
         }
 
 
-        fn read(ini: &Ini) -> Result<Self::Output, crate::WifError> {
+        fn read(ini: &Ini, _ctx: &mut ReadContext) -> Result<Self::Output, crate::WifError> {
             Ok(read_fields! {
                 ini,
                 $($blah)*
@@ -83,8 +92,8 @@ macro_rules! wr_table {
             s.write_table(value);
         }
 
-        fn read(ini: &Ini) -> Result<Self::Output, crate::WifError> {
-            parse_table(ini, Self::NAME)
+        fn read(ini: &Ini, ctx: &mut ReadContext) -> Result<Self::Output, crate::WifError> {
+            parse_table(ini, Self::NAME, ctx)
         }
     };
 }
@@ -124,7 +133,7 @@ pub(crate) struct WeftSymbolPalette;
 impl WifSection for WeftSymbolPalette {
     const NAME: &str = sections::WEFT_SYMBOL_PALETTE;
 
-    type Output = super::WarpSymbolPalette;
+    type Output = super::WeftSymbolPalette;
 
     wr_fields! {entries: "Entries"}
 }
@@ -133,7 +142,7 @@ pub(crate) struct ColorTable;
 impl WifSection for ColorTable {
     const NAME: &str = sections::COLOR_TABLE;
 
-    type Output = super::BTreeMap<u32, Color>;
+    type Output = super::BTreeMap<ColorIndex, Color>;
     wr_table! {}
 }
 
@@ -224,7 +233,7 @@ pub(crate) struct WarpSymbolTable;
 impl WifSection for WarpSymbolTable {
     const NAME: &str = sections::WARP_SYMBOL_TABLE;
 
-    type Output = super::BTreeMap<u32, String>;
+    type Output = super::BTreeMap<SymbolIndex, Symbol>;
     wr_table! {}
 }
 
@@ -232,7 +241,7 @@ pub(crate) struct WeftSymbolTable;
 impl WifSection for WeftSymbolTable {
     const NAME: &str = sections::WEFT_SYMBOL_TABLE;
 
-    type Output = super::BTreeMap<u32, String>;
+    type Output = super::BTreeMap<SymbolIndex, Symbol>;
     wr_table! {}
 }
 
@@ -280,7 +289,7 @@ pub(crate) struct WarpColors;
 impl WifSection for WarpColors {
     const NAME: &str = sections::WARP_COLORS;
 
-    type Output = super::BTreeMap<super::Warp, u32>;
+    type Output = super::BTreeMap<super::Warp, ColorIndex>;
     wr_table! {}
 }
 
@@ -288,7 +297,7 @@ pub(crate) struct WarpSymbols;
 impl WifSection for WarpSymbols {
     const NAME: &str = sections::WARP_SYMBOLS;
 
-    type Output = super::BTreeMap<super::Warp, u32>;
+    type Output = super::BTreeMap<super::Warp, SymbolIndex>;
     wr_table! {}
 }
 
@@ -328,7 +337,7 @@ pub(crate) struct WeftColors;
 impl WifSection for WeftColors {
     const NAME: &str = sections::WEFT_COLORS;
 
-    type Output = super::BTreeMap<super::Weft, u32>;
+    type Output = super::BTreeMap<super::Weft, ColorIndex>;
     wr_table! {}
 }
 
@@ -336,7 +345,7 @@ pub(crate) struct WeftSymbols;
 impl WifSection for WeftSymbols {
     const NAME: &str = sections::WEFT_SYMBOLS;
 
-    type Output = super::BTreeMap<super::Weft, u32>;
+    type Output = super::BTreeMap<super::Weft, SymbolIndex>;
     wr_table! {}
 }
 
@@ -356,27 +365,44 @@ impl WifSection for Liftplan {
     wr_table! {}
 }
 
-fn parse_table<S, T>(ini: &Ini, section_name: &str) -> super::Result<Table<S, T>>
+fn parse_table<S, T>(
+    ini: &Ini,
+    section_name: &str,
+    ctx: &mut ReadContext,
+) -> super::Result<Table<S, T>>
 where
     S: FromStr + Ord,
     T: WifParse,
 {
     let mut rv = Table::new();
     let section = ini
-        .get_map_ref()
-        .get(&section_name.to_lowercase())
+        .section(section_name)
         .ok_or_else(|| WifError::MissingSection {
             section: section_name.into(),
         })?;
-    for (k, v) in section {
+    for (k, v) in section.entries() {
         let Some(v) = v else {
             continue;
         };
-        let id: S = k.parse().map_err(|_| WifError::CouldNotParseTableKey {
-            section: section_name.into(),
-            key: k.clone(),
-        })?;
-        rv.insert(id, T::parse(v.clone()).add_context(section_name, k)?);
+        let row = k
+            .parse()
+            .map_err(|_| WifError::CouldNotParseTableKey {
+                section: section_name.into(),
+                key: k.into(),
+            })
+            .and_then(|id: S| Ok((id, T::parse(v).add_context(section_name, k)?)));
+        match row {
+            Ok((id, value)) => {
+                rv.insert(id, value);
+            }
+            Err(error) if ctx.lenient_tables => ctx.warnings.push(ParseWarning::BadRow {
+                section: section_name.into(),
+                key: k.into(),
+                value: v.into(),
+                error: error.to_string(),
+            }),
+            Err(error) => return Err(error),
+        }
     }
     Ok(rv)
 }
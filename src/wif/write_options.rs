@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+
+use super::{palette_entries, sections, ColorPalette, Wif};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionOrder {
+    /// Sections appear in the order they are generated: header, palettes, then tables.
+    #[default]
+    Insertion,
+    /// Sections appear in the order the spec lists them.
+    Spec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentsStyle {
+    /// CONTENTS lists only the sections that are written.
+    #[default]
+    PresentOnly,
+    /// CONTENTS lists every section in the spec, with `false` for the ones that are absent.
+    Complete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    /// What most Windows weaving programs expect.
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Which of the two equivalent descriptions of the weft sequence to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LiftMode {
+    #[default]
+    AsIs,
+    /// Drop LIFTPLAN when TREADLING and TIEUP can describe the draft on their own.
+    TreadlingOnly,
+    /// Drop TREADLING and TIEUP when a LIFTPLAN is available.
+    LiftplanOnly,
+}
+
+/// Which sections to write. [WIF] and CONTENTS are always written, and CONTENTS only lists
+/// the sections that pass the filter.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SectionFilter {
+    only: Option<Vec<String>>,
+    without: Vec<String>,
+}
+
+impl SectionFilter {
+    /// Every section.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Just the named sections.
+    pub fn only(sections: &[&str]) -> Self {
+        Self {
+            only: Some(sections.iter().map(|s| s.to_string()).collect()),
+            without: vec![],
+        }
+    }
+
+    /// Leave out a section.
+    pub fn without(mut self, section: &str) -> Self {
+        self.without.push(section.to_string());
+        self
+    }
+
+    pub fn allows(&self, section: &str) -> bool {
+        if section.eq_ignore_ascii_case(sections::WIF)
+            || section.eq_ignore_ascii_case(sections::CONTENTS)
+        {
+            return true;
+        }
+        let named = |names: &[String]| names.iter().any(|n| n.eq_ignore_ascii_case(section));
+        self.only.as_deref().is_none_or(named) && !named(&self.without)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetProgram {
+    Fiberworks,
+    WeavePoint,
+    PixeLoom,
+    WeaveIt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub section_order: SectionOrder,
+    pub contents: ContentsStyle,
+    /// A chrono format string for `[WIF] Date`.
+    pub date_format: String,
+    /// Rescale the COLOR TABLE into this range, updating COLOR PALETTE to match or adding
+    /// one. Colors are left alone if either range is empty or reversed.
+    pub color_range: Option<(u32, u32)>,
+    pub lift_mode: LiftMode,
+    pub line_ending: LineEnding,
+    /// Separate sections with an empty line.
+    pub blank_lines: bool,
+    pub sections: SectionFilter,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            section_order: Default::default(),
+            contents: Default::default(),
+            date_format: "%B %d, %Y".into(),
+            color_range: None,
+            lift_mode: Default::default(),
+            line_ending: Default::default(),
+            blank_lines: false,
+            sections: SectionFilter::all(),
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Options that the given program is known to import cleanly.
+    pub fn profile(target: TargetProgram) -> Self {
+        let defaults = WriteOptions {
+            line_ending: LineEnding::CrLf,
+            blank_lines: true,
+            ..WriteOptions::default()
+        };
+        match target {
+            TargetProgram::Fiberworks => WriteOptions {
+                section_order: SectionOrder::Spec,
+                // Fiberworks writes, and reads most reliably, numeric dates.
+                date_format: "%m/%d/%Y".into(),
+                contents: ContentsStyle::Complete,
+                color_range: Some((0, 999)),
+                lift_mode: LiftMode::TreadlingOnly,
+                ..defaults
+            },
+            TargetProgram::WeavePoint => WriteOptions {
+                section_order: SectionOrder::Spec,
+                date_format: "%B %d, %Y".into(),
+                color_range: Some((0, 255)),
+                lift_mode: LiftMode::TreadlingOnly,
+                ..defaults
+            },
+            TargetProgram::PixeLoom => WriteOptions {
+                section_order: SectionOrder::Spec,
+                date_format: "%B %d, %Y".into(),
+                contents: ContentsStyle::Complete,
+                color_range: Some((0, 255)),
+                lift_mode: LiftMode::LiftplanOnly,
+                ..defaults
+            },
+            TargetProgram::WeaveIt => WriteOptions {
+                section_order: SectionOrder::Spec,
+                date_format: "%B %d, %Y".into(),
+                color_range: Some((0, 999)),
+                ..defaults
+            },
+        }
+    }
+
+    /// The draft as it should be written: unchanged unless colors or lifts need adjusting.
+    pub(crate) fn adapt<'a>(&self, wif: &'a Wif) -> Cow<'a, Wif> {
+        let mut wif = Cow::Borrowed(wif);
+        if let Some(range) = self.color_range {
+            let current = wif.color_palette.as_ref().map(|cp| cp.range);
+            let from = current.unwrap_or((0, 999));
+            let increasing = |(low, high): (u32, u32)| low < high;
+            if current != Some(range) && increasing(from) && increasing(range) {
+                let wif = wif.to_mut();
+                if let Some(table) = wif.color_table.as_mut() {
+                    for color in table.values_mut() {
+                        color.red = rescale(color.red, from, range);
+                        color.green = rescale(color.green, from, range);
+                        color.blue = rescale(color.blue, from, range);
+                    }
+                    let entries = palette_entries(table, |idx| idx.0);
+                    wif.color_palette
+                        .get_or_insert(ColorPalette { entries, range })
+                        .range = range;
+                }
+            }
+        }
+        match self.lift_mode {
+            LiftMode::AsIs => {}
+            LiftMode::TreadlingOnly => {
+                if wif.treadling.is_some() && wif.tieup.is_some() && wif.liftplan.is_some() {
+                    wif.to_mut().liftplan = None;
+                }
+            }
+            LiftMode::LiftplanOnly => {
+                if wif.liftplan.is_some() && (wif.treadling.is_some() || wif.tieup.is_some()) {
+                    let wif = wif.to_mut();
+                    wif.treadling = None;
+                    wif.tieup = None;
+                }
+            }
+        }
+        wif
+    }
+
+    pub(crate) fn section_rank(&self, name: &str) -> usize {
+        match self.section_order {
+            SectionOrder::Insertion => 0,
+            SectionOrder::Spec => sections::ALL
+                .iter()
+                .position(|known| known.eq_ignore_ascii_case(name))
+                .unwrap_or(sections::ALL.len()),
+        }
+    }
+}
+
+pub(crate) fn rescale(value: u32, from: (u32, u32), to: (u32, u32)) -> u32 {
+    let span = from.1.saturating_sub(from.0).max(1) as f64;
+    let fraction = value.saturating_sub(from.0) as f64 / span;
+    (to.0 as f64 + fraction * to.1.saturating_sub(to.0) as f64).round() as u32
+}
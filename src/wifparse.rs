@@ -3,20 +3,20 @@ use std::collections::BTreeSet;
 use chrono::NaiveDate;
 
 use crate::wif::BaseColor;
-use crate::{Color, Shaft, Symbol, Treadle, Warp, Weft, WifError};
+use crate::{Color, ColorIndex, Shaft, Symbol, SymbolIndex, Treadle, Warp, Weft, WifError};
 
 pub trait WifParse {
-    fn parse(s: String) -> super::Result<Self>
+    fn parse(s: &str) -> super::Result<Self>
     where
         Self: Sized;
     fn unparse(&self) -> Option<String>;
 }
 impl WifParse for NaiveDate {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
-        NaiveDate::parse_from_str(&s, "%B %d, %Y").map_err(|e| WifError::InvalidDate { error: e })
+        NaiveDate::parse_from_str(s, "%B %d, %Y").map_err(|e| WifError::InvalidDate { error: e })
     }
 
     fn unparse(&self) -> Option<String> {
@@ -27,7 +27,7 @@ impl<T> WifParse for Option<T>
 where
     T: WifParse,
 {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -39,7 +39,7 @@ where
     }
 }
 impl WifParse for u32 {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -51,7 +51,7 @@ impl WifParse for u32 {
     }
 }
 impl WifParse for Shaft {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -63,7 +63,7 @@ impl WifParse for Shaft {
     }
 }
 impl WifParse for Weft {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -75,7 +75,7 @@ impl WifParse for Weft {
     }
 }
 impl WifParse for Warp {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -87,7 +87,31 @@ impl WifParse for Warp {
     }
 }
 impl WifParse for Treadle {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(s.parse()?)
+    }
+
+    fn unparse(&self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+}
+impl WifParse for ColorIndex {
+    fn parse(s: &str) -> crate::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(s.parse()?)
+    }
+
+    fn unparse(&self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+}
+impl WifParse for SymbolIndex {
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -99,7 +123,7 @@ impl WifParse for Treadle {
     }
 }
 impl WifParse for usize {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -112,7 +136,7 @@ impl WifParse for usize {
 }
 
 impl WifParse for f64 {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -125,11 +149,11 @@ impl WifParse for f64 {
 }
 
 impl WifParse for String {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
-        Ok(s)
+        Ok(s.to_string())
     }
 
     fn unparse(&self) -> Option<String> {
@@ -138,7 +162,7 @@ impl WifParse for String {
 }
 
 impl WifParse for bool {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -158,14 +182,14 @@ impl<T> WifParse for (T, T)
 where
     T: WifParse,
 {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
         let (a, b) = s
             .split_once(',')
-            .ok_or_else(|| WifError::ExpectedPair { saw: s.clone() })?;
-        Ok((T::parse(a.into())?, T::parse(b.into())?))
+            .ok_or_else(|| WifError::ExpectedPair { saw: s.to_string() })?;
+        Ok((T::parse(a)?, T::parse(b)?))
     }
 
     fn unparse(&self) -> Option<String> {
@@ -181,11 +205,11 @@ impl<T> WifParse for Vec<T>
 where
     T: WifParse,
 {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
-        s.split(',').map(|s| T::parse(s.into())).collect()
+        s.split(',').map(T::parse).collect()
     }
 
     fn unparse(&self) -> Option<String> {
@@ -200,11 +224,11 @@ impl<T> WifParse for BTreeSet<T>
 where
     T: WifParse + Ord,
 {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
-        s.split(',').map(|s| T::parse(s.into())).collect()
+        s.split(',').map(T::parse).collect()
     }
 
     fn unparse(&self) -> Option<String> {
@@ -215,7 +239,7 @@ where
     }
 }
 impl WifParse for Color {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
@@ -239,12 +263,12 @@ impl WifParse for Color {
     }
 }
 impl WifParse for BaseColor {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
         Ok(BaseColor {
-            idx: u32::parse(s)?,
+            idx: ColorIndex::parse(s)?,
             alt: None,
         })
     }
@@ -255,23 +279,33 @@ impl WifParse for BaseColor {
 }
 
 impl WifParse for Symbol {
-    fn parse(s: String) -> crate::Result<Self>
+    fn parse(s: &str) -> crate::Result<Self>
     where
         Self: Sized,
     {
-        if s.starts_with('\'') {
-            Ok(Symbol::Quoted(s.chars().nth(1).unwrap()))
+        let invalid = || WifError::InvalidSymbol { saw: s.to_string() };
+        if let Some(rest) = s.strip_prefix('\'') {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(c), Some('\''), None) => Ok(Symbol::Quoted(c)),
+                _ => Err(invalid()),
+            }
         } else if let Some(rest) = s.strip_prefix('#') {
-            Ok(Symbol::Code(char::from_u32(rest.parse::<u32>()?).unwrap()))
+            let code = rest.parse::<u32>()?;
+            char::from_u32(code).map(Symbol::Code).ok_or_else(invalid)
         } else {
-            Ok(Symbol::Char(s.chars().nth(0).unwrap()))
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Symbol::Char(c)),
+                _ => Err(invalid()),
+            }
         }
     }
 
     fn unparse(&self) -> Option<String> {
         match self {
             Symbol::Char(c) => Some(c.to_string()),
-            Symbol::Quoted(c) => Some(format!("\\{c}")),
+            Symbol::Quoted(c) => Some(format!("'{c}'")),
             Symbol::Code(c) => Some(format!("#{}", *c as u32)),
         }
     }